@@ -16,6 +16,8 @@ pub fn derive_state(input: TokenStream) -> TokenStream {
         let mut field_getters = Vec::new();
         let mut persist_quotes = Vec::new();
         let mut key_quotes = Vec::new();
+        let mut watermark_quotes = Vec::new();
+        let mut cache_quotes = Vec::new();
 
         if let syn::Fields::Named(ref fields_named) = s.fields {
             for field in fields_named.named.iter() {
@@ -47,6 +49,10 @@ pub fn derive_state(input: TokenStream) -> TokenStream {
                     persist_quotes.push(field_gen);
                     let field_gen = quote! { self.#ident.set_key(key); };
                     key_quotes.push(field_gen);
+                    let field_gen = quote! { self.#ident.set_watermark(watermark); };
+                    watermark_quotes.push(field_gen);
+                    let field_gen = quote! { self.#ident.clear_cache(); };
+                    cache_quotes.push(field_gen);
                 }
 
                 field_getters
@@ -100,6 +106,14 @@ pub fn derive_state(input: TokenStream) -> TokenStream {
                     fn set_key(&mut self, key: u64) {
                         #(#key_quotes)*
                     }
+                    #[inline]
+                    fn set_watermark(&mut self, watermark: u64) {
+                        #(#watermark_quotes)*
+                    }
+                    #[inline]
+                    fn clear_cache(&mut self) {
+                        #(#cache_quotes)*
+                    }
 
                     #tables
                 }
@@ -0,0 +1,346 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+//! Keyed state is split into key groups so that it can be redistributed between backends.
+//!
+//! The key group of an entry is derived from its item key, which is the `u64` key that
+//! an Operator sets on its handles before accessing state. Handles without a `u64` item key
+//! hold state that is not scoped by a key and is therefore not part of any key group.
+
+use crate::{
+    data::Metakey,
+    error::*,
+    serialization::{
+        fixed_bytes::{self, FixedBytes},
+        protobuf,
+    },
+};
+use std::{
+    any::TypeId,
+    fs::{self, File},
+    io::{BufRead, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+const KEY_GROUPS_FILE: &str = "KEY_GROUPS";
+/// Number of entries that are written and read at a time
+const CHUNK_ENTRIES: usize = 1024;
+
+/// Returns the key group of `key` in a key space of `max_key` key groups
+#[inline]
+pub fn key_group(key: u64, max_key: u64) -> u64 {
+    key % max_key
+}
+
+/// Returns true if handles with the item key type `IK` hold keyed state
+#[inline]
+pub fn is_keyed<IK: Metakey>() -> bool {
+    TypeId::of::<IK>() == TypeId::of::<u64>()
+}
+
+/// An inclusive range of key groups
+#[derive(prost::Message, PartialEq, Clone)]
+pub struct KeyGroupRange {
+    /// First key group of the range
+    #[prost(uint64)]
+    pub start: u64,
+    /// Last key group of the range
+    #[prost(uint64)]
+    pub end: u64,
+    /// Total number of key groups
+    #[prost(uint64)]
+    pub max_key: u64,
+}
+
+impl KeyGroupRange {
+    pub fn new(start: u64, end: u64, max_key: u64) -> Self {
+        assert!(start <= end, "start of the range must not be after its end");
+        assert!(end < max_key, "key groups must be within 0..{}", max_key);
+        KeyGroupRange {
+            start,
+            end,
+            max_key,
+        }
+    }
+
+    /// Returns true if the key group lies within the range
+    #[inline]
+    pub fn contains(&self, group: u64) -> bool {
+        group >= self.start && group <= self.end
+    }
+
+    /// Returns true if the key group of `key` lies within the range
+    #[inline]
+    pub fn contains_key(&self, key: u64) -> bool {
+        self.contains(key_group(key, self.max_key))
+    }
+
+    /// Returns true if a serialized backend key belongs to the range
+    ///
+    /// Keyed state is stored with the item key as the leading bytes of the backend key.
+    #[inline]
+    pub fn contains_raw_key(&self, raw_key: &[u8]) -> bool {
+        raw_key
+            .get(..u64::SIZE)
+            .and_then(|bytes| fixed_bytes::deserialize::<u64>(bytes).ok())
+            .map(|key| self.contains_key(key))
+            .unwrap_or(false)
+    }
+}
+
+/// A single exported entry of a keyed handle
+#[derive(prost::Message, PartialEq, Clone)]
+pub struct KeyGroupEntry {
+    /// Id of the handle the entry belongs to
+    #[prost(string)]
+    pub handle: String,
+    #[prost(bytes)]
+    pub key: Vec<u8>,
+    #[prost(bytes)]
+    pub value: Vec<u8>,
+}
+
+/// A chunk of exported entries
+#[derive(prost::Message, PartialEq, Clone)]
+struct KeyGroupChunk {
+    #[prost(message, repeated)]
+    entries: Vec<KeyGroupEntry>,
+}
+
+/// Streams exported key groups into a directory
+///
+/// The range is written first and followed by the entries in chunks of at most
+/// `CHUNK_ENTRIES`, each prefixed by its length, so that neither the writer
+/// nor the [KeyGroupReader] has to hold all entries in memory.
+pub struct KeyGroupWriter {
+    writer: BufWriter<File>,
+    chunk: Vec<KeyGroupEntry>,
+}
+
+impl KeyGroupWriter {
+    /// Starts an export of the key groups `range` into the directory `dir`
+    pub fn create(dir: &Path, range: &KeyGroupRange) -> Result<Self> {
+        if !dir.exists() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut writer = BufWriter::new(File::create(file_path(dir))?);
+        write_frame(&mut writer, &protobuf::serialize(range)?)?;
+        Ok(Self {
+            writer,
+            chunk: Vec::with_capacity(CHUNK_ENTRIES),
+        })
+    }
+
+    /// Adds an entry to the export
+    pub fn write(&mut self, entry: KeyGroupEntry) -> Result<()> {
+        self.chunk.push(entry);
+        if self.chunk.len() >= CHUNK_ENTRIES {
+            self.write_chunk()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the remaining entries and completes the export
+    pub fn finish(mut self) -> Result<()> {
+        self.write_chunk()?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn write_chunk(&mut self) -> Result<()> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+        let chunk = KeyGroupChunk {
+            entries: std::mem::take(&mut self.chunk),
+        };
+        write_frame(&mut self.writer, &protobuf::serialize(&chunk)?)
+    }
+}
+
+/// Reads the entries of an export written by a [KeyGroupWriter] one chunk at a time
+pub struct KeyGroupReader {
+    reader: BufReader<File>,
+    range: KeyGroupRange,
+    chunk: std::vec::IntoIter<KeyGroupEntry>,
+}
+
+impl KeyGroupReader {
+    /// Opens the export in the directory `dir`
+    pub fn open(dir: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(file_path(dir))?);
+        let range = match read_frame(&mut reader)? {
+            Some(bytes) => protobuf::deserialize(&bytes)?,
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "key group export without a range",
+                )
+                .into())
+            }
+        };
+        Ok(Self {
+            reader,
+            range,
+            chunk: Vec::new().into_iter(),
+        })
+    }
+
+    /// Returns the range of key groups of the export
+    pub fn range(&self) -> &KeyGroupRange {
+        &self.range
+    }
+}
+
+impl Iterator for KeyGroupReader {
+    type Item = Result<KeyGroupEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.chunk.next() {
+                return Some(Ok(entry));
+            }
+            let chunk = match read_frame(&mut self.reader) {
+                Ok(Some(bytes)) => protobuf::deserialize::<KeyGroupChunk>(&bytes),
+                Ok(None) => return None,
+                Err(error) => return Some(Err(error)),
+            };
+            match chunk {
+                Ok(chunk) => self.chunk = chunk.entries.into_iter(),
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+}
+
+fn write_frame(writer: &mut impl Write, bytes: &[u8]) -> Result<()> {
+    writer.write_all(&(bytes.len() as u64).to_le_bytes())?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+/// Returns `None` once the end of the export has been reached
+fn read_frame(reader: &mut BufReader<File>) -> Result<Option<Vec<u8>>> {
+    if reader.fill_buf()?.is_empty() {
+        return Ok(None);
+    }
+    let mut len = [0u8; 8];
+    reader.read_exact(&mut len)?;
+    let mut bytes = vec![0u8; u64::from_le_bytes(len) as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(Some(bytes))
+}
+
+fn file_path(dir: &Path) -> PathBuf {
+    let mut p: PathBuf = dir.into();
+    p.push(KEY_GROUPS_FILE);
+    p
+}
+
+#[cfg(all(test, feature = "sled"))]
+mod tests {
+    use super::*;
+    use crate::{Backend, Handle, Sled};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn key_group_range_test() {
+        let range = KeyGroupRange::new(4, 7, 16);
+        assert!(range.contains(4));
+        assert!(range.contains(7));
+        assert!(!range.contains(8));
+        assert!(range.contains_key(16 + 5));
+        assert!(range.contains_raw_key(&21u64.to_le_bytes()));
+        assert!(!range.contains_raw_key(&3u64.to_le_bytes()));
+        assert!(is_keyed::<u64>());
+        assert!(!is_keyed::<()>());
+    }
+
+    #[test]
+    fn key_group_chunks_test() {
+        let dir = TempDir::new().unwrap();
+        let range = KeyGroupRange::new(0, 3, 8);
+        let entries = CHUNK_ENTRIES * 2 + 10;
+
+        let mut writer = KeyGroupWriter::create(dir.path(), &range).unwrap();
+        for i in 0..entries {
+            writer
+                .write(KeyGroupEntry {
+                    handle: "handle".to_string(),
+                    key: (i as u64).to_le_bytes().to_vec(),
+                    value: vec![i as u8],
+                })
+                .unwrap();
+        }
+        writer.finish().unwrap();
+
+        let reader = KeyGroupReader::open(dir.path()).unwrap();
+        assert_eq!(reader.range(), &range);
+        let read: Vec<KeyGroupEntry> = reader.map(|entry| entry.unwrap()).collect();
+        assert_eq!(read.len(), entries);
+        assert_eq!(read[entries - 1].value, vec![(entries - 1) as u8]);
+
+        // an export without entries only holds its range
+        let empty = TempDir::new().unwrap();
+        KeyGroupWriter::create(empty.path(), &range)
+            .unwrap()
+            .finish()
+            .unwrap();
+        assert_eq!(KeyGroupReader::open(empty.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn export_import_key_groups_test() {
+        let max_key = 8;
+        let source_dir = TempDir::new().unwrap();
+        let source = Arc::new(Sled::create(source_dir.path(), "source".to_string()).unwrap());
+
+        let mut keyed = Handle::value("keyed").with_item_key(0u64);
+        source.register_value_handle(&mut keyed);
+        let mut keyed = keyed.activate(source.clone());
+
+        let mut global = Handle::value("global");
+        source.register_value_handle(&mut global);
+        let mut global = global.activate(source.clone());
+        global.fast_set(1000u64).unwrap();
+
+        for key in 0..max_key {
+            keyed.set_item_key(key);
+            keyed.fast_set(key * 10).unwrap();
+        }
+
+        // split the key space in two halves
+        let lower = KeyGroupRange::new(0, 3, max_key);
+        let upper = KeyGroupRange::new(4, 7, max_key);
+        let lower_dir = TempDir::new().unwrap();
+        let upper_dir = TempDir::new().unwrap();
+        source.export_key_groups(&lower, lower_dir.path()).unwrap();
+        source.export_key_groups(&upper, upper_dir.path()).unwrap();
+
+        let target_dir = TempDir::new().unwrap();
+        let target = Arc::new(Sled::create(target_dir.path(), "target".to_string()).unwrap());
+        let mut handle = Handle::value("keyed").with_item_key(0u64);
+        target.register_value_handle(&mut handle);
+        let mut handle = handle.activate(target.clone());
+
+        let imported = target.import_key_groups(upper_dir.path()).unwrap();
+        assert_eq!(imported, upper);
+
+        for key in 0..max_key {
+            handle.set_item_key(key);
+            let value: Option<u64> = handle.get().unwrap();
+            if upper.contains_key(key) {
+                assert_eq!(value, Some(key * 10));
+            } else {
+                assert_eq!(value, None);
+            }
+        }
+
+        // state that is not keyed is not part of any key group
+        let mut global = Handle::<crate::ValueState<u64>>::value("global");
+        target.register_value_handle(&mut global);
+        let global = global.activate(target);
+        assert_eq!(global.get().unwrap(), None);
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 
 pub mod handles;
+pub mod key_groups;
 #[macro_use]
 pub mod macros;
 pub mod ops;
@@ -13,6 +14,7 @@ pub mod test_common;
 
 pub use crate::{
     handles::Handle,
+    key_groups::KeyGroupRange,
     ops::{AggregatorOps, MapOps, ReducerOps, ValueOps, VecOps},
 };
 
@@ -30,6 +32,9 @@ use std::{
     path::{Path, PathBuf},
 };
 
+/// A checkpoint that has been captured by a [Backend] but is yet to be written
+pub type PendingCheckpoint = Box<dyn FnOnce() -> Result<()> + Send>;
+
 #[derive(Debug, Default)]
 pub struct Config {
     pub live_state_base_path: PathBuf,
@@ -143,6 +148,49 @@ pub trait Backend:
 
     fn checkpoint(&self, checkpoint_path: &Path) -> Result<()>;
 
+    /// Captures the current state of the backend for a checkpoint into `checkpoint_path`
+    ///
+    /// Only the capture is done synchronously. Writing the checkpoint is left to the returned
+    /// closure, which may run on another thread while the backend keeps being modified.
+    /// Backends without a cheap way of capturing their state write the checkpoint right away.
+    fn capture_checkpoint(&self, checkpoint_path: &Path) -> Result<PendingCheckpoint> {
+        self.checkpoint(checkpoint_path)?;
+        Ok(Box::new(|| Ok(())))
+    }
+
+    /// Writes all keyed state whose key group lies within `range` into the directory `path`
+    ///
+    /// State of handles that are not scoped by a `u64` item key is not exported.
+    fn export_key_groups(&self, _range: &KeyGroupRange, _path: &Path) -> Result<()> {
+        Unsupported {
+            backend: self.name().to_string(),
+            operation: "export_key_groups",
+        }
+        .fail()
+    }
+
+    /// Inserts the key groups previously exported into the directory `path`
+    ///
+    /// Returns the range of key groups that were imported.
+    fn import_key_groups(&self, _path: &Path) -> Result<KeyGroupRange> {
+        Unsupported {
+            backend: self.name().to_string(),
+            operation: "import_key_groups",
+        }
+        .fail()
+    }
+
+    /// Returns the ids of the handles that hold state which is not part of any key group
+    ///
+    /// This is the state that [Backend::export_key_groups] leaves behind.
+    fn unkeyed_handles(&self) -> Result<Vec<String>> {
+        Unsupported {
+            backend: self.name().to_string(),
+            operation: "unkeyed_handles",
+        }
+        .fail()
+    }
+
     /// should not be called from outside `BackendContainer::session`
     fn start_session(&mut self) {}
 
@@ -4,21 +4,64 @@
 use crate::{
     data::{Key, Metakey, Value},
     error::*,
-    Aggregator, AggregatorState, Backend, Handle, MapState, Reducer, ReducerState, ValueState,
-    VecState,
+    key_groups::{self, KeyGroupEntry, KeyGroupRange, KeyGroupReader, KeyGroupWriter},
+    serialization::protobuf,
+    Aggregator, AggregatorState, Backend, Handle, MapState, PendingCheckpoint, Reducer,
+    ReducerState, ValueState, VecState,
 };
 
 use rocksdb::{
-    checkpoint::Checkpoint, ColumnFamily, ColumnFamilyDescriptor, DBPinnableSlice, Options,
-    SliceTransform, WriteBatch, WriteOptions, DB,
+    checkpoint::Checkpoint, ColumnFamily, ColumnFamilyDescriptor, DBPinnableSlice, IteratorMode,
+    Options, ReadOptions, SliceTransform, WriteBatch, WriteOptions, DB,
 };
 use std::{
     cell::UnsafeCell,
     collections::HashSet,
+    ffi::OsStr,
     fs,
     path::{Path, PathBuf},
+    sync::{Arc, Mutex},
 };
 
+/// Column family that records the ids of all handles holding keyed state
+const KEYED_HANDLES_CF: &str = "__arcon_keyed_handles";
+/// File that describes which SST files a checkpoint shares with the previous one
+const INCREMENTAL_MANIFEST: &str = "INCREMENTAL_MANIFEST";
+
+/// Describes an incremental checkpoint
+///
+/// SST files are immutable and uniquely named within a database, so the SST files that a
+/// checkpoint has in common with the previous checkpoint are hard links to the files of that
+/// checkpoint rather than copies. The file system keeps count of the references to every file,
+/// which means that removing a checkpoint never affects the files of later checkpoints and
+/// every checkpoint directory holds the full set of files needed to restore from it.
+#[derive(prost::Message, Clone)]
+pub struct IncrementalManifest {
+    /// Checkpoint that unchanged SST files are shared with
+    #[prost(string)]
+    pub base: String,
+    /// SST files that were written by this checkpoint
+    #[prost(string, repeated)]
+    pub new_files: Vec<String>,
+    /// SST files that are shared with the base checkpoint
+    #[prost(string, repeated)]
+    pub shared_files: Vec<String>,
+}
+
+impl IncrementalManifest {
+    /// Reads the manifest of the checkpoint in `checkpoint_path`
+    pub fn read(checkpoint_path: &Path) -> Result<Self> {
+        let bytes = fs::read(checkpoint_path.join(INCREMENTAL_MANIFEST))?;
+        protobuf::deserialize(&bytes)
+    }
+
+    fn write(&self, checkpoint_path: &Path) -> Result<()> {
+        let bytes = protobuf::serialize(self)?;
+        fs::write(checkpoint_path.join(INCREMENTAL_MANIFEST), bytes)?;
+        Ok(())
+    }
+}
+
 unsafe impl Send for Rocks {}
 unsafe impl Sync for Rocks {}
 
@@ -28,6 +71,8 @@ pub struct Rocks {
     path: PathBuf,
     restored: bool,
     name: String,
+    /// Latest checkpoint of the database, which the next checkpoint shares SST files with
+    last_checkpoint: Arc<Mutex<Option<PathBuf>>>,
 }
 
 // we use epochs, so WAL is useless for us
@@ -123,6 +168,71 @@ impl Rocks {
         }
         Ok(())
     }
+
+    fn mark_keyed<IK: Metakey>(&self, id: &str) -> Result<()> {
+        if key_groups::is_keyed::<IK>() {
+            self.create_column_family(KEYED_HANDLES_CF, Options::default())?;
+            self.put(KEYED_HANDLES_CF, id, b"")?;
+        }
+        Ok(())
+    }
+}
+
+/// Moves a staged RocksDB checkpoint into `checkpoint_path`
+///
+/// SST files that are part of the previous checkpoint are hard linked from it instead of copied.
+fn write_incremental_checkpoint(
+    staging_path: &Path,
+    checkpoint_path: &Path,
+    last_checkpoint: &Mutex<Option<PathBuf>>,
+) -> Result<()> {
+    if checkpoint_path.exists() {
+        // TODO: add a warning log here
+        // warn!(logger, "Checkpoint path {:?} exists, deleting");
+        fs::remove_dir_all(checkpoint_path)?
+    }
+    fs::create_dir_all(checkpoint_path)?;
+
+    let mut last_checkpoint = last_checkpoint.lock().unwrap();
+    let mut manifest = IncrementalManifest {
+        base: last_checkpoint
+            .as_ref()
+            .map(|base| base.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    for entry in fs::read_dir(staging_path)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let target = checkpoint_path.join(&file_name);
+        if !is_sst(&file_name) {
+            fs::copy(entry.path(), &target)?;
+            continue;
+        }
+
+        let name = file_name.to_string_lossy().into_owned();
+        // the base checkpoint may have been removed or live on another file system
+        let shared = last_checkpoint
+            .as_ref()
+            .map(|base| fs::hard_link(base.join(&file_name), &target).is_ok())
+            .unwrap_or(false);
+        if shared {
+            manifest.shared_files.push(name);
+        } else {
+            fs::copy(entry.path(), &target)?;
+            manifest.new_files.push(name);
+        }
+    }
+
+    fs::remove_dir_all(staging_path)?;
+    manifest.write(checkpoint_path)?;
+    *last_checkpoint = Some(checkpoint_path.to_path_buf());
+    Ok(())
+}
+
+fn is_sst(file_name: &OsStr) -> bool {
+    Path::new(file_name).extension() == Some(OsStr::new("sst"))
 }
 
 fn common_options<IK, N>() -> Options
@@ -177,6 +287,7 @@ impl Backend for Rocks {
             path,
             restored: false,
             name,
+            last_checkpoint: Arc::new(Mutex::new(None)),
         })
     }
 
@@ -201,6 +312,10 @@ impl Backend for Rocks {
                 .expect("Cannot read entry metadata")
                 .is_file());
 
+            if entry.file_name() == INCREMENTAL_MANIFEST {
+                continue;
+            }
+
             let source_path = entry.path();
             // replaces the __DUMMY from above the loop
             target_path.set_file_name(
@@ -215,6 +330,7 @@ impl Backend for Rocks {
         Rocks::create(live_path, name).map(|mut r| {
             //r.get_mut().restored = true;
             r.restored = true;
+            r.last_checkpoint = Arc::new(Mutex::new(Some(checkpoint_path.to_path_buf())));
             r
         })
     }
@@ -224,19 +340,99 @@ impl Backend for Rocks {
     }
 
     fn checkpoint(&self, checkpoint_path: &Path) -> Result<()> {
+        let write_checkpoint = self.capture_checkpoint(checkpoint_path)?;
+        write_checkpoint()
+    }
+
+    fn capture_checkpoint(&self, checkpoint_path: &Path) -> Result<PendingCheckpoint> {
         let db = self.db();
         db.flush()?;
 
-        let checkpointer = Checkpoint::new(db)?;
+        // Stage a RocksDB checkpoint next to the live database, where its SST files are
+        // hard links to the live ones. This is cheap and pins the files of the checkpoint,
+        // so that only what the previous checkpoint lacks has to be moved over later on.
+        let mut staging_path = self.path.clone().into_os_string();
+        staging_path.push(".");
+        staging_path.push(checkpoint_path.file_name().unwrap_or_default());
+        let staging_path = PathBuf::from(staging_path);
+        if staging_path.exists() {
+            fs::remove_dir_all(&staging_path)?;
+        }
+        Checkpoint::new(db)?.create_checkpoint(&staging_path)?;
 
-        if checkpoint_path.exists() {
-            // TODO: add a warning log here
-            // warn!(logger, "Checkpoint path {:?} exists, deleting");
-            fs::remove_dir_all(checkpoint_path)?
+        let checkpoint_path = checkpoint_path.to_path_buf();
+        let last_checkpoint = self.last_checkpoint.clone();
+        Ok(Box::new(move || {
+            write_incremental_checkpoint(&staging_path, &checkpoint_path, &last_checkpoint)
+        }))
+    }
+
+    fn export_key_groups(&self, range: &KeyGroupRange, path: &Path) -> Result<()> {
+        let mut writer = KeyGroupWriter::create(path, range)?;
+        if let Ok(keyed_handles) = self.get_cf_handle(KEYED_HANDLES_CF) {
+            for (handle, _) in self.db().iterator_cf(keyed_handles, IteratorMode::Start) {
+                let handle = String::from_utf8_lossy(&handle).into_owned();
+                let cf = self.get_cf_handle(&handle)?;
+                // the handle column families use a prefix extractor, so ask for a full scan
+                let mut read_opts = ReadOptions::default();
+                read_opts.set_total_order_seek(true);
+                for (key, value) in self
+                    .db()
+                    .iterator_cf_opt(cf, read_opts, IteratorMode::Start)
+                {
+                    if range.contains_raw_key(&key) {
+                        writer.write(KeyGroupEntry {
+                            handle: handle.clone(),
+                            key: key.to_vec(),
+                            value: value.to_vec(),
+                        })?;
+                    }
+                }
+            }
         }
+        writer.finish()
+    }
 
-        checkpointer.create_checkpoint(checkpoint_path)?;
-        Ok(())
+    fn import_key_groups(&self, path: &Path) -> Result<KeyGroupRange> {
+        // exported values are fully merged, so missing column families are created the
+        // same way as when a database is reopened, without the options of their handles
+        let reader = KeyGroupReader::open(path)?;
+        let range = reader.range().clone();
+        self.create_column_family(KEYED_HANDLES_CF, Options::default())?;
+        for entry in reader {
+            let entry = entry?;
+            self.create_column_family(&entry.handle, Options::default())?;
+            self.put(KEYED_HANDLES_CF, &entry.handle, b"")?;
+            self.put(&entry.handle, &entry.key, &entry.value)?;
+        }
+        Ok(range)
+    }
+
+    fn unkeyed_handles(&self) -> Result<Vec<String>> {
+        let keyed_handles = self.get_cf_handle(KEYED_HANDLES_CF).ok();
+        let mut handles = Vec::new();
+        for cf_name in DB::list_cf(&Options::default(), &self.path)? {
+            if cf_name == "default" || cf_name == KEYED_HANDLES_CF {
+                continue;
+            }
+            if let Some(keyed_handles) = keyed_handles {
+                if self.db().get_pinned_cf(keyed_handles, &cf_name)?.is_some() {
+                    continue;
+                }
+            }
+            let cf = self.get_cf_handle(&cf_name)?;
+            let mut read_opts = ReadOptions::default();
+            read_opts.set_total_order_seek(true);
+            if self
+                .db()
+                .iterator_cf_opt(cf, read_opts, IteratorMode::Start)
+                .next()
+                .is_some()
+            {
+                handles.push(cf_name);
+            }
+        }
+        Ok(handles)
     }
 
     fn register_value_handle<'s, T: Value, IK: Metakey, N: Metakey>(
@@ -247,6 +443,8 @@ impl Backend for Rocks {
         let opts = common_options::<IK, N>();
         self.create_column_family(&handle.id, opts)
             .expect("Could not create column family");
+        self.mark_keyed::<IK>(&handle.id)
+            .expect("Could not mark a handle as keyed");
     }
 
     fn register_map_handle<'s, K: Key, V: Value, IK: Metakey, N: Metakey>(
@@ -257,6 +455,8 @@ impl Backend for Rocks {
         let opts = common_options::<IK, N>();
         self.create_column_family(&handle.id, opts)
             .expect("Could not create column family");
+        self.mark_keyed::<IK>(&handle.id)
+            .expect("Could not mark a handle as keyed");
     }
 
     fn register_vec_handle<'s, T: Value, IK: Metakey, N: Metakey>(
@@ -268,6 +468,8 @@ impl Backend for Rocks {
         opts.set_merge_operator_associative("vec_merge", vec_ops::vec_merge);
         self.create_column_family(&handle.id, opts)
             .expect("Could not create column family");
+        self.mark_keyed::<IK>(&handle.id)
+            .expect("Could not mark a handle as keyed");
     }
 
     fn register_reducer_handle<'s, T: Value, F: Reducer<T>, IK: Metakey, N: Metakey>(
@@ -280,6 +482,8 @@ impl Backend for Rocks {
         opts.set_merge_operator_associative("reducer_merge", reducer_merge);
         self.create_column_family(&handle.id, opts)
             .expect("Could not create column family");
+        self.mark_keyed::<IK>(&handle.id)
+            .expect("Could not mark a handle as keyed");
     }
 
     fn register_aggregator_handle<'s, A: Aggregator, IK: Metakey, N: Metakey>(
@@ -292,6 +496,8 @@ impl Backend for Rocks {
         opts.set_merge_operator_associative("aggregator_merge", aggregator_merge);
         self.create_column_family(&handle.id, opts)
             .expect("Could not create column family");
+        self.mark_keyed::<IK>(&handle.id)
+            .expect("Could not mark a handle as keyed");
     }
 }
 
@@ -471,5 +677,53 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn incremental_checkpoint_test() {
+        let live_dir = TempDir::new().unwrap();
+        let checkpoints_dir = TempDir::new().unwrap();
+        let restore_dir = TempDir::new().unwrap();
+        let checkpoint = |epoch: u64| checkpoints_dir.path().join(format!("chkp{}", epoch));
+
+        let db = Rocks::create(live_dir.path(), "testDB".to_string()).unwrap();
+        let column_family = "default";
+
+        db.put(column_family, b"a", b"first").unwrap();
+        db.checkpoint(&checkpoint(0)).unwrap();
+        let first = IncrementalManifest::read(&checkpoint(0)).unwrap();
+        assert!(first.shared_files.is_empty());
+        assert!(!first.new_files.is_empty());
+
+        db.put(column_family, b"b", b"second").unwrap();
+        db.checkpoint(&checkpoint(1)).unwrap();
+        let second = IncrementalManifest::read(&checkpoint(1)).unwrap();
+        // only the SST file of the second write is new
+        assert_eq!(second.shared_files, first.new_files);
+        assert_eq!(second.new_files.len(), 1);
+        assert_eq!(second.base, checkpoint(0).to_string_lossy());
+
+        // a captured checkpoint is written later on, without the writes made in between
+        let write_checkpoint = db.capture_checkpoint(&checkpoint(2)).unwrap();
+        db.put(column_family, b"c", b"third").unwrap();
+        write_checkpoint().unwrap();
+        let third = IncrementalManifest::read(&checkpoint(2)).unwrap();
+        assert_eq!(third.base, checkpoint(1).to_string_lossy());
+        assert!(third.new_files.is_empty());
+
+        // the latest checkpoint holds all of its files even once the earlier ones are gone
+        fs::remove_dir_all(checkpoint(0)).unwrap();
+        fs::remove_dir_all(checkpoint(1)).unwrap();
+        let restored = Rocks::restore(
+            &restore_dir.path().join("restored"),
+            &checkpoint(2),
+            "testDB".to_string(),
+        )
+        .unwrap();
+        for (key, value) in &[(b"a", &b"first"[..]), (b"b", &b"second"[..])] {
+            let stored = restored.get(column_family, &key[..]).unwrap().unwrap();
+            assert_eq!(stored.as_ref(), *value);
+        }
+        assert!(restored.get(column_family, b"c").unwrap().is_none());
+    }
+
     common_state_tests!(TestDb::new());
 }
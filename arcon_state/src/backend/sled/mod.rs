@@ -1,9 +1,12 @@
 // Copyright (c) 2020, KTH Royal Institute of Technology.
 // SPDX-License-Identifier: AGPL-3.0-only
 
+#[cfg(feature = "sled_checkpoints")]
+use crate::PendingCheckpoint;
 use crate::{
     data::{Key, Metakey, Value},
     error::*,
+    key_groups::{self, KeyGroupEntry, KeyGroupRange, KeyGroupReader, KeyGroupWriter},
     Aggregator, AggregatorState, Backend, Handle, MapState, Reducer, ReducerState, ValueState,
     VecState,
 };
@@ -12,10 +15,13 @@ use std::path::Path;
 #[cfg(feature = "sled_checkpoints")]
 use std::{
     fs, io,
-    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    io::{BufReader, BufWriter, Read, Write},
     path::PathBuf,
 };
 
+/// Tree that records the ids of all handles holding keyed state
+const KEYED_HANDLES_TREE: &str = "__arcon_keyed_handles";
+
 #[derive(Debug)]
 pub struct Sled {
     db: Db,
@@ -63,6 +69,14 @@ impl Sled {
         let tree = self.tree(tree_name)?;
         Ok(tree.contains_key(key)?)
     }
+
+    fn mark_keyed<IK: Metakey>(&self, id: &str) {
+        if key_groups::is_keyed::<IK>() {
+            self.tree(KEYED_HANDLES_TREE)
+                .and_then(|tree| Ok(tree.insert(id.as_bytes(), &b""[..])?))
+                .expect("Could not mark a handle as keyed");
+        }
+    }
 }
 
 impl Backend for Sled {
@@ -118,58 +132,81 @@ impl Backend for Sled {
 
     #[cfg(feature = "sled_checkpoints")]
     fn checkpoint(&self, checkpoint_path: &Path) -> Result<()> {
-        // TODO: sled doesn't support checkpoints/snapshots, but that and MVCC is planned
-        //   for now we'll just dump it via the export/import mechanism WHICH MAY BE VERY SLOW
-        let export_data = self.db.export();
-
-        let mut p: PathBuf = checkpoint_path.into();
-        if !p.exists() {
-            fs::create_dir_all(&p)?;
-        }
+        let write_checkpoint = self.capture_checkpoint(checkpoint_path)?;
+        write_checkpoint()
+    }
 
-        p.push("SLED_EXPORT");
-        let out = fs::File::create(&p)?;
-        let mut writer = BufWriter::new(out);
+    #[cfg(feature = "sled_checkpoints")]
+    fn capture_checkpoint(&self, checkpoint_path: &Path) -> Result<PendingCheckpoint> {
+        // TODO: sled doesn't support checkpoints/snapshots, but that and MVCC is planned.
+        //   Until then, the capture is an in-memory copy of the export, which takes time
+        //   linear in the size of the state. Only writing the copy is left to the closure.
+        let export_data: SledExport = self
+            .db
+            .export()
+            .into_iter()
+            .map(|(typ, name, vvecs)| (typ.to_vec(), name.to_vec(), vvecs.collect()))
+            .collect();
+
+        let checkpoint_path = checkpoint_path.to_path_buf();
+        Ok(Box::new(move || {
+            write_sled_export(&checkpoint_path, export_data)
+        }))
+    }
 
-        writer.write_all(&export_data.len().to_le_bytes())?;
+    fn export_key_groups(&self, range: &KeyGroupRange, path: &Path) -> Result<()> {
+        let mut writer = KeyGroupWriter::create(path, range)?;
+        for handle in self.tree(KEYED_HANDLES_TREE)?.iter().keys() {
+            let handle = String::from_utf8_lossy(&handle?).into_owned();
+            for entry in self.tree(&handle)?.iter() {
+                let (key, value) = entry?;
+                if range.contains_raw_key(&key) {
+                    writer.write(KeyGroupEntry {
+                        handle: handle.clone(),
+                        key: key.to_vec(),
+                        value: value.to_vec(),
+                    })?;
+                }
+            }
+        }
+        writer.finish()
+    }
 
-        #[inline]
-        fn write_len_and_bytes(mut w: impl Write, bytes: &[u8]) -> io::Result<()> {
-            w.write_all(&bytes.len().to_le_bytes())?;
-            w.write_all(bytes)?;
-            Ok(())
+    fn import_key_groups(&self, path: &Path) -> Result<KeyGroupRange> {
+        let reader = KeyGroupReader::open(path)?;
+        let range = reader.range().clone();
+        let keyed_handles = self.tree(KEYED_HANDLES_TREE)?;
+        for entry in reader {
+            let entry = entry?;
+            keyed_handles.insert(entry.handle.as_bytes(), &b""[..])?;
+            self.put(&entry.handle, &entry.key, &entry.value)?;
         }
+        Ok(range)
+    }
 
-        for (typ, name, vvecs) in export_data {
-            write_len_and_bytes(&mut writer, &typ)?;
-            write_len_and_bytes(&mut writer, &name)?;
-
-            // we don't know how many elements are in `vvecs`, so let's save the current position,
-            // write a dummy length, write all the elements and then go back and write how many we wrote
-            let length_spot = writer.seek(SeekFrom::Current(0))?;
-            writer.write_all(&0usize.to_le_bytes())?;
-            let mut length = 0usize;
-
-            for vecs in vvecs {
-                length += 1;
-                writer.write_all(&vecs.len().to_le_bytes())?;
-                for vec in vecs {
-                    write_len_and_bytes(&mut writer, &vec)?;
-                }
+    fn unkeyed_handles(&self) -> Result<Vec<String>> {
+        let keyed_handles = self.tree(KEYED_HANDLES_TREE)?;
+        let mut handles = Vec::new();
+        for name in self.db.tree_names() {
+            if name.starts_with(b"__sled__")
+                || &*name == KEYED_HANDLES_TREE.as_bytes()
+                || keyed_handles.contains_key(&name)?
+            {
+                continue;
+            }
+            let handle = String::from_utf8_lossy(&name).into_owned();
+            if !self.tree(&handle)?.is_empty() {
+                handles.push(handle);
             }
-
-            let after_vecs = writer.seek(SeekFrom::Current(0))?;
-            let _ = writer.seek(SeekFrom::Start(length_spot))?;
-            writer.write_all(&length.to_le_bytes())?;
-            let _ = writer.seek(SeekFrom::Start(after_vecs))?;
         }
-        Ok(())
+        Ok(handles)
     }
 
     fn register_value_handle<T: Value, IK: Metakey, N: Metakey>(
         &self,
         handle: &mut Handle<ValueState<T>, IK, N>,
     ) {
+        self.mark_keyed::<IK>(&handle.id);
         handle.registered = true;
     }
 
@@ -177,6 +214,7 @@ impl Backend for Sled {
         &self,
         handle: &mut Handle<MapState<K, V>, IK, N>,
     ) {
+        self.mark_keyed::<IK>(&handle.id);
         handle.registered = true;
     }
 
@@ -187,6 +225,7 @@ impl Backend for Sled {
         let tree = self
             .tree(&handle.id)
             .expect("Could not get the tree when registering a vec");
+        self.mark_keyed::<IK>(&handle.id);
         tree.set_merge_operator(vec_ops::vec_merge);
         handle.registered = true;
     }
@@ -198,6 +237,7 @@ impl Backend for Sled {
         let tree = self
             .tree(&handle.id)
             .expect("Could not get the tree when registering a reducer");
+        self.mark_keyed::<IK>(&handle.id);
         tree.set_merge_operator(reducer_ops::make_reducer_merge(handle.extra_data.clone()));
         handle.registered = true;
     }
@@ -209,6 +249,7 @@ impl Backend for Sled {
         let tree = self
             .tree(&handle.id)
             .expect("Could not get the tree when registering an aggregator");
+        self.mark_keyed::<IK>(&handle.id);
         tree.set_merge_operator(aggregator_ops::make_aggregator_merge(
             handle.extra_data.clone(),
         ));
@@ -222,6 +263,46 @@ mod reducer_ops;
 mod value_ops;
 mod vec_ops;
 
+/// Collections of an exported sled database: (type, name, entries)
+#[cfg(feature = "sled_checkpoints")]
+type SledExport = Vec<(Vec<u8>, Vec<u8>, Vec<Vec<Vec<u8>>>)>;
+
+#[cfg(feature = "sled_checkpoints")]
+fn write_sled_export(checkpoint_path: &Path, export_data: SledExport) -> Result<()> {
+    let mut p: PathBuf = checkpoint_path.into();
+    if !p.exists() {
+        fs::create_dir_all(&p)?;
+    }
+
+    p.push("SLED_EXPORT");
+    let out = fs::File::create(&p)?;
+    let mut writer = BufWriter::new(out);
+
+    writer.write_all(&export_data.len().to_le_bytes())?;
+
+    #[inline]
+    fn write_len_and_bytes(mut w: impl Write, bytes: &[u8]) -> io::Result<()> {
+        w.write_all(&bytes.len().to_le_bytes())?;
+        w.write_all(bytes)?;
+        Ok(())
+    }
+
+    for (typ, name, vvecs) in export_data {
+        write_len_and_bytes(&mut writer, &typ)?;
+        write_len_and_bytes(&mut writer, &name)?;
+
+        writer.write_all(&vvecs.len().to_le_bytes())?;
+        for vecs in vvecs {
+            writer.write_all(&vecs.len().to_le_bytes())?;
+            for vec in vecs {
+                write_len_and_bytes(&mut writer, &vec)?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
 #[cfg(feature = "sled_checkpoints")]
 fn parse_dumped_sled_export(
     dump_path: &Path,
@@ -356,5 +437,27 @@ mod tests {
         assert_eq!(restored_t.get(b"y"), Ok(Some(IVec::from(b"20"))));
     }
 
+    #[cfg(feature = "sled_checkpoints")]
+    #[test]
+    fn test_sled_captured_checkpoint() {
+        let dir = TempDir::new().unwrap();
+        let sled = Sled::create(dir.path(), "testDB".to_string()).unwrap();
+        sled.db.insert(b"a", b"1").unwrap();
+
+        let chkp_dir = TempDir::new().unwrap();
+        let write_checkpoint = sled.capture_checkpoint(chkp_dir.path()).unwrap();
+
+        // modifications after the capture do not end up in the checkpoint
+        sled.db.insert(b"a", b"2").unwrap();
+        sled.db.insert(b"b", b"3").unwrap();
+        write_checkpoint().unwrap();
+
+        let restore_dir = TempDir::new().unwrap();
+        let restored =
+            Sled::restore(restore_dir.path(), chkp_dir.path(), "testDB".to_string()).unwrap();
+        assert_eq!(restored.db.len(), 1);
+        assert_eq!(restored.db.get(b"a"), Ok(Some(IVec::from(b"1"))));
+    }
+
     common_state_tests!(TestDb::new());
 }
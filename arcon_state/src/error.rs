@@ -120,6 +120,12 @@ pub enum ArconStateError {
         source: ::sled::Error,
         backtrace: Backtrace,
     },
+    #[snafu(display("The {} backend does not support {}", backend, operation))]
+    Unsupported {
+        backend: String,
+        operation: &'static str,
+        backtrace: Backtrace,
+    },
     #[snafu(display("Error : {}", msg))]
     Unknown { msg: String },
 }
@@ -1,13 +1,20 @@
 // Copyright (c) 2020, KTH Royal Institute of Technology.
 // SPDX-License-Identifier: AGPL-3.0-only
 
-use super::Application;
+use super::{restore::checkpoint_name, Application};
 use crate::{
     data::ArconType,
+    error::{ArconResult, Error},
+    manager::snapshot::{CommittedEpoch, SnapshotCatalog},
     stream::node::{debug::DebugNode, source::SourceEvent},
+    util::copy_dir,
 };
 use kompact::prelude::{ActorRefFactory, Component};
-use std::sync::Arc;
+use std::{
+    path::Path,
+    sync::{mpsc, Arc},
+    time::Duration,
+};
 
 /// An [`Application`] that has been fully assembled
 pub struct AssembledApplication {
@@ -29,29 +36,31 @@ impl AssembledApplication {
     /// to inject a start message to the source components
     /// of the application.
     ///
-    /// The function will panic if no sources have been created
+    /// The function will panic if no sources have been created. In a distributed
+    /// deployment, only the processes that run source instances have sources, and every
+    /// process should be built before any of them is started.
     pub fn start(&mut self) {
         assert_ne!(
             self.start_flag, true,
             "The AssembledApplication has already been started"
         );
 
-        // Send start message to manager component
-        match &self.app.source_manager {
-            Some(source_manager) => {
-                source_manager.actor_ref().tell(SourceEvent::Start);
-            }
-            None => panic!("Something went wrong, no source manager has been created!"),
+        assert!(
+            !self.app.source_managers.is_empty() || self.app.conf.distributed().is_some(),
+            "Something went wrong, no source manager has been created!"
+        );
+
+        // Send start message to manager components
+        for source_manager in &self.app.source_managers {
+            source_manager.actor_ref().tell(SourceEvent::Start);
         }
 
         // Start epoch manager to begin the injection of epochs into the application.
-        if let Some(epoch_manager) = &self.app.epoch_manager {
-            self.app
-                .ctrl_system
-                .start_notify(epoch_manager)
-                .wait_timeout(std::time::Duration::from_millis(500))
-                .expect("Failed to start EpochManager");
-        }
+        self.app
+            .ctrl_system
+            .start_notify(&self.app.epoch_manager)
+            .wait_timeout(std::time::Duration::from_millis(500))
+            .expect("Failed to start EpochManager");
 
         self.start_flag = true;
     }
@@ -67,6 +76,105 @@ impl AssembledApplication {
         self.app.get_debug_node()
     }
 
+    /// Returns the number of NodeManagers that run on this process
+    ///
+    /// Operators that are chained into the Nodes of their upstream Operator have none.
+    pub(crate) fn num_node_managers(&self) -> usize {
+        self.app.node_managers.len()
+    }
+
+    /// Takes a savepoint of the application into the directory `path`
+    ///
+    /// An epoch is injected right away and, once every operator and source has
+    /// acknowledged it, the checkpoints of the epoch are copied into `path` together
+    /// with a [SnapshotCatalog] describing them. The application may later be restored
+    /// from the savepoint through [RestorePoint::Dir](super::RestorePoint::Dir).
+    ///
+    /// Note that this blocks the current thread until the savepoint has been written
+    /// or the configured savepoint timeout has passed, in which case
+    /// [Error::SavepointTimeout] is returned.
+    pub fn savepoint(&self, path: impl AsRef<Path>) -> ArconResult<CommittedEpoch> {
+        self.take_savepoint(path.as_ref(), false)
+    }
+
+    // Takes a savepoint at the next epoch, after which the sources stop if `stop` is set
+    fn take_savepoint(&self, path: &Path, stop: bool) -> ArconResult<CommittedEpoch> {
+        if !self.start_flag {
+            return Err(Error::Unsupported {
+                msg: "Savepoints can only be taken of a started application".to_string(),
+            });
+        }
+        // Each process only commits the epochs of its own part of the application
+        if self.app.conf.distributed().is_some() {
+            return Err(Error::Unsupported {
+                msg: "Savepoints are not supported for distributed applications".to_string(),
+            });
+        }
+        let epoch_manager = &self.app.epoch_manager;
+
+        let (sender, receiver) = mpsc::channel();
+        // Register the request before the epoch is injected so that its commit is not missed
+        let epoch = self.app.snapshot_manager.on_definition(|cd| {
+            let next_epoch = epoch_manager.on_definition(|ecd| {
+                if stop {
+                    ecd.trigger_final_epoch()
+                } else {
+                    ecd.trigger_epoch()
+                }
+            });
+            cd.request_savepoint(next_epoch, sender);
+            next_epoch
+        });
+
+        let checkpoints_dir = self.app.conf.checkpoints_dir();
+        let timeout = Duration::from_millis(self.app.conf.savepoint_timeout);
+        let committed = match receiver.recv_timeout(timeout) {
+            Ok(committed) => committed,
+            Err(_) => {
+                self.app
+                    .snapshot_manager
+                    .on_definition(|cd| cd.cancel_savepoint(epoch));
+                // the epoch may have been committed and pinned right before the request was withdrawn
+                if let Ok(committed) = receiver.try_recv() {
+                    SnapshotCatalog::unpin(&checkpoints_dir, committed.epoch)?;
+                }
+                return Err(Error::SavepointTimeout { timeout });
+            }
+        };
+
+        let result = write_savepoint(&checkpoints_dir, path, committed.clone());
+        SnapshotCatalog::unpin(&checkpoints_dir, committed.epoch)?;
+        result
+    }
+
+    /// Takes a savepoint of the application into `path` and shuts it down
+    ///
+    /// The sources stop consuming records right after the marker of the savepoint epoch, so
+    /// no record is processed beyond the savepoint. If `drain` is set, the sources instead stop
+    /// before the savepoint is taken and emit a final watermark, which fires every pending
+    /// event-time timer and window so that their output belongs to the savepoint. Event time has
+    /// ended in a drained savepoint, so records that a restored application consumes afterwards
+    /// are late for every event-time window.
+    ///
+    /// The application is only shut down once the savepoint has been written. Otherwise the
+    /// error is returned and the application keeps running with stopped sources, so that the
+    /// savepoint may be retried before the application is shut down.
+    pub fn stop_with_savepoint(
+        &mut self,
+        path: impl AsRef<Path>,
+        drain: bool,
+    ) -> ArconResult<CommittedEpoch> {
+        if drain {
+            for source_manager in &self.app.source_managers {
+                source_manager.actor_ref().tell(SourceEvent::Drain);
+            }
+        }
+        let savepoint = self.take_savepoint(path.as_ref(), !drain)?;
+        let _ = self.app.data_system.clone().shutdown();
+        let _ = self.app.ctrl_system.clone().shutdown();
+        Ok(savepoint)
+    }
+
     /// Awaits termination from the application
     ///
     /// Note that this blocks the current thread
@@ -81,3 +189,23 @@ impl AssembledApplication {
         let _ = self.app.ctrl_system.shutdown();
     }
 }
+
+/// Copies the checkpoints of `committed` into `path` and describes them in a catalog
+fn write_savepoint(
+    checkpoints_dir: &Path,
+    path: &Path,
+    mut committed: CommittedEpoch,
+) -> ArconResult<CommittedEpoch> {
+    for state in committed.snapshots.iter_mut() {
+        let name = checkpoint_name(&state.state_id, committed.epoch);
+        let target = path.join(&name);
+        copy_dir(&checkpoints_dir.join(&name), &target)?;
+        state.snapshot.snapshot_path = target.to_string_lossy().into_owned();
+    }
+
+    let catalog = SnapshotCatalog {
+        epochs: vec![committed.clone()],
+    };
+    catalog.write(path)?;
+    Ok(committed)
+}
@@ -3,6 +3,7 @@
 
 pub mod logger;
 
+use crate::data::flight_serde::FlightSerde;
 use hocon::HoconLoader;
 use kompact::{
     net::buffers::BufferConfig,
@@ -10,7 +11,10 @@ use kompact::{
 };
 use logger::{file_logger, term_logger, ArconLogger, LoggerType};
 use serde::Deserialize;
-use std::path::{Path, PathBuf};
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
 
 /// Types of modes that `arcon` may run in
 #[derive(Deserialize, Clone, Debug)]
@@ -18,9 +22,40 @@ pub enum ExecutionMode {
     Local,
     Distributed(DistributedConf),
 }
+/// Configuration of an Application that is deployed across several processes
+///
+/// Every process builds the same dataflow graph with its own `peer` index and
+/// runs the instances of each Operator and Source that are placed on it.
 #[derive(Deserialize, Clone, Debug)]
 pub struct DistributedConf {
-    peers: Vec<String>, // ["192.168.1.1:2000",  "192.168.1.2:2000"]
+    /// Network addresses of the data systems of all processes
+    pub peers: Vec<String>, // ["192.168.1.1:2000",  "192.168.1.2:2000"]
+    /// Index of this process in `peers`
+    pub peer: usize,
+    /// Serialiser for the elements that are sent between processes
+    #[serde(default)]
+    pub flight_serde: FlightSerde,
+}
+
+impl DistributedConf {
+    /// Returns the index of the peer that runs the instance with index `instance`
+    ///
+    /// The instances of an Operator are spread round-robin over the peers.
+    pub fn placement(&self, instance: usize) -> usize {
+        instance % self.peers.len()
+    }
+
+    /// Returns true if the instance with index `instance` runs on this process
+    pub fn is_local(&self, instance: usize) -> bool {
+        self.placement(instance) == self.peer
+    }
+
+    /// Returns the network address of the peer with index `peer`
+    pub(crate) fn peer_addr(&self, peer: usize) -> SocketAddr {
+        self.peers[peer]
+            .parse()
+            .unwrap_or_else(|_| panic!("Invalid peer address {}", self.peers[peer]))
+    }
 }
 
 /// Configuration for an Arcon Application
@@ -44,14 +79,64 @@ pub struct ApplicationConf {
     /// Generation interval in milliseconds for Watermarks at sources
     #[serde(default = "watermark_interval_default")]
     pub watermark_interval: u64,
+    /// Interval in milliseconds at which nodes advance their processing-time timers
+    #[serde(default = "processing_timer_interval_default")]
+    pub processing_timer_interval: u64,
     /// The highest possible key value for a keyed stream
     ///
+    /// Keys are mapped onto `max_key` key groups, which is the unit that keyed state
+    /// is redistributed in when the parallelism of an Operator changes. It therefore also
+    /// bounds the parallelism of keyed Operators.
     /// This should not be set too low or ridiculously high
     #[serde(default = "max_key_default")]
     pub max_key: u64,
+    /// Number of latest committed epochs whose checkpoints are kept
+    ///
+    /// Checkpoints of older epochs are removed once a new epoch has been committed,
+    /// unless they have been pinned through [SnapshotCatalog::pin](crate::prelude::SnapshotCatalog::pin).
+    #[serde(default = "checkpoint_retention_default")]
+    pub checkpoint_retention: usize,
+    /// Age in milliseconds up to which checkpoints of committed epochs are kept
+    ///
+    /// Extends the retention of [checkpoint_retention](ApplicationConf::checkpoint_retention)
+    /// to every epoch that was committed within the given age.
+    #[serde(default)]
+    pub checkpoint_retention_age: Option<u64>,
+    /// Time in milliseconds to wait for the epoch of a savepoint to be committed
+    #[serde(default = "savepoint_timeout_default")]
+    pub savepoint_timeout: u64,
+    /// Take unaligned checkpoints instead of aligning the epochs of all input channels
+    ///
+    /// Nodes with state forward an epoch as soon as it arrives on any channel, and the
+    /// messages that are in flight on the other channels become part of the checkpoint.
+    /// Operators without state keep aligning their epochs.
+    #[serde(default)]
+    pub unaligned_checkpoints: bool,
+    /// Number of threads that write checkpoints in the background
+    ///
+    /// Operators only capture their state on the data path, the writing of checkpoints
+    /// happens on a separate thread pool of this size. How cheap the capture is depends on the
+    /// backend: RocksDB hard links its files, while Sled copies its whole state into memory.
+    /// Faster captures nothing and writes its checkpoint on the data path.
+    #[serde(default = "checkpoint_threads_default")]
+    pub checkpoint_threads: usize,
     /// Interval in milliseconds for sending off metrics from nodes
     #[serde(default = "node_metrics_interval_default")]
     pub node_metrics_interval: u64,
+    /// Number of instances deployed for an Operator with a managed parallelism
+    ///
+    /// This is the upper bound the runtime may scale the Operator to.
+    #[serde(default = "max_managed_parallelism_default")]
+    pub max_managed_parallelism: usize,
+    /// Interval in milliseconds at which managed Operators reconsider their parallelism
+    #[serde(default = "scaling_interval_default")]
+    pub scaling_interval: u64,
+    /// Inbound events per second per instance above which a managed Operator is scaled up
+    #[serde(default = "scale_up_throughput_default")]
+    pub scale_up_throughput: f64,
+    /// Inbound events per second per instance below which a managed Operator is scaled down
+    #[serde(default = "scale_down_throughput_default")]
+    pub scale_down_throughput: f64,
     /// Amount of buffers pre-allocated to a BufferPool
     #[serde(default = "buffer_pool_size_default")]
     pub buffer_pool_size: usize,
@@ -61,6 +146,18 @@ pub struct ApplicationConf {
     /// Batch size for channels
     #[serde(default = "channel_batch_size_default")]
     pub channel_batch_size: usize,
+    /// Amount of messages that may be in flight on a channel
+    ///
+    /// A sender that runs out of credits on a channel holds back its messages and stops taking
+    /// on input until the receiver has handled some of them. Zero disables the flow control.
+    #[serde(default = "channel_credits_default")]
+    pub channel_credits: usize,
+    /// Fuse stateless Operators into the Nodes of their upstream Operator
+    ///
+    /// Chained Operators skip the channels between them. Disable to give each Operator
+    /// Nodes of its own, e.g., when debugging.
+    #[serde(default = "operator_chaining_default")]
+    pub operator_chaining: bool,
     /// Max amount of bytes allowed to be allocated by the Arcon Allocator
     #[serde(default = "allocator_capacity_default")]
     pub allocator_capacity: usize,
@@ -77,7 +174,8 @@ pub struct ApplicationConf {
     pub kompact_msg_priority: f32,
     /// Host address for the KompactSystem
     ///
-    /// It is set as optional as it is not necessary for local deployments
+    /// It is set as optional as it is not necessary for local deployments.
+    /// In a distributed deployment it defaults to the address of this process in the peers.
     #[serde(default = "kompact_network_host_default")]
     pub kompact_network_host: Option<String>,
     #[serde(default = "kompact_chunk_size_default")]
@@ -98,11 +196,23 @@ impl Default for ApplicationConf {
             kompact_logger_type: Default::default(),
             watermark_interval: watermark_interval_default(),
             epoch_interval: epoch_interval_default(),
+            processing_timer_interval: processing_timer_interval_default(),
             max_key: max_key_default(),
+            checkpoint_retention: checkpoint_retention_default(),
+            checkpoint_retention_age: None,
+            savepoint_timeout: savepoint_timeout_default(),
+            unaligned_checkpoints: false,
+            checkpoint_threads: checkpoint_threads_default(),
             node_metrics_interval: node_metrics_interval_default(),
+            max_managed_parallelism: max_managed_parallelism_default(),
+            scaling_interval: scaling_interval_default(),
+            scale_up_throughput: scale_up_throughput_default(),
+            scale_down_throughput: scale_down_throughput_default(),
             buffer_pool_size: buffer_pool_size_default(),
             buffer_pool_limit: buffer_pool_limit_default(),
             channel_batch_size: channel_batch_size_default(),
+            channel_credits: channel_credits_default(),
+            operator_chaining: operator_chaining_default(),
             allocator_capacity: allocator_capacity_default(),
             ctrl_system_host: ctrl_system_host_default(),
             kompact_threads: kompact_threads_default(),
@@ -218,7 +328,7 @@ impl ApplicationConf {
         );
 
         // Set up Kompact network only if we are gonna use it..
-        if let Some(host) = &self.kompact_network_host {
+        if let Some(host) = self.network_host() {
             let mut buffer_config = BufferConfig::default();
 
             buffer_config.chunk_size(self.kompact_chunk_size);
@@ -236,6 +346,23 @@ impl ApplicationConf {
         cfg
     }
 
+    /// Returns the address the data system listens on for remote messages
+    fn network_host(&self) -> Option<String> {
+        match (&self.kompact_network_host, &self.execution_mode) {
+            (Some(host), _) => Some(host.clone()),
+            (None, ExecutionMode::Distributed(conf)) => Some(conf.peers[conf.peer].clone()),
+            (None, ExecutionMode::Local) => None,
+        }
+    }
+
+    /// Returns the [DistributedConf] if the Application is deployed across several processes
+    pub fn distributed(&self) -> Option<&DistributedConf> {
+        match &self.execution_mode {
+            ExecutionMode::Local => None,
+            ExecutionMode::Distributed(conf) => Some(conf),
+        }
+    }
+
     /// Loads ApplicationConf from a file
     pub fn from_file(path: impl AsRef<Path>) -> ApplicationConf {
         let data = std::fs::read_to_string(path).unwrap();
@@ -271,15 +398,52 @@ fn watermark_interval_default() -> u64 {
     250
 }
 
+pub(crate) fn processing_timer_interval_default() -> u64 {
+    // in milliseconds
+    100
+}
+
 fn max_key_default() -> u64 {
     1024
 }
 
+fn checkpoint_retention_default() -> usize {
+    3
+}
+
+fn savepoint_timeout_default() -> u64 {
+    // in milliseconds
+    60000
+}
+
+fn checkpoint_threads_default() -> usize {
+    2
+}
+
 fn node_metrics_interval_default() -> u64 {
     // in milliseconds
     250
 }
 
+fn max_managed_parallelism_default() -> usize {
+    num_cpus::get()
+}
+
+fn scaling_interval_default() -> u64 {
+    // in milliseconds
+    10000
+}
+
+fn scale_up_throughput_default() -> f64 {
+    // events per second
+    100_000.0
+}
+
+fn scale_down_throughput_default() -> f64 {
+    // events per second
+    10_000.0
+}
+
 fn buffer_pool_size_default() -> usize {
     1024
 }
@@ -292,6 +456,15 @@ fn channel_batch_size_default() -> usize {
     248
 }
 
+fn channel_credits_default() -> usize {
+    // must stay well below the buffer_pool_size
+    64
+}
+
+fn operator_chaining_default() -> bool {
+    true
+}
+
 fn allocator_capacity_default() -> usize {
     // 5 GB
     5368709120
@@ -356,10 +529,32 @@ mod tests {
         // Check defaults
         assert_eq!(conf.node_metrics_interval, node_metrics_interval_default());
         assert_eq!(conf.channel_batch_size, channel_batch_size_default());
+        assert_eq!(conf.channel_credits, channel_credits_default());
+        assert_eq!(conf.operator_chaining, operator_chaining_default());
         assert_eq!(conf.buffer_pool_size, buffer_pool_size_default());
         assert_eq!(conf.allocator_capacity, allocator_capacity_default());
         assert_eq!(conf.kompact_threads, kompact_threads_default());
         assert_eq!(conf.kompact_throughput, kompact_throughput_default());
         assert_eq!(conf.kompact_network_host, kompact_network_host_default());
     }
+
+    #[test]
+    fn distributed_conf_test() {
+        let mut file = NamedTempFile::new().unwrap();
+        let file_path = file.path().to_string_lossy().into_owned();
+        let config_str = r#"{
+            execution_mode: {
+                Distributed: { peers: ["127.0.0.1:2000", "127.0.0.1:2001"], peer: 1 }
+            }
+        }"#;
+        file.write_all(config_str.as_bytes()).unwrap();
+
+        let conf: ApplicationConf = ApplicationConf::from_file(&file_path);
+        let distributed = conf.distributed().expect("expected a DistributedConf");
+        assert_eq!(distributed.peers.len(), 2);
+        assert!(!distributed.is_local(0));
+        assert!(distributed.is_local(1));
+        assert_eq!(distributed.placement(2), 0);
+        assert_eq!(conf.network_host(), Some("127.0.0.1:2001".to_string()));
+    }
 }
@@ -9,7 +9,7 @@ use crate::stream::source::{
     schema::SourceSchema,
 };
 use crate::{
-    application::conf::{logger::ArconLogger, ApplicationConf, ExecutionMode},
+    application::conf::{logger::ArconLogger, ApplicationConf},
     buffer::event::PoolInfo,
     data::ArconMessage,
     dataflow::{
@@ -19,8 +19,13 @@ use crate::{
         dfg::*,
         stream::Context,
     },
+    error::{ArconResult, Error},
+    index::EMPTY_STATE_ID,
     manager::{
-        epoch::{EpochEvent, EpochManager},
+        checkpoint::CheckpointPool,
+        epoch::{EpochEvent, EpochManager, EPOCH_MANAGER_ALIAS},
+        node::CheckpointCompleted,
+        retention::RetentionPolicy,
         snapshot::SnapshotManager,
     },
     prelude::*,
@@ -31,13 +36,16 @@ use crate::{
 };
 use arcon_allocator::Allocator;
 use kompact::{component::AbstractComponent, prelude::KompactSystem};
+use restore::RestoredSnapshot;
 use std::sync::{Arc, Mutex};
 
 mod assembled;
 pub mod conf;
+pub(crate) mod restore;
 
-pub use crate::dataflow::stream::Stream;
+pub use crate::dataflow::stream::{ConnectedStreams, KeyedStream, RebalancedStream, Stream};
 pub use assembled::AssembledApplication;
+pub use restore::RestorePoint;
 
 #[cfg(all(feature = "prometheus_exporter", feature = "metrics", not(test)))]
 use metrics_exporter_prometheus::PrometheusBuilder;
@@ -78,10 +86,12 @@ pub struct Application {
     pub(crate) conf: ApplicationConf,
     /// Arcon allocator for this application
     pub(crate) allocator: Arc<Mutex<Allocator>>,
-    /// SourceManager component for this application
-    pub(crate) source_manager: Option<Arc<dyn AbstractComponent<Message = SourceEvent>>>,
+    /// SourceManager components for this application
+    pub(crate) source_managers: Vec<Arc<dyn AbstractComponent<Message = SourceEvent>>>,
+    /// NodeManager components of the Operators that run Nodes on this process
+    pub(crate) node_managers: Vec<Arc<dyn AbstractComponent<Message = CheckpointCompleted>>>,
     /// EpochManager component for this application
-    pub(crate) epoch_manager: Option<Arc<Component<EpochManager>>>,
+    pub(crate) epoch_manager: Arc<Component<EpochManager>>,
     /// SnapshotManager component for this application
     pub(crate) snapshot_manager: Arc<Component<SnapshotManager>>,
     /// Flag indicating whether to spawn a debug node for the Application
@@ -92,6 +102,10 @@ pub struct Application {
     pub(crate) abstract_debug_node: Option<ErasedComponent>,
     /// Configured Logger for the Application
     pub(crate) arcon_logger: ArconLogger,
+    /// Snapshot that the Application is restored from
+    pub(crate) restored: Option<RestoredSnapshot>,
+    /// Thread pool that Operators write their checkpoints on
+    pub(crate) checkpoint_pool: CheckpointPool,
 }
 
 impl Default for Application {
@@ -129,6 +143,7 @@ impl Application {
 
         let (ctrl_system, data_system, snapshot_manager, epoch_manager) =
             Self::setup(&conf, &arcon_logger);
+        let checkpoint_pool = CheckpointPool::new(conf.checkpoint_threads);
 
         Self {
             ctrl_system,
@@ -137,11 +152,14 @@ impl Application {
             allocator,
             snapshot_manager,
             epoch_manager,
-            source_manager: None,
+            source_managers: Vec::new(),
+            node_managers: Vec::new(),
             debug_node_flag: false,
             debug_node: None,
             abstract_debug_node: None,
             arcon_logger,
+            restored: None,
+            checkpoint_pool,
         }
     }
 
@@ -159,7 +177,7 @@ impl Application {
         KompactSystem,
         KompactSystem,
         Arc<Component<SnapshotManager>>,
-        Option<Arc<Component<EpochManager>>>,
+        Arc<Component<EpochManager>>,
     ) {
         let data_system = arcon_conf
             .data_system_conf()
@@ -172,26 +190,50 @@ impl Application {
 
         let timeout = std::time::Duration::from_millis(500);
 
-        let snapshot_manager = ctrl_system.create(SnapshotManager::new);
-
-        let epoch_manager = match arcon_conf.execution_mode {
-            ExecutionMode::Local => {
-                let snapshot_manager_ref = snapshot_manager.actor_ref().hold().expect("fail");
-                let epoch_manager = ctrl_system.create(|| {
-                    EpochManager::new(
-                        arcon_conf.epoch_interval,
-                        snapshot_manager_ref,
-                        logger.clone(),
-                    )
-                });
+        let retention_policy = RetentionPolicy::from_conf(&arcon_conf);
+        let snapshot_manager = ctrl_system.create(|| SnapshotManager::new(retention_policy));
+
+        // In a distributed deployment, every process injects the epochs of its own sources,
+        // while the epoch markers flow across the process boundaries. The EpochManager of the
+        // first peer commits an epoch once the EpochManagers of all peers have acked it, which
+        // is why the EpochManagers run on the networked data system.
+        let snapshot_manager_ref = snapshot_manager.actor_ref().hold().expect("fail");
+        let epoch_manager = EpochManager::new(
+            arcon_conf.epoch_interval,
+            snapshot_manager_ref,
+            logger.clone(),
+        );
+        let epoch_manager = match arcon_conf.distributed() {
+            None => {
+                let epoch_manager = ctrl_system.create(|| epoch_manager);
                 ctrl_system
                     .start_notify(&epoch_manager)
                     .wait_timeout(timeout)
                     .expect("EpochManager comp never started!");
-
-                Some(epoch_manager)
+                epoch_manager
+            }
+            Some(conf) => {
+                let paths = (0..conf.peers.len())
+                    .map(|peer| {
+                        NamedPath::with_socket(
+                            Transport::Tcp,
+                            conf.peer_addr(peer),
+                            vec![EPOCH_MANAGER_ALIAS.to_string()],
+                        )
+                        .into()
+                    })
+                    .collect();
+                let epoch_manager =
+                    data_system.create(|| epoch_manager.with_peers(conf.peer, paths));
+                data_system
+                    .register_by_alias(&epoch_manager, EPOCH_MANAGER_ALIAS)
+                    .wait_expect(timeout, "Failed to register alias of EpochManager");
+                data_system
+                    .start_notify(&epoch_manager)
+                    .wait_timeout(timeout)
+                    .expect("EpochManager comp never started!");
+                epoch_manager
             }
-            ExecutionMode::Distributed(_) => None,
         };
 
         ctrl_system
@@ -229,19 +271,23 @@ impl Application {
     {
         let parallelism = builder_type.parallelism();
 
-        let mut state_dir = self.arcon_conf().state_dir();
-        state_dir.push("source_manager");
-        let backend = Arc::new(B::create(&state_dir, String::from("source_manager")).unwrap());
+        // An application may have several sources, so scope the manager by the source name
+        let descriptor = format!("source_manager_{}", builder_type.name());
+        let backend = self
+            .create_backend::<B>(&descriptor, parallelism)
+            .unwrap_or_else(|error| {
+                panic!("Failed to create backend of {}: {}", descriptor, error)
+            });
         let time = builder_type.time();
         let manager_constructor = source_manager_constructor::<S, B>(
-            String::from("source_manager"),
+            descriptor,
             builder_type,
             backend,
             self.arcon_conf().watermark_interval,
             time,
         );
         let mut ctx = Context::new(self);
-        let kind = DFGNodeKind::Source(Default::default(), manager_constructor);
+        let kind = DFGNodeKind::Source(manager_constructor);
         let incoming_channels = 0; // sources have 0 incoming channels..
         let outgoing_channels = parallelism;
         let dfg_node = DFGNode::new(kind, outgoing_channels, incoming_channels, vec![]);
@@ -357,6 +403,70 @@ impl Application {
         self.parallel_source(builder)
     }
 
+    /// Restore the Application from a checkpoint
+    ///
+    /// Finds the latest epoch that every operator and source has completed a checkpoint for,
+    /// and continues from it: operator backends (including their timers) are restored and
+    /// sources resume from the offsets they had reached at that epoch. Nodes replay the
+    /// channel state of [unaligned checkpoints](ApplicationConf::unaligned_checkpoints).
+    /// Restoring with [RestorePoint::Latest] starts the Application from scratch if no
+    /// checkpoint has been completed yet.
+    ///
+    /// Operators and sources are matched with their checkpoints by StateID and source name,
+    /// so sources should be named through [SourceConf::set_source_name] and the pipeline
+    /// must be defined after this call. Defining a stateful Operator or source that has no
+    /// complete checkpoint in the restored epoch panics.
+    ///
+    /// An Operator may be restored at another parallelism than the one it was checkpointed at.
+    /// If the key groups of its local instances differ from the ones held by the checkpoint,
+    /// they are redistributed into its backend (see [Region::export](crate::data::partition::Region::export)).
+    ///
+    /// Example
+    /// ```no_run
+    /// use arcon::prelude::*;
+    /// let stream: Stream<u64> = Application::default()
+    ///     .restore_from(RestorePoint::Latest)
+    ///     .unwrap()
+    ///     .iterator(0u64..100, |conf| {
+    ///         conf.set_source_name("numbers".to_string());
+    ///         conf.set_arcon_time(ArconTime::Process);
+    ///     });
+    /// ```
+    pub fn restore_from(mut self, point: RestorePoint) -> ArconResult<Self> {
+        let snapshot = match &point {
+            RestorePoint::Latest => RestoredSnapshot::find(&self.conf.checkpoints_dir())?,
+            RestorePoint::Dir(dir) => match RestoredSnapshot::find(dir)? {
+                Some(snapshot) => Some(snapshot),
+                None => {
+                    return Err(Error::Restore {
+                        msg: format!("no complete checkpoint found in {:?}", dir),
+                    })
+                }
+            },
+        };
+
+        match &snapshot {
+            Some(snapshot) => {
+                info!(
+                    self.arcon_logger,
+                    "Restoring Application from epoch {}", snapshot.epoch
+                );
+                self.epoch_manager.on_definition(|cd| {
+                    cd.set_next_epoch(snapshot.next_epoch());
+                });
+            }
+            None => {
+                info!(
+                    self.arcon_logger,
+                    "No complete checkpoint found, starting Application from scratch"
+                );
+            }
+        }
+
+        self.restored = snapshot;
+        Ok(self)
+    }
+
     /// Enable DebugNode for the Application
     ///
     ///
@@ -366,6 +476,46 @@ impl Application {
         self
     }
 
+    // Internal helper that creates the Backend of `state_id`, or restores it if it is part of the restored snapshot
+    //
+    // `instances` is the parallelism of the Operator, which decides the key groups that the backend holds.
+    pub(crate) fn create_backend<B: Backend>(
+        &self,
+        state_id: &str,
+        instances: usize,
+    ) -> ArconResult<Arc<B>> {
+        let mut state_dir = self.conf.state_dir();
+        state_dir.push(state_id);
+
+        let backend = match &self.restored {
+            Some(snapshot) => match snapshot.checkpoint(state_id) {
+                Some(checkpoint) => restore::restore_backend(
+                    state_id,
+                    &state_dir,
+                    checkpoint,
+                    snapshot.key_groups(state_id),
+                    instances,
+                    self.conf.max_key,
+                    |node| self.is_local(node.id as usize),
+                )?,
+                // empty states carry nothing to restore
+                None if state_id.starts_with(EMPTY_STATE_ID) => {
+                    B::create(&state_dir, state_id.to_string())?
+                }
+                None => {
+                    return Err(Error::Restore {
+                        msg: format!(
+                            "{} has no complete checkpoint at epoch {}",
+                            state_id, snapshot.epoch
+                        ),
+                    })
+                }
+            },
+            None => B::create(&state_dir, state_id.to_string())?,
+        };
+        Ok(Arc::new(backend))
+    }
+
     // Internal helper for creating PoolInfo for a ChannelStrategy
     pub(crate) fn get_pool_info(&self) -> PoolInfo {
         PoolInfo::new(
@@ -395,22 +545,34 @@ impl Application {
         &self.conf
     }
 
+    /// Returns true if the instance with index `instance` of an Operator or Source runs on this process
+    pub(crate) fn is_local(&self, instance: usize) -> bool {
+        self.conf
+            .distributed()
+            .map_or(true, |conf| conf.is_local(instance))
+    }
+
     pub(crate) fn epoch_manager(&self) -> ActorRefStrong<EpochEvent> {
-        if let Some(epoch_manager) = &self.epoch_manager {
-            epoch_manager
-                .actor_ref()
-                .hold()
-                .expect("Failed to fetch actor ref")
-        } else {
-            panic!(
-                "Only local reference supported for now. should really be an ActorPath later on"
-            );
-        }
+        self.epoch_manager
+            .actor_ref()
+            .hold()
+            .expect("Failed to fetch actor ref")
     }
     pub fn debug_node_enabled(&self) -> bool {
         self.debug_node_flag
     }
 
+    /// Merges the Stream settings of `other`, another handle to this Application, into this one
+    ///
+    /// Panics if `other` is a different Application, as its components would never be started.
+    pub(crate) fn merge(&mut self, other: Application) {
+        assert!(
+            Arc::ptr_eq(&self.epoch_manager, &other.epoch_manager),
+            "Streams of different Applications cannot be combined"
+        );
+        self.debug_node_flag |= other.debug_node_flag;
+    }
+
     // internal helper to create a DebugNode from a Stream object
     pub(crate) fn create_debug_node<A>(&mut self, node: DebugNode<A>)
     where
@@ -0,0 +1,437 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{
+    data::{partition::Region, Epoch, NodeID, StateID},
+    error::{ArconResult, Error},
+    index::EMPTY_STATE_ID,
+    manager::snapshot::SnapshotCatalog,
+};
+use arcon_state::{key_groups::KeyGroupRange, Backend};
+use fxhash::FxHashMap;
+use std::{
+    collections::BTreeSet,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const CHECKPOINT_PREFIX: &str = "checkpoint_";
+/// File that marks a checkpoint directory as completely written
+const COMPLETE_MARKER: &str = "CHECKPOINT_COMPLETE";
+
+/// Defines which checkpoint an [Application](super::Application) is restored from
+#[derive(Debug, Clone)]
+pub enum RestorePoint {
+    /// The latest complete checkpoint in the checkpoint directory of the [ApplicationConf](super::conf::ApplicationConf)
+    Latest,
+    /// The latest complete checkpoint in the given checkpoint directory
+    Dir(PathBuf),
+}
+
+/// A set of checkpoints that together form a consistent snapshot of an Application
+#[derive(Debug, Clone)]
+pub(crate) struct RestoredSnapshot {
+    /// The epoch the checkpoints were taken at
+    pub(crate) epoch: u64,
+    /// Checkpoint directory per StateID
+    checkpoints: FxHashMap<StateID, PathBuf>,
+    /// Key groups held by the checkpoint per StateID, if they were recorded
+    key_groups: FxHashMap<StateID, Vec<KeyGroupRange>>,
+}
+
+impl RestoredSnapshot {
+    /// Finds the latest committed epoch of the checkpoint directory `dir`
+    ///
+    /// The [SnapshotCatalog] of the directory is authoritative. Directories without a catalog
+    /// are scanned for the latest epoch that every StateID has a checkpoint for.
+    pub(crate) fn find(dir: &Path) -> ArconResult<Option<Self>> {
+        match SnapshotCatalog::read(dir)? {
+            Some(catalog) => Ok(catalog.latest().map(|committed| {
+                let checkpoints = committed
+                    .state_ids()
+                    .map(|state_id| {
+                        let mut path = dir.to_path_buf();
+                        path.push(checkpoint_name(state_id, committed.epoch));
+                        (state_id.to_string(), path)
+                    })
+                    .collect();
+                let key_groups = committed
+                    .snapshots
+                    .iter()
+                    .filter(|s| !s.snapshot.key_groups.is_empty())
+                    .map(|s| (s.state_id.clone(), s.snapshot.key_groups.clone()))
+                    .collect();
+                RestoredSnapshot {
+                    epoch: committed.epoch,
+                    checkpoints,
+                    key_groups,
+                }
+            })),
+            None => Self::scan(dir),
+        }
+    }
+
+    /// Finds the latest epoch that every StateID in `dir` has a complete checkpoint for
+    ///
+    /// Checkpoints of empty states are not taken into account as they carry no state,
+    /// and their StateIDs are not stable between runs. Whether every StateID of the
+    /// pipeline is part of the snapshot is validated once its backend is restored.
+    fn scan(dir: &Path) -> ArconResult<Option<Self>> {
+        let mut epochs: FxHashMap<StateID, BTreeSet<u64>> = FxHashMap::default();
+        if dir.exists() {
+            for entry in fs::read_dir(dir)? {
+                let entry = entry?;
+                if !entry.file_type()?.is_dir() || !is_complete(&entry.path()) {
+                    continue;
+                }
+                let name = entry.file_name();
+                if let Some((state_id, epoch)) = name.to_str().and_then(parse_checkpoint_name) {
+                    if !state_id.starts_with(EMPTY_STATE_ID) {
+                        epochs
+                            .entry(state_id.to_string())
+                            .or_insert_with(BTreeSet::new)
+                            .insert(epoch);
+                    }
+                }
+            }
+        }
+
+        // complete epochs are the ones that every StateID has a checkpoint for
+        let mut sets = epochs.values();
+        let complete = match sets.next() {
+            Some(first) => sets.fold(first.clone(), |complete, other| {
+                complete.intersection(other).copied().collect()
+            }),
+            None => return Ok(None),
+        };
+
+        Ok(complete.iter().last().map(|epoch| {
+            let checkpoints = epochs
+                .keys()
+                .map(|state_id| {
+                    let mut path = dir.to_path_buf();
+                    path.push(checkpoint_name(state_id, *epoch));
+                    (state_id.clone(), path)
+                })
+                .collect();
+            RestoredSnapshot {
+                epoch: *epoch,
+                checkpoints,
+                key_groups: FxHashMap::default(),
+            }
+        }))
+    }
+
+    /// Returns the checkpoint of `state_id`, if it is part of the snapshot
+    pub(crate) fn checkpoint(&self, state_id: &str) -> Option<&PathBuf> {
+        self.checkpoints.get(state_id)
+    }
+
+    /// Returns the key groups held by the checkpoint of `state_id`, if they were recorded
+    pub(crate) fn key_groups(&self, state_id: &str) -> Option<&[KeyGroupRange]> {
+        self.key_groups.get(state_id).map(Vec::as_slice)
+    }
+
+    /// Returns the epoch that the restored Application continues from
+    pub(crate) fn next_epoch(&self) -> Epoch {
+        Epoch::new(self.epoch + 1)
+    }
+}
+
+/// Restores the backend of `state_id` into `state_dir` for the instances that `local` selects
+///
+/// A backend is shared by the local instances of an Operator, so a checkpoint that holds
+/// exactly their key groups is restored as is. Otherwise, the checkpoint is restored next
+/// to `state_dir` and the key groups of the local instances are streamed out of it and
+/// into a new backend. Only state of handles with a `u64` item key is part of a key group,
+/// so redistributing a checkpoint that holds state of other handles fails with an error
+/// naming them rather than dropping their state.
+pub(crate) fn restore_backend<B: Backend>(
+    state_id: &str,
+    state_dir: &Path,
+    checkpoint: &Path,
+    held: Option<&[KeyGroupRange]>,
+    instances: usize,
+    max_key: u64,
+    local: impl Fn(NodeID) -> bool,
+) -> ArconResult<B> {
+    // live state of a previous run is superseded by the checkpoint
+    if state_dir.exists() {
+        fs::remove_dir_all(state_dir)?;
+    }
+
+    let held = match held {
+        Some(held) => held,
+        None => return Ok(B::restore(state_dir, checkpoint, state_id.to_string())?),
+    };
+    let region = Region::new(instances, max_key as usize);
+    let needed: Vec<KeyGroupRange> = region
+        .ranges()
+        .iter()
+        .filter(|(node, _)| local(*node))
+        .filter_map(|(node, _)| region.key_groups(*node))
+        .collect();
+    let (held_groups, needed_groups) = (groups(held), groups(&needed));
+    if held_groups == needed_groups {
+        return Ok(B::restore(state_dir, checkpoint, state_id.to_string())?);
+    }
+    if let Some(group) = needed_groups.difference(&held_groups).next() {
+        return Err(Error::Unsupported {
+            msg: format!(
+                "key group {} of {} is not held by checkpoint {:?}",
+                group, state_id, checkpoint
+            ),
+        });
+    }
+
+    let staging_dir = sibling(state_dir, "restore");
+    let exports_dir = sibling(state_dir, "key_groups");
+    for dir in &[&staging_dir, &exports_dir] {
+        if dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+    }
+
+    let staging = B::restore(&staging_dir, checkpoint, state_id.to_string())?;
+    let mut unkeyed = staging.unkeyed_handles()?;
+    if !unkeyed.is_empty() {
+        drop(staging);
+        fs::remove_dir_all(&staging_dir)?;
+        unkeyed.sort();
+        return Err(Error::Unsupported {
+            msg: format!(
+                "key groups of {} cannot be redistributed as handles {} hold state without a u64 item key",
+                state_id,
+                unkeyed.join(", ")
+            ),
+        });
+    }
+    let exports = region.export_where(&staging, &exports_dir, local)?;
+    drop(staging);
+
+    let backend = B::create(state_dir, state_id.to_string())?;
+    for (_, path) in &exports {
+        backend.import_key_groups(path)?;
+    }
+
+    fs::remove_dir_all(&staging_dir)?;
+    fs::remove_dir_all(&exports_dir)?;
+    Ok(backend)
+}
+
+/// Returns the key groups that `ranges` cover
+fn groups(ranges: &[KeyGroupRange]) -> BTreeSet<(u64, u64)> {
+    ranges
+        .iter()
+        .flat_map(|range| (range.start..=range.end).map(move |group| (range.max_key, group)))
+        .collect()
+}
+
+/// Returns the path of `dir` with `.{suffix}` appended to its name
+fn sibling(dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(dir.as_os_str());
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Marks the checkpoint directory `dir` as completely written
+pub(crate) fn mark_complete(dir: &Path) -> ArconResult<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(COMPLETE_MARKER), b"")?;
+    Ok(())
+}
+
+/// Returns true if the checkpoint directory `dir` has been completely written
+pub(crate) fn is_complete(dir: &Path) -> bool {
+    dir.join(COMPLETE_MARKER).exists()
+}
+
+/// Returns the directory name of the checkpoint of `state_id` at `epoch`
+pub(crate) fn checkpoint_name(state_id: &str, epoch: u64) -> String {
+    format!("{}{}_{}", CHECKPOINT_PREFIX, state_id, epoch)
+}
+
+/// Returns the StateID that the channel state of an unaligned checkpoint of `state_id` is stored under
+pub(crate) fn channel_state_id(state_id: &str) -> StateID {
+    format!("{}_channels", state_id)
+}
+
+/// Splits a checkpoint directory name into its StateID and epoch
+///
+/// StateIDs may themselves contain underscores, so the epoch is everything after the last one.
+pub(crate) fn parse_checkpoint_name(name: &str) -> Option<(&str, u64)> {
+    let name = name.strip_prefix(CHECKPOINT_PREFIX)?;
+    let split = name.rfind('_')?;
+    let epoch = name[split + 1..].parse().ok()?;
+    Some((&name[..split], epoch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_checkpoint_name_test() {
+        assert_eq!(
+            parse_checkpoint_name("checkpoint_source_manager_numbers_12"),
+            Some(("source_manager_numbers", 12))
+        );
+        assert_eq!(parse_checkpoint_name("checkpoint_sums_x"), None);
+        assert_eq!(parse_checkpoint_name("savepoint_sums_1"), None);
+        assert_eq!(checkpoint_name("sums", 3), "checkpoint_sums_3");
+    }
+
+    #[test]
+    fn find_latest_complete_snapshot_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let create = |state_id: &str, epoch: u64| {
+            let mut path = dir.path().to_path_buf();
+            path.push(checkpoint_name(state_id, epoch));
+            mark_complete(&path).unwrap();
+        };
+
+        assert!(RestoredSnapshot::find(dir.path()).unwrap().is_none());
+
+        for epoch in 0..3 {
+            create("source_manager_numbers", epoch);
+            create("sums", epoch);
+        }
+        // the crash happened before `sums` completed epoch 3
+        create("source_manager_numbers", 3);
+        fs::create_dir_all(dir.path().join(checkpoint_name("sums", 3))).unwrap();
+        // empty states are not part of the snapshot
+        create(&format!("{}_{}", EMPTY_STATE_ID, "a1b2"), 1);
+
+        let snapshot = RestoredSnapshot::find(dir.path()).unwrap().unwrap();
+        assert_eq!(snapshot.epoch, 2);
+        assert_eq!(snapshot.next_epoch(), Epoch::new(3));
+        assert!(snapshot
+            .checkpoint("sums")
+            .unwrap()
+            .ends_with("checkpoint_sums_2"));
+        assert!(snapshot.checkpoint("map").is_none());
+    }
+
+    #[test]
+    fn find_snapshot_from_catalog_test() {
+        use crate::manager::snapshot::{CommittedEpoch, Snapshot, StateSnapshot};
+
+        let dir = tempfile::tempdir().unwrap();
+        // checkpoints of an epoch that never got committed
+        for state_id in &["source_manager_numbers", "sums"] {
+            let mut path = dir.path().to_path_buf();
+            path.push(checkpoint_name(state_id, 2));
+            fs::create_dir_all(path).unwrap();
+        }
+
+        let mut catalog = SnapshotCatalog::default();
+        catalog.epochs.push(CommittedEpoch {
+            epoch: 1,
+            committed_at: 0,
+            snapshots: vec![StateSnapshot {
+                state_id: "sums".to_string(),
+                snapshot: Snapshot::new("Sled".to_string(), 1, String::new()),
+            }],
+        });
+        catalog.write(dir.path()).unwrap();
+
+        let snapshot = RestoredSnapshot::find(dir.path()).unwrap().unwrap();
+        assert_eq!(snapshot.epoch, 1);
+        assert!(snapshot
+            .checkpoint("sums")
+            .unwrap()
+            .ends_with("checkpoint_sums_1"));
+    }
+
+    #[test]
+    fn redistribute_key_groups_test() {
+        use crate::dataflow::conf::DefaultBackend;
+        use arcon_state::{Handle, ValueState};
+        use std::sync::Arc;
+
+        let max_key = 8;
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoint = dir.path().join(checkpoint_name("sums", 1));
+        {
+            let backend =
+                Arc::new(DefaultBackend::create(&dir.path().join("live"), "sums".into()).unwrap());
+            let mut handle = Handle::value("keyed").with_item_key(0u64);
+            backend.register_value_handle(&mut handle);
+            let mut handle = handle.activate(backend.clone());
+            for key in 0..max_key {
+                handle.set_item_key(key);
+                handle.fast_set(key * 10).unwrap();
+            }
+            backend.checkpoint(&checkpoint).unwrap();
+        }
+
+        // the checkpoint was taken by a single instance that held every key group
+        let held = vec![KeyGroupRange::new(0, max_key - 1, max_key)];
+        let upper = |node: NodeID| node.id == 1;
+        let state_dir = dir.path().join("restored");
+        let backend: DefaultBackend = restore_backend(
+            "sums",
+            &state_dir,
+            &checkpoint,
+            Some(&held),
+            2,
+            max_key,
+            upper,
+        )
+        .unwrap();
+        let backend = Arc::new(backend);
+        let mut handle = Handle::<ValueState<u64>>::value("keyed").with_item_key(0u64);
+        backend.register_value_handle(&mut handle);
+        let mut handle = handle.activate(backend);
+        for key in 0..max_key {
+            handle.set_item_key(key);
+            let expected = if key >= max_key / 2 {
+                Some(key * 10)
+            } else {
+                None
+            };
+            assert_eq!(handle.get().unwrap(), expected);
+        }
+
+        // state without a u64 item key cannot be redistributed
+        let unkeyed_checkpoint = dir.path().join(checkpoint_name("counts", 1));
+        {
+            let backend = Arc::new(
+                DefaultBackend::create(&dir.path().join("live_counts"), "counts".into()).unwrap(),
+            );
+            let mut handle = Handle::value("global");
+            backend.register_value_handle(&mut handle);
+            let handle = handle.activate(backend.clone());
+            handle.fast_set(1u64).unwrap();
+            backend.checkpoint(&unkeyed_checkpoint).unwrap();
+        }
+        let unkeyed = restore_backend::<DefaultBackend>(
+            "counts",
+            &dir.path().join("restored_counts"),
+            &unkeyed_checkpoint,
+            Some(&held),
+            2,
+            max_key,
+            upper,
+        );
+        match unkeyed {
+            Err(Error::Unsupported { msg }) => assert!(msg.contains("global")),
+            _ => panic!("expected the unkeyed handle to be rejected"),
+        }
+
+        // key groups that the checkpoint does not hold cannot be restored
+        let lower_half = vec![KeyGroupRange::new(0, max_key / 2 - 1, max_key)];
+        let missing = restore_backend::<DefaultBackend>(
+            "sums",
+            &dir.path().join("missing"),
+            &checkpoint,
+            Some(&lower_half),
+            2,
+            max_key,
+            upper,
+        );
+        assert!(missing.is_err());
+    }
+}
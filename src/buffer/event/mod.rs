@@ -87,6 +87,12 @@ impl<T> EventBuffer<T> {
         self.free.store(true, Ordering::Relaxed);
     }
 
+    /// Returns true if the EventBuffer is not held by a writer or reader
+    #[inline]
+    pub fn is_free(&self) -> bool {
+        self.free.load(Ordering::Relaxed)
+    }
+
     /// Attempt to reserve the EventBuffer
     ///
     /// Should only be called from the writer
@@ -214,6 +220,12 @@ impl<T> BufferReader<T> {
         self.buffer.as_ptr()
     }
 
+    /// Returns the EventBuffer that is read
+    #[inline]
+    pub(crate) fn buffer(&self) -> &Arc<EventBuffer<T>> {
+        &self.buffer
+    }
+
     /// Return reader as slice
     #[inline]
     pub fn as_slice(&self) -> &[T] {
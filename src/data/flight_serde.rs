@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 
 /// An Enum holding possible serialisation/deserialisation options for in-flight data
-#[derive(Clone)]
+#[derive(serde::Deserialize, Clone, Debug)]
 pub enum FlightSerde {
     /// A rather slower option using Protobuf to serialise and deserialise.
     ///
@@ -347,13 +347,22 @@ mod test {
         let element = ArconElement::new(data);
 
         comp.on_definition(|cd| {
-            channel_strategy.push(ArconEvent::Element(element.clone()));
-            channel_strategy.push(ArconEvent::Element(element.clone()));
-            channel_strategy.push(ArconEvent::Element(element.clone()));
-            channel_strategy.push(ArconEvent::Element(element));
+            channel_strategy
+                .push(ArconEvent::Element(element.clone()))
+                .unwrap();
+            channel_strategy
+                .push(ArconEvent::Element(element.clone()))
+                .unwrap();
+            channel_strategy
+                .push(ArconEvent::Element(element.clone()))
+                .unwrap();
+            channel_strategy.push(ArconEvent::Element(element)).unwrap();
 
             // force a flush through a marker
-            for (channel, msg) in channel_strategy.push(ArconEvent::Watermark(Watermark::new(0))) {
+            for (channel, msg) in channel_strategy
+                .push(ArconEvent::Watermark(Watermark::new(0)))
+                .unwrap()
+            {
                 let _ = send(&channel, msg, cd);
             }
         });
@@ -8,9 +8,9 @@ pub mod flight_serde;
 #[allow(dead_code)]
 pub mod partition;
 /// Known Serialisation IDs for Arcon Types
-mod ser_id;
+pub(crate) mod ser_id;
 
-use crate::buffer::event::BufferReader;
+use crate::{buffer::event::BufferReader, stream::channel::flow_control::Credit};
 #[cfg(feature = "unsafe_flight")]
 use abomonation::Abomonation;
 #[cfg(feature = "unsafe_flight")]
@@ -133,6 +133,72 @@ impl<A: ArconType> From<ArconEvent<A>> for ArconEventWrapper<A> {
     }
 }
 
+/// An input of a two-input operator, originating from either the first or second stream
+#[cfg_attr(feature = "arcon_serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "unsafe_flight", derive(Abomonation))]
+#[derive(POneof, Clone)]
+#[cfg_attr(feature = "arcon_serde", serde(bound = "A: ArconType, B: ArconType"))]
+pub enum CoInput<A: ArconType, B: ArconType> {
+    /// Element of the first stream
+    #[prost(message, tag = "1")]
+    First(A),
+    /// Element of the second stream
+    #[prost(message, tag = "2")]
+    Second(B),
+}
+
+/// Wrapper around [CoInput] for the same reason as [ArconEventWrapper]
+///
+/// Used as the data type of the edges into a two-input operator.
+#[cfg_attr(feature = "arcon_serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "unsafe_flight", derive(Abomonation))]
+#[derive(PMessage, Clone)]
+#[cfg_attr(feature = "arcon_serde", serde(bound = "A: ArconType, B: ArconType"))]
+pub struct CoElement<A: ArconType, B: ArconType> {
+    #[prost(oneof = "CoInput::<A, B>", tags = "1, 2")]
+    inner: Option<CoInput<A, B>>,
+}
+
+impl<A: ArconType, B: ArconType> CoElement<A, B> {
+    /// Creates a CoElement from an element of the first stream
+    pub fn first(data: A) -> Self {
+        CoElement {
+            inner: Some(CoInput::First(data)),
+        }
+    }
+    /// Creates a CoElement from an element of the second stream
+    pub fn second(data: B) -> Self {
+        CoElement {
+            inner: Some(CoInput::Second(data)),
+        }
+    }
+    /// Returns a reference to the wrapped input
+    pub fn input(&self) -> &CoInput<A, B> {
+        self.inner
+            .as_ref()
+            .expect("CoElement.inner is None. Prost deserialization error?")
+    }
+    pub fn unwrap(self) -> CoInput<A, B> {
+        self.inner
+            .expect("CoElement.inner is None. Prost deserialization error?")
+    }
+}
+
+impl<A: ArconType, B: ArconType> ArconType for CoElement<A, B> {
+    #[cfg(feature = "unsafe_flight")]
+    const UNSAFE_SER_ID: SerId = ser_id::UNSAFE_CO_ELEMENT_ID;
+    const RELIABLE_SER_ID: SerId = ser_id::RELIABLE_CO_ELEMENT_ID;
+    const VERSION_ID: VersionId = 1;
+
+    fn get_key(&self) -> u64 {
+        match &self.inner {
+            Some(CoInput::First(data)) => data.get_key(),
+            Some(CoInput::Second(data)) => data.get_key(),
+            None => 0,
+        }
+    }
+}
+
 /// A Stream element containing some data and timestamp
 #[cfg_attr(feature = "arcon_serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "unsafe_flight", derive(Abomonation))]
@@ -233,6 +299,8 @@ pub struct ArconMessage<A: ArconType> {
     pub events: BufferReader<ArconEventWrapper<A>>,
     /// ID identifying where the message is sent from
     pub sender: NodeID,
+    /// Credit of the channel that the message consumed, granted back once the message is dropped
+    pub(crate) credit: Credit,
 }
 
 /// A raw ArconMessage for serialisation
@@ -268,6 +336,7 @@ impl<A: ArconType> ArconMessage<A> {
         ArconMessage {
             events: vec![ArconEvent::<A>::Watermark(Watermark { timestamp }).into()].into(),
             sender,
+            credit: Default::default(),
         }
     }
     /// Creates an ArconMessage with a single [ArconEvent::Epoch] event
@@ -277,6 +346,7 @@ impl<A: ArconType> ArconMessage<A> {
         ArconMessage {
             events: vec![ArconEvent::<A>::Epoch(Epoch { epoch }).into()].into(),
             sender,
+            credit: Default::default(),
         }
     }
     /// Creates an ArconMessage with a single [ArconEvent::Death] event
@@ -286,6 +356,7 @@ impl<A: ArconType> ArconMessage<A> {
         ArconMessage {
             events: vec![ArconEvent::<A>::Death(msg).into()].into(),
             sender,
+            credit: Default::default(),
         }
     }
     /// Creates an ArconMessage with a single [ArconEvent::Element] event
@@ -295,6 +366,7 @@ impl<A: ArconType> ArconMessage<A> {
         ArconMessage {
             events: vec![ArconEvent::Element(ArconElement { data, timestamp }).into()].into(),
             sender,
+            credit: Default::default(),
         }
     }
 }
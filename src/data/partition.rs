@@ -1,5 +1,139 @@
-use super::NodeID;
+use super::{ArconType, NodeID};
+use crate::{dataflow::conf::KeyExtractor, error::ArconResult};
+use arcon_state::{
+    backend::Backend,
+    key_groups::{self, KeyGroupRange},
+};
 use prost::*;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Returns the index of the instance that owns `key`
+///
+/// The key space of `max_key` key groups is split into `instances` contiguous key ranges.
+#[inline]
+pub fn key_owner(key: u64, instances: u64, max_key: u64) -> usize {
+    (key_groups::key_group(key, max_key) * instances / max_key) as usize
+}
+
+/// Decides which channel of a keyed edge an element is sent to
+///
+/// By default, keyed edges split the key space into contiguous key ranges,
+/// see [KeyGroupPartitioner]. A custom partitioner may be set on an edge through
+/// [KeyedStream::with_partitioner](crate::prelude::KeyedStream::with_partitioner),
+/// for example to colocate hot keys or to align with the sharding of an external system.
+pub trait Partitioner<A: ArconType>: Send + Sync + 'static {
+    /// Returns the index of the channel that `elem` is sent to
+    ///
+    /// The index must be lower than `num_channels`.
+    fn partition(&self, elem: &A, num_channels: usize) -> usize;
+}
+
+/// Partitions elements onto contiguous ranges of the key groups of their key
+///
+/// This is the partitioning that keyed state is checkpointed and rescaled by.
+pub struct KeyGroupPartitioner<A: ArconType> {
+    key_extractor: KeyExtractor<A>,
+    max_key: u64,
+}
+
+impl<A: ArconType> KeyGroupPartitioner<A> {
+    pub fn new(key_extractor: KeyExtractor<A>, max_key: u64) -> Self {
+        Self {
+            key_extractor,
+            max_key,
+        }
+    }
+}
+
+impl<A: ArconType> Partitioner<A> for KeyGroupPartitioner<A> {
+    #[inline]
+    fn partition(&self, elem: &A, num_channels: usize) -> usize {
+        key_owner(
+            (self.key_extractor)(elem),
+            num_channels as u64,
+            self.max_key,
+        )
+    }
+}
+
+/// Partitions elements by the [KeyRange] that their key lies within
+///
+/// The element goes to the channel with the index of its range, modulo the number of channels.
+/// Keys outside of every range are spread over the channels by their value.
+pub struct RangePartitioner<A: ArconType> {
+    ranges: Vec<KeyRange>,
+    key_extractor: KeyExtractor<A>,
+}
+
+impl<A: ArconType> RangePartitioner<A> {
+    /// Creates a RangePartitioner over the raw keys returned by `key_extractor`
+    pub fn new(
+        ranges: Vec<KeyRange>,
+        key_extractor: impl Fn(&A) -> u64 + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            ranges,
+            key_extractor: Arc::new(key_extractor),
+        }
+    }
+}
+
+impl<A: ArconType> Partitioner<A> for RangePartitioner<A> {
+    #[inline]
+    fn partition(&self, elem: &A, num_channels: usize) -> usize {
+        let key = (self.key_extractor)(elem);
+        match self.ranges.iter().position(|range| range.contains(key)) {
+            Some(index) => index % num_channels,
+            None => (key % num_channels as u64) as usize,
+        }
+    }
+}
+
+/// Partitions elements through consistent hashing of their key
+///
+/// When the number of channels changes, only the keys of the added or
+/// removed channels move to other channels.
+pub struct ConsistentHashPartitioner<A: ArconType> {
+    key_extractor: KeyExtractor<A>,
+}
+
+impl<A: ArconType> ConsistentHashPartitioner<A> {
+    /// Creates a ConsistentHashPartitioner over the keys returned by `key_extractor`
+    pub fn new(key_extractor: impl Fn(&A) -> u64 + Send + Sync + 'static) -> Self {
+        Self {
+            key_extractor: Arc::new(key_extractor),
+        }
+    }
+}
+
+impl<A: ArconType> Default for ConsistentHashPartitioner<A> {
+    /// Hashes the key of [ArconType::get_key]
+    fn default() -> Self {
+        Self::new(|elem: &A| elem.get_key())
+    }
+}
+
+impl<A: ArconType> Partitioner<A> for ConsistentHashPartitioner<A> {
+    #[inline]
+    fn partition(&self, elem: &A, num_channels: usize) -> usize {
+        jump_consistent_hash((self.key_extractor)(elem), num_channels)
+    }
+}
+
+/// Jump consistent hash by Lamping and Veach, maps `key` onto one of `buckets`
+fn jump_consistent_hash(mut key: u64, buckets: usize) -> usize {
+    let mut bucket: i64 = -1;
+    let mut next: i64 = 0;
+    while next < buckets as i64 {
+        bucket = next;
+        key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+        next = ((bucket + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+    bucket as usize
+}
 
 /// Keyed state in Arcon is split into Regions.
 #[derive(Debug)]
@@ -7,10 +141,17 @@ pub struct Region {
     /// A Region may internally further split up the key ranges
     /// Example: ((0, KeyRange(0, 31), (1, KeyRange(32, 64), ....)
     ranges: Vec<(NodeID, KeyRange)>,
+    /// Total number of key groups
+    max_key: u64,
 }
 
 impl Region {
     pub fn new(instances: usize, max_key: usize) -> Self {
+        assert!(
+            instances > 0 && instances <= max_key,
+            "number of instances must be within 1..={}",
+            max_key
+        );
         let mut ranges = Vec::new();
         for index in 0..instances {
             let start = (index * max_key + instances - 1) / instances;
@@ -21,11 +162,70 @@ impl Region {
             ));
         }
 
-        Self { ranges }
+        Self {
+            ranges,
+            max_key: max_key as u64,
+        }
+    }
+
+    /// Returns the key ranges of the Region
+    pub fn ranges(&self) -> &[(NodeID, KeyRange)] {
+        &self.ranges
+    }
+
+    /// Returns the instance that owns `key`
+    ///
+    /// Matches the placement of elements done by the Keyed channel strategy.
+    #[inline]
+    pub fn owner(&self, key: u64) -> NodeID {
+        let index = key_owner(key, self.ranges.len() as u64, self.max_key);
+        self.ranges[index].0
+    }
+
+    /// Returns the key groups owned by `node`
+    pub fn key_groups(&self, node: NodeID) -> Option<KeyGroupRange> {
+        self.ranges
+            .iter()
+            .find(|(id, _)| *id == node)
+            .map(|(_, range)| range.key_groups(self.max_key))
+    }
+
+    /// Splits the keyed state of `backend` according to the key ranges of the Region
+    ///
+    /// Each instance gets its key groups exported into a `key_groups_{node}` directory under `dir`,
+    /// which can be imported by the backend of that instance. This allows state that was
+    /// checkpointed at one parallelism to be restored at another.
+    pub fn export<B: Backend>(
+        &self,
+        backend: &B,
+        dir: &Path,
+    ) -> ArconResult<Vec<(NodeID, PathBuf)>> {
+        self.export_where(backend, dir, |_| true)
+    }
+
+    /// Exports the key groups of the instances for which `include` returns true
+    ///
+    /// See [export](Self::export).
+    pub fn export_where<B: Backend>(
+        &self,
+        backend: &B,
+        dir: &Path,
+        include: impl Fn(NodeID) -> bool,
+    ) -> ArconResult<Vec<(NodeID, PathBuf)>> {
+        let mut exports = Vec::new();
+        for (node, range) in self.ranges.iter().filter(|(node, _)| include(*node)) {
+            let mut path = dir.to_path_buf();
+            path.push(format!("key_groups_{}", node.id));
+            backend.export_key_groups(&range.key_groups(self.max_key), &path)?;
+            exports.push((*node, path));
+        }
+        Ok(exports)
     }
 }
 
 /// A Key Range with a start and end position
+///
+/// Both positions are inclusive.
 #[derive(Message, PartialEq, Clone)]
 pub struct KeyRange {
     /// Start of the Key Range
@@ -39,7 +239,86 @@ pub struct KeyRange {
 impl KeyRange {
     /// Creates a new KeyRange
     pub fn new(start: u64, end: u64) -> KeyRange {
-        assert!(start < end, "start range has to be smaller than end range");
+        assert!(start <= end, "start range cannot be larger than end range");
         KeyRange { start, end }
     }
+
+    /// Returns true if the key group lies within the range
+    #[inline]
+    pub fn contains(&self, group: u64) -> bool {
+        group >= self.start && group <= self.end
+    }
+
+    /// Returns the range as key groups of a key space with `max_key` key groups
+    pub fn key_groups(&self, max_key: u64) -> KeyGroupRange {
+        KeyGroupRange::new(self.start, self.end, max_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_key_ranges_test() {
+        let max_key = 128;
+        for instances in &[1, 3, 4, 7, 128] {
+            let region = Region::new(*instances, max_key);
+            let ranges = region.ranges();
+            assert_eq!(ranges.len(), *instances);
+            assert_eq!(ranges[0].1.start, 0);
+            assert_eq!(ranges[*instances - 1].1.end, max_key as u64 - 1);
+            for window in ranges.windows(2) {
+                assert_eq!(window[0].1.end + 1, window[1].1.start);
+            }
+            // the owner of a key is the instance that holds its key group
+            for key in 0..(max_key as u64 * 2) {
+                let owner = region.owner(key);
+                let group = key_groups::key_group(key, max_key as u64);
+                assert!(region.key_groups(owner).unwrap().contains(group));
+            }
+        }
+    }
+
+    #[test]
+    fn range_partitioner_test() {
+        let partitioner = RangePartitioner::new(
+            vec![
+                KeyRange::new(0, 9),
+                KeyRange::new(10, 99),
+                KeyRange::new(100, 999),
+            ],
+            |x: &u64| *x,
+        );
+        assert_eq!(partitioner.partition(&5, 3), 0);
+        assert_eq!(partitioner.partition(&10, 3), 1);
+        assert_eq!(partitioner.partition(&999, 3), 2);
+        // ranges wrap around fewer channels
+        assert_eq!(partitioner.partition(&500, 2), 0);
+        // keys outside of the ranges are spread by their value
+        assert_eq!(partitioner.partition(&1001, 3), 2);
+    }
+
+    #[test]
+    fn consistent_hash_partitioner_test() {
+        let partitioner = ConsistentHashPartitioner::new(|x: &u64| *x);
+        let keys = 0..10_000u64;
+        let before: Vec<usize> = keys.clone().map(|k| partitioner.partition(&k, 8)).collect();
+        let after: Vec<usize> = keys.map(|k| partitioner.partition(&k, 9)).collect();
+
+        // every channel gets a share of the keys
+        for channel in 0..8 {
+            assert!(before.iter().filter(|c| **c == channel).count() > 1000);
+        }
+        // keys only move onto the added channel
+        for (b, a) in before.iter().zip(after.iter()) {
+            assert!(a == b || *a == 8);
+        }
+        let moved = before
+            .iter()
+            .zip(after.iter())
+            .filter(|(b, a)| b != a)
+            .count();
+        assert!(moved < 2000);
+    }
 }
@@ -34,3 +34,13 @@ pub const RELIABLE_STRING_ID: SerId = 63;
 #[cfg(feature = "unsafe_flight")]
 pub const UNSAFE_BOOLEAN_ID: SerId = 64;
 pub const RELIABLE_BOOLEAN_ID: SerId = 65;
+
+#[cfg(feature = "unsafe_flight")]
+pub const UNSAFE_CO_ELEMENT_ID: SerId = 66;
+pub const RELIABLE_CO_ELEMENT_ID: SerId = 67;
+
+/// [PeerEpochEvent](crate::manager::epoch::PeerEpochEvent) of a distributed Application
+pub const PEER_EPOCH_ID: SerId = 68;
+
+/// [CreditGrant](crate::stream::channel::flow_control::CreditGrant) of a Node on another process
+pub const CREDIT_GRANT_ID: SerId = 69;
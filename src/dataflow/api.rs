@@ -6,7 +6,7 @@ use crate::{
     dataflow::conf::{DefaultBackend, OperatorConf, SourceConf},
     index::{ArconState, EMPTY_STATE_ID},
     stream::{
-        operator::Operator,
+        operator::{co_operator::CoOperator, Operator},
         source::Source,
         time::{ArconTime, Time},
     },
@@ -38,13 +38,6 @@ pub struct OperatorBuilder<OP: Operator, Backend = DefaultBackend> {
 }
 
 impl<OP: Operator, Backend: arcon_state::Backend> OperatorBuilder<OP, Backend> {
-    pub(crate) fn create_backend(
-        &self,
-        state_dir: std::path::PathBuf,
-        name: String,
-    ) -> Arc<Backend> {
-        Arc::new(Backend::create(&state_dir, name).unwrap())
-    }
     pub(crate) fn state_id(&self) -> StateID {
         let mut state_id = OP::OperatorState::STATE_ID.to_owned();
         if state_id == EMPTY_STATE_ID {
@@ -56,6 +49,20 @@ impl<OP: Operator, Backend: arcon_state::Backend> OperatorBuilder<OP, Backend> {
     }
 }
 
+/// CoOperator Builder
+///
+/// Defines everything needed in order for Arcon to instantiate
+/// and manage a [CoOperator] during runtime.
+#[derive(Clone)]
+pub struct CoOperatorBuilder<C: CoOperator, Backend = DefaultBackend> {
+    /// CoOperator Constructor
+    pub operator: Arc<dyn Fn() -> C + Send + Sync + 'static>,
+    /// State Constructor
+    pub state: Arc<dyn Fn(Arc<Backend>) -> C::OperatorState + Send + Sync + 'static>,
+    /// Operator Config
+    pub conf: OperatorConf,
+}
+
 type SourceIndex = usize;
 type TotalSources = usize;
 
@@ -85,6 +92,12 @@ where
             SourceBuilderType::Parallel(builder) => builder.conf.time,
         }
     }
+    pub fn name(&self) -> &str {
+        match self {
+            SourceBuilderType::Single(builder) => &builder.conf.name,
+            SourceBuilderType::Parallel(builder) => &builder.conf.name,
+        }
+    }
 }
 
 /// Source Builder
@@ -121,4 +134,21 @@ pub enum Assigner {
         length: Time,
         late_arrival: Time,
     },
+    /// Gap-based session windows
+    ///
+    /// A session is closed once no element has arrived for `gap` time.
+    /// Sessions that are bridged by an element are merged into one.
+    Session {
+        gap: Time,
+        late_arrival: Time,
+    },
+    /// A single window per key that never closes
+    ///
+    /// The window only emits results when its [Trigger](crate::prelude::Trigger) fires,
+    /// so the window operator fails to start unless a trigger has been set.
+    Global,
+    /// Windows of `size` elements per key
+    Count {
+        size: u64,
+    },
 }
@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 
 use super::api::Assigner;
-use crate::{data::ArconType, stream::time::ArconTime};
+use crate::{
+    data::ArconType,
+    stream::time::{ArconTime, Time},
+};
 use hocon::HoconLoader;
 use serde::Deserialize;
 use std::{path::Path, sync::Arc};
@@ -30,6 +33,19 @@ pub enum ParallelismStrategy {
     /// Use a static number of Arcon nodes
     Static(usize),
     /// Tells the runtime to manage the parallelism
+    ///
+    /// The Operator is deployed with [ApplicationConf::max_managed_parallelism](crate::prelude::ApplicationConf)
+    /// instances and the runtime periodically decides how many of them own a key range
+    /// based on their inbound throughput. A new parallelism is applied by all upstream
+    /// instances after the marker of the same epoch.
+    ///
+    /// The instances share the backend of the Operator. At that epoch, every instance persists
+    /// its state and drops what it cached in memory, so the new owner of a key range reads its
+    /// keyed state from the backend. Timers are handed off to the instance that owns their key
+    /// after the epoch.
+    ///
+    /// Managed Operators always take aligned checkpoints, do not support custom
+    /// [Partitioner](crate::prelude::Partitioner)s and cannot be deployed across processes.
     Managed,
 }
 
@@ -83,6 +99,14 @@ impl OperatorConf {
 
 pub type TimestampExtractor<A> = Arc<dyn Fn(&A) -> u64 + Send + Sync>;
 
+/// Function that extracts the key used for partitioning and state of a record
+pub type KeyExtractor<A> = Arc<dyn Fn(&A) -> u64 + Send + Sync>;
+
+/// Returns a [KeyExtractor] that relies on [ArconType::get_key]
+pub fn default_key_extractor<A: ArconType>() -> KeyExtractor<A> {
+    Arc::new(|data: &A| data.get_key())
+}
+
 /// Source Configuration
 #[derive(Clone)]
 pub struct SourceConf<S: ArconType> {
@@ -127,3 +151,14 @@ pub struct WindowConf {
     pub assigner: Assigner,
     pub kind: StreamKind,
 }
+
+/// Configuration for an interval join
+///
+/// An element `a` of the first stream is joined with an element `b`
+/// of the second stream if `a.ts - lower <= b.ts <= a.ts + upper`.
+#[derive(Clone)]
+pub struct IntervalJoinConf {
+    pub lower: Time,
+    pub upper: Time,
+    pub kind: StreamKind,
+}
@@ -3,28 +3,43 @@
 
 use crate::{
     application::conf::logger::ArconLogger,
-    application::Application,
+    application::{restore::channel_state_id, Application},
     buffer::event::PoolInfo,
-    data::{ArconMessage, ArconType, NodeID},
+    data::{
+        flight_serde::FlightSerde,
+        partition::{Partitioner, Region},
+        ArconMessage, ArconNever, ArconType, NodeID,
+    },
     dataflow::{
         api::{OperatorBuilder, SourceBuilderType},
-        conf::{ParallelismStrategy, SourceConf},
-        dfg::ChannelKind,
+        conf::{default_key_extractor, KeyExtractor, ParallelismStrategy, SourceConf},
+        dfg::{ChannelKind, ErasedKeyExtractor, SideOutputEdge},
     },
+    index::{EmptyState, EMPTY_STATE_ID},
     manager::{
-        node::{NodeManager, NodeManagerPort},
+        node::{CheckpointCompleted, NodeManager, NodeManagerPort},
+        scaling::{ManagedParallelism, ScalingPolicy},
         source::{SourceManager, SourceManagerPort},
     },
     stream::{
         channel::{
-            strategy::{forward::Forward, keyed::Keyed, *},
+            node_alias,
+            strategy::{
+                chain::{ChainedOperator, ChainedOutput},
+                forward::Forward,
+                keyed::Keyed,
+                rebalance::Rebalance,
+                round_robin::RoundRobin,
+                *,
+            },
             Channel,
         },
         node::{
+            side_output::{SideOutput, SideOutputChannel},
             source::{SourceEvent, SourceNode},
             Node, NodeState,
         },
-        operator::Operator,
+        operator::{side_output::SideOutputKey, Operator},
         source::Source,
         time::ArconTime,
     },
@@ -37,33 +52,130 @@ use kompact::{
         RequiredRef, *,
     },
 };
-use std::{any::Any, convert::TryInto, sync::Arc};
+use std::{any::Any, sync::Arc};
 
-pub type SourceManagerConstructor = Box<
-    dyn FnOnce(
-        Vec<Arc<dyn Any + Send + Sync>>,
-        ChannelKind,
-        &mut Application,
-    ) -> ErasedSourceManager,
->;
+/// Constructs the SourceManager of a Source
+///
+/// Returns `None` if none of the instances of the Source run on this process.
+pub type SourceManagerConstructor =
+    Box<dyn FnOnce(Vec<NodeID>, OutgoingEdges, &mut Application) -> Option<ErasedSourceManager>>;
 pub type SourceConstructor = Box<
     dyn FnOnce(
-        Vec<Arc<dyn Any + Send + Sync>>,
-        ChannelKind,
+        OutgoingEdges,
         &mut KompactSystem,
     ) -> Arc<dyn AbstractComponent<Message = SourceEvent>>,
 >;
 pub type ErasedSourceManager = Arc<dyn AbstractComponent<Message = SourceEvent>>;
+/// Constructs the Nodes of an Operator
+///
+/// The arguments are the expected incoming channels, the IDs to assign to the created Nodes,
+/// and the outgoing edges of the Operator and its side outputs.
 pub type NodeManagerConstructor = Box<
-    dyn FnOnce(Vec<NodeID>, ErasedComponents, ChannelKind, &mut Application) -> ErasedComponents,
+    dyn FnOnce(
+        Vec<NodeID>,
+        Vec<NodeID>,
+        OutgoingEdges,
+        SideOutputEdges,
+        &mut Application,
+    ) -> ErasedComponents,
 >;
 
 pub type ErasedComponent = Arc<dyn Any + Send + Sync>;
 pub type ErasedComponents = Vec<ErasedComponent>;
+/// Outgoing edges of a node, each with the downstream components and the [ChannelKind] used to reach them
+pub type OutgoingEdges = Vec<(ChannelKind, ErasedComponents)>;
+/// Creates a [SideOutput] of a Node from the outgoing edges of the side output
+pub type SideOutputConstructor =
+    Arc<dyn Fn(OutgoingEdges, NodeID, usize, PoolInfo, u64) -> Box<dyn SideOutput>>;
+/// Outgoing edges of the side outputs of a node, grouped by tag
+pub type SideOutputEdges = Vec<(SideOutputEdge, OutgoingEdges)>;
+/// Constructs an Operator that is fused into the Nodes of its upstream Operator
+///
+/// Returns a type-erased [ChainLink] that the upstream Nodes create their chained Operator from.
+pub type ChainConstructor = Box<dyn FnOnce(OutgoingEdges, &mut Application) -> ErasedComponent>;
+/// Creates the chained Operator of an upstream Node with the given NodeID and instance index
+type ChainLink<IN> =
+    Arc<dyn Fn(NodeID, usize, PoolInfo, u64) -> Box<dyn ChainedOutput<IN>> + Send + Sync>;
+
+/// A Node of a distributed Application that runs on another process
+///
+/// Stands in for the component of the Node in the targets of its upstream Nodes.
+#[derive(Clone)]
+struct RemoteNode {
+    path: ActorPath,
+    serde: FlightSerde,
+}
+
+/// Returns the target of the instance `instance` of an Operator that runs on another process
+fn remote_node(app: &Application, instance: usize, node_id: NodeID) -> ErasedComponent {
+    let conf = app
+        .conf
+        .distributed()
+        .expect("Remote Nodes require a distributed Application");
+    let addr = conf.peer_addr(conf.placement(instance));
+    let path = NamedPath::with_socket(Transport::Tcp, addr, vec![node_alias(node_id)]).into();
+    Arc::new(RemoteNode {
+        path,
+        serde: conf.flight_serde.clone(),
+    })
+}
+
+/// Creates a Channel to a target that is either a local component or a [RemoteNode]
+fn target_channel<OUT: ArconType>(component: ErasedComponent) -> Channel<OUT> {
+    match component.downcast::<Arc<dyn AbstractComponent<Message = ArconMessage<OUT>>>>() {
+        Ok(target_node) => {
+            let actor_ref = target_node.actor_ref().hold().expect("failed to fetch");
+            Channel::Local(actor_ref)
+        }
+        Err(component) => {
+            let remote = component
+                .downcast::<RemoteNode>()
+                .expect("Target of unexpected type");
+            Channel::Remote(remote.path.clone(), remote.serde.clone())
+        }
+    }
+}
 
 fn channel_strategy<OUT: ArconType>(
+    mut edges: OutgoingEdges,
+    node_id: NodeID,
+    instance: usize,
+    pool_info: PoolInfo,
+    max_key: u64,
+) -> ChannelStrategy<OUT> {
+    if edges.len() == 1 {
+        let (channel_kind, components) = edges.remove(0);
+        edge_strategy(
+            components,
+            node_id,
+            instance,
+            pool_info,
+            max_key,
+            channel_kind,
+        )
+    } else {
+        // Each outgoing edge gets its own strategy
+        let strategies = edges
+            .into_iter()
+            .map(|(channel_kind, components)| {
+                edge_strategy(
+                    components,
+                    node_id,
+                    instance,
+                    pool_info.clone(),
+                    max_key,
+                    channel_kind,
+                )
+            })
+            .collect();
+        ChannelStrategy::Multiple(strategies)
+    }
+}
+
+fn edge_strategy<OUT: ArconType>(
     mut components: ErasedComponents,
     node_id: NodeID,
+    instance: usize,
     pool_info: PoolInfo,
     max_key: u64,
     channel_kind: ChannelKind,
@@ -71,24 +183,48 @@ fn channel_strategy<OUT: ArconType>(
     match channel_kind {
         ChannelKind::Forward => {
             assert_eq!(components.len(), 1, "Expected a single component target");
-            let target_node = components
-                .remove(0)
-                .downcast::<Arc<dyn AbstractComponent<Message = ArconMessage<OUT>>>>()
-                .unwrap();
-            let actor_ref = target_node.actor_ref().hold().expect("failed to fetch");
-            ChannelStrategy::Forward(Forward::new(Channel::Local(actor_ref), node_id, pool_info))
+            let channel = target_channel(components.remove(0));
+            ChannelStrategy::Forward(Forward::new(channel, node_id, pool_info))
         }
-        ChannelKind::Keyed => {
-            let mut channels = Vec::new();
-            for component in components {
-                let target_node = component
-                    .downcast::<Arc<dyn AbstractComponent<Message = ArconMessage<OUT>>>>()
-                    .unwrap();
-                let actor_ref = target_node.actor_ref().hold().expect("failed to fetch");
-                let channel = Channel::Local(actor_ref);
-                channels.push(channel);
-            }
-            ChannelStrategy::Keyed(Keyed::new(max_key, channels, node_id, pool_info))
+        ChannelKind::Keyed(key_extractor) => ChannelStrategy::Keyed(keyed_strategy(
+            components,
+            node_id,
+            pool_info,
+            max_key,
+            key_extractor,
+        )),
+        ChannelKind::Managed(key_extractor, parallelism) => ChannelStrategy::Keyed(
+            keyed_strategy(components, node_id, pool_info, max_key, key_extractor)
+                .with_managed_parallelism(parallelism),
+        ),
+        ChannelKind::Partitioned(partitioner) => {
+            let partitioner = partitioner
+                .downcast::<Arc<dyn Partitioner<OUT>>>()
+                .map(|partitioner| (*partitioner).clone())
+                .expect("Partitioner of unexpected type");
+            let channels = components.into_iter().map(target_channel).collect();
+            ChannelStrategy::Keyed(Keyed::with_partitioner(
+                channels,
+                node_id,
+                pool_info,
+                partitioner,
+            ))
+        }
+        ChannelKind::RoundRobin => {
+            let channels = components.into_iter().map(target_channel).collect();
+            ChannelStrategy::RoundRobin(RoundRobin::new(channels, node_id, pool_info))
+        }
+        ChannelKind::Rebalance => {
+            let channels = components.into_iter().map(target_channel).collect();
+            ChannelStrategy::Rebalance(Rebalance::new(channels, node_id, pool_info))
+        }
+        ChannelKind::Chained => {
+            assert_eq!(components.len(), 1, "Expected a single chained Operator");
+            let link = components
+                .remove(0)
+                .downcast::<ChainLink<OUT>>()
+                .expect("Chained Operator of unexpected type");
+            ChannelStrategy::Chained((*link)(node_id, instance, pool_info, max_key))
         }
         ChannelKind::Console => ChannelStrategy::Console,
         ChannelKind::Mute => ChannelStrategy::Mute,
@@ -96,6 +232,74 @@ fn channel_strategy<OUT: ArconType>(
     }
 }
 
+fn keyed_strategy<OUT: ArconType>(
+    components: ErasedComponents,
+    node_id: NodeID,
+    pool_info: PoolInfo,
+    max_key: u64,
+    key_extractor: Option<ErasedKeyExtractor>,
+) -> Keyed<OUT> {
+    let key_extractor: KeyExtractor<OUT> = match key_extractor {
+        Some(erased) => erased
+            .downcast::<KeyExtractor<OUT>>()
+            .map(|extractor| (*extractor).clone())
+            .expect("KeyExtractor of unexpected type"),
+        None => default_key_extractor(),
+    };
+    let channels = components.into_iter().map(target_channel).collect();
+    Keyed::with_key_extractor(max_key, channels, node_id, pool_info, key_extractor)
+}
+
+pub(crate) fn side_output_constructor<T: ArconType>(
+    key: SideOutputKey,
+    credits: usize,
+) -> SideOutputConstructor {
+    Arc::new(
+        move |edges: OutgoingEdges,
+              node_id: NodeID,
+              instance: usize,
+              pool_info: PoolInfo,
+              max_key: u64| {
+            let strategy = channel_strategy::<T>(edges, node_id, instance, pool_info, max_key);
+            Box::new(SideOutputChannel::new(key.clone(), strategy, credits)) as Box<dyn SideOutput>
+        },
+    )
+}
+
+pub(crate) fn chain_constructor<OP>(
+    descriptor: String,
+    operator: Arc<dyn Fn() -> OP + Send + Sync>,
+) -> ChainConstructor
+where
+    OP: Operator<TimerState = ArconNever, OperatorState = EmptyState> + 'static,
+{
+    Box::new(move |edges: OutgoingEdges, app: &mut Application| {
+        let credits = app.conf.channel_credits;
+        let logger = app.arcon_logger.clone();
+        let link: ChainLink<OP::IN> = Arc::new(
+            move |node_id: NodeID, instance: usize, pool_info: PoolInfo, max_key: u64| {
+                let strategy = channel_strategy::<OP::OUT>(
+                    edges.clone(),
+                    node_id,
+                    instance,
+                    pool_info,
+                    max_key,
+                );
+                // chained instances run within the upstream instance of the same index
+                Box::new(ChainedOperator::new(
+                    format!("{}_{}", descriptor, instance),
+                    operator(),
+                    instance as u32,
+                    strategy,
+                    credits,
+                    logger.clone(),
+                )) as Box<dyn ChainedOutput<OP::IN>>
+            },
+        );
+        Arc::new(link) as ErasedComponent
+    })
+}
+
 pub(crate) fn source_manager_constructor<S: Source + 'static, B: Backend>(
     descriptor: String,
     builder_type: SourceBuilderType<S, B>,
@@ -104,9 +308,15 @@ pub(crate) fn source_manager_constructor<S: Source + 'static, B: Backend>(
     time: ArconTime,
 ) -> SourceManagerConstructor {
     Box::new(
-        move |components: Vec<Arc<dyn std::any::Any + Send + Sync>>,
-              channel_kind: ChannelKind,
-              app: &mut Application| {
+        move |node_ids: Vec<NodeID>, edges: OutgoingEdges, app: &mut Application| {
+            // Indices of the source instances that run on this process
+            let local_sources: Vec<usize> = (0..builder_type.parallelism())
+                .filter(|source_index| app.is_local(*source_index))
+                .collect();
+            if local_sources.is_empty() {
+                return None;
+            }
+
             let epoch_manager_ref = app.epoch_manager();
 
             let manager = SourceManager::new(
@@ -117,8 +327,19 @@ pub(crate) fn source_manager_constructor<S: Source + 'static, B: Backend>(
                 backend.clone(),
                 app.arcon_logger.clone(),
             );
+            // Offsets that the sources resume from, non-zero if the backend was restored
+            let offsets: Vec<u64> = (0..builder_type.parallelism())
+                .map(|source_index| manager.offset(source_index))
+                .collect();
             let source_manager_comp = app.ctrl_system().create(|| manager);
 
+            // Connect SourceManager to the SnapshotManager of the app
+            app.snapshot_manager.on_definition(|scd| {
+                source_manager_comp.on_definition(|cd| {
+                    biconnect_ports(&mut scd.manager_port, &mut cd.snapshot_manager_port);
+                });
+            });
+
             match builder_type {
                 SourceBuilderType::Single(builder) => {
                     let source_cons = builder.constructor;
@@ -128,26 +349,28 @@ pub(crate) fn source_manager_constructor<S: Source + 'static, B: Backend>(
                     create_source_node(
                         app,
                         source_index,
-                        components.clone(),
-                        channel_kind,
+                        node_ids[source_index],
+                        edges.clone(),
                         source,
                         source_conf,
+                        offsets[source_index],
                         &source_manager_comp,
                     );
                 }
                 SourceBuilderType::Parallel(builder) => {
                     let source_cons = builder.constructor;
                     let parallelism = builder.parallelism;
-                    for source_index in 0..builder.parallelism {
+                    for source_index in local_sources {
                         let source_conf = builder.conf.clone();
                         let source = source_cons(backend.clone(), source_index, parallelism); // todo
                         create_source_node(
                             app,
                             source_index,
-                            components.clone(),
-                            channel_kind,
+                            node_ids[source_index],
+                            edges.clone(),
                             source,
                             source_conf,
+                            offsets[source_index],
                             &source_manager_comp,
                         );
                     }
@@ -156,19 +379,17 @@ pub(crate) fn source_manager_constructor<S: Source + 'static, B: Backend>(
             let source_ref: ActorRefStrong<SourceEvent> =
                 source_manager_comp.actor_ref().hold().expect("fail");
 
-            // Set source reference at the EpochManager
-            if let Some(epoch_manager) = &app.epoch_manager {
-                epoch_manager.on_definition(|cd| {
-                    cd.source_manager = Some(source_ref);
-                });
-            }
+            // Add source reference to the EpochManager
+            app.epoch_manager.on_definition(|cd| {
+                cd.source_managers.push(source_ref);
+            });
 
             app.ctrl_system()
                 .start_notify(&source_manager_comp)
                 .wait_timeout(std::time::Duration::from_millis(2000))
                 .expect("Failed to start SourceManager");
 
-            source_manager_comp
+            Some(source_manager_comp)
         },
     )
 }
@@ -177,10 +398,11 @@ pub(crate) fn source_manager_constructor<S: Source + 'static, B: Backend>(
 fn create_source_node<S, B>(
     app: &mut Application,
     source_index: usize,
-    components: Vec<Arc<dyn std::any::Any + Send + Sync>>,
-    channel_kind: ChannelKind,
+    node_id: NodeID,
+    edges: OutgoingEdges,
     source: S,
     source_conf: SourceConf<S::Item>,
+    offset: u64,
     source_manager_comp: &Arc<Component<SourceManager<B>>>,
 ) where
     S: Source,
@@ -188,21 +410,26 @@ fn create_source_node<S, B>(
 {
     let pool_info = app.get_pool_info();
     let max_key = app.conf.max_key;
-    let channel_strategy = channel_strategy(
-        components.clone(),
-        NodeID::new(source_index as u32),
-        pool_info,
-        max_key,
-        channel_kind,
-    );
+    let channel_strategy = channel_strategy(edges, node_id, source_index, pool_info, max_key);
     let source_node = SourceNode::new(
         source_index,
         source,
         source_conf,
         channel_strategy,
         app.arcon_logger.clone(),
-    );
+    )
+    .with_offset(offset)
+    .with_channel_credits(app.conf.channel_credits);
     let source_node_comp = app.data_system().create(|| source_node);
+    if app.conf.distributed().is_some() {
+        // Nodes on other processes grant credits back to the source through its alias
+        app.data_system()
+            .register_by_alias(&source_node_comp, node_alias(node_id))
+            .wait_expect(
+                std::time::Duration::from_millis(2000),
+                "Failed to register alias of Source Node",
+            );
+    }
 
     app.data_system()
         .start_notify(&source_node_comp)
@@ -215,7 +442,7 @@ fn create_source_node<S, B>(
     let source_node_comp_dyn: Arc<dyn AbstractComponent<Message = SourceEvent>> = source_node_comp;
 
     source_manager_comp.on_definition(|cd| {
-        cd.add_source(source_node_comp_dyn);
+        cd.add_source(source_index, source_node_comp_dyn);
     });
 }
 
@@ -224,20 +451,41 @@ pub(crate) fn node_manager_constructor<OP: Operator + 'static, B: Backend>(
     data_system: KompactSystem,
     builder: OperatorBuilder<OP, B>,
     backend: Arc<B>,
+    key_extractor: KeyExtractor<OP::IN>,
     logger: ArconLogger,
+    managed: Option<ManagedParallelism>,
 ) -> NodeManagerConstructor {
     Box::new(
         move |in_channels: Vec<NodeID>,
-              components: ErasedComponents,
-              channel_kind: ChannelKind,
+              node_ids: Vec<NodeID>,
+              edges: OutgoingEdges,
+              side_edges: SideOutputEdges,
               app: &mut Application| {
             let epoch_manager_ref = app.epoch_manager();
 
             // How many instances of this Operator we are initially creating
-            let instances = match builder.conf.parallelism_strategy {
-                ParallelismStrategy::Static(s) => s,
-                _ => panic!("Managed ParallelismStrategy not supported yet"),
+            let instances = match (&builder.conf.parallelism_strategy, &managed) {
+                (ParallelismStrategy::Static(s), _) => *s,
+                (ParallelismStrategy::Managed, Some(parallelism)) => parallelism.max(),
+                (ParallelismStrategy::Managed, None) => {
+                    panic!("Managed ParallelismStrategy requires a ManagedParallelism")
+                }
             };
+            assert_eq!(instances, node_ids.len(), "Expected a NodeID per instance");
+            // Key ranges change owner through the backend that the instances share
+            assert!(
+                managed.is_none() || app.conf.distributed().is_none(),
+                "Managed parallelism is not supported for distributed applications"
+            );
+
+            // The instances that run on other processes are only reached through remote channels
+            if !(0..instances).any(|instance| app.is_local(instance)) {
+                return node_ids
+                    .into_iter()
+                    .enumerate()
+                    .map(|(instance, node_id)| remote_node(app, instance, node_id))
+                    .collect();
+            }
 
             let max_key = app.conf.max_key as usize;
 
@@ -257,7 +505,47 @@ pub(crate) fn node_manager_constructor<OP: Operator + 'static, B: Backend>(
                 backend.clone(),
                 logger.clone(),
                 builder,
-            );
+            )
+            .with_checkpoint_pool(app.checkpoint_pool.clone());
+            let region = Region::new(instances, max_key);
+            let key_groups = node_ids
+                .iter()
+                .enumerate()
+                .filter(|(instance, _)| app.is_local(*instance))
+                .filter_map(|(instance, _)| region.key_groups(NodeID::new(instance as u32)))
+                .collect();
+            let manager = manager.with_key_groups(key_groups);
+            // Operators without state have nothing to restore the channel state into, and
+            // managed Operators hand off key ranges once every channel has delivered the epoch
+            let unaligned = app.conf.unaligned_checkpoints
+                && !descriptor.starts_with(EMPTY_STATE_ID)
+                && managed.is_none();
+            let manager = if unaligned {
+                manager.with_unaligned_checkpoints()
+            } else {
+                manager
+            };
+            // Epoch to continue from if the Application has been restored
+            let restored_epoch = app.restored.as_ref().map(|snapshot| snapshot.next_epoch());
+            let restored_channel_state = app
+                .restored
+                .as_ref()
+                .and_then(|snapshot| snapshot.checkpoint(&channel_state_id(&descriptor)))
+                .cloned();
+            let manager = match restored_epoch {
+                Some(epoch) => manager
+                    .with_epoch(epoch)
+                    .expect("Failed to restore NodeManager"),
+                None => manager,
+            };
+            let manager = match &managed {
+                Some(parallelism) => manager.with_managed_parallelism(
+                    parallelism.clone(),
+                    ScalingPolicy::from_conf(&app.conf),
+                    app.conf.scaling_interval,
+                ),
+                None => manager,
+            };
             // Create the actual NodeManager component
             let manager_comp = app.ctrl_system().create(|| manager);
 
@@ -271,30 +559,92 @@ pub(crate) fn node_manager_constructor<OP: Operator + 'static, B: Backend>(
             // Fetch PoolInfo object that ChannelStrategies use to organise their buffers
             let pool_info = app.get_pool_info();
 
-            // Create `instances` number of Nodes and add them into the NodeManager
-            for (curr_node_id, _) in (0..instances).enumerate() {
+            // Create the Nodes of the instances that run on this process and add them into the NodeManager
+            for (curr_node_id, node_id) in node_ids.iter().copied().enumerate() {
+                if !app.is_local(curr_node_id) {
+                    continue;
+                }
                 let node_descriptor = format!("{}_{}", descriptor, curr_node_id);
-                let node_id = NodeID::new(curr_node_id.try_into().unwrap());
+
+                let side_outputs = side_edges
+                    .iter()
+                    .map(|(side_output, edges)| {
+                        (side_output.constructor)(
+                            edges.clone(),
+                            node_id,
+                            curr_node_id,
+                            pool_info.clone(),
+                            max_key as u64,
+                        )
+                    })
+                    .collect();
 
                 let node = Node::new(
                     node_descriptor,
                     channel_strategy(
-                        components.clone(),
+                        edges.clone(),
                         node_id,
+                        curr_node_id,
                         pool_info.clone(),
                         max_key as u64,
-                        channel_kind,
                     ),
                     operator(),
                     operator_state(backend.clone()),
+                    key_extractor.clone(),
                     NodeState::new(node_id, in_channels.clone(), backend.clone()),
                     backend.clone(),
                     app.arcon_logger.clone(),
                     #[cfg(all(feature = "hardware_counters", target_os = "linux", not(test)))]
                     perf_events.clone(),
-                );
+                )
+                .with_instance(curr_node_id as u32)
+                .with_side_outputs(side_outputs)
+                .with_processing_timer_interval(app.conf.processing_timer_interval)
+                .with_channel_credits(app.conf.channel_credits);
+
+                let node = if managed.is_some() {
+                    node.with_throughput_reports(app.conf.node_metrics_interval)
+                } else {
+                    node
+                };
+
+                let node = if unaligned {
+                    node.with_unaligned_checkpoints()
+                } else {
+                    node
+                };
+
+                let node = match restored_epoch {
+                    Some(epoch) => node.with_epoch(epoch).expect("Failed to restore Node"),
+                    None => node,
+                };
+
+                let node = match &restored_channel_state {
+                    Some(dir) => node
+                        .with_channel_state(dir)
+                        .expect("Failed to restore channel state of Node"),
+                    None => node,
+                };
+
+                let node = match &managed {
+                    Some(parallelism) => node.with_managed_parallelism(
+                        parallelism.clone(),
+                        curr_node_id,
+                        max_key as u64,
+                    ),
+                    None => node,
+                };
 
                 let node_comp = app.data_system().create(|| node);
+                if app.conf.distributed().is_some() {
+                    // Upstream Nodes on other processes address the Node through its alias
+                    app.data_system()
+                        .register_by_alias(&node_comp, node_alias(node_id))
+                        .wait_expect(
+                            std::time::Duration::from_millis(2000),
+                            "Failed to register alias of Node",
+                        );
+                }
                 let required_ref: RequiredRef<NodeManagerPort> = node_comp.required_ref();
                 biconnect_components::<NodeManagerPort, _, _>(&manager_comp, &node_comp)
                     .expect("fail");
@@ -317,13 +667,20 @@ pub(crate) fn node_manager_constructor<OP: Operator + 'static, B: Backend>(
                 .start_notify(&manager_comp)
                 .wait_timeout(std::time::Duration::from_millis(2000))
                 .expect("Failed to start NodeManager");
+            let abstract_manager: Arc<dyn AbstractComponent<Message = CheckpointCompleted>> =
+                manager_comp.clone();
+            app.node_managers.push(abstract_manager);
 
             // Fetch all created Nodes on this NodeManager and return them as Erased
-            // for the next stage..
+            // for the next stage, ordered by instance so that every process partitions alike..
             let nodes: ErasedComponents = manager_comp.on_definition(|cd| {
-                cd.nodes
-                    .values()
-                    .map(|(comp, _)| Arc::new(comp.clone()) as ErasedComponent)
+                node_ids
+                    .iter()
+                    .enumerate()
+                    .map(|(instance, node_id)| match cd.nodes.get(node_id) {
+                        Some((comp, _)) => Arc::new(comp.clone()) as ErasedComponent,
+                        None => remote_node(app, instance, *node_id),
+                    })
                     .collect()
             });
 
@@ -1,4 +1,9 @@
 use super::constructor::*;
+use crate::{manager::scaling::ManagedParallelism, stream::operator::side_output::SideOutputKey};
+use std::{
+    any::{Any, TypeId},
+    sync::Arc,
+};
 
 /// A logical dataflow-graph.
 #[allow(dead_code)]
@@ -19,6 +24,20 @@ impl DFG {
         id
     }
 
+    /// Appends all nodes of `other` to this dataflow graph.
+    ///
+    /// Returns the offset that has been added to the [`DFGNodeID`]s of `other`.
+    pub fn merge(&mut self, other: DFG) -> usize {
+        let offset = self.graph.len();
+        for mut node in other.graph {
+            for id in node.ingoing.iter_mut() {
+                id.0 += offset;
+            }
+            self.graph.push(node);
+        }
+        offset
+    }
+
     /// Returns a reference to the [`DFGNode`] associated to a [`DFGNodeID`].
     #[allow(dead_code)]
     pub fn get(&self, id: &DFGNodeID) -> &DFGNode {
@@ -44,7 +63,14 @@ pub struct DFGNode {
     pub(crate) ingoing_channels: usize,
     /// Ingoing edges to a node.
     pub(crate) ingoing: Vec<DFGNodeID>,
+    /// How elements are partitioned on the ingoing edges of this node.
     pub(crate) channel_kind: ChannelKind,
+    /// Set if the ingoing edges of this node carry a side output of the ingoing nodes.
+    pub(crate) side_output: Option<SideOutputEdge>,
+    /// Set if the Operator of this node is stateless and may be fused into its ingoing node.
+    pub(crate) chain: Option<ChainConstructor>,
+    /// Type of the elements the Operator of this node consumes, which its late elements are of.
+    pub(crate) input_type: Option<TypeId>,
 }
 
 impl DFGNode {
@@ -60,12 +86,15 @@ impl DFGNode {
             ingoing_channels,
             ingoing,
             channel_kind: Default::default(),
+            side_output: None,
+            chain: None,
+            input_type: None,
         }
     }
 }
 
 pub enum DFGNodeKind {
-    Source(ChannelKind, SourceManagerConstructor),
+    Source(SourceManagerConstructor),
     Node(NodeManagerConstructor),
 }
 
@@ -75,20 +104,44 @@ pub enum SourceKind {
     Parallel,
 }
 
-#[derive(Clone, Copy, Debug)]
+/// An edge that carries the side output with id `tag` instead of the regular output
+#[derive(Clone)]
+pub struct SideOutputEdge {
+    pub(crate) tag: SideOutputKey,
+    pub(crate) constructor: SideOutputConstructor,
+}
+
+/// A type-erased [KeyExtractor](super::conf::KeyExtractor) attached to a keyed edge
+pub type ErasedKeyExtractor = Arc<dyn Any + Send + Sync>;
+/// A type-erased `Arc<dyn Partitioner<A>>` attached to a keyed edge
+pub type ErasedPartitioner = Arc<dyn Any + Send + Sync>;
+
+#[derive(Clone)]
 #[allow(dead_code)]
 pub enum ChannelKind {
     Forward,
     Broadcast,
+    /// Spread elements evenly over the instances
     RoundRobin,
-    Keyed,
+    /// Send elements to the instance with the most free buffer capacity
+    Rebalance,
+    /// Partition by key
+    ///
+    /// If no extractor is set, the key is fetched through [ArconType::get_key](crate::data::ArconType::get_key).
+    Keyed(Option<ErasedKeyExtractor>),
+    /// Partition by key onto the active instances of an Operator with a managed parallelism
+    Managed(Option<ErasedKeyExtractor>, ManagedParallelism),
+    /// Partition through a user-defined [Partitioner](crate::data::partition::Partitioner)
+    Partitioned(ErasedPartitioner),
+    /// Fuse the Operator into the Nodes of its upstream Operator
+    Chained,
     Console,
     Mute,
 }
 
 impl Default for ChannelKind {
     fn default() -> Self {
-        ChannelKind::Keyed
+        ChannelKind::Keyed(None)
     }
 }
 
@@ -3,31 +3,50 @@
 
 use crate::{
     application::Application,
-    data::{ArconType, NodeID},
+    data::{partition::Partitioner, ArconNever, ArconType, CoElement, CoInput, NodeID},
     dataflow::{
-        api::OperatorBuilder,
-        conf::ParallelismStrategy,
+        api::{CoOperatorBuilder, OperatorBuilder},
+        conf::{
+            default_key_extractor, DefaultBackend, IntervalJoinConf, KeyExtractor,
+            ParallelismStrategy, WindowConf,
+        },
         constructor::*,
-        dfg::{ChannelKind, DFGNode, DFGNodeID, DFGNodeKind, DFG},
+        dfg::{
+            ChannelKind, DFGNode, DFGNodeID, DFGNodeKind, ErasedKeyExtractor, ErasedPartitioner,
+            SideOutputEdge, DFG,
+        },
     },
     index::EmptyState,
     prelude::AssembledApplication,
     stream::{
         node::debug::DebugNode,
         operator::{
+            co_operator::{CoOperator, CoOperatorAdapter},
             function::{Filter, FlatMap, Map, MapInPlace},
+            join::{IntervalJoin, IntervalJoinState, WindowJoin, WindowJoinState},
+            side_output::{OutputTag, LATE_TAG},
             Operator,
         },
     },
     util::ArconFnBounds,
 };
-use std::{marker::PhantomData, sync::Arc};
+use arcon_state::Backend;
+use fxhash::FxHasher;
+use std::{
+    any::TypeId,
+    cell::RefCell,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    rc::Rc,
+    sync::Arc,
+};
 
 #[derive(Default)]
 pub struct Context {
     pub(crate) dfg: DFG,
     app: Application,
-    console_output: bool,
+    // Outputs of nodes that are printed to the console, along with the side output they are of.
+    console_outputs: Vec<(DFGNodeID, Option<SideOutputEdge>)>,
 }
 
 impl Context {
@@ -35,7 +54,7 @@ impl Context {
         Self {
             dfg: Default::default(),
             app,
-            console_output: false,
+            console_outputs: Vec::new(),
         }
     }
 }
@@ -43,9 +62,26 @@ impl Context {
 /// High-level object representing a sequence of stream transformations.
 pub struct Stream<IN: ArconType> {
     _marker: PhantomData<IN>,
-    // ID of the node which outputs this stream.
-    prev_dfg_id: DFGNodeID,
-    ctx: Context,
+    // IDs of the nodes which output this stream.
+    prev_dfg_ids: Vec<DFGNodeID>,
+    // Set if this stream is a side output of the nodes.
+    side_output: Option<SideOutputEdge>,
+    // Context shared between all branches of the dataflow graph.
+    ctx: Rc<RefCell<Context>>,
+}
+
+impl<IN: ArconType> Clone for Stream<IN> {
+    /// Creates a new branch of the stream
+    ///
+    /// Both branches receive every element of the stream.
+    fn clone(&self) -> Self {
+        Self {
+            _marker: PhantomData,
+            prev_dfg_ids: self.prev_dfg_ids.clone(),
+            side_output: self.side_output.clone(),
+            ctx: self.ctx.clone(),
+        }
+    }
 }
 
 impl<IN: ArconType> Stream<IN> {
@@ -67,7 +103,7 @@ impl<IN: ArconType> Stream<IN> {
         OUT: ArconType,
         F: Fn(IN) -> OUT + ArconFnBounds,
     {
-        self.operator(OperatorBuilder {
+        self.chainable_operator(OperatorBuilder {
             operator: Arc::new(move || Map::new(f.clone())),
             state: Arc::new(|_| EmptyState),
             conf: Default::default(),
@@ -91,7 +127,7 @@ impl<IN: ArconType> Stream<IN> {
     where
         F: Fn(&mut IN) + ArconFnBounds,
     {
-        self.operator(OperatorBuilder {
+        self.chainable_operator(OperatorBuilder {
             operator: Arc::new(move || MapInPlace::new(f.clone())),
             state: Arc::new(|_| EmptyState),
             conf: Default::default(),
@@ -115,7 +151,7 @@ impl<IN: ArconType> Stream<IN> {
     where
         F: Fn(&IN) -> bool + ArconFnBounds,
     {
-        self.operator(OperatorBuilder {
+        self.chainable_operator(OperatorBuilder {
             operator: Arc::new(move || Filter::new(f.clone())),
             state: Arc::new(|_| EmptyState),
             conf: Default::default(),
@@ -141,7 +177,7 @@ impl<IN: ArconType> Stream<IN> {
         OUTS::Item: ArconType,
         F: Fn(IN) -> OUTS + ArconFnBounds,
     {
-        self.operator(OperatorBuilder {
+        self.chainable_operator(OperatorBuilder {
             operator: Arc::new(move || FlatMap::new(f.clone())),
             state: Arc::new(|_| EmptyState),
             conf: Default::default(),
@@ -163,61 +199,378 @@ impl<IN: ArconType> Stream<IN> {
     ///         conf: Default::default(),
     ///     });
     /// ```
-    pub fn operator<OP>(mut self, builder: OperatorBuilder<OP>) -> Stream<OP::OUT>
+    pub fn operator<OP>(self, builder: OperatorBuilder<OP>) -> Stream<OP::OUT>
     where
         OP: Operator<IN = IN> + 'static,
     {
-        // Set up directory for the operator and create Backend
-        let mut state_dir = self.ctx.app.arcon_conf().state_dir();
+        self.keyed_operator(builder, default_key_extractor(), ChannelKind::default())
+    }
+
+    /// Partition the stream by the key returned from `f`
+    ///
+    /// The key decides which instance of the next [`Operator`] an element is sent to
+    /// and which key its state is scoped to. This makes it possible to key the same
+    /// type differently in different parts of an application.
+    ///
+    /// Example
+    /// ```no_run
+    /// use arcon::prelude::*;
+    /// let stream: Stream<u64> = Application::default()
+    ///     .iterator(0u64..100, |conf| {
+    ///         conf.set_arcon_time(ArconTime::Process);
+    ///     })
+    ///     .key_by(|x| x % 10)
+    ///     .operator(OperatorBuilder {
+    ///         operator: Arc::new(|| Map::new(|x| x + 10)),
+    ///         state: Arc::new(|_| EmptyState),
+    ///         conf: Default::default(),
+    ///     });
+    /// ```
+    pub fn key_by<K, F>(self, f: F) -> KeyedStream<K, IN>
+    where
+        K: Hash + 'static,
+        F: Fn(&IN) -> K + ArconFnBounds,
+    {
+        let key_extractor: KeyExtractor<IN> = Arc::new(move |data: &IN| hash_key(f(data)));
+
+        KeyedStream {
+            stream: self,
+            key_extractor,
+            partitioner: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Spread the stream evenly over the instances of the next [`Operator`]
+    ///
+    /// Elements are sent to the instances in round-robin order, regardless of their key.
+    ///
+    /// Example
+    /// ```no_run
+    /// use arcon::prelude::*;
+    /// let stream: Stream<u64> = Application::default()
+    ///     .iterator(0u64..100, |conf| {
+    ///         conf.set_arcon_time(ArconTime::Process);
+    ///     })
+    ///     .shuffle()
+    ///     .map(|x| x + 10);
+    /// ```
+    pub fn shuffle(self) -> RebalancedStream<IN> {
+        RebalancedStream {
+            stream: self,
+            channel_kind: ChannelKind::RoundRobin,
+        }
+    }
+
+    /// Spread the stream over the instances of the next [`Operator`] according to their load
+    ///
+    /// Batches of elements are sent to the instance with the most free buffer capacity,
+    /// so that instances that fall behind receive less of the stream. This lets stateless
+    /// stages after a skewed keyed stage spread their load.
+    ///
+    /// Example
+    /// ```no_run
+    /// use arcon::prelude::*;
+    /// let stream: Stream<u64> = Application::default()
+    ///     .iterator(0u64..100, |conf| {
+    ///         conf.set_arcon_time(ArconTime::Process);
+    ///     })
+    ///     .rebalance()
+    ///     .map(|x| x + 10);
+    /// ```
+    pub fn rebalance(self) -> RebalancedStream<IN> {
+        RebalancedStream {
+            stream: self,
+            channel_kind: ChannelKind::Rebalance,
+        }
+    }
+
+    /// Adds a stateless [`Operator`] that may be fused into the Nodes of its upstream Operator
+    ///
+    /// Chained instances of the Operator run within the upstream Nodes and get neither
+    /// Nodes nor a NodeManager of their own.
+    fn chainable_operator<OP>(self, builder: OperatorBuilder<OP>) -> Stream<OP::OUT>
+    where
+        OP: Operator<IN = IN, TimerState = ArconNever, OperatorState = EmptyState> + 'static,
+    {
+        let chain = chain_constructor(builder.state_id(), builder.operator.clone());
+        let stream = self.operator(builder);
+        let dfg_id = stream.prev_dfg_ids[0];
+        stream.ctx.borrow_mut().dfg.get_mut(&dfg_id).chain = Some(chain);
+        stream
+    }
+
+    fn keyed_operator<OP, B>(
+        self,
+        builder: OperatorBuilder<OP, B>,
+        key_extractor: KeyExtractor<IN>,
+        channel_kind: ChannelKind,
+    ) -> Stream<OP::OUT>
+    where
+        OP: Operator<IN = IN> + 'static,
+        B: Backend,
+    {
+        let mut ctx = self.ctx.borrow_mut();
+
+        // Managed Operators are deployed with their max parallelism of which
+        // the runtime decides how many instances are active.
+        let (outgoing_channels, managed) = match builder.conf.parallelism_strategy {
+            ParallelismStrategy::Static(num) => (num, None),
+            ParallelismStrategy::Managed => {
+                let max = ctx.app.arcon_conf().max_managed_parallelism;
+                (max, Some(ManagedParallelism::new(1, max)))
+            }
+        };
+
+        // Create or restore the Backend of the operator
         let state_id = builder.state_id();
-        state_dir.push(state_id.clone());
-        let backend = builder.create_backend(state_dir, state_id.clone());
+        let backend = ctx
+            .app
+            .create_backend(&state_id, outgoing_channels)
+            .unwrap_or_else(|error| panic!("Failed to create backend of {}: {}", state_id, error));
+        if let Some(parallelism) = &managed {
+            parallelism.restore(backend.clone());
+        }
 
-        let outgoing_channels = match builder.conf.parallelism_strategy {
-            ParallelismStrategy::Static(num) => num,
-            _ => unreachable!("Managed Parallelism not Supported yet"),
+        let channel_kind = match (channel_kind, &managed) {
+            (ChannelKind::Keyed(key_extractor), Some(parallelism)) => {
+                ChannelKind::Managed(key_extractor, parallelism.clone())
+            }
+            (ChannelKind::Partitioned(_), Some(_)) => {
+                panic!("Partitioners are not supported for Operators with a managed parallelism")
+            }
+            (channel_kind, _) => channel_kind,
         };
 
         let manager_constructor = node_manager_constructor::<OP, _>(
             state_id,
-            self.ctx.app.data_system.clone(),
+            ctx.app.data_system.clone(),
             builder,
             backend,
-            self.ctx.app.arcon_logger.clone(),
+            key_extractor,
+            ctx.app.arcon_logger.clone(),
+            managed,
         );
 
-        let prev_dfg_node = self.ctx.dfg.get_mut(&self.prev_dfg_id);
-        let incoming_channels = prev_dfg_node.outgoing_channels;
+        let incoming_channels = self
+            .prev_dfg_ids
+            .iter()
+            .map(|id| ctx.dfg.get(id).outgoing_channels)
+            .sum();
 
-        let next_dfg_id = self.ctx.dfg.insert(DFGNode::new(
+        let mut dfg_node = DFGNode::new(
             DFGNodeKind::Node(manager_constructor),
             outgoing_channels,
             incoming_channels,
-            vec![self.prev_dfg_id],
-        ));
+            self.prev_dfg_ids.clone(),
+        );
+        dfg_node.channel_kind = channel_kind;
+        dfg_node.side_output = self.side_output.clone();
+        dfg_node.input_type = Some(TypeId::of::<IN>());
+        let next_dfg_id = ctx.dfg.insert(dfg_node);
 
-        self.prev_dfg_id = next_dfg_id;
+        drop(ctx);
         Stream {
             _marker: PhantomData,
-            prev_dfg_id: self.prev_dfg_id,
+            prev_dfg_ids: vec![next_dfg_id],
+            side_output: None,
             ctx: self.ctx,
         }
     }
 
     /// Will make sure the most downstream Node will print its result to the console
     ///
-    /// Note that if the Application has been configured with a debug node, it will take precedence.
+    /// Only this branch of the dataflow graph is printed. Note that if the Application has
+    /// been configured with a debug node, it will take precedence for the built stream.
     #[allow(clippy::wrong_self_convention)]
-    pub fn to_console(mut self) -> Stream<IN> {
-        self.ctx.console_output = true;
+    pub fn to_console(self) -> Stream<IN> {
+        let mut ctx = self.ctx.borrow_mut();
+        for id in &self.prev_dfg_ids {
+            ctx.console_outputs.push((*id, self.side_output.clone()));
+        }
+        drop(ctx);
+
+        Stream {
+            _marker: PhantomData,
+            prev_dfg_ids: self.prev_dfg_ids,
+            side_output: self.side_output,
+            ctx: self.ctx,
+        }
+    }
 
+    /// Returns the side output identified by `tag` as a separate stream
+    ///
+    /// The stream receives the elements that the operators of this stream emit through
+    /// [OperatorContext::output_to](crate::prelude::OperatorContext::output_to).
+    /// Elements that arrive after the watermark has passed their timestamp are found
+    /// under [OutputTag::late], typed by the input of the operator, or this panics.
+    ///
+    /// Example
+    /// ```no_run
+    /// use arcon::prelude::*;
+    /// let stream: Stream<u64> = Application::default()
+    ///     .iterator(0u64..100, |conf| {
+    ///         conf.set_arcon_time(ArconTime::Event);
+    ///         conf.set_timestamp_extractor(|x: &u64| *x);
+    ///     })
+    ///     .map(|x| x + 10);
+    ///
+    /// let late: Stream<u64> = stream.side_output(OutputTag::late());
+    /// let app = late.to_console().build();
+    /// ```
+    pub fn side_output<T: ArconType>(&self, tag: OutputTag<T>) -> Stream<T> {
+        assert!(
+            self.side_output.is_none(),
+            "A side output has no side outputs of its own"
+        );
+        let ctx = self.ctx.borrow();
+        if tag.id() == LATE_TAG {
+            let input_types = self
+                .prev_dfg_ids
+                .iter()
+                .filter_map(|id| ctx.dfg.get(id).input_type);
+            for input_type in input_types {
+                assert!(
+                    input_type == TypeId::of::<T>(),
+                    "Late elements are of the input type of the operator, not {}",
+                    std::any::type_name::<T>()
+                );
+            }
+        }
+        let credits = ctx.app.conf.channel_credits;
+        drop(ctx);
+
+        let tag = tag.key().clone();
         Stream {
             _marker: PhantomData,
-            prev_dfg_id: self.prev_dfg_id,
+            prev_dfg_ids: self.prev_dfg_ids.clone(),
+            side_output: Some(SideOutputEdge {
+                constructor: side_output_constructor::<T>(tag.clone(), credits),
+                tag,
+            }),
+            ctx: self.ctx.clone(),
+        }
+    }
+
+    /// Split the stream into two branches
+    ///
+    /// Every element of the stream is sent to both branches, which makes it possible to
+    /// feed several downstream pipelines from a single stream. Building any of the branches
+    /// builds the whole dataflow graph. This is equivalent to cloning the stream.
+    ///
+    /// Example
+    /// ```no_run
+    /// use arcon::prelude::*;
+    /// let (evens, odds) = Application::default()
+    ///     .iterator(0u64..100, |conf| {
+    ///         conf.set_arcon_time(ArconTime::Process);
+    ///     })
+    ///     .split();
+    ///
+    /// let _evens = evens.filter(|x| x % 2 == 0).map(|x| x + 10);
+    /// let app = odds.filter(|x| x % 2 == 1).to_console().build();
+    /// ```
+    pub fn split(self) -> (Stream<IN>, Stream<IN>) {
+        (self.clone(), self)
+    }
+
+    /// Merge this stream with another stream of the same type
+    ///
+    /// The next [`Operator`] receives the elements of both streams. Watermarks and epochs
+    /// are aligned across the inputs, so the resulting stream progresses at the pace of the slowest one.
+    ///
+    /// Both streams must be created from the same [`Application`], or this panics.
+    /// Unless both streams were split off the same stream, the graph of `other` is moved into
+    /// the graph of this stream. This panics if branches that were split off `other` through
+    /// [`Stream::split`] are still in use, so union those branches with `other` first.
+    ///
+    /// Example
+    /// ```no_run
+    /// use arcon::prelude::*;
+    /// let app = Application::default();
+    /// let other: Stream<u64> = app.clone().iterator(100u64..200, |conf| {
+    ///     conf.set_arcon_time(ArconTime::Process);
+    /// });
+    /// let stream: Stream<u64> = app
+    ///     .iterator(0u64..100, |conf| {
+    ///         conf.set_arcon_time(ArconTime::Process);
+    ///     })
+    ///     .union(other)
+    ///     .map(|x| x + 10);
+    /// ```
+    pub fn union(mut self, other: Stream<IN>) -> Stream<IN> {
+        assert!(
+            self.side_output.is_none() && other.side_output.is_none(),
+            "Side outputs must pass through an operator before a union"
+        );
+        let offset = if Rc::ptr_eq(&self.ctx, &other.ctx) {
+            // Branches of the same graph, nothing to merge
+            0
+        } else {
+            let other_ctx = match Rc::try_unwrap(other.ctx) {
+                Ok(ctx) => ctx.into_inner(),
+                Err(_) => {
+                    panic!("Cannot union with a Stream whose other branches are still in use")
+                }
+            };
+            let mut ctx = self.ctx.borrow_mut();
+            ctx.app.merge(other_ctx.app);
+            let offset = ctx.dfg.merge(other_ctx.dfg);
+            let console_outputs = other_ctx
+                .console_outputs
+                .into_iter()
+                .map(|(id, side_output)| (DFGNodeID(id.0 + offset), side_output));
+            ctx.console_outputs.extend(console_outputs);
+            offset
+        };
+
+        for id in other.prev_dfg_ids {
+            let id = DFGNodeID(id.0 + offset);
+            assert!(
+                !self.prev_dfg_ids.contains(&id),
+                "Cannot union a Stream with itself"
+            );
+            self.prev_dfg_ids.push(id);
+        }
+
+        Stream {
+            _marker: PhantomData,
+            prev_dfg_ids: self.prev_dfg_ids,
+            side_output: None,
             ctx: self.ctx,
         }
     }
 
+    /// Connect this stream with another stream of a possibly different type
+    ///
+    /// The returned [`ConnectedStreams`] may be consumed by a [`CoOperator`] that
+    /// handles the elements of each input separately while sharing state between them.
+    ///
+    /// Both streams must be created from the same [`Application`], or this panics,
+    /// and the same restriction on split branches as for [`Stream::union`] applies.
+    ///
+    /// Example
+    /// ```no_run
+    /// use arcon::prelude::*;
+    /// let app = Application::default();
+    /// let other: Stream<String> = app.clone().iterator(vec![String::from("a")], |conf| {
+    ///     conf.set_arcon_time(ArconTime::Process);
+    /// });
+    /// let connected: ConnectedStreams<u64, String> = app
+    ///     .iterator(0u64..100, |conf| {
+    ///         conf.set_arcon_time(ArconTime::Process);
+    ///     })
+    ///     .connect(other);
+    /// ```
+    pub fn connect<B: ArconType>(self, other: Stream<B>) -> ConnectedStreams<IN, B> {
+        let first = self.map(CoElement::<IN, B>::first);
+        let second = other.map(CoElement::<IN, B>::second);
+        ConnectedStreams {
+            stream: first.union(second),
+            key_extractor: None,
+        }
+    }
+
     /// Builds the Dataflow graph
     ///
     /// Returns a [`AssembledApplication`] where all runtime components
@@ -225,66 +578,579 @@ impl<IN: ArconType> Stream<IN> {
     ///
     /// Note that this method only builds the application. In order
     /// to start it, see the following [method](AssembledApplication::start).
-    pub fn build(mut self) -> AssembledApplication {
-        let mut target_nodes: Option<Vec<Arc<dyn std::any::Any + Send + Sync>>> = None;
+    pub fn build(self) -> AssembledApplication {
+        let (graph, mut app, console_outputs) = {
+            let mut ctx = self.ctx.borrow_mut();
+            let graph = std::mem::take(&mut ctx.dfg.graph);
+            let app = ctx.app.clone();
+            (graph, app, std::mem::take(&mut ctx.console_outputs))
+        };
+
+        let mut successors: Vec<Vec<DFGNodeID>> = vec![Vec::new(); graph.len()];
+        for (idx, dfg_node) in graph.iter().enumerate() {
+            for prev in &dfg_node.ingoing {
+                successors[prev.0].push(DFGNodeID(idx));
+            }
+        }
+
+        // Stateless operators that are the only regular successor of their ingoing node, and
+        // run with the same parallelism, are fused into the Nodes of the ingoing node.
+        let chaining = app.conf.operator_chaining && app.conf.distributed().is_none();
+        let mut chained: Vec<bool> = vec![false; graph.len()];
+        for (idx, dfg_node) in graph.iter().enumerate() {
+            let prints_side_output = console_outputs
+                .iter()
+                .any(|(id, side_output)| id.0 == idx && side_output.is_some());
+            chained[idx] = chaining
+                && !prints_side_output
+                && self.is_chainable(&graph, &successors, idx, dfg_node);
+        }
+
+        // Assign NodeIDs that are unique across the whole graph,
+        // so that nodes with several inputs can tell their senders apart.
+        // Chained nodes send on behalf of the Nodes they are fused into.
+        let mut next_node_id = 0;
+        let mut node_ids: Vec<Vec<NodeID>> = Vec::with_capacity(graph.len());
+        for (idx, dfg_node) in graph.iter().enumerate() {
+            if chained[idx] {
+                let ids = node_ids[dfg_node.ingoing[0].0].clone();
+                node_ids.push(ids);
+                continue;
+            }
+            let ids = (next_node_id..next_node_id + dfg_node.outgoing_channels)
+                .map(|i| NodeID::new(i as u32))
+                .collect();
+            next_node_id += dfg_node.outgoing_channels;
+            node_ids.push(ids);
+        }
+
+        let mut in_channels: Vec<Vec<NodeID>> = Vec::with_capacity(graph.len());
+        for dfg_node in graph.iter() {
+            in_channels.push(
+                dfg_node
+                    .ingoing
+                    .iter()
+                    .flat_map(|prev| node_ids[prev.0].clone())
+                    .collect(),
+            );
+        }
+
+        // Each edge is partitioned according to the ChannelKind of the consuming node
+        let channel_kinds: Vec<ChannelKind> = graph
+            .iter()
+            .zip(chained.iter())
+            .map(|(dfg_node, chained)| {
+                if *chained {
+                    ChannelKind::Chained
+                } else {
+                    dfg_node.channel_kind.clone()
+                }
+            })
+            .collect();
+        let side_outputs: Vec<Option<SideOutputEdge>> = graph
+            .iter()
+            .map(|dfg_node| dfg_node.side_output.clone())
+            .collect();
+
+        if app.debug_node_enabled() {
+            let node: DebugNode<IN> = DebugNode::new();
+            app.create_debug_node(node);
+        }
+        let debug_node = app.abstract_debug_node.clone();
+
+        let mut targets: Vec<Option<ErasedComponents>> = vec![None; graph.len()];
+
+        for (idx, dfg_node) in graph.into_iter().enumerate().rev() {
+            let outputs_stream = self.prev_dfg_ids.contains(&DFGNodeID(idx));
+            let mut edges: OutgoingEdges = Vec::new();
+            let mut side_edges: SideOutputEdges = Vec::new();
+
+            for next in &successors[idx] {
+                let edge = (
+                    channel_kinds[next.0].clone(),
+                    targets[next.0].clone().expect("successor not built"),
+                );
+                match &side_outputs[next.0] {
+                    Some(side_output) => push_side_edge(&mut side_edges, side_output, edge),
+                    None => edges.push(edge),
+                }
+            }
+
+            // Only the output of this stream matches the type of the debug node
+            let debug_edge = match &debug_node {
+                Some(debug_node) if outputs_stream => {
+                    Some((ChannelKind::Forward, vec![debug_node.clone()]))
+                }
+                _ => None,
+            };
+
+            for (_, side_output) in console_outputs.iter().filter(|(id, _)| id.0 == idx) {
+                let is_this_stream = match (side_output, &self.side_output) {
+                    (Some(a), Some(b)) => a.tag == b.tag,
+                    (a, b) => a.is_none() && b.is_none(),
+                };
+                if is_this_stream && debug_edge.is_some() {
+                    // The debug node takes precedence
+                    continue;
+                }
+                let edge = (ChannelKind::Console, vec![]);
+                match side_output {
+                    Some(side_output) => push_side_edge(&mut side_edges, side_output, edge),
+                    None => edges.push(edge),
+                }
+            }
+
+            if let Some(edge) = debug_edge {
+                match &self.side_output {
+                    Some(side_output) => push_side_edge(&mut side_edges, side_output, edge),
+                    None => edges.push(edge),
+                }
+            }
+
+            if edges.is_empty() {
+                // At the end of the graph....
+                edges.push((ChannelKind::Mute, vec![]));
+            }
+
+            if chained[idx] {
+                let chain = dfg_node
+                    .chain
+                    .expect("chained node without chain constructor");
+                targets[idx] = Some(vec![chain(edges, &mut app)]);
+                continue;
+            }
 
-        for dfg_node in self.ctx.dfg.graph.into_iter().rev() {
             match dfg_node.kind {
-                DFGNodeKind::Source(channel_kind, source_manager_cons) => {
-                    let nodes = target_nodes.take().unwrap();
+                DFGNodeKind::Source(source_manager_cons) => {
+                    assert!(side_edges.is_empty(), "Sources have no side outputs");
                     let source_manager =
-                        source_manager_cons(nodes, channel_kind, &mut self.ctx.app);
+                        source_manager_cons(node_ids[idx].clone(), edges, &mut app);
 
-                    self.ctx.app.source_manager = Some(source_manager);
+                    if let Some(source_manager) = source_manager {
+                        app.source_managers.push(source_manager);
+                    }
                 }
                 DFGNodeKind::Node(manager_cons) => {
-                    let (channel_kind, components) = {
-                        match target_nodes {
-                            Some(comps) => (dfg_node.channel_kind, comps),
-                            None => {
-                                // At the end of the graph....
-                                if self.ctx.app.debug_node_enabled() {
-                                    let node: DebugNode<IN> = DebugNode::new();
-                                    self.ctx.app.create_debug_node(node);
-                                }
-
-                                match self.ctx.app.abstract_debug_node {
-                                    Some(ref debug_node) => {
-                                        (ChannelKind::Forward, vec![debug_node.clone()])
-                                    }
-                                    None => (
-                                        if self.ctx.console_output {
-                                            ChannelKind::Console
-                                        } else {
-                                            ChannelKind::Mute
-                                        },
-                                        vec![],
-                                    ),
-                                }
-                            }
-                        }
-                    };
-
-                    // Create expected incoming channels ids
-                    let in_channels: Vec<NodeID> = (0..dfg_node.ingoing_channels)
-                        .map(|i| NodeID::new(i as u32))
-                        .collect();
-
-                    let nodes =
-                        manager_cons(in_channels, components, channel_kind, &mut self.ctx.app);
-
-                    target_nodes = Some(nodes);
+                    let nodes = manager_cons(
+                        std::mem::take(&mut in_channels[idx]),
+                        node_ids[idx].clone(),
+                        edges,
+                        side_edges,
+                        &mut app,
+                    );
+
+                    targets[idx] = Some(nodes);
                 }
             }
         }
-        AssembledApplication::new(self.ctx.app)
+        AssembledApplication::new(app)
+    }
+
+    /// Returns true if the node at `idx` may be fused into the Nodes of its ingoing node
+    fn is_chainable(
+        &self,
+        graph: &[DFGNode],
+        successors: &[Vec<DFGNodeID>],
+        idx: usize,
+        dfg_node: &DFGNode,
+    ) -> bool {
+        if dfg_node.chain.is_none() || dfg_node.side_output.is_some() {
+            return false;
+        }
+        // Only edges that keep elements on the instance they were produced on may be fused,
+        // rebalanced and explicitly keyed edges distribute the stream on purpose.
+        match &dfg_node.channel_kind {
+            ChannelKind::Forward | ChannelKind::Keyed(None) => (),
+            _ => return false,
+        }
+        let prev = match dfg_node.ingoing.as_slice() {
+            [prev] => prev,
+            _ => return false,
+        };
+        // Instances of a managed Operator may be deactivated along with their chains
+        if let ChannelKind::Managed(..) = graph[prev.0].channel_kind {
+            return false;
+        }
+        let regular_successors = successors[prev.0]
+            .iter()
+            .filter(|next| graph[next.0].side_output.is_none())
+            .count();
+        if regular_successors != 1 || graph[prev.0].outgoing_channels != dfg_node.outgoing_channels
+        {
+            return false;
+        }
+        // Chained Operators have no side outputs, which includes the late elements of their input
+        let has_side_outputs = successors[idx]
+            .iter()
+            .any(|next| graph[next.0].side_output.is_some())
+            || (self.side_output.is_some() && self.prev_dfg_ids.contains(&DFGNodeID(idx)));
+        !has_side_outputs
     }
 
     pub(crate) fn new(ctx: Context) -> Self {
         Self {
             _marker: PhantomData,
-            prev_dfg_id: DFGNodeID(0),
-            ctx,
+            prev_dfg_ids: vec![DFGNodeID(0)],
+            side_output: None,
+            ctx: Rc::new(RefCell::new(ctx)),
         }
     }
 }
+
+/// A [`Stream`] that has been partitioned by a user-defined key
+///
+/// Created through [`Stream::key_by`].
+pub struct KeyedStream<K, IN: ArconType> {
+    stream: Stream<IN>,
+    key_extractor: KeyExtractor<IN>,
+    partitioner: Option<Arc<dyn Partitioner<IN>>>,
+    _marker: PhantomData<K>,
+}
+
+impl<K, IN: ArconType> KeyedStream<K, IN> {
+    /// Decide the instance of the next [`Operator`] an element is sent to through `partitioner`
+    ///
+    /// The state of the operator remains scoped by the extracted key, so the partitioner
+    /// should send all elements of a key to the same instance.
+    ///
+    /// Example
+    /// ```no_run
+    /// use arcon::prelude::*;
+    /// let stream: Stream<u64> = Application::default()
+    ///     .iterator(0u64..100, |conf| {
+    ///         conf.set_arcon_time(ArconTime::Process);
+    ///     })
+    ///     .key_by(|x| *x)
+    ///     .with_partitioner(ConsistentHashPartitioner::new(|x: &u64| *x))
+    ///     .operator(OperatorBuilder {
+    ///         operator: Arc::new(|| Map::new(|x| x + 10)),
+    ///         state: Arc::new(|_| EmptyState),
+    ///         conf: Default::default(),
+    ///     });
+    /// ```
+    pub fn with_partitioner(mut self, partitioner: impl Partitioner<IN>) -> Self {
+        self.partitioner = Some(Arc::new(partitioner));
+        self
+    }
+
+    /// Add an [`Operator`] that consumes the keyed stream
+    ///
+    /// Elements are partitioned across the instances of the operator
+    /// and its state is scoped by the extracted key.
+    pub fn operator<OP>(self, builder: OperatorBuilder<OP>) -> Stream<OP::OUT>
+    where
+        OP: Operator<IN = IN> + 'static,
+    {
+        let channel_kind = match self.partitioner {
+            Some(partitioner) => {
+                let erased: ErasedPartitioner = Arc::new(partitioner);
+                ChannelKind::Partitioned(erased)
+            }
+            None => {
+                let erased: ErasedKeyExtractor = Arc::new(self.key_extractor.clone());
+                ChannelKind::Keyed(Some(erased))
+            }
+        };
+        self.stream
+            .keyed_operator(builder, self.key_extractor, channel_kind)
+    }
+}
+
+/// A [`Stream`] whose elements are spread over the instances of the next [`Operator`] regardless of their key
+///
+/// Created through [`Stream::shuffle`] and [`Stream::rebalance`]. As elements of the same
+/// key may end up at any instance, it is meant to be consumed by stateless operators.
+pub struct RebalancedStream<IN: ArconType> {
+    stream: Stream<IN>,
+    channel_kind: ChannelKind,
+}
+
+impl<IN: ArconType> RebalancedStream<IN> {
+    /// Add an [`Operator`] that consumes the rebalanced stream
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Operator has timers or keyed state, as these are scoped to keys
+    /// that the rebalanced stream does not route by.
+    pub fn operator<OP>(self, builder: OperatorBuilder<OP>) -> Stream<OP::OUT>
+    where
+        OP: Operator<IN = IN> + 'static,
+    {
+        assert!(
+            TypeId::of::<OP::TimerState>() == TypeId::of::<ArconNever>()
+                && TypeId::of::<OP::OperatorState>() == TypeId::of::<EmptyState>(),
+            "Rebalanced streams are not supported for Operators with timers or keyed state"
+        );
+        // elements of a rebalanced stream are not scoped to a key
+        let key_extractor: KeyExtractor<IN> = Arc::new(|_: &IN| 0);
+        self.stream
+            .keyed_operator(builder, key_extractor, self.channel_kind)
+    }
+
+    /// Adds a stateless Map operator that consumes the rebalanced stream
+    pub fn map<F, OUT>(self, f: F) -> Stream<OUT>
+    where
+        OUT: ArconType,
+        F: Fn(IN) -> OUT + ArconFnBounds,
+    {
+        self.operator(OperatorBuilder {
+            operator: Arc::new(move || Map::new(f.clone())),
+            state: Arc::new(|_| EmptyState),
+            conf: Default::default(),
+        })
+    }
+
+    /// Adds a stateless Filter operator that consumes the rebalanced stream
+    pub fn filter<F>(self, f: F) -> Stream<IN>
+    where
+        F: Fn(&IN) -> bool + ArconFnBounds,
+    {
+        self.operator(OperatorBuilder {
+            operator: Arc::new(move || Filter::new(f.clone())),
+            state: Arc::new(|_| EmptyState),
+            conf: Default::default(),
+        })
+    }
+
+    /// Adds a stateless Flatmap operator that consumes the rebalanced stream
+    pub fn flatmap<F, OUTS>(self, f: F) -> Stream<OUTS::Item>
+    where
+        OUTS: IntoIterator + 'static,
+        OUTS::Item: ArconType,
+        F: Fn(IN) -> OUTS + ArconFnBounds,
+    {
+        self.operator(OperatorBuilder {
+            operator: Arc::new(move || FlatMap::new(f.clone())),
+            state: Arc::new(|_| EmptyState),
+            conf: Default::default(),
+        })
+    }
+}
+
+/// Two streams that have been connected through [`Stream::connect`]
+///
+/// The elements of both streams are consumed by a single [`CoOperator`].
+pub struct ConnectedStreams<IN1: ArconType, IN2: ArconType> {
+    stream: Stream<CoElement<IN1, IN2>>,
+    key_extractor: Option<KeyExtractor<CoElement<IN1, IN2>>>,
+}
+
+impl<IN1: ArconType, IN2: ArconType> ConnectedStreams<IN1, IN2> {
+    /// Partition both streams by a key of the same type
+    ///
+    /// Elements of the two streams with equal keys are sent to the same
+    /// instance of the next [`CoOperator`] and share its keyed state.
+    ///
+    /// Example
+    /// ```no_run
+    /// use arcon::prelude::*;
+    /// let app = Application::default();
+    /// let other: Stream<u32> = app.clone().iterator(0u32..100, |conf| {
+    ///     conf.set_arcon_time(ArconTime::Process);
+    /// });
+    /// let connected: ConnectedStreams<u64, u32> = app
+    ///     .iterator(0u64..100, |conf| {
+    ///         conf.set_arcon_time(ArconTime::Process);
+    ///     })
+    ///     .connect(other)
+    ///     .key_by(|x| x % 10, |y| (y % 10) as u64);
+    /// ```
+    pub fn key_by<K, F1, F2>(mut self, f1: F1, f2: F2) -> ConnectedStreams<IN1, IN2>
+    where
+        K: Hash + 'static,
+        F1: Fn(&IN1) -> K + ArconFnBounds,
+        F2: Fn(&IN2) -> K + ArconFnBounds,
+    {
+        self.key_extractor = Some(Arc::new(move |data: &CoElement<IN1, IN2>| {
+            match data.input() {
+                CoInput::First(data) => hash_key(f1(data)),
+                CoInput::Second(data) => hash_key(f2(data)),
+            }
+        }));
+        self
+    }
+
+    /// Add a [`CoOperator`] that consumes both streams
+    ///
+    /// If the streams have been keyed through [`ConnectedStreams::key_by`], the
+    /// elements are partitioned by the extracted keys.
+    pub fn operator<C>(self, builder: CoOperatorBuilder<C>) -> Stream<C::OUT>
+    where
+        C: CoOperator<IN1 = IN1, IN2 = IN2> + 'static,
+    {
+        self.backend_operator(builder)
+    }
+
+    // Adds a CoOperator whose state is kept in the Backend `B`
+    fn backend_operator<C, B>(self, builder: CoOperatorBuilder<C, B>) -> Stream<C::OUT>
+    where
+        C: CoOperator<IN1 = IN1, IN2 = IN2> + 'static,
+        B: Backend,
+    {
+        let co_operator = builder.operator;
+        let builder = OperatorBuilder {
+            operator: Arc::new(move || CoOperatorAdapter::new(co_operator())),
+            state: builder.state,
+            conf: builder.conf,
+        };
+
+        match self.key_extractor {
+            Some(key_extractor) => {
+                let erased: ErasedKeyExtractor = Arc::new(key_extractor.clone());
+                self.stream
+                    .keyed_operator(builder, key_extractor, ChannelKind::Keyed(Some(erased)))
+            }
+            None => {
+                self.stream
+                    .keyed_operator(builder, default_key_extractor(), ChannelKind::default())
+            }
+        }
+    }
+
+    /// Join the elements of both streams that fall into the same event-time window
+    ///
+    /// Every pair of elements of a window is passed to `join_fn` once the window closes.
+    /// For a [StreamKind::Keyed](crate::prelude::StreamKind) join, only elements with equal
+    /// keys are paired, see [`ConnectedStreams::key_by`].
+    ///
+    /// Example
+    /// ```no_run
+    /// use arcon::prelude::*;
+    /// let app = Application::default();
+    /// let other: Stream<u64> = app.clone().iterator(0u64..100, |conf| {
+    ///     conf.set_arcon_time(ArconTime::Event);
+    ///     conf.set_timestamp_extractor(|x: &u64| *x);
+    /// });
+    /// let joined: Stream<u64> = app
+    ///     .iterator(0u64..100, |conf| {
+    ///         conf.set_arcon_time(ArconTime::Event);
+    ///         conf.set_timestamp_extractor(|x: &u64| *x);
+    ///     })
+    ///     .connect(other)
+    ///     .key_by(|x| x % 10, |y| y % 10)
+    ///     .window_join(
+    ///         WindowConf {
+    ///             assigner: Assigner::Tumbling {
+    ///                 length: Time::seconds(10),
+    ///                 late_arrival: Time::seconds(0),
+    ///             },
+    ///             kind: StreamKind::Keyed,
+    ///         },
+    ///         |a, b| a + b,
+    ///     );
+    /// ```
+    pub fn window_join<OUT, F>(self, conf: WindowConf, join_fn: F) -> Stream<OUT>
+    where
+        OUT: ArconType,
+        F: Fn(&IN1, &IN2) -> OUT + ArconFnBounds,
+    {
+        self.window_join_in::<DefaultBackend, _, _>(conf, join_fn)
+    }
+
+    /// Same as [`ConnectedStreams::window_join`], but keeps the buffered elements in the Backend `B`
+    ///
+    /// Example
+    /// ```no_run
+    /// use arcon::prelude::*;
+    /// # fn join(connected: ConnectedStreams<u64, u64>, conf: WindowConf) -> Stream<u64> {
+    /// connected.window_join_in::<Sled, _, _>(conf, |a, b| a + b)
+    /// # }
+    /// ```
+    pub fn window_join_in<B, OUT, F>(self, conf: WindowConf, join_fn: F) -> Stream<OUT>
+    where
+        B: Backend,
+        OUT: ArconType,
+        F: Fn(&IN1, &IN2) -> OUT + ArconFnBounds,
+    {
+        self.backend_operator(CoOperatorBuilder {
+            operator: Arc::new(move || {
+                WindowJoin::<_, _, _, _, B>::new(conf.clone(), join_fn.clone())
+            }),
+            state: Arc::new(WindowJoinState::new),
+            conf: Default::default(),
+        })
+    }
+
+    /// Join each element of the first stream with the elements of the second stream within an interval
+    ///
+    /// An element `a` is joined with an element `b` if `a.ts - lower <= b.ts <= a.ts + upper`,
+    /// see [IntervalJoinConf](crate::prelude::IntervalJoinConf).
+    ///
+    /// Example
+    /// ```no_run
+    /// use arcon::prelude::*;
+    /// let app = Application::default();
+    /// let other: Stream<u64> = app.clone().iterator(0u64..100, |conf| {
+    ///     conf.set_arcon_time(ArconTime::Event);
+    ///     conf.set_timestamp_extractor(|x: &u64| *x);
+    /// });
+    /// let joined: Stream<u64> = app
+    ///     .iterator(0u64..100, |conf| {
+    ///         conf.set_arcon_time(ArconTime::Event);
+    ///         conf.set_timestamp_extractor(|x: &u64| *x);
+    ///     })
+    ///     .connect(other)
+    ///     .key_by(|x| x % 10, |y| y % 10)
+    ///     .interval_join(
+    ///         IntervalJoinConf {
+    ///             lower: Time::seconds(2),
+    ///             upper: Time::seconds(2),
+    ///             kind: StreamKind::Keyed,
+    ///         },
+    ///         |a, b| a + b,
+    ///     );
+    /// ```
+    pub fn interval_join<OUT, F>(self, conf: IntervalJoinConf, join_fn: F) -> Stream<OUT>
+    where
+        OUT: ArconType,
+        F: Fn(&IN1, &IN2) -> OUT + ArconFnBounds,
+    {
+        self.interval_join_in::<DefaultBackend, _, _>(conf, join_fn)
+    }
+
+    /// Same as [`ConnectedStreams::interval_join`], but keeps the buffered elements in the Backend `B`
+    ///
+    /// Example
+    /// ```no_run
+    /// use arcon::prelude::*;
+    /// # fn join(connected: ConnectedStreams<u64, u64>, conf: IntervalJoinConf) -> Stream<u64> {
+    /// connected.interval_join_in::<Sled, _, _>(conf, |a, b| a + b)
+    /// # }
+    /// ```
+    pub fn interval_join_in<B, OUT, F>(self, conf: IntervalJoinConf, join_fn: F) -> Stream<OUT>
+    where
+        B: Backend,
+        OUT: ArconType,
+        F: Fn(&IN1, &IN2) -> OUT + ArconFnBounds,
+    {
+        self.backend_operator(CoOperatorBuilder {
+            operator: Arc::new(move || {
+                IntervalJoin::<_, _, _, _, B>::new(conf.clone(), join_fn.clone())
+            }),
+            state: Arc::new(IntervalJoinState::new),
+            conf: Default::default(),
+        })
+    }
+}
+
+// Adds `edge` to the outgoing edges of the side output it belongs to
+fn push_side_edge(
+    side_edges: &mut SideOutputEdges,
+    side_output: &SideOutputEdge,
+    edge: (ChannelKind, ErasedComponents),
+) {
+    match side_edges
+        .iter_mut()
+        .find(|(s, _)| s.tag == side_output.tag)
+    {
+        Some((_, edges)) => edges.push(edge),
+        None => side_edges.push((side_output.clone(), vec![edge])),
+    }
+}
+
+// Hashes a user-defined key into the u64 key used by the runtime
+fn hash_key<K: Hash>(key: K) -> u64 {
+    let mut hasher = FxHasher::default();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
@@ -15,7 +15,7 @@ pub mod timer;
 use arcon_state::error::ArconStateError;
 use arrow::error::ArrowError;
 use snafu::{Backtrace, Snafu};
-use std::{io, io::ErrorKind};
+use std::{io, io::ErrorKind, time::Duration};
 
 // Inspired by Sled's error management approach.
 // http://sled.rs/errors.html
@@ -37,6 +37,12 @@ pub enum Error {
         msg
     ))]
     ReportableBug { msg: String },
+    /// The Application could not be restored from a checkpoint.
+    #[snafu(display("Failed to restore from a checkpoint: {}", msg))]
+    Restore { msg: String },
+    /// A savepoint was not committed within the configured timeout.
+    #[snafu(display("Savepoint was not committed within {:?}", timeout))]
+    SavepointTimeout { timeout: Duration },
     /// A read or write error has happened when interacting with the file system.
     #[snafu(display("An IO error occured {}", error))]
     Io { error: io::Error },
@@ -63,6 +69,14 @@ impl From<Error> for io::Error {
                 ErrorKind::InvalidInput,
                 format!("operation not supported: {:?}", msg),
             ),
+            Restore { ref msg } => io::Error::new(
+                ErrorKind::NotFound,
+                format!("failed to restore from a checkpoint: {:?}", msg),
+            ),
+            SavepointTimeout { timeout } => io::Error::new(
+                ErrorKind::TimedOut,
+                format!("savepoint was not committed within {:?}", timeout),
+            ),
             ReportableBug { ref msg } => io::Error::new(
                 ErrorKind::Other,
                 format!(
@@ -8,6 +8,11 @@ use std::fmt::Debug;
 /// TimerResult type utilised while scheduling timers
 pub type TimerResult<A> = ArconResult<std::result::Result<(), TimerExpiredError<A>>>;
 
+/// RescheduleResult type utilised while rescheduling timers
+///
+/// Holds `None` if no timer was pending for the rescheduled key.
+pub type RescheduleResult<A> = ArconResult<Option<std::result::Result<(), TimerExpiredError<A>>>>;
+
 #[derive(Debug, Snafu)]
 #[snafu(display(
     "Attempted to schedule timer entry {:?} at {} when time is {}",
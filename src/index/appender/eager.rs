@@ -3,7 +3,10 @@
 
 use crate::{
     error::ArconResult,
-    index::{AppenderIndex, IndexOps},
+    index::{
+        ttl::{Ttl, TtlConf},
+        AppenderIndex, IndexOps,
+    },
     table::ImmutableTable,
 };
 use arcon_state::{
@@ -24,6 +27,9 @@ where
 {
     /// A handle to the VecState
     handle: ActiveHandle<B, VecState<V>, u64>,
+    current_key: u64,
+    /// Optional time-to-live of the appenders
+    ttl: Option<Ttl<u64, B>>,
 }
 
 impl<V, B> EagerAppender<V, B>
@@ -36,7 +42,40 @@ where
         let mut handle = Handle::vec(id.into()).with_item_key(0);
         backend.register_vec_handle(&mut handle);
         let handle: ActiveHandle<B, VecState<V>, u64> = handle.activate(backend);
-        EagerAppender { handle }
+        EagerAppender {
+            handle,
+            current_key: 0,
+            ttl: None,
+        }
+    }
+
+    /// Creates an EagerAppender whose appenders expire according to a [TtlConf]
+    pub fn with_ttl(id: impl Into<String>, backend: Arc<B>, conf: TtlConf) -> Self {
+        let id = id.into();
+        let ttl = Ttl::new(&id, backend.clone(), conf);
+        let mut index = Self::new(id, backend);
+        index.ttl = Some(ttl);
+        index
+    }
+
+    #[inline]
+    fn expired(&self) -> Result<bool> {
+        match &self.ttl {
+            Some(ttl) => ttl.is_expired(&self.current_key),
+            None => Ok(false),
+        }
+    }
+
+    /// Removes all appenders that have outlived their ttl
+    fn purge_expired(&mut self) -> Result<()> {
+        if let Some(ttl) = &self.ttl {
+            for key in ttl.drain_expired()? {
+                self.handle.set_item_key(key);
+                self.handle.clear()?;
+            }
+            self.handle.set_item_key(self.current_key);
+        }
+        Ok(())
     }
 }
 
@@ -46,11 +85,18 @@ where
     B: Backend,
 {
     fn persist(&mut self) -> ArconResult<()> {
+        self.purge_expired()?;
         Ok(())
     }
     fn set_key(&mut self, key: u64) {
+        self.current_key = key;
         self.handle.set_item_key(key);
     }
+    fn set_watermark(&mut self, watermark: u64) {
+        if let Some(ttl) = &mut self.ttl {
+            ttl.set_watermark(watermark);
+        }
+    }
     fn table(&mut self) -> ArconResult<Option<ImmutableTable>> {
         Ok(None)
     }
@@ -63,16 +109,29 @@ where
 {
     #[inline]
     fn append(&mut self, data: V) -> Result<()> {
+        if self.expired()? {
+            self.handle.clear()?;
+        }
+        if let Some(ttl) = &self.ttl {
+            ttl.on_write(self.current_key)?;
+        }
         self.handle.append(data)
     }
     #[inline]
     fn consume(&mut self) -> Result<Vec<V>> {
+        let expired = self.expired()?;
+        if let Some(ttl) = &self.ttl {
+            ttl.remove(&self.current_key)?;
+        }
         let stored = self.handle.get()?;
         self.handle.clear()?;
-        Ok(stored)
+        Ok(if expired { Vec::new() } else { stored })
     }
     #[inline]
     fn len(&self) -> usize {
+        if self.expired().unwrap_or(false) {
+            return 0;
+        }
         self.handle.len().unwrap_or(0)
     }
     #[inline]
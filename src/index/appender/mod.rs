@@ -144,4 +144,36 @@ mod tests {
         let index = EagerAppender::new("appender", backend);
         assert!(index_test(index).is_ok());
     }
+
+    #[test]
+    fn eager_appender_ttl_test() {
+        use crate::{index::TtlConf, stream::time::Time};
+        let backend = Arc::new(temp_backend::<Sled>());
+        let conf = TtlConf::event_time(Time::seconds(10));
+        let mut index: EagerAppender<u64, _> = EagerAppender::with_ttl("appender", backend, conf);
+
+        index.set_watermark(0);
+        index.set_key(0);
+        index.append(1).unwrap();
+        index.set_key(1);
+        index.append(2).unwrap();
+
+        // refresh key 1 while key 0 is left untouched
+        index.set_watermark(5);
+        index.append(3).unwrap();
+
+        index.set_watermark(10);
+        index.set_key(0);
+        assert!(index.is_empty());
+        // appending to an expired appender starts it over
+        index.append(4).unwrap();
+        assert_eq!(index.consume().unwrap(), vec![4]);
+        index.set_key(1);
+        assert_eq!(index.len(), 2);
+
+        index.set_watermark(15);
+        index.persist().unwrap();
+        index.set_watermark(0);
+        assert!(index.consume().unwrap().is_empty());
+    }
 }
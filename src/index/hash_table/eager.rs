@@ -2,7 +2,13 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 
 use crate::error::ArconResult;
-use crate::{index::IndexOps, table::ImmutableTable};
+use crate::{
+    index::{
+        ttl::{Ttl, TtlConf},
+        IndexOps,
+    },
+    table::ImmutableTable,
+};
 use arcon_state::{
     backend::{
         handles::{ActiveHandle, BoxedIteratorOfResult, Handle},
@@ -21,6 +27,8 @@ where
 {
     /// Map Handle
     handle: ActiveHandle<B, MapState<K, V>>,
+    /// Optional time-to-live of the entries
+    ttl: Option<Ttl<K, B>>,
 }
 
 impl<K, V, B> EagerHashTable<K, V, B>
@@ -33,30 +41,74 @@ where
         let mut handle = Handle::map(id.into());
         backend.register_map_handle(&mut handle);
         let handle = handle.activate(backend);
-        Self { handle }
+        Self { handle, ttl: None }
     }
+
+    /// Creates an EagerHashTable whose entries expire according to a [TtlConf]
+    pub fn with_ttl(id: impl Into<String>, backend: Arc<B>, conf: TtlConf) -> Self {
+        let id = id.into();
+        let ttl = Ttl::new(&id, backend.clone(), conf);
+        let mut table = Self::new(id, backend);
+        table.ttl = Some(ttl);
+        table
+    }
+
     /// Insert a key-value record
     #[inline(always)]
     pub fn put(&mut self, key: K, value: V) -> Result<()> {
+        if let Some(ttl) = &self.ttl {
+            ttl.on_write(key.clone())?;
+        }
         self.handle.fast_insert(key, value)
     }
 
     #[inline(always)]
     pub fn get(&self, k: &K) -> Result<Option<V>> {
-        self.handle.get(k)
+        if self.expired(k)? {
+            return Ok(None);
+        }
+        let value = self.handle.get(k)?;
+        if let (Some(ttl), Some(_)) = (&self.ttl, &value) {
+            ttl.on_read(k.clone())?;
+        }
+        Ok(value)
     }
 
     #[inline(always)]
     pub fn remove(&self, k: &K) -> Result<Option<V>> {
-        self.handle.remove(k)
+        let expired = self.expired(k)?;
+        if let Some(ttl) = &self.ttl {
+            ttl.remove(k)?;
+        }
+        let value = self.handle.remove(k)?;
+        Ok(if expired { None } else { value })
     }
     #[inline(always)]
     pub fn contains(&self, k: &K) -> Result<bool> {
-        self.handle.contains(k)
+        Ok(!self.expired(k)? && self.handle.contains(k)?)
     }
     #[inline(always)]
     pub fn iter(&self) -> Result<BoxedIteratorOfResult<(K, V)>> {
-        self.handle.iter()
+        let iter = self.handle.iter()?;
+        match &self.ttl {
+            Some(ttl) => Ok(Box::new(iter.filter_map(move |entry| match entry {
+                Ok((key, value)) => match ttl.is_expired(&key) {
+                    Ok(true) => None,
+                    Ok(false) => Some(Ok((key, value))),
+                    Err(error) => Some(Err(error)),
+                },
+                Err(error) => Some(Err(error)),
+            }))),
+            None => Ok(iter),
+        }
+    }
+
+    #[inline(always)]
+    fn expired(&self, k: &K) -> Result<bool> {
+        match &self.ttl {
+            Some(ttl) => ttl.is_expired(k),
+            None => Ok(false),
+        }
     }
 }
 
@@ -67,9 +119,20 @@ where
     B: Backend,
 {
     fn persist(&mut self) -> ArconResult<()> {
+        // purge entries that have outlived their ttl
+        if let Some(ttl) = &self.ttl {
+            for key in ttl.drain_expired()? {
+                let _ = self.handle.remove(&key)?;
+            }
+        }
         Ok(())
     }
     fn set_key(&mut self, _: u64) {}
+    fn set_watermark(&mut self, watermark: u64) {
+        if let Some(ttl) = &mut self.ttl {
+            ttl.set_watermark(watermark);
+        }
+    }
     fn table(&mut self) -> ArconResult<Option<ImmutableTable>> {
         Ok(None)
     }
@@ -72,6 +72,8 @@ where
     hash_builder: fxhash::FxBuildHasher,
     /// In-memory RawTable
     raw_table: UnsafeCell<RawTable<K, V>>,
+    /// Capacities of the MOD and READ lanes of the RawTable
+    capacity: (usize, usize),
     /// HashTable Handle
     handle: ActiveHandle<B, MapState<K, V>>,
 }
@@ -101,6 +103,7 @@ where
                 DEFAULT_MOD_LANE_SIZE,
                 DEFAULT_READ_LANE_SIZE,
             )),
+            capacity: (DEFAULT_MOD_LANE_SIZE, DEFAULT_READ_LANE_SIZE),
             handle,
         }
     }
@@ -122,6 +125,7 @@ where
         HashTable {
             hash_builder: DefaultHashBuilder::default(),
             raw_table: UnsafeCell::new(RawTable::with_capacity(mod_capacity, read_capacity)),
+            capacity: (mod_capacity, read_capacity),
             handle,
         }
     }
@@ -369,6 +373,10 @@ where
     fn table(&mut self) -> ArconResult<Option<ImmutableTable>> {
         Ok(None)
     }
+    fn clear_cache(&mut self) {
+        let (mod_capacity, read_capacity) = self.capacity;
+        *self.raw_table_mut() = RawTable::with_capacity(mod_capacity, read_capacity);
+    }
 }
 
 #[cfg(test)]
@@ -426,4 +434,63 @@ mod tests {
             assert_eq!(value, &(key + 1));
         }
     }
+
+    #[test]
+    fn eager_ttl_test() {
+        use crate::{index::TtlConf, stream::time::Time};
+        use eager::EagerHashTable;
+        let backend = Arc::new(temp_backend::<Sled>());
+        let conf = TtlConf::event_time(Time::seconds(10));
+        let mut table: EagerHashTable<u64, u64, Sled> =
+            EagerHashTable::with_ttl("table", backend.clone(), conf);
+
+        table.set_watermark(0);
+        table.put(0, 0).unwrap();
+        table.put(1, 1).unwrap();
+        table.set_watermark(5);
+        table.put(1, 2).unwrap();
+        table.put(2, 2).unwrap();
+
+        table.set_watermark(10);
+        assert_eq!(table.get(&0).unwrap(), None);
+        assert!(!table.contains(&0).unwrap());
+        assert_eq!(table.get(&1).unwrap(), Some(2));
+        let mut keys: Vec<u64> = table.iter().unwrap().map(|e| e.unwrap().0).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, vec![1, 2]);
+
+        // expired entries are purged on persist
+        table.persist().unwrap();
+        table.set_watermark(0);
+        assert_eq!(table.get(&0).unwrap(), None);
+
+        // the expiry order is rebuilt when the table is restored
+        let mut table: EagerHashTable<u64, u64, Sled> =
+            EagerHashTable::with_ttl("table", backend, conf);
+        table.set_watermark(15);
+        table.persist().unwrap();
+        table.set_watermark(0);
+        assert_eq!(table.get(&1).unwrap(), None);
+        assert_eq!(table.get(&2).unwrap(), None);
+    }
+
+    #[test]
+    fn eager_processing_time_ttl_test() {
+        use crate::{index::TtlConf, stream::time::Time};
+        use eager::EagerHashTable;
+        let backend = Arc::new(temp_backend::<Sled>());
+        let conf = TtlConf::processing_time(Time::seconds(1));
+        let mut table: EagerHashTable<u64, u64, Sled> =
+            EagerHashTable::with_ttl("table", backend, conf);
+
+        table.put(0, 0).unwrap();
+        assert_eq!(table.get(&0).unwrap(), Some(0));
+        table.persist().unwrap();
+        assert_eq!(table.get(&0).unwrap(), Some(0));
+
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+        assert_eq!(table.get(&0).unwrap(), None);
+        table.persist().unwrap();
+        assert_eq!(table.iter().unwrap().count(), 0);
+    }
 }
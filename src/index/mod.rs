@@ -5,6 +5,7 @@
 pub mod appender;
 pub mod hash_table;
 pub mod timer;
+pub mod ttl;
 pub mod value;
 pub mod window;
 
@@ -26,6 +27,7 @@ pub use self::{
     appender::eager::EagerAppender,
     hash_table::{eager::EagerHashTable, HashTable},
     timer::{Timer, TimerEvent},
+    ttl::{TtlConf, TtlUpdate},
     value::{EagerValue, LazyValue, LocalValue},
     window::appender::AppenderWindow,
     window::arrow::ArrowWindow,
@@ -43,6 +45,17 @@ pub trait IndexOps {
 
     /// Create a [ImmutableTable] from the data in the Index
     fn table(&mut self) -> ArconResult<Option<ImmutableTable>>;
+
+    /// Inform the index about the current watermark
+    ///
+    /// Used by indexes that expire entries in event time.
+    fn set_watermark(&mut self, _watermark: u64) {}
+
+    /// Drop the entries that the index keeps in memory so that they are read from the Backend again
+    ///
+    /// Only called right after [persist](IndexOps::persist), when other instances of the
+    /// Operator may modify the same keys, for example once its parallelism has changed.
+    fn clear_cache(&mut self) {}
 }
 
 /// Active Arcon State
@@ -61,6 +74,10 @@ pub trait ArconState: Send + 'static {
 
     fn persist(&mut self) -> ArconResult<()>;
     fn set_key(&mut self, key: u64);
+    /// Inform the state about the current watermark
+    fn set_watermark(&mut self, _watermark: u64) {}
+    /// Drop the entries that the state keeps in memory, see [IndexOps::clear_cache]
+    fn clear_cache(&mut self) {}
 
     /// Returns a Vec of registered tables
     fn tables(&mut self) -> Vec<ImmutableTable>;
@@ -199,4 +216,12 @@ pub trait WindowIndex: Send + Sized + IndexOps + 'static {
     fn result(&mut self, ctx: WindowContext) -> ArconResult<Self::OUT>;
     /// Clears the window state for the passed context
     fn clear(&mut self, ctx: WindowContext) -> ArconResult<()>;
+    /// Returns whether this index is able to [merge](WindowIndex::merge) windows
+    fn can_merge(&self) -> bool {
+        true
+    }
+    /// Merges the window state of `from` into `into` and clears `from`
+    ///
+    /// Called by merging window assigners such as session windows.
+    fn merge(&mut self, from: WindowContext, into: WindowContext) -> ArconResult<()>;
 }
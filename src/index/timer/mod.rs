@@ -3,7 +3,7 @@
 
 use super::{hash_table::eager::EagerHashTable, IndexOps};
 use crate::{
-    error::timer::{TimerExpiredError, TimerResult},
+    error::timer::{RescheduleResult, TimerExpiredError, TimerResult},
     error::ArconResult,
     table::ImmutableTable,
 };
@@ -15,6 +15,7 @@ use arcon_state::{
     data::{Key, Value},
     error::Result,
 };
+use fxhash::FxHashMap;
 use hierarchical_hash_wheel_timer::{
     wheels::{quad_wheel::*, *},
     *,
@@ -32,19 +33,58 @@ pub struct TimerEvent<E: Value> {
     timeout_millis: u64,
     #[prost(message, required, tag = "3")]
     payload: E,
+    /// Identifies the wheel entry that belongs to this event
+    #[prost(uint64, tag = "4")]
+    generation: u64,
+    /// State key that was active when the timer was scheduled
+    ///
+    /// Decides which instance of a rescaled Operator the timer moves to.
+    #[prost(uint64, tag = "5")]
+    key: u64,
 }
 
 impl<E: Value> TimerEvent<E> {
-    fn new(time_when_scheduled: u64, timeout_millis: u64, payload: E) -> Self {
+    fn new(
+        time_when_scheduled: u64,
+        timeout_millis: u64,
+        payload: E,
+        generation: u64,
+        key: u64,
+    ) -> Self {
         TimerEvent {
             time_when_scheduled,
             timeout_millis,
             payload,
+            generation,
+            key,
         }
     }
+
+    /// Time at which the event is due
+    #[inline]
+    fn deadline(&self) -> u64 {
+        self.time_when_scheduled + self.timeout_millis
+    }
 }
 
-pub trait ArconTimer: Send {
+/// Pending timers that one instance of an Operator hands off to another
+#[derive(prost::Message, PartialEq, Clone)]
+pub struct TimerHandoff<K: Key, E: Value> {
+    #[prost(message, repeated, tag = "1")]
+    ids: Vec<K>,
+    #[prost(message, repeated, tag = "2")]
+    events: Vec<TimerEvent<E>>,
+}
+
+/// Returns the key of the handoff from instance `from` to instance `to`
+///
+/// Every instance writes its own handoffs, so instances never modify the same entry.
+#[inline]
+fn handoff_slot(to: usize, from: usize) -> u64 {
+    ((to as u64) << 32) | from as u64
+}
+
+pub trait ArconTimer: IndexOps + Send {
     type Key: Eq + Hash;
     type Value: std::fmt::Debug;
 
@@ -54,8 +94,41 @@ pub trait ArconTimer: Send {
         time: u64,
         entry: Self::Value,
     ) -> TimerResult<Self::Value>;
-    fn advance_to(&mut self, ts: u64) -> Result<Vec<Self::Value>>;
+    fn advance_to(&mut self, ts: u64) -> Result<Vec<Self::Value>> {
+        let fired = self.advance_keyed(ts)?;
+        Ok(fired.into_iter().map(|(_, entry)| entry).collect())
+    }
+    /// Like [advance_to](ArconTimer::advance_to), but with the state key each timer was scheduled under
+    fn advance_keyed(&mut self, ts: u64) -> Result<Vec<(u64, Self::Value)>>;
     fn get_time(&self) -> Result<u64>;
+    /// Cancel a pending timer
+    ///
+    /// Returns the entry of the timer if one was pending for `id`.
+    fn cancel(&mut self, id: &Self::Key) -> Result<Option<Self::Value>>;
+    /// Move a pending timer to a new time while keeping its entry
+    ///
+    /// Returns `Ok(None)` if no timer is pending for `id`.
+    fn reschedule(&mut self, id: Self::Key, time: u64) -> RescheduleResult<Self::Value>;
+    /// Returns the time a pending timer is scheduled at, if any
+    fn pending(&self, id: &Self::Key) -> Result<Option<u64>>;
+    /// Returns true if no timer is pending
+    fn is_empty(&self) -> Result<bool>;
+    /// Moves the pending timers that `owner` assigns to another instance than `instance`
+    /// into the handoff map `handoff_id`, which all instances of the Operator share
+    fn hand_off(
+        &mut self,
+        _handoff_id: &str,
+        _instance: usize,
+        _owner: &dyn Fn(u64) -> usize,
+    ) -> Result<()> {
+        Ok(())
+    }
+    /// Schedules the timers that any of `instances` handed off to `instance`
+    ///
+    /// Timers whose time has already passed fire on the next advance.
+    fn take_over(&mut self, _handoff_id: &str, _instance: usize, _instances: usize) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// An Index for Stream Timers
@@ -63,17 +136,45 @@ pub trait ArconTimer: Send {
 /// The Index utilises the [QuadWheelWithOverflow] data structure
 /// in order to manage the timers. The remaining state is kept in
 /// other indexes such as Map/Value.
+///
+/// The persisted timeouts are the source of truth for which timers are pending.
+/// Every scheduled timeout gets a new generation that is stored both in the wheel
+/// and in the timeout, so a wheel entry only fires the timeout it was inserted for.
+///
+/// The wheel cannot remove single entries, so cancelled or rescheduled timers leave
+/// dead entries behind that are dropped once they are ticked. To keep frequent
+/// rescheduling from growing the wheel, it is rebuilt from the pending timeouts once
+/// the dead entries outnumber both the pending ones and [MAX_DEAD_ENTRIES]. Rebuilding
+/// costs a scan of the pending timeouts, which is amortised over the cancellations
+/// that made it necessary.
+///
+/// Every timeout records the state key that was set through [IndexOps::set_key] when it
+/// was scheduled. When the parallelism of an Operator changes, its instances hand off the
+/// timers of the keys they no longer own through the backend, see [ArconTimer::hand_off].
 pub struct Timer<K, V, B>
 where
     K: Key + Eq + Hash,
     V: Value,
     B: Backend,
 {
-    timer: QuadWheelWithOverflow<K>,
+    timer: QuadWheelWithOverflow<(K, u64)>,
     timeouts: EagerHashTable<K, TimerEvent<V>, B>,
     time_handle: ActiveHandle<B, ValueState<u64>>,
+    /// Generation of the next scheduled timeout
+    next_generation: u64,
+    /// Number of wheel entries that belong to a pending timeout
+    live_entries: usize,
+    /// Number of wheel entries left behind by cancelled or rescheduled timers
+    dead_entries: usize,
+    /// State key that new timeouts are scheduled under
+    current_key: u64,
+    /// Backend that timers are handed off through
+    backend: Arc<B>,
 }
 
+/// Number of dead wheel entries that are always tolerated before the wheel is rebuilt
+pub const MAX_DEAD_ENTRIES: usize = 1024;
+
 impl<K, V, B> Timer<K, V, B>
 where
     K: Key + Eq + Hash,
@@ -92,8 +193,13 @@ where
 
         let mut timer = Self {
             timer: QuadWheelWithOverflow::default(),
-            timeouts: EagerHashTable::new(timeouts_id, backend),
+            timeouts: EagerHashTable::new(timeouts_id, backend.clone()),
             time_handle,
+            next_generation: 0,
+            live_entries: 0,
+            dead_entries: 0,
+            current_key: 0,
+            backend,
         };
 
         // replay and insert back if any exists
@@ -104,19 +210,29 @@ where
 
     fn replay_events(&mut self) {
         let time = self.current_time().unwrap();
+        self.live_entries = 0;
+        self.dead_entries = 0;
 
         for res in self.timeouts.iter().expect("could not get timeouts") {
             let (id, entry) = res.expect("could not get timeout entry");
-            let delay = entry.time_when_scheduled + entry.timeout_millis - time;
+            let delay = entry.deadline() - time;
+            self.next_generation = self.next_generation.max(entry.generation + 1);
             if let Err(f) = self
                 .timer
-                .insert_with_delay(id, Duration::from_millis(delay))
+                .insert_with_delay((id, entry.generation), Duration::from_millis(delay))
             {
                 panic!("A timeout has expired during replay: {:?}", f);
             }
+            self.live_entries += 1;
         }
     }
 
+    // Drops the dead entries of the wheel by inserting the pending timeouts into a new one
+    fn rebuild_wheel(&mut self) {
+        self.timer = QuadWheelWithOverflow::default();
+        self.replay_events();
+    }
+
     #[inline(always)]
     pub fn set_time(&mut self, ts: u64) -> Result<()> {
         self.time_handle.fast_set(ts)
@@ -136,7 +252,7 @@ where
     }
 
     #[inline(always)]
-    pub fn tick_and_collect(&mut self, mut time_left: u32, res: &mut Vec<V>) -> Result<()> {
+    pub fn tick_and_collect(&mut self, mut time_left: u32, res: &mut Vec<(u64, V)>) -> Result<()> {
         while time_left > 0 {
             match self.timer.can_skip() {
                 Skip::Empty => {
@@ -159,8 +275,8 @@ where
                     }
                 }
                 Skip::None => {
-                    for e in self.timer.tick() {
-                        if let Some(entry) = self.take_entry(e) {
+                    for (id, generation) in self.timer.tick() {
+                        if let Some(entry) = self.take_entry(id, generation)? {
                             res.push(entry);
                         }
                     }
@@ -173,22 +289,41 @@ where
     }
 
     // Lookup id, remove from storage, and return Executable action
+    //
+    // Entries of cancelled or rescheduled timers no longer match the generation
+    // of the pending timeout and are ignored.
     #[inline(always)]
-    fn take_entry(&mut self, id: K) -> Option<V> {
-        self.timeouts
-            .remove(&id)
-            .expect("no timeout found for id") // this wouldn't necessarily be an error anymore if we add a cancellation API at some point
-            .map(|e| e.payload)
+    fn take_entry(&mut self, id: K, generation: u64) -> Result<Option<(u64, V)>> {
+        match self.timeouts.get(&id)? {
+            Some(event) if event.generation == generation => {
+                self.timeouts.remove(&id)?;
+                self.live_entries -= 1;
+                Ok(Some((event.key, event.payload)))
+            }
+            _ => {
+                self.dead_entries = self.dead_entries.saturating_sub(1);
+                Ok(None)
+            }
+        }
     }
 
     #[inline(always)]
     pub fn schedule_after(&mut self, id: K, delay: u64, entry: V) -> TimerResult<V> {
+        let generation = self.next_generation;
         match self
             .timer
-            .insert_with_delay(id.clone(), Duration::from_millis(delay))
+            .insert_with_delay((id.clone(), generation), Duration::from_millis(delay))
         {
             Ok(_) => {
-                let event = TimerEvent::new(self.current_time().unwrap(), delay, entry);
+                self.next_generation += 1;
+                self.live_entries += 1;
+                let event = TimerEvent::new(
+                    self.current_time().unwrap(),
+                    delay,
+                    entry,
+                    generation,
+                    self.current_key,
+                );
                 self.timeouts.put(id, event)?;
                 Ok(Ok(()))
             }
@@ -233,7 +368,7 @@ where
     }
 
     #[inline]
-    fn advance_to(&mut self, ts: u64) -> Result<Vec<Self::Value>> {
+    fn advance_keyed(&mut self, ts: u64) -> Result<Vec<(u64, Self::Value)>> {
         let mut res = Vec::new();
         let curr_time = self.current_time().unwrap();
         if ts < curr_time {
@@ -243,6 +378,11 @@ where
 
         let mut time_left = ts - curr_time;
         while time_left > std::u32::MAX as u64 {
+            if let Skip::Empty = self.timer.can_skip() {
+                // Nothing left to fire, so large jumps such as a final watermark are cheap
+                self.add_time(time_left)?;
+                return Ok(res);
+            }
             self.tick_and_collect(std::u32::MAX, &mut res)?;
             time_left -= std::u32::MAX as u64;
         }
@@ -254,6 +394,104 @@ where
         let time = self.time_handle.get()?;
         Ok(time.unwrap_or(0))
     }
+
+    #[inline]
+    fn cancel(&mut self, id: &Self::Key) -> Result<Option<Self::Value>> {
+        let event = self.timeouts.remove(id)?;
+        if event.is_some() {
+            self.live_entries -= 1;
+            self.dead_entries += 1;
+            if self.dead_entries > MAX_DEAD_ENTRIES && self.dead_entries > self.live_entries {
+                self.rebuild_wheel();
+            }
+        }
+        Ok(event.map(|e| e.payload))
+    }
+
+    #[inline]
+    fn reschedule(&mut self, id: Self::Key, time: u64) -> RescheduleResult<Self::Value> {
+        match self.cancel(&id)? {
+            Some(entry) => self.schedule_at(id, time, entry).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    #[inline]
+    fn pending(&self, id: &Self::Key) -> Result<Option<u64>> {
+        let event = self.timeouts.get(id)?;
+        Ok(event.map(|e| e.deadline()))
+    }
+
+    fn is_empty(&self) -> Result<bool> {
+        Ok(self.timeouts.iter()?.next().is_none())
+    }
+
+    fn hand_off(
+        &mut self,
+        handoff_id: &str,
+        instance: usize,
+        owner: &dyn Fn(u64) -> usize,
+    ) -> Result<()> {
+        let mut moved: FxHashMap<usize, TimerHandoff<K, V>> = FxHashMap::default();
+        for res in self.timeouts.iter()? {
+            let (id, event) = res?;
+            let to = owner(event.key);
+            if to != instance {
+                let handoff = moved.entry(to).or_default();
+                handoff.ids.push(id);
+                handoff.events.push(event);
+            }
+        }
+        if moved.is_empty() {
+            return Ok(());
+        }
+
+        let mut handoffs = EagerHashTable::new(handoff_id, self.backend.clone());
+        for (to, mut handoff) in moved {
+            for id in &handoff.ids {
+                let _ = self.timeouts.remove(id)?;
+            }
+            let slot = handoff_slot(to, instance);
+            // timers handed off earlier that have not been taken over yet
+            if let Some(pending) = handoffs.get(&slot)? {
+                handoff.ids.extend(pending.ids);
+                handoff.events.extend(pending.events);
+            }
+            handoffs.put(slot, handoff)?;
+        }
+        self.rebuild_wheel();
+        Ok(())
+    }
+
+    fn take_over(&mut self, handoff_id: &str, instance: usize, instances: usize) -> Result<()> {
+        let handoffs: EagerHashTable<u64, TimerHandoff<K, V>, B> =
+            EagerHashTable::new(handoff_id, self.backend.clone());
+        let time = self.current_time()?;
+        for from in (0..instances).filter(|from| *from != instance) {
+            let handoff = match handoffs.remove(&handoff_slot(instance, from))? {
+                Some(handoff) => handoff,
+                None => continue,
+            };
+            for (id, event) in handoff.ids.into_iter().zip(handoff.events) {
+                let delay = event.deadline().saturating_sub(time).max(1);
+                let generation = self.next_generation;
+                match self
+                    .timer
+                    .insert_with_delay((id.clone(), generation), Duration::from_millis(delay))
+                {
+                    Ok(_) => {
+                        self.next_generation += 1;
+                        self.live_entries += 1;
+                        let event =
+                            TimerEvent::new(time, delay, event.payload, generation, event.key);
+                        self.timeouts.put(id, event)?;
+                    }
+                    Err(f) => panic!("Could not take over timer entry {:?}", f),
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<K, V, B> IndexOps for Timer<K, V, B>
@@ -266,7 +504,9 @@ where
         self.timeouts.persist()?;
         Ok(())
     }
-    fn set_key(&mut self, _: u64) {}
+    fn set_key(&mut self, key: u64) {
+        self.current_key = key;
+    }
     fn table(&mut self) -> ArconResult<Option<ImmutableTable>> {
         Ok(None)
     }
@@ -292,5 +532,121 @@ mod tests {
         let evs = timer.advance_to(2000).unwrap();
         assert_eq!(evs.len(), 1);
     }
-    // TODO: more elaborate tests
+
+    #[test]
+    fn timer_cancel_and_reschedule_test() {
+        let backend = Arc::new(temp_backend::<Sled>());
+        let mut timer = Timer::new("mytimer", backend);
+
+        let _ = timer.schedule_at(1, 1000, 10).unwrap();
+        let _ = timer.schedule_at(2, 1000, 20).unwrap();
+        let _ = timer.schedule_at(3, 1000, 30).unwrap();
+        assert_eq!(timer.pending(&1).unwrap(), Some(1000));
+
+        assert_eq!(timer.cancel(&1).unwrap(), Some(10));
+        assert_eq!(timer.pending(&1).unwrap(), None);
+        assert_eq!(timer.cancel(&1).unwrap(), None);
+
+        // move one timer later and one earlier
+        timer.reschedule(2, 1500).unwrap().unwrap().unwrap();
+        timer.reschedule(3, 500).unwrap().unwrap().unwrap();
+        assert_eq!(timer.pending(&2).unwrap(), Some(1500));
+        assert!(timer.reschedule(1, 1500).unwrap().is_none());
+
+        assert_eq!(timer.advance_to(600).unwrap(), vec![30]);
+        assert!(timer.advance_to(1200).unwrap().is_empty());
+        assert_eq!(timer.advance_to(2000).unwrap(), vec![20]);
+        assert_eq!(timer.pending(&2).unwrap(), None);
+    }
+    #[test]
+    fn timer_stale_entry_test() {
+        let backend = Arc::new(temp_backend::<Sled>());
+        let mut timer = Timer::new("mytimer", backend);
+
+        // a cancelled timer that is scheduled again at the same time fires once
+        let _ = timer.schedule_at(1, 1000, 10).unwrap();
+        assert_eq!(timer.cancel(&1).unwrap(), Some(10));
+        let _ = timer.schedule_at(1, 1000, 11).unwrap();
+
+        // rescheduling away and back leaves two stale entries behind
+        let _ = timer.schedule_at(2, 1000, 20).unwrap();
+        timer.reschedule(2, 1500).unwrap().unwrap().unwrap();
+        timer.reschedule(2, 1000).unwrap().unwrap().unwrap();
+
+        let mut fired = timer.advance_to(1200).unwrap();
+        fired.sort_unstable();
+        assert_eq!(fired, vec![11, 20]);
+        assert!(timer.advance_to(2000).unwrap().is_empty());
+        assert!(timer.is_empty().unwrap());
+    }
+
+    #[test]
+    fn timer_dead_entries_test() {
+        let backend = Arc::new(temp_backend::<Sled>());
+        let mut timer = Timer::new("mytimer", backend);
+
+        let _ = timer.schedule_at(1, 1000, 10).unwrap();
+        let _ = timer.schedule_at(2, 100_000, 20).unwrap();
+        // e.g. pushing the cleanup of an idle key further back on every element
+        for time in 1001..(1001 + 10 * MAX_DEAD_ENTRIES as u64) {
+            timer.reschedule(1, time).unwrap().unwrap().unwrap();
+            assert!(timer.dead_entries <= MAX_DEAD_ENTRIES);
+        }
+        assert_eq!(timer.live_entries, 2);
+
+        // the rebuilt wheel still fires the pending timeouts at their time
+        let last = 1000 + 10 * MAX_DEAD_ENTRIES as u64;
+        assert!(timer.advance_to(last - 1).unwrap().is_empty());
+        assert_eq!(timer.advance_to(last).unwrap(), vec![10]);
+        assert_eq!(timer.advance_to(100_000).unwrap(), vec![20]);
+        assert_eq!(timer.live_entries, 0);
+        assert!(timer.is_empty().unwrap());
+    }
+
+    #[test]
+    fn timer_replay_test() {
+        let backend = Arc::new(temp_backend::<Sled>());
+        let mut timer = Timer::new("mytimer", backend.clone());
+        let _ = timer.schedule_at(1, 1000, 10).unwrap();
+        timer.reschedule(1, 1500).unwrap().unwrap().unwrap();
+        timer.persist().unwrap();
+
+        // a restored timer continues with newer generations
+        let mut timer: Timer<u64, u64, Sled> = Timer::new("mytimer", backend);
+        let _ = timer.schedule_at(2, 1200, 20).unwrap();
+        timer.reschedule(2, 1500).unwrap().unwrap().unwrap();
+        assert!(timer.advance_to(1200).unwrap().is_empty());
+        assert_eq!(timer.advance_to(1500).unwrap().len(), 2);
+    }
+    #[test]
+    fn timer_handoff_test() {
+        let backend = Arc::new(temp_backend::<Sled>());
+        let mut first = Timer::new("first", backend.clone());
+        let mut second: Timer<u64, u64, Sled> = Timer::new("second", backend);
+        let owner = |key: u64| (key % 2) as usize;
+
+        for key in 0..4u64 {
+            first.set_key(key);
+            let _ = first.schedule_at(key, 1000 + key, key * 10).unwrap();
+        }
+        let _ = second.advance_to(500).unwrap();
+
+        // keys that move to the second instance take their timers along
+        first.hand_off("_handoffs", 0, &owner).unwrap();
+        second.take_over("_handoffs", 1, 2).unwrap();
+        assert_eq!(first.pending(&1).unwrap(), None);
+        assert_eq!(second.pending(&1).unwrap(), Some(1001));
+
+        assert_eq!(
+            first.advance_keyed(2000).unwrap(),
+            vec![(0, 0u64), (2, 20u64)]
+        );
+        assert_eq!(
+            second.advance_keyed(2000).unwrap(),
+            vec![(1, 10u64), (3, 30u64)]
+        );
+        // a handoff is only taken over once
+        second.take_over("_handoffs", 1, 2).unwrap();
+        assert!(second.is_empty().unwrap());
+    }
 }
@@ -0,0 +1,250 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::stream::time::{ArconTime, Time};
+use arcon_state::{
+    backend::{
+        handles::{ActiveHandle, Handle},
+        serialization::protobuf,
+        Backend, MapState,
+    },
+    data::Key,
+    error::*,
+};
+use std::sync::Arc;
+
+/// Defines when the timestamp of a keyed entry is refreshed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TtlUpdate {
+    /// Refresh on creation and on every write
+    OnWrite,
+    /// Refresh on creation, on every write, and on every read
+    OnReadAndWrite,
+}
+
+impl Default for TtlUpdate {
+    fn default() -> Self {
+        TtlUpdate::OnWrite
+    }
+}
+
+/// Time-to-live configuration of a keyed index
+///
+/// Entries that have not been refreshed within `ttl` are invisible to reads
+/// and are purged from the backend when the index is persisted.
+#[derive(Clone, Copy, Debug)]
+pub struct TtlConf {
+    /// How long an entry lives after it was last refreshed
+    pub ttl: Time,
+    /// Notion of time the ttl is measured in
+    ///
+    /// Event time follows the watermark of the Node while processing time follows the wall clock.
+    pub time: ArconTime,
+    /// When the timestamp of an entry is refreshed
+    pub update: TtlUpdate,
+}
+
+impl TtlConf {
+    /// Creates a TtlConf measured in event time
+    pub fn event_time(ttl: Time) -> Self {
+        Self {
+            ttl,
+            time: ArconTime::Event,
+            update: TtlUpdate::default(),
+        }
+    }
+    /// Creates a TtlConf measured in processing time
+    pub fn processing_time(ttl: Time) -> Self {
+        Self {
+            ttl,
+            time: ArconTime::Process,
+            update: TtlUpdate::default(),
+        }
+    }
+    /// Set the [TtlUpdate] policy
+    pub fn with_update(mut self, update: TtlUpdate) -> Self {
+        self.update = update;
+        self
+    }
+}
+
+/// Keys whose last refresh fell into the same time bucket
+#[derive(prost::Message, Clone)]
+pub(crate) struct ExpiryBucket {
+    /// Serialised keys
+    #[prost(bytes, repeated, tag = "1")]
+    keys: Vec<Vec<u8>>,
+}
+
+/// Keeps track of when the entries of a keyed index were last refreshed
+///
+/// Next to the last refresh time of every key, the keys are grouped by that time into
+/// buckets of one ttl each. Both live in the backend, so expired keys are found by
+/// scanning the few buckets that may hold them rather than every key of the index.
+#[derive(Debug)]
+pub(crate) struct Ttl<K, B>
+where
+    K: Key,
+    B: Backend,
+{
+    conf: TtlConf,
+    /// Last refresh time per key
+    timestamps: ActiveHandle<B, MapState<K, u64>>,
+    /// Keys per bucket of refresh time
+    ///
+    /// A key is added to the bucket of every refresh that moves it into a new bucket and
+    /// is only dropped once that bucket is scanned, so stale entries are checked against
+    /// `timestamps` before a key is considered expired.
+    expiry: ActiveHandle<B, MapState<u64, ExpiryBucket>>,
+    /// Current watermark used for event-time ttl
+    watermark: u64,
+}
+
+impl<K, B> Ttl<K, B>
+where
+    K: Key,
+    B: Backend,
+{
+    pub(crate) fn new(id: &str, backend: Arc<B>, conf: TtlConf) -> Self {
+        let mut timestamps = Handle::map(format!("_{}_ttl", id));
+        backend.register_map_handle(&mut timestamps);
+        let mut expiry = Handle::map(format!("_{}_ttl_expiry", id));
+        backend.register_map_handle(&mut expiry);
+        Self {
+            conf,
+            timestamps: timestamps.activate(backend.clone()),
+            expiry: expiry.activate(backend),
+            watermark: 0,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn now(&self) -> u64 {
+        match self.conf.time {
+            ArconTime::Event => self.watermark,
+            ArconTime::Process => crate::util::get_system_time(),
+        }
+    }
+
+    /// The ttl expressed in the unit of [Ttl::now]
+    #[inline]
+    fn ttl(&self) -> u64 {
+        match self.conf.time {
+            ArconTime::Event => self.conf.ttl.0,
+            ArconTime::Process => self.conf.ttl.0 * 1000,
+        }
+    }
+
+    #[inline]
+    fn bucket(&self, timestamp: u64) -> u64 {
+        timestamp / self.ttl().max(1)
+    }
+
+    #[inline]
+    pub(crate) fn set_watermark(&mut self, watermark: u64) {
+        self.watermark = watermark;
+    }
+
+    /// Returns true if an entry last refreshed at `timestamp` has outlived the ttl
+    #[inline]
+    pub(crate) fn is_expired_at(&self, timestamp: u64) -> bool {
+        timestamp.saturating_add(self.ttl()) <= self.now()
+    }
+
+    /// Returns true if the entry of `key` has outlived the ttl
+    #[inline]
+    pub(crate) fn is_expired(&self, key: &K) -> Result<bool> {
+        let last = self.timestamps.get(key)?;
+        Ok(last.map(|ts| self.is_expired_at(ts)).unwrap_or(false))
+    }
+
+    /// Returns true if reads refresh the timestamp of an entry
+    #[inline]
+    pub(crate) fn refresh_on_read(&self) -> bool {
+        self.conf.update == TtlUpdate::OnReadAndWrite
+    }
+
+    #[inline]
+    pub(crate) fn on_write(&self, key: K) -> Result<()> {
+        self.on_write_at(key, self.now())
+    }
+
+    /// Records that `key` was refreshed at `timestamp`
+    pub(crate) fn on_write_at(&self, key: K, timestamp: u64) -> Result<()> {
+        let bucket = self.bucket(timestamp);
+        let moved = match self.timestamps.get(&key)? {
+            Some(last) => self.bucket(last) != bucket,
+            None => true,
+        };
+        if moved {
+            let mut entry = self.expiry.get(&bucket)?.unwrap_or_default();
+            entry.keys.push(protobuf::serialize(&key)?);
+            self.expiry.fast_insert(bucket, entry)?;
+        }
+        self.timestamps.fast_insert(key, timestamp)
+    }
+
+    #[inline]
+    pub(crate) fn on_read(&self, key: K) -> Result<()> {
+        if self.refresh_on_read() {
+            self.on_write(key)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Stops tracking `key`
+    ///
+    /// Its bucket entry is left behind and dropped once the bucket is scanned.
+    #[inline]
+    pub(crate) fn remove(&self, key: &K) -> Result<()> {
+        let _ = self.timestamps.remove(key)?;
+        Ok(())
+    }
+
+    /// Stops tracking all expired keys and returns them
+    pub(crate) fn drain_expired(&self) -> Result<Vec<K>> {
+        let now = self.now();
+        let ttl = self.ttl();
+        if now < ttl {
+            return Ok(Vec::new());
+        }
+        // buckets past this one only hold keys refreshed after the expiry bound
+        let last_bucket = self.bucket(now - ttl);
+
+        let mut candidates = Vec::new();
+        for entry in self.expiry.iter()? {
+            let (bucket, _) = entry?;
+            if bucket <= last_bucket {
+                candidates.push(bucket);
+            }
+        }
+
+        let mut keys = Vec::new();
+        for bucket in candidates {
+            let entry = match self.expiry.remove(&bucket)? {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let mut alive = Vec::new();
+            for bytes in entry.keys {
+                let key: K = protobuf::deserialize(&bytes)?;
+                match self.timestamps.get(&key)? {
+                    // refreshed into a later bucket, which tracks it from now on
+                    Some(ts) if self.bucket(ts) != bucket => {}
+                    Some(ts) if ts.saturating_add(ttl) <= now => {
+                        let _ = self.timestamps.remove(&key)?;
+                        keys.push(key);
+                    }
+                    Some(_) => alive.push(bytes),
+                    None => {}
+                }
+            }
+            if !alive.is_empty() {
+                self.expiry
+                    .fast_insert(bucket, ExpiryBucket { keys: alive })?;
+            }
+        }
+        Ok(keys)
+    }
+}
@@ -3,7 +3,10 @@
 
 use crate::{
     error::ArconResult,
-    index::{IndexOps, IndexValue, ValueIndex},
+    index::{
+        ttl::{Ttl, TtlConf},
+        IndexOps, IndexValue, ValueIndex,
+    },
     table::ImmutableTable,
 };
 use arcon_state::{
@@ -23,6 +26,8 @@ where
     /// A handle to the ValueState
     handle: ActiveHandle<B, MapState<u64, V>>,
     current_key: u64,
+    /// Optional time-to-live of the values
+    ttl: Option<Ttl<u64, B>>,
 }
 
 impl<V, B> EagerValue<V, B>
@@ -40,7 +45,35 @@ where
         EagerValue {
             handle,
             current_key: 0,
+            ttl: None,
+        }
+    }
+
+    /// Creates an EagerValue whose values expire according to a [TtlConf]
+    pub fn with_ttl(id: impl Into<String>, backend: Arc<B>, conf: TtlConf) -> Self {
+        let id = id.into();
+        let ttl = Ttl::new(&id, backend.clone(), conf);
+        let mut index = Self::new(id, backend);
+        index.ttl = Some(ttl);
+        index
+    }
+
+    #[inline]
+    fn expired(&self) -> Result<bool> {
+        match &self.ttl {
+            Some(ttl) => ttl.is_expired(&self.current_key),
+            None => Ok(false),
+        }
+    }
+
+    /// Removes all values that have outlived their ttl
+    fn purge_expired(&mut self) -> Result<()> {
+        if let Some(ttl) = &self.ttl {
+            for key in ttl.drain_expired()? {
+                let _ = self.handle.remove(&key)?;
+            }
         }
+        Ok(())
     }
 }
 
@@ -50,14 +83,28 @@ where
     B: Backend,
 {
     fn put(&mut self, value: V) -> Result<()> {
+        if let Some(ttl) = &self.ttl {
+            ttl.on_write(self.current_key)?;
+        }
         self.handle.fast_insert(self.current_key, value)
     }
     fn get(&self) -> Result<Option<Cow<V>>> {
+        if self.expired()? {
+            return Ok(None);
+        }
         let value = self.handle.get(&self.current_key)?;
+        if let (Some(ttl), Some(_)) = (&self.ttl, &value) {
+            ttl.on_read(self.current_key)?;
+        }
         Ok(value.map(Cow::Owned))
     }
     fn take(&mut self) -> Result<Option<V>> {
-        self.handle.remove(&self.current_key)
+        let expired = self.expired()?;
+        if let Some(ttl) = &self.ttl {
+            ttl.remove(&self.current_key)?;
+        }
+        let value = self.handle.remove(&self.current_key)?;
+        Ok(if expired { None } else { value })
     }
     fn clear(&mut self) -> Result<()> {
         let _ = self.take()?;
@@ -84,12 +131,19 @@ where
     B: Backend,
 {
     fn persist(&mut self) -> ArconResult<()> {
+        self.purge_expired()?;
         Ok(())
     }
     fn set_key(&mut self, key: u64) {
         self.current_key = key;
     }
+    fn set_watermark(&mut self, watermark: u64) {
+        if let Some(ttl) = &mut self.ttl {
+            ttl.set_watermark(watermark);
+        }
+    }
     fn table(&mut self) -> ArconResult<Option<ImmutableTable>> {
+        self.purge_expired()?;
         let mut table = V::table();
         let values = self.handle.values()?;
         table
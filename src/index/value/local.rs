@@ -114,4 +114,9 @@ where
     fn table(&mut self) -> ArconResult<Option<ImmutableTable>> {
         Ok(None)
     }
+    fn clear_cache(&mut self) {
+        if !self.modified {
+            self.data = Some(self.handle.get().ok().flatten().unwrap_or_default());
+        }
+    }
 }
@@ -1,11 +1,14 @@
 // Copyright (c) 2020, KTH Royal Institute of Technology.
 // SPDX-License-Identifier: AGPL-3.0-only
 
-use super::{HashTable, IndexOps, IndexValue, ValueIndex};
+use super::{
+    ttl::{Ttl, TtlConf},
+    HashTable, IndexOps, IndexValue, ValueIndex,
+};
 use crate::error::ArconResult;
 use crate::table::ImmutableTable;
 use arcon_state::{error::*, Backend};
-use std::{borrow::Cow, sync::Arc};
+use std::{borrow::Cow, cell::RefCell, collections::HashMap, sync::Arc};
 
 mod eager;
 mod local;
@@ -21,6 +24,12 @@ where
 {
     current_key: u64,
     hash_table: HashTable<u64, V, B>,
+    /// Optional time-to-live of the values
+    ttl: Option<Ttl<u64, B>>,
+    /// Refresh times that have not been handed to the [Ttl] yet
+    ///
+    /// Like the values themselves, these are only written to the backend on persist.
+    refreshed: RefCell<HashMap<u64, u64>>,
 }
 
 impl<V, B> LazyValue<V, B>
@@ -35,8 +44,53 @@ where
         Self {
             current_key: 0,
             hash_table,
+            ttl: None,
+            refreshed: RefCell::new(HashMap::new()),
         }
     }
+
+    /// Creates a LazyValue whose values expire according to a [TtlConf]
+    pub fn with_ttl(id: impl Into<String>, backend: Arc<B>, conf: TtlConf) -> Self {
+        let id = id.into();
+        let ttl = Ttl::new(&id, backend.clone(), conf);
+        let mut index = Self::new(id, backend);
+        index.ttl = Some(ttl);
+        index
+    }
+
+    #[inline]
+    fn expired(&self) -> Result<bool> {
+        match &self.ttl {
+            Some(ttl) => match self.refreshed.borrow().get(&self.current_key) {
+                Some(ts) => Ok(ttl.is_expired_at(*ts)),
+                None => ttl.is_expired(&self.current_key),
+            },
+            None => Ok(false),
+        }
+    }
+
+    /// Buffers a refresh of the current key
+    #[inline]
+    fn refresh(&self) {
+        if let Some(ttl) = &self.ttl {
+            self.refreshed
+                .borrow_mut()
+                .insert(self.current_key, ttl.now());
+        }
+    }
+
+    /// Hands the buffered refreshes to the [Ttl] and removes all values that have outlived their ttl
+    fn purge_expired(&mut self) -> Result<()> {
+        if let Some(ttl) = &self.ttl {
+            for (key, ts) in self.refreshed.get_mut().drain() {
+                ttl.on_write_at(key, ts)?;
+            }
+            for key in ttl.drain_expired()? {
+                let _ = self.hash_table.remove(&key)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<V, B> ValueIndex<V> for LazyValue<V, B>
@@ -46,16 +100,31 @@ where
 {
     #[inline]
     fn put(&mut self, value: V) -> Result<()> {
+        self.refresh();
         self.hash_table.put(self.current_key, value)
     }
     #[inline]
     fn get(&self) -> Result<Option<Cow<V>>> {
+        if self.expired()? {
+            return Ok(None);
+        }
         let value = self.hash_table.get(&self.current_key)?;
+        if let (Some(ttl), Some(_)) = (&self.ttl, &value) {
+            if ttl.refresh_on_read() {
+                self.refresh();
+            }
+        }
         Ok(value.map(|v| Cow::Borrowed(v)))
     }
     #[inline]
     fn take(&mut self) -> Result<Option<V>> {
-        self.hash_table.remove(&self.current_key)
+        let expired = self.expired()?;
+        if let Some(ttl) = &self.ttl {
+            self.refreshed.get_mut().remove(&self.current_key);
+            ttl.remove(&self.current_key)?;
+        }
+        let value = self.hash_table.remove(&self.current_key)?;
+        Ok(if expired { None } else { value })
     }
     #[inline]
     fn clear(&mut self) -> Result<()> {
@@ -67,6 +136,10 @@ where
     where
         F: FnMut(&mut V) + Sized,
     {
+        if self.expired()? {
+            let _ = self.take()?;
+        }
+        self.refresh();
         self.hash_table.rmw(&self.current_key, V::default, f)
     }
 }
@@ -78,14 +151,26 @@ where
 {
     #[inline]
     fn persist(&mut self) -> ArconResult<()> {
+        self.purge_expired()?;
         self.hash_table.persist()
     }
     #[inline]
     fn set_key(&mut self, key: u64) {
         self.current_key = key;
     }
+    #[inline]
+    fn set_watermark(&mut self, watermark: u64) {
+        if let Some(ttl) = &mut self.ttl {
+            ttl.set_watermark(watermark);
+        }
+    }
+    #[inline]
+    fn clear_cache(&mut self) {
+        self.hash_table.clear_cache();
+    }
 
     fn table(&mut self) -> ArconResult<Option<ImmutableTable>> {
+        self.purge_expired()?;
         let (_, values) = self.hash_table.full_iter()?;
         let mut table = V::table();
         table
@@ -131,6 +216,46 @@ mod tests {
         Ok(())
     }
 
+    fn ttl_index_test(mut index: impl ValueIndex<u64>) -> Result<()> {
+        index.set_watermark(0);
+        index.set_key(0);
+        index.put(1u64)?;
+        index.set_key(1);
+        index.put(2u64)?;
+
+        // refresh key 1 while key 0 is left untouched
+        index.set_watermark(5);
+        index.put(3u64)?;
+
+        index.set_watermark(10);
+        index.set_key(0);
+        assert_eq!(index.get()?, None);
+        index.set_key(1);
+        assert_eq!(index.get()?.unwrap().as_ref(), &3u64);
+
+        // expired values are purged on persist while refreshed ones are kept
+        index.persist().unwrap();
+        index.set_key(0);
+        assert_eq!(index.take()?, None);
+        index.set_key(1);
+        assert_eq!(index.get()?.unwrap().as_ref(), &3u64);
+
+        // a refresh in a later ttl bucket outlives the entry in the earlier one
+        index.set_watermark(12);
+        index.put(4u64)?;
+        index.persist().unwrap();
+        index.set_watermark(20);
+        index.persist().unwrap();
+        assert_eq!(index.get()?.unwrap().as_ref(), &4u64);
+
+        index.set_watermark(22);
+        assert_eq!(index.get()?, None);
+        index.persist().unwrap();
+        assert_eq!(index.take()?, None);
+
+        Ok(())
+    }
+
     #[test]
     fn lazy_value_index_test() {
         let backend = Arc::new(temp_backend::<Sled>());
@@ -143,4 +268,14 @@ mod tests {
         let index: EagerValue<u64, _> = EagerValue::new("myvalue", backend);
         assert!(index_test(index).is_ok());
     }
+    #[test]
+    fn ttl_value_index_test() {
+        use crate::{index::TtlConf, stream::time::Time};
+        let backend = Arc::new(temp_backend::<Sled>());
+        let conf = TtlConf::event_time(Time::seconds(10));
+        let lazy: LazyValue<u64, _> = LazyValue::with_ttl("lazy", backend.clone(), conf);
+        assert!(ttl_index_test(lazy).is_ok());
+        let eager: EagerValue<u64, _> = EagerValue::with_ttl("eager", backend, conf);
+        assert!(ttl_index_test(eager).is_ok());
+    }
 }
@@ -1,7 +1,7 @@
 use super::super::WindowIndex;
 use crate::data::ArconType;
 use crate::error::ArconResult;
-use crate::stream::operator::window::WindowContext;
+use crate::stream::operator::window::{Evictor, WindowContext};
 use crate::util::ArconFnBounds;
 use crate::{index::IndexOps, table::ImmutableTable};
 use arcon_state::{backend::handles::ActiveHandle, Backend, Handle, VecState};
@@ -16,6 +16,7 @@ where
 {
     handle: ActiveHandle<B, VecState<IN>, u64, u64>,
     materializer: F,
+    evictor: Option<Arc<dyn Evictor<IN>>>,
 }
 impl<IN, OUT, F, B> AppenderWindow<IN, OUT, F, B>
 where
@@ -36,8 +37,15 @@ where
         Self {
             handle,
             materializer,
+            evictor: None,
         }
     }
+
+    /// Set an [Evictor] that is applied to the window buffer before materialization
+    pub fn with_evictor(mut self, evictor: impl Evictor<IN>) -> Self {
+        self.evictor = Some(Arc::new(evictor));
+        self
+    }
 }
 
 impl<IN, OUT, F, B> WindowIndex for AppenderWindow<IN, OUT, F, B>
@@ -62,8 +70,20 @@ where
         self.handle.set_item_key(ctx.key);
         self.handle.set_namespace(ctx.index);
 
-        let buf = self.handle.get()?;
-        Ok((self.materializer)(&buf))
+        let mut buf = self.handle.get()?;
+        match &self.evictor {
+            Some(evictor) => {
+                let len = buf.len();
+                evictor.evict(&mut buf);
+                let result = (self.materializer)(&buf);
+                // Evicted elements are removed from the window state
+                if buf.len() != len {
+                    self.handle.set(buf)?;
+                }
+                Ok(result)
+            }
+            None => Ok((self.materializer)(&buf)),
+        }
     }
 
     fn clear(&mut self, ctx: WindowContext) -> ArconResult<()> {
@@ -73,6 +93,20 @@ where
         self.handle.clear()?;
         Ok(())
     }
+
+    fn merge(&mut self, from: WindowContext, into: WindowContext) -> ArconResult<()> {
+        self.handle.set_item_key(from.key);
+        self.handle.set_namespace(from.index);
+
+        let buf = self.handle.get()?;
+        self.handle.clear()?;
+
+        self.handle.set_item_key(into.key);
+        self.handle.set_namespace(into.index);
+
+        self.handle.add_all(buf)?;
+        Ok(())
+    }
 }
 impl<IN, OUT, F, B> IndexOps for AppenderWindow<IN, OUT, F, B>
 where
@@ -95,6 +95,28 @@ where
 
         Ok(())
     }
+
+    fn merge(&mut self, from: WindowContext, into: WindowContext) -> ArconResult<()> {
+        // drain in-memory batches of the merged window
+        let mut raw_batches = match self.map.remove(&from) {
+            Some(mut table) => table.raw_batches()?,
+            None => Vec::new(),
+        };
+
+        self.handle.set_item_key(from.key);
+        self.handle.set_namespace(from.index);
+
+        raw_batches.append(&mut self.handle.get()?);
+        self.handle.clear()?;
+
+        // move everything into the backend layer of the surviving window
+        self.handle.set_item_key(into.key);
+        self.handle.set_namespace(into.index);
+
+        self.handle.add_all(raw_batches)?;
+
+        Ok(())
+    }
 }
 impl<IN, OUT, F, B> IndexOps for ArrowWindow<IN, OUT, F, B>
 where
@@ -116,6 +138,10 @@ where
         Ok(())
     }
     fn set_key(&mut self, _: u64) {}
+    fn clear_cache(&mut self) {
+        // persisting has drained the in-memory tables into the backend
+        self.map.clear();
+    }
     fn table(&mut self) -> ArconResult<Option<ImmutableTable>> {
         Ok(None)
     }
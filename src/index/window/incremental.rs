@@ -4,10 +4,16 @@ use crate::{
     error::ArconResult,
     index::{IndexOps, WindowIndex},
     prelude::*,
+    reportable_error,
     table::ImmutableTable,
     util::{prost_helpers::ProstOption, ArconFnBounds},
 };
-use arcon_state::{backend::handles::ActiveHandle, Aggregator, AggregatorState, Backend};
+use arcon_state::{
+    backend::handles::ActiveHandle, Aggregator, AggregatorState, Backend, ValueState,
+};
+
+/// Function used to combine the partial aggregates of two merged windows
+pub type IncrementalMergeFn<OUT> = Arc<dyn Fn(&OUT, &OUT) -> OUT + Send + Sync>;
 
 #[derive(Clone)]
 pub struct IncrementalWindowAggregator<IN, OUT, INIT, AGG>
@@ -64,10 +70,31 @@ where
 type AggState<IN, OUT, INIT, AGG> =
     AggregatorState<IncrementalWindowAggregator<IN, OUT, INIT, AGG>>;
 
+// Windows that are never merged aggregate through the backend's AggregatorState,
+// while mergeable windows keep their partial aggregate in a ValueState.
+enum IncrementalHandle<IN, OUT, INIT, AGG, B>
+where
+    IN: ArconType,
+    OUT: ArconType,
+    INIT: Fn(IN) -> OUT + ArconFnBounds,
+    AGG: Fn(IN, &OUT) -> OUT + ArconFnBounds,
+    B: Backend,
+{
+    Aggregating(ActiveHandle<B, AggState<IN, OUT, INIT, AGG>, u64, u64>),
+    Merging {
+        handle: ActiveHandle<B, ValueState<OUT>, u64, u64>,
+        init: INIT,
+        agg: AGG,
+        merge: IncrementalMergeFn<OUT>,
+    },
+}
+
 /// A window index that incrementally aggregates elements
 ///
 /// Used for associative and commutative operations
 ///
+/// Only windows created through [IncrementalWindow::merging] may be merged
+/// (e.g., by session windows).
 pub struct IncrementalWindow<IN, OUT, INIT, AGG, B>
 where
     IN: ArconType,
@@ -76,7 +103,7 @@ where
     AGG: Fn(IN, &OUT) -> OUT + ArconFnBounds,
     B: Backend,
 {
-    aggregator: ActiveHandle<B, AggState<IN, OUT, INIT, AGG>, u64, u64>,
+    handle: IncrementalHandle<IN, OUT, INIT, AGG, B>,
 }
 
 impl<IN, OUT, INIT, AGG, B> IncrementalWindow<IN, OUT, INIT, AGG, B>
@@ -103,7 +130,36 @@ where
 
         let aggregator = aggregator.activate(backend);
 
-        Self { aggregator }
+        Self {
+            handle: IncrementalHandle::Aggregating(aggregator),
+        }
+    }
+
+    /// Create an incremental window that may be merged with other windows
+    ///
+    /// `merge` combines the aggregates of two windows.
+    pub fn merging(
+        backend: Arc<B>,
+        init: INIT,
+        agg: AGG,
+        merge: impl Fn(&OUT, &OUT) -> OUT + Send + Sync + 'static,
+    ) -> Self {
+        let mut handle = Handle::value("incremental_window_merging_state")
+            .with_item_key(0)
+            .with_namespace(0);
+
+        backend.register_value_handle(&mut handle);
+
+        let handle = handle.activate(backend);
+
+        Self {
+            handle: IncrementalHandle::Merging {
+                handle,
+                init,
+                agg,
+                merge: Arc::new(merge),
+            },
+        }
     }
 }
 
@@ -118,30 +174,99 @@ where
     type IN = IN;
     type OUT = OUT;
     fn on_element(&mut self, element: Self::IN, ctx: WindowContext) -> ArconResult<()> {
-        self.aggregator.set_item_key(ctx.key);
-        self.aggregator.set_namespace(ctx.index);
+        match &mut self.handle {
+            IncrementalHandle::Aggregating(aggregator) => {
+                aggregator.set_item_key(ctx.key);
+                aggregator.set_namespace(ctx.index);
+                aggregator.aggregate(element)?;
+            }
+            IncrementalHandle::Merging {
+                handle, init, agg, ..
+            } => {
+                handle.set_item_key(ctx.key);
+                handle.set_namespace(ctx.index);
 
-        self.aggregator.aggregate(element)?;
+                let agg = match handle.get()? {
+                    Some(current) => (agg)(element, &current),
+                    None => (init)(element),
+                };
+                handle.fast_set(agg)?;
+            }
+        }
 
         Ok(())
     }
 
     fn result(&mut self, ctx: WindowContext) -> ArconResult<Self::OUT> {
-        self.aggregator.set_item_key(ctx.key);
-        self.aggregator.set_namespace(ctx.index);
+        match &mut self.handle {
+            IncrementalHandle::Aggregating(aggregator) => {
+                aggregator.set_item_key(ctx.key);
+                aggregator.set_namespace(ctx.index);
+                let result = aggregator.get()?;
+                Ok(result)
+            }
+            IncrementalHandle::Merging { handle, .. } => {
+                handle.set_item_key(ctx.key);
+                handle.set_namespace(ctx.index);
 
-        let result = self.aggregator.get()?;
-        Ok(result)
+                match handle.get()? {
+                    Some(result) => Ok(result),
+                    None => reportable_error!("uninitialized incremental window"),
+                }
+            }
+        }
     }
 
     fn clear(&mut self, ctx: WindowContext) -> ArconResult<()> {
-        self.aggregator.set_item_key(ctx.key);
-        self.aggregator.set_namespace(ctx.index);
+        match &mut self.handle {
+            IncrementalHandle::Aggregating(aggregator) => {
+                aggregator.set_item_key(ctx.key);
+                aggregator.set_namespace(ctx.index);
+                let _ = aggregator.clear()?;
+            }
+            IncrementalHandle::Merging { handle, .. } => {
+                handle.set_item_key(ctx.key);
+                handle.set_namespace(ctx.index);
+                handle.clear()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn can_merge(&self) -> bool {
+        matches!(self.handle, IncrementalHandle::Merging { .. })
+    }
+
+    fn merge(&mut self, from: WindowContext, into: WindowContext) -> ArconResult<()> {
+        let (handle, merge) = match &mut self.handle {
+            IncrementalHandle::Merging { handle, merge, .. } => (handle, merge.clone()),
+            IncrementalHandle::Aggregating(_) => {
+                return reportable_error!("merge called on a non-merging IncrementalWindow")
+            }
+        };
+
+        handle.set_item_key(from.key);
+        handle.set_namespace(from.index);
+
+        let from_agg = handle.get()?;
+        handle.clear()?;
+
+        handle.set_item_key(into.key);
+        handle.set_namespace(into.index);
+
+        let merged = match (handle.get()?, from_agg) {
+            (Some(into_agg), Some(from_agg)) => merge(&into_agg, &from_agg),
+            (into_agg, from_agg) => match into_agg.or(from_agg) {
+                Some(agg) => agg,
+                None => return Ok(()),
+            },
+        };
+        handle.fast_set(merged)?;
 
-        let _ = self.aggregator.clear()?;
         Ok(())
     }
 }
+
 impl<IN, OUT, INIT, AGG, B> IndexOps for IncrementalWindow<IN, OUT, INIT, AGG, B>
 where
     IN: ArconType,
@@ -44,6 +44,7 @@ mod tests {
         }
 
         let mut window = IncrementalWindow::new(backend, &init, &aggregation);
+        assert!(!window.can_merge());
 
         for i in 0..10 {
             let _ = window.on_element(i, WindowContext::new(0, 0));
@@ -58,4 +59,33 @@ mod tests {
         let sum_two = window.result(WindowContext::new(1, 1)).unwrap();
         assert_eq!(sum_two, 190);
     }
+
+    #[test]
+    fn merge_incremental_window_test() {
+        let backend = Arc::new(temp_backend::<Sled>());
+
+        fn init(i: i32) -> u64 {
+            i as u64
+        }
+        fn aggregation(i: i32, agg: &u64) -> u64 {
+            agg + i as u64
+        }
+
+        let mut window =
+            IncrementalWindow::merging(backend, &init, &aggregation, |fst, snd| fst + snd);
+        assert!(window.can_merge());
+
+        for i in 0..10 {
+            let _ = window.on_element(i, WindowContext::new(0, 0));
+            let _ = window.on_element(i, WindowContext::new(0, 1));
+        }
+
+        window
+            .merge(WindowContext::new(0, 1), WindowContext::new(0, 0))
+            .unwrap();
+
+        let sum = window.result(WindowContext::new(0, 0)).unwrap();
+        assert_eq!(sum, 90);
+        assert!(window.result(WindowContext::new(0, 1)).is_err());
+    }
 }
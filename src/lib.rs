@@ -124,7 +124,11 @@ pub mod test_utils {
     use arcon_allocator::Allocator;
     use arcon_state::backend::Backend;
     use once_cell::sync::Lazy;
-    use std::sync::{Arc, Mutex};
+    use std::{
+        sync::{Arc, Mutex},
+        thread,
+        time::{Duration, Instant},
+    };
 
     pub static ALLOCATOR: Lazy<Arc<Mutex<Allocator>>> =
         Lazy::new(|| Arc::new(Mutex::new(Allocator::new(1073741824))));
@@ -134,24 +138,58 @@ pub mod test_utils {
         let path = test_dir.path();
         B::create(path, "testDB".to_string()).unwrap()
     }
+
+    /// Polls `condition` until it holds or `timeout` has passed
+    ///
+    /// Returns whether the condition held, so that tests wait on an outcome rather than a fixed time.
+    pub fn wait_until(timeout: Duration, mut condition: impl FnMut() -> bool) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if condition() {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
 }
 
 /// Helper module that imports everything related to arcon into scope
 pub mod prelude {
     pub use crate::{
-        application::conf::{logger::LoggerType, ApplicationConf},
-        application::{Application, AssembledApplication, Stream},
-        data::{ArconElement, ArconNever, ArconType, StateID, VersionId},
+        application::conf::{logger::LoggerType, ApplicationConf, DistributedConf, ExecutionMode},
+        application::{
+            Application, AssembledApplication, ConnectedStreams, KeyedStream, RebalancedStream,
+            RestorePoint, Stream,
+        },
+        data::{
+            partition::{ConsistentHashPartitioner, KeyRange, Partitioner, RangePartitioner},
+            ArconElement, ArconNever, ArconType, CoElement, CoInput, StateID, VersionId,
+        },
         dataflow::{
-            api::{Assigner, OperatorBuilder, SourceBuilder},
-            conf::{OperatorConf, ParallelismStrategy, SourceConf, StreamKind, WindowConf},
+            api::{Assigner, CoOperatorBuilder, OperatorBuilder, SourceBuilder},
+            conf::{
+                IntervalJoinConf, KeyExtractor, OperatorConf, ParallelismStrategy, SourceConf,
+                StreamKind, WindowConf,
+            },
         },
-        manager::snapshot::Snapshot,
+        manager::snapshot::{CommittedEpoch, Snapshot, SnapshotCatalog, StateSnapshot},
         stream::{
             operator::{
+                co_operator::CoOperator,
                 function::{Filter, FlatMap, Map, MapInPlace},
-                sink::local_file::LocalFileSink,
-                window::{WindowAssigner, WindowState},
+                join::{IntervalJoin, IntervalJoinState, WindowJoin, WindowJoinState},
+                side_output::OutputTag,
+                sink::{
+                    local_file::{LocalFileSink, TransactionalFileSink},
+                    two_phase::{TwoPhaseCommit, TwoPhaseCommitSink},
+                },
+                window::{
+                    CountEvictor, CountTrigger, Evictor, PurgingTrigger, Trigger, TriggerContext,
+                    TriggerResult, WindowAssigner, WindowState,
+                },
                 Operator, OperatorContext,
             },
             source::{schema::ProtoSchema, Source},
@@ -194,7 +232,7 @@ pub mod prelude {
     pub use crate::index::{
         timer, AppenderIndex, AppenderWindow, ArrowWindow, EagerAppender, EagerHashTable,
         EagerValue, EmptyState, HashTable, IncrementalWindow, IndexOps, LazyValue, LocalValue,
-        ValueIndex,
+        TtlConf, TtlUpdate, ValueIndex,
     };
 
     pub use prost::*;
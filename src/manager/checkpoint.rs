@@ -0,0 +1,79 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A pool of threads that write checkpoints off the data path
+///
+/// The threads exit once every handle to the pool has been dropped.
+#[derive(Clone)]
+pub(crate) struct CheckpointPool {
+    sender: Arc<Mutex<Sender<Job>>>,
+}
+
+impl CheckpointPool {
+    pub(crate) fn new(threads: usize) -> Self {
+        assert!(threads > 0, "a CheckpointPool requires at least one thread");
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for index in 0..threads {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("arcon-checkpoint-{}", index))
+                .spawn(move || worker(receiver))
+                .expect("failed to spawn checkpoint thread");
+        }
+        Self {
+            sender: Arc::new(Mutex::new(sender)),
+        }
+    }
+
+    /// Runs `job` on one of the threads of the pool
+    pub(crate) fn execute<F>(&self, job: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let sender = self.sender.lock().unwrap();
+        // the threads only exit once the sender is gone
+        let _ = sender.send(Box::new(job));
+    }
+}
+
+fn worker(receiver: Arc<Mutex<Receiver<Job>>>) {
+    loop {
+        let job = match receiver.lock().unwrap().recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        job();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn checkpoint_pool_test() {
+        let pool = CheckpointPool::new(2);
+        let (tx, rx) = mpsc::channel();
+        for i in 0..4u64 {
+            let tx = tx.clone();
+            pool.execute(move || tx.send(i).unwrap());
+        }
+        let mut results: Vec<u64> = (0..4)
+            .map(|_| rx.recv_timeout(Duration::from_secs(5)).unwrap())
+            .collect();
+        results.sort_unstable();
+        assert_eq!(results, vec![0, 1, 2, 3]);
+    }
+}
@@ -3,20 +3,79 @@
 
 use crate::{
     application::conf::logger::ArconLogger,
-    data::{Epoch, StateID},
-    stream::node::source::SourceEvent,
+    data::{ser_id::PEER_EPOCH_ID, Epoch, StateID},
+    manager::scaling::ManagedParallelism,
+    stream::{channel::Dispatcher, node::source::SourceEvent},
 };
 use kompact::prelude::*;
-use std::collections::HashSet;
+use prost::Message;
+use std::collections::{BTreeMap, HashSet};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EpochCommit(pub Epoch);
 
+/// Alias that the EpochManager of a distributed Application is registered by
+pub(crate) const EPOCH_MANAGER_ALIAS: &str = "epoch_manager";
+/// Index of the peer whose EpochManager commits the epochs of a distributed Application
+const COORDINATOR: usize = 0;
+
+/// Message that the EpochManagers of a distributed Application exchange
+///
+/// Peers ack an epoch to the coordinator once all of their own StateIDs have acked it,
+/// and the coordinator tells every peer to commit the epoch once all peers have acked it.
+#[derive(prost::Message, Clone)]
+pub struct PeerEpochEvent {
+    /// Index of the peer that sent the event
+    #[prost(uint32, tag = "1")]
+    pub peer: u32,
+    #[prost(uint64, tag = "2")]
+    pub epoch: u64,
+    /// Set if the epoch is committed, otherwise the peer acks it
+    #[prost(bool, tag = "3")]
+    pub commit: bool,
+}
+
+impl Serialisable for PeerEpochEvent {
+    fn ser_id(&self) -> SerId {
+        PEER_EPOCH_ID
+    }
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.encoded_len())
+    }
+    fn serialise(&self, mut buf: &mut dyn BufMut) -> Result<(), SerError> {
+        self.encode(&mut buf)
+            .map_err(|e| SerError::InvalidData(e.to_string()))
+    }
+    fn local(self: Box<Self>) -> Result<Box<dyn Any + Send>, Box<dyn Serialisable>> {
+        Ok(self)
+    }
+}
+
+impl Deserialiser<PeerEpochEvent> for PeerEpochEvent {
+    const SER_ID: SerId = PEER_EPOCH_ID;
+
+    fn deserialise(buf: &mut dyn Buf) -> Result<PeerEpochEvent, SerError> {
+        PeerEpochEvent::decode(buf.chunk()).map_err(|e| SerError::InvalidData(e.to_string()))
+    }
+}
+
+/// The EpochManagers of the other processes of a distributed Application
+struct Peers {
+    /// Index of this process
+    peer: usize,
+    /// Paths of the EpochManagers of every process, including this one
+    paths: Vec<ActorPath>,
+    /// Peers that have acked each uncommitted epoch, only kept by the coordinator
+    acks: BTreeMap<u64, HashSet<u32>>,
+}
+
 #[derive(Debug)]
 pub enum EpochEvent {
     /// Acknowledgement that `StateID` has committed a checkpoint for epoch `Epoch`
     Ack(StateID, Epoch),
     Register(StateID),
+    /// Request to change a managed parallelism from the next injected epoch onwards
+    Rescale(ManagedParallelism, usize),
     Halt,
 }
 
@@ -29,8 +88,10 @@ pub struct EpochManager {
     next_epoch: u64,
     /// Interval in millis to schedule injection timer
     epoch_interval: u64,
-    /// Reference to the SourceManager
-    pub(crate) source_manager: Option<ActorRefStrong<SourceEvent>>,
+    /// References to the SourceManagers of the application
+    pub(crate) source_managers: Vec<ActorRefStrong<SourceEvent>>,
+    /// Number of SourceManagers that have signalled the end of their sources
+    halted_sources: usize,
     /// Kompact Timer
     epoch_timeout: Option<ScheduledTimer>,
     /// Set of known state ids the EpochManager expects acknowledgements from
@@ -41,6 +102,12 @@ pub struct EpochManager {
     epoch_acks: HashSet<(StateID, Epoch)>,
     /// Actor Reference to the SnapshotManager
     snapshot_manager: ActorRefStrong<EpochCommit>,
+    /// Set if epochs may arrive from the sources of other processes
+    remote_sources: bool,
+    /// Set if the epochs are committed together with the other processes
+    peers: Option<Peers>,
+    /// Rescales that are applied from the next injected epoch onwards
+    pending_rescales: Vec<(ManagedParallelism, usize)>,
     logger: ArconLogger,
 }
 
@@ -58,20 +125,50 @@ impl EpochManager {
             ongoing_epoch_commit: 0,
             epoch_interval,
             snapshot_manager,
-            source_manager: None,
+            source_managers: Vec::new(),
+            halted_sources: 0,
             epoch_timeout: None,
+            remote_sources: false,
+            peers: None,
+            pending_rescales: Vec::new(),
             logger,
         }
     }
+
+    /// Commit every epoch together with the EpochManagers at `paths`
+    ///
+    /// An epoch is only committed once the processes of all `paths` have acked it, so that
+    /// the checkpoints of a committed epoch form a consistent snapshot of the whole
+    /// Application. `peer` is the index of this process in `paths`.
+    ///
+    /// Epochs may then also be injected by the sources of other processes, so a process
+    /// without sources of its own only acks the epochs of its state.
+    pub(crate) fn with_peers(mut self, peer: usize, paths: Vec<ActorPath>) -> Self {
+        self.remote_sources = true;
+        self.peers = Some(Peers {
+            peer,
+            paths,
+            acks: BTreeMap::new(),
+        });
+        self
+    }
+
+    /// Continue injecting epochs from `epoch` after the Application has been restored
+    pub(crate) fn set_next_epoch(&mut self, epoch: Epoch) {
+        self.next_epoch = epoch.epoch;
+        self.ongoing_epoch_commit = epoch.epoch;
+    }
+
     fn handle_timeout(&mut self, timeout_id: ScheduledTimer) -> Handled {
         match self.epoch_timeout {
             Some(ref timeout) if *timeout == timeout_id => {
-                if let Some(source_manager) = &self.source_manager {
-                    source_manager.tell(SourceEvent::Epoch(Epoch::new(self.next_epoch)));
-                    self.next_epoch += 1;
+                if self.source_managers.is_empty() {
+                    if !self.remote_sources {
+                        #[cfg(not(test))]
+                        error!(self.logger, "SourceManager was never set");
+                    }
                 } else {
-                    #[cfg(not(test))]
-                    error!(self.logger, "SourceManager was never set");
+                    self.inject_epoch();
                 }
                 Handled::Ok
             }
@@ -82,19 +179,130 @@ impl EpochManager {
             } // can happen during restart or teardown
         }
     }
+
+    /// Injects an epoch right away instead of waiting for the injection timer
+    ///
+    /// Returns the injected epoch.
+    pub(crate) fn trigger_epoch(&mut self) -> Epoch {
+        let epoch = Epoch::new(self.next_epoch);
+        self.inject_epoch();
+        epoch
+    }
+
+    /// Injects an epoch right away, after which the sources stop polling records
+    ///
+    /// Returns the injected epoch.
+    pub(crate) fn trigger_final_epoch(&mut self) -> Epoch {
+        let epoch = Epoch::new(self.next_epoch);
+        self.inject_marker(SourceEvent::StopAfter);
+        epoch
+    }
+
+    /// Sends the next epoch to every SourceManager
+    fn inject_epoch(&mut self) {
+        self.inject_marker(SourceEvent::Epoch);
+    }
+
+    /// Sends the `marker` of the next epoch to every SourceManager
+    ///
+    /// Pending rescales are scheduled for the epoch before its marker enters the pipeline.
+    fn inject_marker(&mut self, marker: fn(Epoch) -> SourceEvent) {
+        for (parallelism, active) in self.pending_rescales.drain(..) {
+            parallelism.schedule(self.next_epoch, active);
+        }
+        for source_manager in &self.source_managers {
+            source_manager.tell(marker(Epoch::new(self.next_epoch)));
+        }
+        self.next_epoch += 1;
+    }
+
+    /// Commits the ongoing epoch and any following epochs that every StateID has acked
+    fn commit_acked_epochs(&mut self) {
+        loop {
+            let epoch = Epoch::new(self.ongoing_epoch_commit);
+            let acks = self.epoch_acks.iter().filter(|(_, e)| *e == epoch).count();
+            if acks < self.known_state_ids.len() {
+                break;
+            }
+            self.complete_epoch(epoch);
+            self.epoch_acks.retain(|(_, e)| *e != epoch);
+            self.ongoing_epoch_commit += 1;
+        }
+    }
+
+    /// Commits an epoch that every local StateID has acked, or acks it to the coordinator
+    fn complete_epoch(&mut self, epoch: Epoch) {
+        let peer = match &self.peers {
+            Some(peers) => peers.peer,
+            None => return self.snapshot_manager.tell(EpochCommit(epoch)),
+        };
+        let ack = PeerEpochEvent {
+            peer: peer as u32,
+            epoch: epoch.epoch,
+            commit: false,
+        };
+        if peer == COORDINATOR {
+            self.handle_peer_event(ack);
+        } else {
+            self.tell_peer(COORDINATOR, ack);
+        }
+    }
+
+    fn handle_peer_event(&mut self, event: PeerEpochEvent) {
+        if event.commit {
+            self.snapshot_manager
+                .tell(EpochCommit(Epoch::new(event.epoch)));
+            return;
+        }
+        let peers = match self.peers.as_mut() {
+            Some(peers) if peers.peer == COORDINATOR => peers,
+            _ => {
+                warn!(self.logger, "Ignoring epoch ack of peer {}", event.peer);
+                return;
+            }
+        };
+        let num_peers = peers.paths.len();
+        let acks = peers.acks.entry(event.epoch).or_insert_with(HashSet::new);
+        acks.insert(event.peer);
+        if acks.len() < num_peers {
+            return;
+        }
+        // every peer acks its epochs in order, so earlier epochs have been committed already
+        peers.acks.remove(&event.epoch);
+        let commit = PeerEpochEvent {
+            peer: COORDINATOR as u32,
+            epoch: event.epoch,
+            commit: true,
+        };
+        for peer in 0..num_peers {
+            if peer != COORDINATOR {
+                self.tell_peer(peer, commit.clone());
+            }
+        }
+        self.handle_peer_event(commit);
+    }
+
+    fn tell_peer(&self, peer: usize, event: PeerEpochEvent) {
+        if let Some(peers) = &self.peers {
+            if let Err(error) = self.tell_serialised(&peers.paths[peer], Box::new(event)) {
+                error!(
+                    self.logger,
+                    "Failed to send epoch event to peer {}: {:?}", peer, error
+                );
+            }
+        }
+    }
+
     fn handle_epoch_event(&mut self, event: EpochEvent) {
         match event {
             EpochEvent::Ack(state_id, epoch) => {
                 // verify the state_id
                 if self.known_state_ids.contains(&state_id) {
-                    // Make sure the epoch is for the ongoing checkpoint
-                    if epoch.epoch == self.ongoing_epoch_commit {
+                    // Checkpoints are written in the background and may complete out of order,
+                    // so acks of later epochs are kept until the ongoing epoch is committed.
+                    if epoch.epoch >= self.ongoing_epoch_commit {
                         self.epoch_acks.insert((state_id, epoch));
-                        if self.epoch_acks.len() == self.known_state_ids.len() {
-                            self.ongoing_epoch_commit = epoch.epoch + 1;
-                            self.snapshot_manager.tell(EpochCommit(epoch));
-                            self.epoch_acks.clear();
-                        }
+                        self.commit_acked_epochs();
                     }
                 } else {
                     info!(
@@ -111,19 +319,30 @@ impl EpochManager {
                     self.known_state_ids.insert(state_id);
                 }
             }
+            EpochEvent::Rescale(parallelism, active) => {
+                self.pending_rescales
+                    .retain(|(p, _)| !p.is_same(&parallelism));
+                self.pending_rescales.push((parallelism, active));
+            }
             EpochEvent::Halt => {
-                if let Some(source_manager) = &self.source_manager {
+                if self.source_managers.is_empty() {
+                    error!(self.logger, "SourceManager was never set");
+                    return;
+                }
+
+                self.halted_sources += 1;
+
+                // Wait until every SourceManager has signalled the end of its sources
+                if self.halted_sources == self.source_managers.len() {
                     // Send a final epoch marker before revoking the timer
                     info!(
                         self.logger,
                         "EpochManager sending final epoch marker {:?}", self.next_epoch
                     );
-                    source_manager.tell(SourceEvent::Epoch(Epoch::new(self.next_epoch)));
+                    self.inject_epoch();
                     if let Some(timeout) = self.epoch_timeout.take() {
                         self.cancel_timer(timeout);
                     }
-                } else {
-                    error!(self.logger, "SourceManager was never set");
                 }
             }
         }
@@ -137,8 +356,12 @@ impl Actor for EpochManager {
         self.handle_epoch_event(msg);
         Handled::Ok
     }
-    fn receive_network(&mut self, _: NetMessage) -> Handled {
-        unimplemented!();
+    fn receive_network(&mut self, msg: NetMessage) -> Handled {
+        match msg.try_deserialise::<PeerEpochEvent, PeerEpochEvent>() {
+            Ok(event) => self.handle_peer_event(event),
+            Err(error) => error!(self.logger, "Failed to unpack PeerEpochEvent: {:?}", error),
+        }
+        Handled::Ok
     }
 }
 
@@ -1,10 +1,16 @@
 // Copyright (c) 2020, KTH Royal Institute of Technology.
 // SPDX-License-Identifier: AGPL-3.0-only
 
+/// Thread pool that writes checkpoints in the background
+pub(crate) mod checkpoint;
 /// A [kompact] component that injects epoch markers into the dataflow
 pub mod epoch;
 /// A [kompact] component that keeps track of a set of Arcon nodes
 pub mod node;
+/// Retention of checkpoints of committed epochs
+pub mod retention;
+/// Scaling decisions for Operators with a managed parallelism
+pub mod scaling;
 /// A [kompact] component that keeps track of one or more source components
 pub mod source;
 
@@ -2,30 +2,35 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 
 use crate::{
-    application::conf::logger::ArconLogger,
+    application::{
+        conf::logger::ArconLogger,
+        restore::{channel_state_id, checkpoint_name, mark_complete},
+    },
     data::{ArconMessage, Epoch, NodeID, StateID, Watermark},
     error::*,
     index::{HashTable, IndexOps, LocalValue, ValueIndex, EMPTY_STATE_ID},
     manager::{
-        epoch::EpochEvent,
+        checkpoint::CheckpointPool,
+        epoch::{EpochCommit, EpochEvent},
+        scaling::{ManagedParallelism, ScalingPolicy, PARALLELISM_ID},
         snapshot::{Snapshot, SnapshotEvent, SnapshotManagerPort},
     },
     prelude::OperatorBuilder,
     reportable_error,
-    stream::operator::Operator,
+    stream::{node::channel_state_path, operator::Operator},
 };
 
 #[cfg(feature = "metrics")]
 use metrics::{gauge, histogram, register_gauge, register_histogram};
 
 use arcon_macros::ArconState;
-use arcon_state::Backend;
+use arcon_state::{key_groups::KeyGroupRange, Backend};
 use fxhash::FxHashMap;
 use kompact::{component::AbstractComponent, prelude::*};
 
 #[cfg(feature = "metrics")]
 use std::time::Instant;
-use std::{collections::HashSet, fs, sync::Arc};
+use std::{collections::HashSet, fs, path::PathBuf, sync::Arc};
 
 pub type AbstractNode<IN> = (
     Arc<dyn AbstractComponent<Message = ArconMessage<IN>>>,
@@ -65,6 +70,10 @@ pub enum CheckpointResponse {
 #[derive(Clone, Debug)]
 pub enum NodeEvent {
     CheckpointResponse(CheckpointResponse),
+    /// Another Node has started an unaligned checkpoint of the Epoch
+    StartEpoch(Epoch),
+    /// Every Operator and source of the Application has completed a checkpoint of the Epoch
+    EpochCommit(Epoch),
 }
 
 /// Enum representing events that a Node may send to its manager
@@ -74,6 +83,10 @@ pub enum NodeManagerEvent {
     Watermark(NodeID, Watermark),
     Epoch(NodeID, Epoch),
     Checkpoint(CheckpointRequest),
+    /// Inbound events per second of a Node since its last report
+    Throughput(NodeID, f64),
+    /// Serialised in-flight messages of a Node for an unaligned checkpoint of an Epoch
+    ChannelState(NodeID, Epoch, Vec<u8>),
 }
 
 /// A [kompact] port for bidirectional communication between a Node and its NodeManager
@@ -89,8 +102,13 @@ pub struct NodeManagerState<B: Backend> {
     epochs: HashTable<NodeID, Epoch, B>,
     current_watermark: LocalValue<Watermark, B>,
     current_epoch: LocalValue<Epoch, B>,
+    /// Number of active instances as of the latest checkpoint, if the parallelism is managed
+    parallelism: LocalValue<u64, B>,
     #[ephemeral]
     checkpoint_acks: HashSet<(NodeID, Epoch)>,
+    /// Latest reported inbound throughput per Node
+    #[ephemeral]
+    throughputs: FxHashMap<NodeID, f64>,
 }
 
 impl<B: Backend> NodeManagerState<B> {
@@ -99,8 +117,10 @@ impl<B: Backend> NodeManagerState<B> {
             watermarks: HashTable::with_capacity("_watermarks", backend.clone(), 64, 64),
             epochs: HashTable::with_capacity("_epochs", backend.clone(), 64, 64),
             current_watermark: LocalValue::new("_curr_watermark", backend.clone()),
-            current_epoch: LocalValue::new("_curr_epoch", backend),
+            current_epoch: LocalValue::new("_curr_epoch", backend.clone()),
+            parallelism: LocalValue::new(PARALLELISM_ID, backend),
             checkpoint_acks: HashSet::new(),
+            throughputs: FxHashMap::default(),
         }
     }
 }
@@ -157,6 +177,39 @@ where
     latest_snapshot: Option<Snapshot>,
     builder: OperatorBuilder<OP, B>,
     logger: ArconLogger,
+    /// Set if the runtime manages the parallelism of the Operator
+    scaling: Option<Scaling>,
+    /// Pool that checkpoints are written on, otherwise they are written on the data path
+    checkpoint_pool: Option<CheckpointPool>,
+    /// Set if the Nodes take unaligned checkpoints
+    unaligned_checkpoints: bool,
+    /// Written checkpoints per epoch that are waiting for the channel state of the Nodes
+    written_checkpoints: FxHashMap<u64, String>,
+    /// Channel state per epoch and Node of unaligned checkpoints
+    channel_states: FxHashMap<u64, FxHashMap<NodeID, Vec<u8>>>,
+    /// Key groups of the local instances, which are the ones held by the backend
+    key_groups: Vec<KeyGroupRange>,
+}
+
+/// Outcome of writing a checkpoint in the background
+#[derive(Debug)]
+pub struct CheckpointCompleted {
+    epoch: u64,
+    checkpoint_dir: String,
+    result: Result<(), String>,
+    #[cfg(feature = "metrics")]
+    started: Instant,
+}
+
+/// Scaling state of an Operator with a managed parallelism
+struct Scaling {
+    parallelism: ManagedParallelism,
+    policy: ScalingPolicy,
+    /// Interval in milliseconds between scaling decisions
+    interval: u64,
+    timeout: Option<ScheduledTimer>,
+    /// Parallelism that has been requested but not yet scheduled for an epoch
+    requested: Option<usize>,
 }
 
 impl<OP, B> NodeManager<OP, B>
@@ -176,6 +229,7 @@ where
         #[cfg(feature = "metrics")]
         {
             register_gauge!("nodes", "node_manager" => state_id.clone());
+            register_gauge!("parallelism", "node_manager" => state_id.clone());
             register_histogram!("checkpoint_execution_time_ms", "node_manager" => state_id.clone());
             register_gauge!("last_checkpoint_size", "node_manager"=> state_id.clone());
         }
@@ -196,10 +250,95 @@ where
             latest_snapshot: None,
             logger,
             builder,
+            scaling: None,
+            checkpoint_pool: None,
+            unaligned_checkpoints: false,
+            written_checkpoints: FxHashMap::default(),
+            channel_states: FxHashMap::default(),
+            key_groups: Vec::new(),
         }
     }
 
-    #[inline]
+    /// Record the key groups of the local instances in the snapshots of the Operator
+    ///
+    /// They allow the checkpoints to be redistributed when restored at another parallelism.
+    pub(crate) fn with_key_groups(mut self, key_groups: Vec<KeyGroupRange>) -> Self {
+        self.key_groups = key_groups;
+        self
+    }
+
+    /// Continue from `epoch` after the backend of the Operator has been restored
+    pub(crate) fn with_epoch(mut self, epoch: Epoch) -> ArconResult<Self> {
+        self.manager_state.current_epoch().put(epoch)?;
+        Ok(self)
+    }
+
+    /// Let the NodeManager decide the parallelism of its Operator
+    ///
+    /// Every `interval` milliseconds, `policy` is evaluated against the throughput
+    /// reported by the Nodes. A new parallelism is handed to the EpochManager,
+    /// which schedules it for the next epoch it injects.
+    pub(crate) fn with_managed_parallelism(
+        mut self,
+        parallelism: ManagedParallelism,
+        policy: ScalingPolicy,
+        interval: u64,
+    ) -> Self {
+        self.scaling = Some(Scaling {
+            parallelism,
+            policy,
+            interval,
+            timeout: None,
+            requested: None,
+        });
+        self
+    }
+
+    fn handle_scaling_timeout(&mut self, timeout_id: ScheduledTimer) -> Handled {
+        let scaling = match &mut self.scaling {
+            Some(scaling) if scaling.timeout.as_ref() == Some(&timeout_id) => scaling,
+            _ => return Handled::Ok, // just ignore outdated timeouts
+        };
+
+        let scheduled = scaling.parallelism.active();
+        if scaling.requested == Some(scheduled) {
+            scaling.requested = None;
+        }
+        let current = scaling.requested.unwrap_or(scheduled);
+        let throughputs: Vec<f64> = self.manager_state.throughputs.values().copied().collect();
+        let target = scaling.policy.target(current, &throughputs);
+
+        if target != current {
+            info!(
+                self.logger,
+                "Rescaling {} from {} to {} instances", self.state_id, current, target
+            );
+            // The EpochManager schedules the new parallelism for the next epoch
+            scaling.requested = Some(target);
+            self.epoch_manager
+                .tell(EpochEvent::Rescale(scaling.parallelism.clone(), target));
+            #[cfg(feature = "metrics")]
+            gauge!("parallelism", target as f64, "node_manager" => self.state_id.clone());
+        }
+
+        Handled::Ok
+    }
+
+    /// Write the checkpoints of the Operator on `pool` instead of on the data path
+    pub(crate) fn with_checkpoint_pool(mut self, pool: CheckpointPool) -> Self {
+        self.checkpoint_pool = Some(pool);
+        self
+    }
+
+    /// Commit the checkpoints of the Operator together with the channel state of its Nodes
+    pub(crate) fn with_unaligned_checkpoints(mut self) -> Self {
+        self.unaligned_checkpoints = true;
+        self
+    }
+
+    /// Captures the state of the backend and writes the checkpoint in the background
+    ///
+    /// The epoch is acked once the checkpoint has been written, see [complete_checkpoint](Self::complete_checkpoint).
     fn checkpoint(&mut self) -> ArconResult<()> {
         if let Some(base_dir) = &self.ctx.config()["checkpoint_dir"].as_string() {
             let curr_epoch = match self.manager_state.current_epoch().get()? {
@@ -208,51 +347,42 @@ where
             };
 
             let checkpoint_dir = format!(
-                "{}/checkpoint_{id}_{epoch}",
+                "{}/{}",
                 base_dir,
-                id = self.state_id,
-                epoch = curr_epoch,
+                checkpoint_name(&self.state_id, curr_epoch)
             );
 
-            self.backend.checkpoint(checkpoint_dir.as_ref())?;
-
-            // Send snapshot to SnapshotManager
-            if self.has_snapshot_state() {
-                let snapshot = Snapshot::new(
-                    std::any::type_name::<B>().to_string(),
-                    curr_epoch,
-                    checkpoint_dir.clone(),
-                );
-
-                self.snapshot_manager_port.trigger(SnapshotEvent::Snapshot(
-                    self.state_id.clone(),
-                    snapshot.clone(),
-                ));
-
-                #[cfg(feature = "metrics")]
-                {
-                    let metadata = fs::metadata(checkpoint_dir.clone())?;
-                    gauge!("last_checkpoint_size", metadata.len() as f64,"node_manager" => self.state_id.clone());
-                }
-
-                self.latest_snapshot = Some(snapshot);
+            // a restored Operator continues with the parallelism that follows the checkpoint
+            if let Some(scaling) = &self.scaling {
+                let active = scaling.parallelism.active_after(curr_epoch) as u64;
+                self.manager_state.parallelism.put(active)?;
+                self.manager_state.parallelism.persist()?;
             }
 
-            // Send Ack to EpochManager
-            self.epoch_manager.tell(EpochEvent::Ack(
-                self.state_id.clone(),
-                Epoch::new(curr_epoch),
-            ));
+            #[cfg(feature = "metrics")]
+            let started = Instant::now();
+            let write_checkpoint = self.backend.capture_checkpoint(checkpoint_dir.as_ref())?;
 
             // bump epoch
             self.manager_state.current_epoch().rmw(|e| {
                 e.epoch += 1;
             })?;
 
-            debug!(
-                self.logger,
-                "Completed a Checkpoint to path {}", checkpoint_dir
-            );
+            let manager = self.actor_ref();
+            let write = move || {
+                let result = write_checkpoint().map_err(|err| err.to_string());
+                manager.tell(CheckpointCompleted {
+                    epoch: curr_epoch,
+                    checkpoint_dir,
+                    result,
+                    #[cfg(feature = "metrics")]
+                    started,
+                });
+            };
+            match &self.checkpoint_pool {
+                Some(pool) => pool.execute(write),
+                None => write(),
+            }
         } else {
             return reportable_error!("Failed to fetch checkpoint_dir from Config");
         }
@@ -260,6 +390,108 @@ where
         Ok(())
     }
 
+    /// Finishes a checkpoint once it has been written by the backend
+    fn complete_checkpoint(&mut self, completed: CheckpointCompleted) -> ArconResult<()> {
+        let CheckpointCompleted {
+            epoch,
+            checkpoint_dir,
+            result,
+            ..
+        } = completed;
+
+        if let Err(msg) = result {
+            return reportable_error!(
+                "Failed to write checkpoint {} with err {}",
+                checkpoint_dir,
+                msg
+            );
+        }
+
+        #[cfg(feature = "metrics")]
+        {
+            let elapsed = completed.started.elapsed();
+            histogram!("checkpoint_execution_time_ms", elapsed.as_millis() as f64,"node_manager" => self.state_id.clone());
+        }
+
+        if self.unaligned_checkpoints {
+            self.written_checkpoints.insert(epoch, checkpoint_dir);
+            self.commit_unaligned_checkpoint(epoch)
+        } else {
+            self.commit_checkpoint(epoch, checkpoint_dir)
+        }
+    }
+
+    /// Commits an unaligned checkpoint once it has been written and every Node has sent its channel state
+    fn commit_unaligned_checkpoint(&mut self, epoch: u64) -> ArconResult<()> {
+        let complete = self.written_checkpoints.contains_key(&epoch)
+            && self.channel_states.get(&epoch).map(|states| states.len()) == Some(self.nodes.len());
+        if !complete {
+            return Ok(());
+        }
+        let checkpoint_dir = self.written_checkpoints.remove(&epoch).unwrap();
+        let channel_states = self.channel_states.remove(&epoch).unwrap_or_default();
+
+        let channel_state_id = channel_state_id(&self.state_id);
+        let mut channels_dir = PathBuf::from(&checkpoint_dir);
+        channels_dir.set_file_name(checkpoint_name(&channel_state_id, epoch));
+        fs::create_dir_all(&channels_dir)?;
+        for (node, bytes) in channel_states {
+            fs::write(channel_state_path(&channels_dir, node), bytes)?;
+        }
+        mark_complete(&channels_dir)?;
+
+        let snapshot = Snapshot::new(
+            String::from("ChannelState"),
+            epoch,
+            channels_dir.to_string_lossy().into_owned(),
+        );
+        self.snapshot_manager_port
+            .trigger(SnapshotEvent::Snapshot(channel_state_id, snapshot));
+
+        self.commit_checkpoint(epoch, checkpoint_dir)
+    }
+
+    /// Hands a written checkpoint to the SnapshotManager and acks its epoch
+    fn commit_checkpoint(&mut self, epoch: u64, checkpoint_dir: String) -> ArconResult<()> {
+        // Send snapshot to SnapshotManager
+        if self.has_snapshot_state() {
+            mark_complete(checkpoint_dir.as_ref())?;
+            let snapshot = Snapshot::new(
+                std::any::type_name::<B>().to_string(),
+                epoch,
+                checkpoint_dir.clone(),
+            )
+            .with_key_groups(self.key_groups.clone());
+
+            self.snapshot_manager_port.trigger(SnapshotEvent::Snapshot(
+                self.state_id.clone(),
+                snapshot.clone(),
+            ));
+
+            #[cfg(feature = "metrics")]
+            {
+                let metadata = fs::metadata(checkpoint_dir.clone())?;
+                gauge!("last_checkpoint_size", metadata.len() as f64,"node_manager" => self.state_id.clone());
+            }
+
+            // checkpoints may complete out of order
+            if self.latest_snapshot.as_ref().map(|s| s.epoch) < Some(epoch) {
+                self.latest_snapshot = Some(snapshot);
+            }
+        }
+
+        // Send Ack to EpochManager
+        self.epoch_manager
+            .tell(EpochEvent::Ack(self.state_id.clone(), Epoch::new(epoch)));
+
+        debug!(
+            self.logger,
+            "Completed a Checkpoint to path {}", checkpoint_dir
+        );
+
+        Ok(())
+    }
+
     /// Helper method to check if the NodeManager is responsible for any state
     /// that should go to the SnapshotManager.
     ///
@@ -277,6 +509,16 @@ where
             NodeManagerEvent::Epoch(id, e) => {
                 self.manager_state.epochs.put(id, e)?;
             }
+            NodeManagerEvent::Throughput(id, throughput) => {
+                self.manager_state.throughputs.insert(id, throughput);
+            }
+            NodeManagerEvent::ChannelState(id, epoch, bytes) => {
+                self.channel_states
+                    .entry(epoch.epoch)
+                    .or_insert_with(FxHashMap::default)
+                    .insert(id, bytes);
+                self.commit_unaligned_checkpoint(epoch.epoch)?;
+            }
             NodeManagerEvent::Checkpoint(request) => {
                 if self.nodes.contains_key(&request.id) {
                     let epoch = match self.manager_state.current_epoch().get()? {
@@ -288,16 +530,20 @@ where
                             .checkpoint_acks
                             .insert((request.id, request.epoch));
 
-                        if self.manager_state.checkpoint_acks.len() == self.nodes.len() {
-                            #[cfg(feature = "metrics")]
-                            let start_time = Instant::now();
+                        // the shared state is captured once every Node has started the epoch
+                        if self.unaligned_checkpoints
+                            && self.manager_state.checkpoint_acks.len() == 1
+                        {
+                            for (id, (_, port_ref)) in self.nodes.iter() {
+                                if *id != request.id {
+                                    self.data_system
+                                        .trigger_i(NodeEvent::StartEpoch(request.epoch), port_ref);
+                                }
+                            }
+                        }
 
+                        if self.manager_state.checkpoint_acks.len() == self.nodes.len() {
                             self.checkpoint()?;
-                            #[cfg(feature = "metrics")]
-                            {
-                                let elapsed = start_time.elapsed();
-                                histogram!("checkpoint_execution_time_ms", elapsed.as_millis() as f64,"node_manager" => self.state_id.clone());
-                            }
                             self.manager_state.checkpoint_acks.clear();
 
                             for (_, port_ref) in self.nodes.values() {
@@ -334,6 +580,20 @@ where
         self.epoch_manager
             .tell(EpochEvent::Register(self.state_id.clone()));
 
+        if let Some(interval) = self.scaling.as_ref().map(|s| s.interval) {
+            let duration = std::time::Duration::from_millis(interval);
+            let timeout = self.schedule_periodic(duration, duration, Self::handle_scaling_timeout);
+            if let Some(scaling) = &mut self.scaling {
+                scaling.timeout = Some(timeout);
+            }
+        }
+
+        Handled::Ok
+    }
+    fn on_stop(&mut self) -> Handled {
+        if let Some(timeout) = self.scaling.as_mut().and_then(|s| s.timeout.take()) {
+            self.cancel_timer(timeout);
+        }
         Handled::Ok
     }
 }
@@ -342,8 +602,12 @@ where
     OP: Operator + 'static,
     B: Backend,
 {
-    fn handle(&mut self, _: Never) -> Handled {
-        unreachable!("can't be instantiated!");
+    fn handle(&mut self, commit: EpochCommit) -> Handled {
+        for (_, port_ref) in self.nodes.values() {
+            self.data_system
+                .trigger_i(NodeEvent::EpochCommit(commit.0), port_ref);
+        }
+        Handled::Ok
     }
 }
 
@@ -390,8 +654,15 @@ where
     OP: Operator + 'static,
     B: Backend,
 {
-    type Message = Never;
-    fn receive_local(&mut self, _: Self::Message) -> Handled {
+    type Message = CheckpointCompleted;
+    fn receive_local(&mut self, msg: Self::Message) -> Handled {
+        if let Err(err) = self.complete_checkpoint(msg) {
+            error!(
+                self.logger,
+                "Failed to complete checkpoint {:?}",
+                err.to_string()
+            );
+        }
         Handled::Ok
     }
     fn receive_network(&mut self, _: NetMessage) -> Handled {
@@ -0,0 +1,147 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{
+    application::{conf::ApplicationConf, restore::parse_checkpoint_name},
+    error::ArconResult,
+    manager::snapshot::SnapshotCatalog,
+};
+use std::{collections::BTreeSet, fs, path::Path};
+
+/// Decides which committed epochs keep their checkpoints
+#[derive(Clone, Debug)]
+pub struct RetentionPolicy {
+    /// Number of latest committed epochs to keep
+    pub keep_last: usize,
+    /// Age in milliseconds up to which committed epochs are kept
+    pub max_age: Option<u64>,
+}
+
+impl RetentionPolicy {
+    pub fn new(keep_last: usize, max_age: Option<u64>) -> Self {
+        assert!(keep_last > 0, "at least one committed epoch must be kept");
+        Self { keep_last, max_age }
+    }
+
+    /// Creates a RetentionPolicy from the checkpoint settings of an [ApplicationConf]
+    pub fn from_conf(conf: &ApplicationConf) -> Self {
+        Self::new(conf.checkpoint_retention, conf.checkpoint_retention_age)
+    }
+
+    /// Returns the epochs of `catalog` that are retained at time `now` (in milliseconds)
+    pub fn retained(&self, catalog: &SnapshotCatalog, now: u64) -> BTreeSet<u64> {
+        let latest: BTreeSet<u64> = catalog
+            .epochs
+            .iter()
+            .rev()
+            .take(self.keep_last)
+            .map(|e| e.epoch)
+            .collect();
+
+        catalog
+            .epochs
+            .iter()
+            .filter(|e| {
+                latest.contains(&e.epoch)
+                    || self
+                        .max_age
+                        .map(|age| now.saturating_sub(e.committed_at) <= age)
+                        .unwrap_or(false)
+            })
+            .map(|e| e.epoch)
+            .collect()
+    }
+}
+
+/// Removes every checkpoint directory in `dir` whose epoch matches `remove`
+///
+/// Returns the number of removed directories.
+pub(crate) fn remove_checkpoints<F>(dir: &Path, remove: F) -> ArconResult<usize>
+where
+    F: Fn(u64) -> bool,
+{
+    let mut removed = 0;
+    if !dir.exists() {
+        return Ok(removed);
+    }
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let name = entry.file_name();
+        if let Some((_, epoch)) = name.to_str().and_then(parse_checkpoint_name) {
+            if remove(epoch) {
+                fs::remove_dir_all(entry.path())?;
+                removed += 1;
+            }
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{application::restore::checkpoint_name, manager::snapshot::CommittedEpoch};
+
+    fn catalog(commits: &[(u64, u64)]) -> SnapshotCatalog {
+        SnapshotCatalog {
+            epochs: commits
+                .iter()
+                .map(|(epoch, committed_at)| CommittedEpoch {
+                    epoch: *epoch,
+                    committed_at: *committed_at,
+                    snapshots: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn retention_policy_test() {
+        let catalog = catalog(&[(0, 1000), (1, 2000), (2, 3000), (3, 4000)]);
+
+        let policy = RetentionPolicy::new(2, None);
+        assert_eq!(
+            policy
+                .retained(&catalog, 5000)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+
+        let policy = RetentionPolicy::new(1, Some(2500));
+        assert_eq!(
+            policy
+                .retained(&catalog, 5000)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn remove_checkpoints_test() {
+        let dir = tempfile::tempdir().unwrap();
+        for epoch in 0..4 {
+            let mut path = dir.path().to_path_buf();
+            path.push(checkpoint_name("sums", epoch));
+            fs::create_dir_all(path).unwrap();
+        }
+        SnapshotCatalog::pin(dir.path(), 0).unwrap();
+        SnapshotCatalog::pin(dir.path(), 2).unwrap();
+        SnapshotCatalog::unpin(dir.path(), 2).unwrap();
+
+        let pinned = SnapshotCatalog::pinned(dir.path()).unwrap();
+        assert_eq!(pinned.iter().copied().collect::<Vec<_>>(), vec![0]);
+
+        let removed =
+            remove_checkpoints(dir.path(), |epoch| epoch < 3 && !pinned.contains(&epoch)).unwrap();
+        assert_eq!(removed, 2);
+
+        assert!(dir.path().join(checkpoint_name("sums", 0)).exists());
+        assert!(dir.path().join(checkpoint_name("sums", 3)).exists());
+        assert!(!dir.path().join(checkpoint_name("sums", 1)).exists());
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{
+    application::conf::ApplicationConf,
+    index::{LocalValue, ValueIndex},
+};
+use arcon_state::Backend;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+/// Id of the value that the NodeManager checkpoints the parallelism of its Operator under
+pub(crate) const PARALLELISM_ID: &str = "_managed_parallelism";
+
+/// Number of active instances of an Operator with a managed parallelism
+///
+/// An Operator with [ParallelismStrategy::Managed](crate::dataflow::conf::ParallelismStrategy::Managed)
+/// is deployed with `max` instances, but only the first `active` of them own a key range.
+///
+/// The parallelism is part of the epoch protocol. The NodeManager of the Operator requests a new
+/// parallelism from the EpochManager, which schedules it for the next epoch before injecting its
+/// marker. The Keyed strategies of upstream nodes look up the parallelism of every epoch marker
+/// they forward, so all of them apply a new parallelism after the same marker.
+#[derive(Clone, Debug)]
+pub struct ManagedParallelism {
+    /// Parallelism per epoch from which it applies
+    schedule: Arc<Mutex<BTreeMap<u64, usize>>>,
+    max: usize,
+}
+
+impl ManagedParallelism {
+    pub fn new(initial: usize, max: usize) -> Self {
+        assert!(
+            initial > 0 && initial <= max,
+            "initial parallelism must be within 1..={}",
+            max
+        );
+        let mut schedule = BTreeMap::new();
+        schedule.insert(0, initial);
+        Self {
+            schedule: Arc::new(Mutex::new(schedule)),
+            max,
+        }
+    }
+
+    /// Returns the latest scheduled number of instances that own a key range
+    #[inline]
+    pub fn active(&self) -> usize {
+        let schedule = self.schedule.lock().unwrap();
+        schedule.values().next_back().copied().unwrap_or(1)
+    }
+
+    /// Returns the number of instances that own a key range after the marker of `epoch`
+    #[inline]
+    pub fn active_after(&self, epoch: u64) -> usize {
+        let schedule = self.schedule.lock().unwrap();
+        schedule
+            .range(..=epoch)
+            .next_back()
+            .map(|(_, active)| *active)
+            .unwrap_or(1)
+    }
+
+    /// Returns the number of deployed instances
+    #[inline]
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Applies `active` from the marker of `epoch` onwards
+    ///
+    /// Must be called before the marker of `epoch` is injected into the sources.
+    pub(crate) fn schedule(&self, epoch: u64, active: usize) {
+        let mut schedule = self.schedule.lock().unwrap();
+        schedule.insert(epoch, active.max(1).min(self.max));
+    }
+
+    /// Continues from the parallelism that has been checkpointed in `backend`, if any
+    ///
+    /// Must be called before the channels to the Operator are created.
+    pub(crate) fn restore<B: Backend>(&self, backend: Arc<B>) {
+        let checkpointed = LocalValue::<u64, B>::new(PARALLELISM_ID, backend);
+        if let Ok(Some(active)) = checkpointed.get() {
+            if *active > 0 {
+                self.schedule(0, *active as usize);
+            }
+        }
+    }
+
+    /// Returns true if both handles belong to the same Operator
+    #[inline]
+    pub(crate) fn is_same(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.schedule, &other.schedule)
+    }
+}
+
+/// Decides the parallelism of a managed Operator from the inbound throughput of its instances
+#[derive(Clone, Debug)]
+pub struct ScalingPolicy {
+    /// Inbound events per second per instance above which the Operator is scaled up
+    pub scale_up_throughput: f64,
+    /// Inbound events per second per instance below which the Operator is scaled down
+    pub scale_down_throughput: f64,
+    /// Upper bound of the parallelism
+    pub max_parallelism: usize,
+}
+
+impl ScalingPolicy {
+    pub fn new(
+        scale_up_throughput: f64,
+        scale_down_throughput: f64,
+        max_parallelism: usize,
+    ) -> Self {
+        assert!(
+            scale_down_throughput < scale_up_throughput,
+            "scale down throughput must be lower than scale up throughput"
+        );
+        Self {
+            scale_up_throughput,
+            scale_down_throughput,
+            max_parallelism,
+        }
+    }
+
+    /// Creates a ScalingPolicy from the managed parallelism settings of an [ApplicationConf]
+    pub fn from_conf(conf: &ApplicationConf) -> Self {
+        Self::new(
+            conf.scale_up_throughput,
+            conf.scale_down_throughput,
+            conf.max_managed_parallelism,
+        )
+    }
+
+    /// Returns the parallelism to use given the current one and the inbound throughput of each instance
+    ///
+    /// The parallelism is kept as long as the average throughput per active instance lies between
+    /// the scale down and scale up throughput. Otherwise, a parallelism that brings the average
+    /// back into the middle of that interval is chosen.
+    pub fn target(&self, current: usize, throughputs: &[f64]) -> usize {
+        let current = current.max(1);
+        let total: f64 = throughputs.iter().sum();
+        let average = total / current as f64;
+
+        if average >= self.scale_down_throughput && average <= self.scale_up_throughput {
+            return current;
+        }
+
+        let desired = (self.scale_up_throughput + self.scale_down_throughput) / 2.0;
+        let target = (total / desired).ceil() as usize;
+        target.max(1).min(self.max_parallelism)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaling_policy_test() {
+        let policy = ScalingPolicy::new(1000.0, 200.0, 8);
+
+        // within bounds
+        assert_eq!(policy.target(2, &[500.0, 500.0]), 2);
+        // overloaded instances
+        assert_eq!(policy.target(2, &[3000.0, 3000.0]), 8);
+        assert_eq!(policy.target(1, &[1800.0]), 3);
+        // idle instances
+        assert_eq!(policy.target(4, &[100.0, 100.0, 50.0, 50.0]), 1);
+        assert_eq!(policy.target(4, &[0.0, 0.0, 0.0, 0.0]), 1);
+    }
+
+    #[test]
+    fn managed_parallelism_bounds_test() {
+        let parallelism = ManagedParallelism::new(1, 4);
+        parallelism.schedule(1, 8);
+        assert_eq!(parallelism.active(), 4);
+        parallelism.schedule(2, 0);
+        assert_eq!(parallelism.active(), 1);
+    }
+
+    #[test]
+    fn managed_parallelism_schedule_test() {
+        let parallelism = ManagedParallelism::new(2, 8);
+        parallelism.schedule(5, 4);
+        parallelism.schedule(9, 3);
+
+        assert_eq!(parallelism.active_after(0), 2);
+        assert_eq!(parallelism.active_after(4), 2);
+        assert_eq!(parallelism.active_after(5), 4);
+        assert_eq!(parallelism.active_after(8), 4);
+        assert_eq!(parallelism.active_after(12), 3);
+        assert_eq!(parallelism.active(), 3);
+        assert!(parallelism.is_same(&parallelism.clone()));
+        assert!(!parallelism.is_same(&ManagedParallelism::new(2, 8)));
+    }
+}
@@ -1,11 +1,29 @@
 // Copyright (c) 2020, KTH Royal Institute of Technology.
 // SPDX-License-Identifier: AGPL-3.0-only
 
-use super::epoch::EpochCommit;
-use crate::data::{Epoch, StateID};
+use super::{
+    epoch::EpochCommit,
+    retention::{self, RetentionPolicy},
+};
+use crate::{
+    data::{Epoch, StateID},
+    error::ArconResult,
+    reportable_error,
+    util::get_system_time,
+};
+use arcon_state::{backend::serialization::protobuf, key_groups::KeyGroupRange};
 use fxhash::FxHashMap;
 use kompact::prelude::*;
-use std::{collections::HashSet, sync::mpsc::Sender};
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+};
+
+const CATALOG_FILE: &str = "SNAPSHOT_CATALOG";
+const PIN_PREFIX: &str = "PINNED_";
 
 #[derive(Debug, Clone)]
 pub enum SnapshotEvent {
@@ -14,11 +32,20 @@ pub enum SnapshotEvent {
     Register(StateID),
 }
 
-#[derive(Clone, Debug)]
+#[derive(prost::Message, Clone)]
 pub struct Snapshot {
+    #[prost(uint64)]
     pub epoch: u64,
+    #[prost(string)]
     pub snapshot_path: String,
+    #[prost(string)]
     pub backend_name: String,
+    /// Offset per source index, only set for snapshots of a SourceManager
+    #[prost(uint64, repeated)]
+    pub source_offsets: Vec<u64>,
+    /// Key groups held by the checkpoint, only set for snapshots of an Operator
+    #[prost(message, repeated)]
+    pub key_groups: Vec<KeyGroupRange>,
 }
 
 impl Snapshot {
@@ -27,14 +54,169 @@ impl Snapshot {
             epoch,
             snapshot_path,
             backend_name,
+            source_offsets: Vec::new(),
+            key_groups: Vec::new(),
+        }
+    }
+
+    pub fn with_source_offsets(mut self, offsets: Vec<u64>) -> Self {
+        self.source_offsets = offsets;
+        self
+    }
+
+    pub fn with_key_groups(mut self, key_groups: Vec<KeyGroupRange>) -> Self {
+        self.key_groups = key_groups;
+        self
+    }
+}
+
+/// Snapshot of a single StateID that is part of a committed epoch
+#[derive(prost::Message, Clone)]
+pub struct StateSnapshot {
+    #[prost(string)]
+    pub state_id: String,
+    #[prost(message, required)]
+    pub snapshot: Snapshot,
+}
+
+/// Snapshots that together form a consistent snapshot of an Application
+#[derive(prost::Message, Clone)]
+pub struct CommittedEpoch {
+    #[prost(uint64)]
+    pub epoch: u64,
+    /// Time of the commit in milliseconds since the Unix epoch
+    #[prost(uint64)]
+    pub committed_at: u64,
+    #[prost(message, repeated)]
+    pub snapshots: Vec<StateSnapshot>,
+}
+
+impl CommittedEpoch {
+    /// Returns the snapshot of `state_id` at this epoch
+    pub fn snapshot(&self, state_id: &str) -> Option<&Snapshot> {
+        self.snapshots
+            .iter()
+            .find(|s| s.state_id == state_id)
+            .map(|s| &s.snapshot)
+    }
+
+    /// Returns the StateIDs that took part in the epoch
+    pub fn state_ids(&self) -> impl Iterator<Item = &str> {
+        self.snapshots.iter().map(|s| s.state_id.as_str())
+    }
+}
+
+/// Durable catalog of the committed epochs of an Application
+///
+/// The catalog is kept in the checkpoint directory and rewritten every time an epoch
+/// is committed. Any `checkpoint_{id}_{epoch}` directory that is not listed in it
+/// does not belong to a consistent snapshot.
+///
+/// ```no_run
+/// use arcon::prelude::*;
+/// let conf = ApplicationConf::default();
+/// if let Some(catalog) = SnapshotCatalog::read(&conf.checkpoints_dir()).unwrap() {
+///     for epoch in &catalog.epochs {
+///         println!("{} {:?}", epoch.epoch, epoch.state_ids().collect::<Vec<_>>());
+///     }
+/// }
+/// ```
+#[derive(prost::Message, Clone)]
+pub struct SnapshotCatalog {
+    /// Committed epochs in ascending order
+    #[prost(message, repeated)]
+    pub epochs: Vec<CommittedEpoch>,
+}
+
+impl SnapshotCatalog {
+    /// Reads the catalog of the checkpoint directory `dir`
+    ///
+    /// Returns `None` if no epoch has been committed to the directory.
+    pub fn read(dir: &Path) -> ArconResult<Option<Self>> {
+        let path = catalog_path(dir, CATALOG_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = fs::read(path)?;
+        Ok(Some(protobuf::deserialize(&bytes)?))
+    }
+
+    /// Writes the catalog into the checkpoint directory `dir`
+    ///
+    /// The catalog is written to a temporary file that then replaces the old catalog,
+    /// so that a crash never leaves a partially written catalog behind.
+    pub(crate) fn write(&self, dir: &Path) -> ArconResult<()> {
+        fs::create_dir_all(dir)?;
+        let tmp_path = catalog_path(dir, &format!("{}.tmp", CATALOG_FILE));
+        let bytes = protobuf::serialize(self)?;
+        {
+            let mut file = fs::File::create(&tmp_path)?;
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+        }
+        fs::rename(tmp_path, catalog_path(dir, CATALOG_FILE))?;
+        Ok(())
+    }
+
+    /// Returns the latest committed epoch
+    pub fn latest(&self) -> Option<&CommittedEpoch> {
+        self.epochs.last()
+    }
+
+    /// Returns the committed epoch `epoch`
+    pub fn get(&self, epoch: u64) -> Option<&CommittedEpoch> {
+        self.epochs.iter().find(|e| e.epoch == epoch)
+    }
+
+    /// Pins the checkpoints of `epoch` in the checkpoint directory `dir`
+    ///
+    /// Pinned checkpoints are never removed by the retention policy of an Application,
+    /// which allows external tooling to hold on to them.
+    pub fn pin(dir: &Path, epoch: u64) -> ArconResult<()> {
+        fs::create_dir_all(dir)?;
+        fs::File::create(catalog_path(dir, &format!("{}{}", PIN_PREFIX, epoch)))?;
+        Ok(())
+    }
+
+    /// Releases a pin created through [SnapshotCatalog::pin]
+    pub fn unpin(dir: &Path, epoch: u64) -> ArconResult<()> {
+        let path = catalog_path(dir, &format!("{}{}", PIN_PREFIX, epoch));
+        if path.exists() {
+            fs::remove_file(path)?;
         }
+        Ok(())
     }
+
+    /// Returns the pinned epochs of the checkpoint directory `dir`
+    pub fn pinned(dir: &Path) -> ArconResult<BTreeSet<u64>> {
+        let mut pinned = BTreeSet::new();
+        if dir.exists() {
+            for entry in fs::read_dir(dir)? {
+                let name = entry?.file_name();
+                if let Some(epoch) = name
+                    .to_str()
+                    .and_then(|name| name.strip_prefix(PIN_PREFIX))
+                    .and_then(|epoch| epoch.parse().ok())
+                {
+                    pinned.insert(epoch);
+                }
+            }
+        }
+        Ok(pinned)
+    }
+}
+
+fn catalog_path(dir: &Path, file: &str) -> PathBuf {
+    let mut path = dir.to_path_buf();
+    path.push(file);
+    path
 }
 
 pub struct SnapshotManagerPort;
 
 impl Port for SnapshotManagerPort {
-    type Indication = Never;
+    /// Epochs that have been committed, used to complete two-phase commits
+    type Indication = EpochCommit;
     type Request = SnapshotEvent;
 }
 
@@ -49,49 +231,207 @@ pub struct SnapshotManager {
     ///
     /// Used to verify that users do not watch for state ids that do not exist
     pub(crate) registered_state_ids: HashSet<StateID>,
-    /// Snapshot catalog of committed snapshots
-    uncommitted_catalog: FxHashMap<Epoch, FxHashMap<StateID, Snapshot>>,
     /// Snapshot catalog of uncommitted snapshots
-    committed_catalog: FxHashMap<Epoch, FxHashMap<StateID, Snapshot>>,
+    uncommitted_catalog: FxHashMap<Epoch, FxHashMap<StateID, Snapshot>>,
+    /// Epochs that have been committed by the EpochManager but still lack a snapshot
+    ///
+    /// Snapshots and commits arrive on different channels, so a commit may overtake
+    /// the snapshots of its epoch.
+    pending_commits: BTreeSet<Epoch>,
+    /// Snapshot catalog of committed snapshots
+    ///
+    /// Persisted to the checkpoint directory on every committed epoch.
+    committed_catalog: SnapshotCatalog,
     /// A map matching state ids to a channel Sender
     pub(crate) channels: FxHashMap<StateID, Sender<Snapshot>>,
     /// A map of component subscribers per State ID
     pub(crate) subscribers: FxHashMap<StateID, Vec<ActorRefStrong<Snapshot>>>,
+    /// Decides which committed epochs keep their checkpoints
+    retention_policy: RetentionPolicy,
+    /// Savepoints waiting for an epoch to be committed
+    savepoint_requests: Vec<(u64, Sender<CommittedEpoch>)>,
 }
 
 impl SnapshotManager {
-    pub fn new() -> Self {
+    pub fn new(retention_policy: RetentionPolicy) -> Self {
         Self {
             ctx: ComponentContext::uninitialised(),
             manager_port: ProvidedPort::uninitialised(),
             registered_state_ids: HashSet::new(),
             uncommitted_catalog: FxHashMap::default(),
-            committed_catalog: FxHashMap::default(),
+            pending_commits: BTreeSet::new(),
+            committed_catalog: SnapshotCatalog::default(),
             channels: FxHashMap::default(),
             subscribers: FxHashMap::default(),
+            retention_policy,
+            savepoint_requests: Vec::new(),
         }
     }
 
-    fn handle_epoch_commit(&mut self, commit: EpochCommit) {
+    /// Requests the first committed epoch that is equal to or later than `epoch`
+    ///
+    /// The checkpoints of the epoch are pinned before the epoch is sent to `sender`,
+    /// and must be unpinned once they are no longer needed.
+    pub(crate) fn request_savepoint(&mut self, epoch: Epoch, sender: Sender<CommittedEpoch>) {
+        self.savepoint_requests.push((epoch.epoch, sender));
+    }
+
+    /// Withdraws the savepoint requests of `epoch` whose requester gave up waiting
+    pub(crate) fn cancel_savepoint(&mut self, epoch: Epoch) {
+        self.savepoint_requests.retain(|(e, _)| *e != epoch.epoch);
+    }
+
+    fn checkpoint_dir(&self) -> Option<PathBuf> {
+        self.ctx.config()["checkpoint_dir"]
+            .as_string()
+            .map(PathBuf::from)
+    }
+
+    fn handle_epoch_commit(&mut self, commit: EpochCommit) -> ArconResult<()> {
         let epoch = commit.0;
+        if self.has_all_snapshots(epoch) {
+            self.commit_epoch(epoch)
+        } else {
+            self.pending_commits.insert(epoch);
+            Ok(())
+        }
+    }
 
-        if let Some(snapshot_map) = self.uncommitted_catalog.remove(&epoch) {
-            for (state_id, snapshot) in &snapshot_map {
-                // check for component subscribers
-                if let Some(subscribers) = self.subscribers.get(state_id) {
-                    for sub in subscribers {
-                        sub.tell(snapshot.clone());
-                    }
+    /// Returns true if every registered StateID has sent its snapshot of `epoch`
+    fn has_all_snapshots(&self, epoch: Epoch) -> bool {
+        match self.uncommitted_catalog.get(&epoch) {
+            Some(snapshots) => self
+                .registered_state_ids
+                .iter()
+                .all(|state_id| snapshots.contains_key(state_id)),
+            None => self.registered_state_ids.is_empty(),
+        }
+    }
+
+    /// Commits `epoch` if it has been waiting for the snapshot that was just received
+    fn handle_snapshot(&mut self, state_id: StateID, snapshot: Snapshot) -> ArconResult<()> {
+        let epoch = Epoch::new(snapshot.epoch);
+        self.uncommitted_catalog
+            .entry(epoch)
+            .or_insert_with(FxHashMap::default)
+            .insert(state_id, snapshot);
+
+        if self.pending_commits.contains(&epoch) && self.has_all_snapshots(epoch) {
+            self.commit_epoch(epoch)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn commit_epoch(&mut self, epoch: Epoch) -> ArconResult<()> {
+        // a commit supersedes the pending commits of earlier epochs
+        self.pending_commits.retain(|e| e.epoch > epoch.epoch);
+        let snapshot_map = self.uncommitted_catalog.remove(&epoch).unwrap_or_default();
+
+        for (state_id, snapshot) in &snapshot_map {
+            // check for component subscribers
+            if let Some(subscribers) = self.subscribers.get(state_id) {
+                for sub in subscribers {
+                    sub.tell(snapshot.clone());
                 }
+            }
+
+            // check for channel subscriptions
+            if let Some(channel) = self.channels.get(state_id) {
+                channel.send(snapshot.clone()).unwrap();
+            }
+        }
+
+        // insert snapshot map into the committed catalog
+        let mut snapshots: Vec<StateSnapshot> = snapshot_map
+            .into_iter()
+            .map(|(state_id, snapshot)| StateSnapshot { state_id, snapshot })
+            .collect();
+        snapshots.sort_by(|a, b| a.state_id.cmp(&b.state_id));
+        self.committed_catalog.epochs.push(CommittedEpoch {
+            epoch: epoch.epoch,
+            committed_at: get_system_time(),
+            snapshots,
+        });
+        // snapshots of earlier epochs can no longer be committed
+        self.uncommitted_catalog
+            .retain(|e, _| e.epoch > epoch.epoch);
 
-                // check for channel subscriptions
-                if let Some(channel) = self.channels.get(state_id) {
-                    channel.send(snapshot.clone()).unwrap();
+        let dir = match self.checkpoint_dir() {
+            Some(dir) => dir,
+            None => return reportable_error!("Failed to fetch checkpoint_dir from Config"),
+        };
+        self.complete_savepoints(&dir, epoch.epoch)?;
+        self.collect_garbage(&dir, epoch.epoch)?;
+
+        // the commit is durable once the catalog has been written
+        self.manager_port.trigger(EpochCommit(epoch));
+        Ok(())
+    }
+
+    /// Hands the committed epoch to the savepoints that have been waiting for it
+    fn complete_savepoints(&mut self, dir: &Path, committed: u64) -> ArconResult<()> {
+        let (completed, pending) = std::mem::take(&mut self.savepoint_requests)
+            .into_iter()
+            .partition(|(epoch, _)| *epoch <= committed);
+        self.savepoint_requests = pending;
+
+        if let Some(committed) = self.committed_catalog.get(committed) {
+            for (_, sender) in completed {
+                // pinned before it is handed out so that the checkpoints outlive the copy
+                SnapshotCatalog::pin(dir, committed.epoch)?;
+                if sender.send(committed.clone()).is_err() {
+                    debug!(
+                        self.ctx.log(),
+                        "Savepoint of epoch {} is no longer awaited", committed.epoch
+                    );
+                    SnapshotCatalog::unpin(dir, committed.epoch)?;
                 }
             }
-            // insert snapshot map into the committed catalog
-            self.committed_catalog.insert(epoch, snapshot_map);
         }
+        Ok(())
+    }
+
+    /// Persists the catalog and removes checkpoints that fall outside of the retention policy
+    ///
+    /// The catalog is written before any checkpoint is removed so that it never
+    /// refers to checkpoints that no longer exist.
+    fn collect_garbage(&mut self, dir: &Path, committed: u64) -> ArconResult<()> {
+        let pinned = SnapshotCatalog::pinned(dir)?;
+        let retained = self
+            .retention_policy
+            .retained(&self.committed_catalog, get_system_time());
+        self.committed_catalog
+            .epochs
+            .retain(|e| retained.contains(&e.epoch) || pinned.contains(&e.epoch));
+        self.committed_catalog.write(dir)?;
+
+        // checkpoints of later epochs may still be part of an ongoing commit
+        let removed = retention::remove_checkpoints(dir, |epoch| {
+            epoch < committed && !retained.contains(&epoch) && !pinned.contains(&epoch)
+        })?;
+        if removed > 0 {
+            debug!(self.ctx.log(), "Removed {} expired checkpoints", removed);
+        }
+        Ok(())
+    }
+
+    /// Removes checkpoints of a previous run that never became part of a committed epoch
+    fn remove_orphaned_checkpoints(&self, dir: &Path) -> ArconResult<()> {
+        let pinned = SnapshotCatalog::pinned(dir)?;
+        let committed: HashSet<u64> = self
+            .committed_catalog
+            .epochs
+            .iter()
+            .map(|e| e.epoch)
+            .collect();
+        let removed = retention::remove_checkpoints(dir, |epoch| {
+            !committed.contains(&epoch) && !pinned.contains(&epoch)
+        })?;
+        if removed > 0 {
+            info!(self.ctx.log(), "Removed {} orphaned checkpoints", removed);
+        }
+        Ok(())
     }
 }
 
@@ -99,7 +439,13 @@ impl Actor for SnapshotManager {
     type Message = EpochCommit;
 
     fn receive_local(&mut self, msg: Self::Message) -> Handled {
-        self.handle_epoch_commit(msg);
+        let epoch = msg.0;
+        if let Err(err) = self.handle_epoch_commit(msg) {
+            error!(
+                self.ctx.log(),
+                "Failed to persist snapshot catalog for {:?} with error {:?}", epoch, err
+            );
+        }
         Handled::Ok
     }
     fn receive_network(&mut self, _: NetMessage) -> Handled {
@@ -113,13 +459,15 @@ impl Provide<SnapshotManagerPort> for SnapshotManager {
 
         match event {
             SnapshotEvent::Snapshot(id, snapshot) => {
-                let epoch = Epoch::new(snapshot.epoch);
-                let snapshot_map = self
-                    .uncommitted_catalog
-                    .entry(epoch)
-                    .or_insert_with(FxHashMap::default);
-
-                snapshot_map.insert(id, snapshot);
+                let epoch = snapshot.epoch;
+                if let Err(err) = self.handle_snapshot(id, snapshot) {
+                    error!(
+                        self.ctx.log(),
+                        "Failed to persist snapshot catalog for epoch {} with error {:?}",
+                        epoch,
+                        err
+                    );
+                }
             }
             SnapshotEvent::Register(id) => {
                 if self.registered_state_ids.contains(&id) {
@@ -135,4 +483,160 @@ impl Provide<SnapshotManagerPort> for SnapshotManager {
     }
 }
 
-ignore_lifecycle!(SnapshotManager);
+impl ComponentLifecycle for SnapshotManager {
+    fn on_start(&mut self) -> Handled {
+        // Continue the catalog of a previous run of the Application
+        if let Some(dir) = self.checkpoint_dir() {
+            match SnapshotCatalog::read(&dir) {
+                Ok(Some(catalog)) => {
+                    self.committed_catalog = catalog;
+                    if let Err(err) = self.remove_orphaned_checkpoints(&dir) {
+                        error!(
+                            self.ctx.log(),
+                            "Failed to remove orphaned checkpoints with error {:?}", err
+                        );
+                    }
+                }
+                Ok(None) => (),
+                Err(err) => error!(
+                    self.ctx.log(),
+                    "Failed to read snapshot catalog with error {:?}", err
+                ),
+            }
+        }
+        Handled::Ok
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_catalog_test() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(SnapshotCatalog::read(dir.path()).unwrap().is_none());
+
+        let snapshot = |state_id: &str, epoch: u64| StateSnapshot {
+            state_id: state_id.to_string(),
+            snapshot: Snapshot::new(
+                "Sled".to_string(),
+                epoch,
+                format!("checkpoint_{}_{}", state_id, epoch),
+            ),
+        };
+        let mut catalog = SnapshotCatalog::default();
+        for epoch in 0..3 {
+            let mut source = snapshot("source_manager_numbers", epoch);
+            source.snapshot.source_offsets = vec![epoch * 10];
+            catalog.epochs.push(CommittedEpoch {
+                epoch,
+                committed_at: epoch * 1000,
+                snapshots: vec![snapshot("sums", epoch), source],
+            });
+            catalog.write(dir.path()).unwrap();
+        }
+
+        let catalog = SnapshotCatalog::read(dir.path()).unwrap().unwrap();
+        let latest = catalog.latest().unwrap();
+        assert_eq!(latest.epoch, 2);
+        assert_eq!(
+            latest.state_ids().collect::<Vec<_>>(),
+            vec!["sums", "source_manager_numbers"]
+        );
+        assert_eq!(
+            latest
+                .snapshot("source_manager_numbers")
+                .unwrap()
+                .source_offsets,
+            vec![20]
+        );
+        assert_eq!(
+            catalog
+                .get(1)
+                .unwrap()
+                .snapshot("sums")
+                .unwrap()
+                .snapshot_path,
+            "checkpoint_sums_1"
+        );
+        assert!(catalog.get(3).is_none());
+    }
+
+    #[test]
+    fn commit_awaits_snapshots_test() {
+        use crate::application::conf::ApplicationConf;
+
+        let base_dir = tempfile::tempdir().unwrap();
+        let conf = ApplicationConf {
+            base_dir: base_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let system = conf.ctrl_system_conf().build().unwrap();
+        let manager = system.create(|| SnapshotManager::new(RetentionPolicy::new(1, None)));
+        let snapshot = |state_id: &str| {
+            SnapshotEvent::Snapshot(
+                state_id.to_string(),
+                Snapshot::new("Sled".to_string(), 1, String::new()),
+            )
+        };
+
+        manager.on_definition(|cd| {
+            cd.handle(SnapshotEvent::Register("sums".to_string()));
+            cd.handle(SnapshotEvent::Register(
+                "source_manager_numbers".to_string(),
+            ));
+            cd.handle(snapshot("source_manager_numbers"));
+
+            // the commit overtook the snapshot of `sums`
+            cd.receive_local(EpochCommit(Epoch::new(1)));
+            assert!(cd.committed_catalog.epochs.is_empty());
+            assert!(SnapshotCatalog::read(&conf.checkpoints_dir())
+                .unwrap()
+                .is_none());
+
+            cd.handle(snapshot("sums"));
+            let committed = cd.committed_catalog.latest().unwrap();
+            assert_eq!(committed.epoch, 1);
+            assert_eq!(committed.snapshots.len(), 2);
+        });
+        let catalog = SnapshotCatalog::read(&conf.checkpoints_dir())
+            .unwrap()
+            .unwrap();
+        assert_eq!(catalog.latest().unwrap().epoch, 1);
+        system.shutdown().unwrap();
+    }
+
+    #[test]
+    fn abandoned_savepoint_test() {
+        use crate::application::conf::ApplicationConf;
+        use std::sync::mpsc;
+
+        let base_dir = tempfile::tempdir().unwrap();
+        let conf = ApplicationConf {
+            base_dir: base_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let dir = conf.checkpoints_dir();
+        let system = conf.ctrl_system_conf().build().unwrap();
+        let manager = system.create(|| SnapshotManager::new(RetentionPolicy::new(1, None)));
+
+        manager.on_definition(|cd| {
+            // the requester of epoch 1 gave up waiting without withdrawing its request
+            let (sender, receiver) = mpsc::channel();
+            cd.request_savepoint(Epoch::new(1), sender);
+            drop(receiver);
+            // the request of epoch 2 is withdrawn
+            let (sender, _receiver) = mpsc::channel();
+            cd.request_savepoint(Epoch::new(2), sender);
+            cd.cancel_savepoint(Epoch::new(2));
+            assert_eq!(cd.savepoint_requests.len(), 1);
+
+            cd.receive_local(EpochCommit(Epoch::new(1)));
+            cd.receive_local(EpochCommit(Epoch::new(2)));
+            assert!(cd.savepoint_requests.is_empty());
+        });
+        assert!(SnapshotCatalog::pinned(&dir).unwrap().is_empty());
+        system.shutdown().unwrap();
+    }
+}
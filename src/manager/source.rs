@@ -1,23 +1,53 @@
 // Copyright (c) 2020, KTH Royal Institute of Technology.
 // SPDX-License-Identifier: AGPL-3.0-only
 
-use super::epoch::EpochEvent;
+use super::{
+    epoch::{EpochCommit, EpochEvent},
+    snapshot::{Snapshot, SnapshotEvent, SnapshotManagerPort},
+};
 #[cfg(feature = "metrics")]
 use metrics::{gauge, register_gauge};
 
 use crate::{
-    application::conf::logger::ArconLogger,
-    data::StateID,
+    application::{
+        conf::logger::ArconLogger,
+        restore::{checkpoint_name, mark_complete},
+    },
+    data::{Epoch, StateID},
+    error::*,
+    index::{HashTable, IndexOps},
+    reportable_error,
     stream::{node::source::SourceEvent, time::ArconTime},
 };
+use arcon_macros::ArconState;
 use arcon_state::Backend;
 use kompact::{component::AbstractComponent, prelude::*};
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 #[derive(Debug, Clone)]
 pub enum SourceManagerEvent {
     /// Signal the end of a Source Stream
     End,
+    /// Offset of the source with the given index at the time it forwarded an Epoch
+    Offset(usize, Epoch, u64),
+}
+
+#[derive(ArconState)]
+pub struct SourceManagerState<B: Backend> {
+    /// Offset per source index as of the latest completed Epoch
+    offsets: HashTable<u64, u64, B>,
+    /// Sources that have reported their offset for the ongoing Epoch
+    #[ephemeral]
+    offset_acks: HashSet<(usize, Epoch)>,
+}
+
+impl<B: Backend> SourceManagerState<B> {
+    fn new(backend: Arc<B>) -> Self {
+        Self {
+            offsets: HashTable::with_capacity("_source_offsets", backend, 64, 64),
+            offset_acks: HashSet::new(),
+        }
+    }
 }
 
 pub struct SourceManagerPort;
@@ -33,6 +63,8 @@ pub(crate) struct SourceManager<B: Backend> {
     /// Component Context
     ctx: ComponentContext<Self>,
     manager_port: ProvidedPort<SourceManagerPort>,
+    /// Port for the SnapshotManager component
+    pub(crate) snapshot_manager_port: RequiredPort<SnapshotManagerPort>,
     /// What type of time that is used.
     ///
     /// Either Event or Processing
@@ -47,8 +79,14 @@ pub(crate) struct SourceManager<B: Backend> {
     /// May contain more than 1 component if the source supports parallelism
     pub(crate) sources: Vec<Arc<dyn AbstractComponent<Message = SourceEvent>>>,
     pub source_refs: Vec<ActorRefStrong<SourceEvent>>,
+    /// Indices of the sources, which may skip the sources that run on other processes
+    source_indices: Vec<usize>,
+    /// Number of sources that have reached the end of their stream
+    ended_sources: usize,
     /// A shared backend for sources
-    _backend: Arc<B>,
+    backend: Arc<B>,
+    /// Internal manager state
+    manager_state: SourceManagerState<B>,
     /// Reference to the EpochManager
     epoch_manager: ActorRefStrong<EpochEvent>,
 
@@ -70,22 +108,105 @@ impl<B: Backend> SourceManager<B> {
         Self {
             ctx: ComponentContext::uninitialised(),
             manager_port: ProvidedPort::uninitialised(),
+            snapshot_manager_port: RequiredPort::uninitialised(),
             arcon_time,
             watermark_interval,
             watermark_timeout: None,
             state_id,
             sources: Vec::new(),
             source_refs: Vec::new(),
-            _backend: backend,
+            source_indices: Vec::new(),
+            ended_sources: 0,
+            manager_state: SourceManagerState::new(backend.clone()),
+            backend,
             epoch_manager,
             logger,
         }
     }
 
-    pub(crate) fn add_source(&mut self, source: Arc<dyn AbstractComponent<Message = SourceEvent>>) {
+    pub(crate) fn add_source(
+        &mut self,
+        source_index: usize,
+        source: Arc<dyn AbstractComponent<Message = SourceEvent>>,
+    ) {
         let source_ref = source.actor_ref().hold().expect("failed to fetch ref");
         self.sources.push(source);
         self.source_refs.push(source_ref);
+        self.source_indices.push(source_index);
+    }
+
+    /// Returns the offset that the source with the given index should resume from
+    ///
+    /// The offset is 0 unless the backend of the SourceManager has been restored from a checkpoint.
+    pub(crate) fn offset(&self, source_index: usize) -> u64 {
+        self.manager_state
+            .offsets
+            .get(&(source_index as u64))
+            .ok()
+            .flatten()
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Records the offset of a source and checkpoints the offsets once every source has reported
+    fn handle_offset(&mut self, source_index: usize, epoch: Epoch, offset: u64) -> ArconResult<()> {
+        self.manager_state
+            .offsets
+            .put(source_index as u64, offset)?;
+        self.manager_state.offset_acks.insert((source_index, epoch));
+
+        let acks = self
+            .manager_state
+            .offset_acks
+            .iter()
+            .filter(|(_, e)| *e == epoch)
+            .count();
+
+        if acks == self.sources.len() {
+            self.manager_state.offset_acks.retain(|(_, e)| *e != epoch);
+            self.checkpoint(epoch)?;
+        }
+        Ok(())
+    }
+
+    fn checkpoint(&mut self, epoch: Epoch) -> ArconResult<()> {
+        if let Some(base_dir) = &self.ctx.config()["checkpoint_dir"].as_string() {
+            self.manager_state.persist()?;
+
+            let checkpoint_dir = format!(
+                "{}/{}",
+                base_dir,
+                checkpoint_name(&self.state_id, epoch.epoch)
+            );
+            self.backend.checkpoint(checkpoint_dir.as_ref())?;
+            mark_complete(checkpoint_dir.as_ref())?;
+
+            // Send snapshot including the source offsets to the SnapshotManager
+            let offsets = self
+                .source_indices
+                .iter()
+                .map(|i| self.offset(*i))
+                .collect();
+            let snapshot = Snapshot::new(
+                std::any::type_name::<B>().to_string(),
+                epoch.epoch,
+                checkpoint_dir.clone(),
+            )
+            .with_source_offsets(offsets);
+            self.snapshot_manager_port
+                .trigger(SnapshotEvent::Snapshot(self.state_id.clone(), snapshot));
+
+            self.epoch_manager
+                .tell(EpochEvent::Ack(self.state_id.clone(), epoch));
+
+            debug!(
+                self.logger,
+                "Completed a Checkpoint to path {}", checkpoint_dir
+            );
+            Ok(())
+        } else {
+            reportable_error!("Failed to fetch checkpoint_dir from Config")
+        }
     }
 
     fn handle_watermark_timeout(&mut self, timeout_id: ScheduledTimer) -> Handled {
@@ -110,6 +231,13 @@ impl<B: Backend> SourceManager<B> {
 impl<B: Backend> ComponentLifecycle for SourceManager<B> {
     fn on_start(&mut self) -> Handled {
         info!(self.logger, "Started SourceManager for {}", self.state_id,);
+
+        // Source offsets are part of every epoch
+        self.snapshot_manager_port
+            .trigger(SnapshotEvent::Register(self.state_id.clone()));
+        self.epoch_manager
+            .tell(EpochEvent::Register(self.state_id.clone()));
+
         Handled::Ok
     }
     fn on_stop(&mut self) -> Handled {
@@ -147,6 +275,15 @@ impl<B: Backend> Actor for SourceManager<B> {
     }
 }
 
+impl<B> Require<SnapshotManagerPort> for SourceManager<B>
+where
+    B: Backend,
+{
+    fn handle(&mut self, _: EpochCommit) -> Handled {
+        Handled::Ok
+    }
+}
+
 impl<B> Provide<SourceManagerPort> for SourceManager<B>
 where
     B: Backend,
@@ -154,7 +291,19 @@ where
     fn handle(&mut self, event: SourceManagerEvent) -> Handled {
         match event {
             SourceManagerEvent::End => {
-                self.epoch_manager.tell(EpochEvent::Halt);
+                self.ended_sources += 1;
+                // Only halt once every source of this manager has ended
+                if self.ended_sources == self.sources.len() {
+                    self.epoch_manager.tell(EpochEvent::Halt);
+                }
+            }
+            SourceManagerEvent::Offset(source_index, epoch, offset) => {
+                if let Err(err) = self.handle_offset(source_index, epoch, offset) {
+                    error!(
+                        self.logger,
+                        "Failed to checkpoint source offsets with error {:?}", err
+                    );
+                }
             }
         }
         Handled::Ok
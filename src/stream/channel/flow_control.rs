@@ -0,0 +1,503 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{
+    data::{ser_id::CREDIT_GRANT_ID, ArconMessage, ArconType, NodeID},
+    stream::channel::{node_alias, strategy::send, Channel, Dispatcher},
+};
+use kompact::prelude::*;
+use prost::Message;
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::Notify;
+
+/// Message by which a Node grants a credit back to a sender on another process
+///
+/// The Node sends it once it has handled a message of the sender.
+#[derive(prost::Message, Clone)]
+pub struct CreditGrant {
+    /// NodeID of the Node that handled the message
+    #[prost(uint32, tag = "1")]
+    pub receiver: u32,
+}
+
+impl Serialisable for CreditGrant {
+    fn ser_id(&self) -> SerId {
+        CREDIT_GRANT_ID
+    }
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.encoded_len())
+    }
+    fn serialise(&self, mut buf: &mut dyn BufMut) -> Result<(), SerError> {
+        self.encode(&mut buf)
+            .map_err(|e| SerError::InvalidData(e.to_string()))
+    }
+    fn local(self: Box<Self>) -> Result<Box<dyn Any + Send>, Box<dyn Serialisable>> {
+        Ok(self)
+    }
+}
+
+impl Deserialiser<CreditGrant> for CreditGrant {
+    const SER_ID: SerId = CREDIT_GRANT_ID;
+
+    fn deserialise(buf: &mut dyn Buf) -> Result<CreditGrant, SerError> {
+        CreditGrant::decode(buf.chunk()).map_err(|e| SerError::InvalidData(e.to_string()))
+    }
+}
+
+/// Wakes a backpressured sender once one of its channels has been granted a credit
+///
+/// A component shares one waker between the flow controls of its outputs.
+#[derive(Clone, Default)]
+pub struct CreditWaker(Arc<Notify>);
+
+impl CreditWaker {
+    #[inline]
+    fn wake(&self) {
+        self.0.notify_one();
+    }
+
+    /// Completes once a credit has been granted since the last wait
+    pub(crate) async fn granted(self) {
+        self.0.notified().await
+    }
+}
+
+/// Credits that the receiver of a channel has granted back
+struct CreditLine {
+    granted: AtomicUsize,
+    waker: CreditWaker,
+}
+
+impl CreditLine {
+    #[inline]
+    fn grant(&self) {
+        self.granted.fetch_add(1, Ordering::AcqRel);
+        self.waker.wake();
+    }
+
+    #[inline]
+    fn granted(&self) -> usize {
+        self.granted.load(Ordering::Acquire)
+    }
+}
+
+/// The credit that a message of a local channel consumed
+///
+/// The credit is granted back once the receiver is done with the message, that is when it drops
+/// the message. A clone of the message does not carry the credit, so the receiver alone grants it.
+#[derive(Default)]
+pub(crate) struct Credit(Option<Arc<CreditLine>>);
+
+impl Clone for Credit {
+    fn clone(&self) -> Self {
+        Credit(None)
+    }
+}
+
+impl fmt::Debug for Credit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Credit").field(&self.0.is_some()).finish()
+    }
+}
+
+impl Drop for Credit {
+    fn drop(&mut self) {
+        if let Some(line) = self.0.take() {
+            line.grant();
+        }
+    }
+}
+
+/// Credits of a channel that are held by messages in flight
+struct Credits<A: ArconType> {
+    channel: Arc<Channel<A>>,
+    line: Arc<CreditLine>,
+    /// Number of messages that have been sent on the channel
+    sent: usize,
+    /// Number of messages of the channel that are held back
+    held_back: usize,
+}
+
+impl<A: ArconType> Credits<A> {
+    #[inline]
+    fn in_flight(&self) -> usize {
+        self.sent - self.line.granted()
+    }
+}
+
+/// Credit-based flow control for the channels of a [ChannelStrategy](super::strategy::ChannelStrategy)
+///
+/// Every channel is granted a fixed amount of credits. A message that is sent consumes a
+/// credit of its channel, which its receiver grants back once it has handled the message.
+/// Messages of a channel that is out of credits are held back in order, and as long as any
+/// message is held back the sender is backpressured and should not take on more input.
+///
+/// Receivers on other processes grant credits through a [CreditGrant], which the sender hands
+/// to [FlowControl::grant]. Either way, the [CreditWaker] of the sender is woken.
+pub(crate) struct FlowControl<A: ArconType> {
+    /// Credits of each channel, zero disables flow control
+    credits: usize,
+    /// Credits of the channels that have been sent on
+    in_flight: Vec<Credits<A>>,
+    /// Messages that wait for credits, in the order they were sent
+    held_back: VecDeque<(Arc<Channel<A>>, ArconMessage<A>)>,
+    /// Waker of the sender, handed to the receivers along with the credits
+    waker: CreditWaker,
+    /// Time at which the sender became backpressured
+    backpressured_since: Option<Instant>,
+    /// Time spent backpressured since the backpressure ratio was last taken
+    backpressured_time: Duration,
+    /// Time at which the backpressure ratio was last taken
+    ratio_start: Instant,
+}
+
+impl<A: ArconType> FlowControl<A> {
+    /// Creates a FlowControl that grants each channel `credits` messages in flight
+    pub(crate) fn new(credits: usize) -> Self {
+        FlowControl {
+            credits,
+            in_flight: Vec::new(),
+            held_back: VecDeque::new(),
+            waker: CreditWaker::default(),
+            backpressured_since: None,
+            backpressured_time: Duration::default(),
+            ratio_start: Instant::now(),
+        }
+    }
+
+    /// Wakes `waker` whenever a channel is granted a credit
+    ///
+    /// Must be set before the first message is sent.
+    pub(crate) fn set_waker(&mut self, waker: &CreditWaker) {
+        debug_assert!(self.in_flight.is_empty());
+        self.waker = waker.clone();
+    }
+
+    /// Returns true if messages are held back until their channels are granted credits
+    #[inline]
+    pub(crate) fn is_backpressured(&self) -> bool {
+        !self.held_back.is_empty()
+    }
+
+    /// Sends `message` on `channel`, or holds it back if the channel is out of credits
+    #[inline]
+    pub(crate) fn send(
+        &mut self,
+        channel: Arc<Channel<A>>,
+        message: ArconMessage<A>,
+        source: &dyn Dispatcher,
+    ) -> Result<(), SerError> {
+        self.send_with(channel, message, |channel, message| {
+            send(channel, message, source)
+        })
+    }
+
+    /// Dispatches `message` through `dispatch`, or holds it back if the channel is out of credits
+    #[inline]
+    pub(crate) fn send_with<F>(
+        &mut self,
+        channel: Arc<Channel<A>>,
+        message: ArconMessage<A>,
+        dispatch: F,
+    ) -> Result<(), SerError>
+    where
+        F: FnOnce(&Channel<A>, ArconMessage<A>) -> Result<(), SerError>,
+    {
+        // messages of a channel may not overtake the ones held back before them
+        let limit = self.credits;
+        let hold_back = match self.credits_mut(&channel) {
+            Some(credits) if credits.held_back > 0 || credits.in_flight() >= limit => {
+                credits.held_back += 1;
+                true
+            }
+            _ => false,
+        };
+        if hold_back {
+            if self.held_back.is_empty() {
+                self.backpressured_since = Some(Instant::now());
+            }
+            self.held_back.push_back((channel, message));
+            return Ok(());
+        }
+        let message = self.consume_credit(&channel, message);
+        dispatch(&channel, message)
+    }
+
+    /// Sends the held back messages of the channels that have been granted credits
+    pub(crate) fn flush(&mut self, source: &dyn Dispatcher) -> Result<(), SerError> {
+        self.flush_with(|channel, message| send(channel, message, source))
+    }
+
+    /// Dispatches the held back messages of the channels that have been granted credits
+    pub(crate) fn flush_with<F>(&mut self, mut dispatch: F) -> Result<(), SerError>
+    where
+        F: FnMut(&Channel<A>, ArconMessage<A>) -> Result<(), SerError>,
+    {
+        let mut blocked: Vec<Arc<Channel<A>>> = Vec::new();
+        let mut held_back = VecDeque::with_capacity(self.held_back.len());
+        while let Some((channel, message)) = self.held_back.pop_front() {
+            if blocked.iter().any(|c| Arc::ptr_eq(c, &channel)) {
+                held_back.push_back((channel, message));
+            } else if !self.has_credits(&channel) {
+                blocked.push(channel.clone());
+                held_back.push_back((channel, message));
+            } else {
+                if let Some(credits) = self.credits_mut(&channel) {
+                    credits.held_back -= 1;
+                }
+                let message = self.consume_credit(&channel, message);
+                if let Err(error) = dispatch(&channel, message) {
+                    held_back.extend(self.held_back.drain(..));
+                    self.held_back = held_back;
+                    return Err(error);
+                }
+            }
+        }
+        self.held_back = held_back;
+
+        if self.held_back.is_empty() {
+            if let Some(since) = self.backpressured_since.take() {
+                self.backpressured_time += since.elapsed();
+            }
+        }
+        Ok(())
+    }
+
+    /// Grants a credit to the remote channel of the Node `receiver`
+    ///
+    /// Returns false if none of the channels leads to the Node.
+    pub(crate) fn grant(&mut self, receiver: NodeID) -> bool {
+        let alias = node_alias(receiver);
+        match self
+            .in_flight
+            .iter()
+            .find(|credits| is_remote_node(&credits.channel, &alias))
+        {
+            Some(credits) => {
+                credits.line.grant();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the fraction of time the sender was backpressured since the ratio was last taken
+    #[cfg_attr(not(any(feature = "metrics", test)), allow(dead_code))]
+    pub(crate) fn backpressure_ratio(&mut self) -> f64 {
+        let now = Instant::now();
+        let mut backpressured = std::mem::take(&mut self.backpressured_time);
+        if let Some(since) = self.backpressured_since.as_mut() {
+            backpressured += now.duration_since(*since);
+            *since = now;
+        }
+        let elapsed = now.duration_since(self.ratio_start);
+        self.ratio_start = now;
+        if elapsed.as_nanos() == 0 {
+            return 0.0;
+        }
+        (backpressured.as_secs_f64() / elapsed.as_secs_f64()).min(1.0)
+    }
+
+    /// Returns true if a message may be sent on `channel`
+    fn has_credits(&self, channel: &Arc<Channel<A>>) -> bool {
+        if self.credits == 0 {
+            return true;
+        }
+        match self
+            .in_flight
+            .iter()
+            .find(|credits| Arc::ptr_eq(&credits.channel, channel))
+        {
+            Some(credits) => credits.in_flight() < self.credits,
+            None => true,
+        }
+    }
+
+    /// Returns the credits of `channel` if it has been sent on and flow control is enabled
+    fn credits_mut(&mut self, channel: &Arc<Channel<A>>) -> Option<&mut Credits<A>> {
+        if self.credits == 0 {
+            return None;
+        }
+        self.in_flight
+            .iter_mut()
+            .find(|credits| Arc::ptr_eq(&credits.channel, channel))
+    }
+
+    /// Consumes a credit of `channel` for `message`
+    ///
+    /// Messages of local channels carry their credit to the receiver.
+    fn consume_credit(
+        &mut self,
+        channel: &Arc<Channel<A>>,
+        mut message: ArconMessage<A>,
+    ) -> ArconMessage<A> {
+        if self.credits == 0 {
+            return message;
+        }
+        let index = match self
+            .in_flight
+            .iter()
+            .position(|credits| Arc::ptr_eq(&credits.channel, channel))
+        {
+            Some(index) => index,
+            None => {
+                self.in_flight.push(Credits {
+                    channel: channel.clone(),
+                    line: Arc::new(CreditLine {
+                        granted: AtomicUsize::new(0),
+                        waker: self.waker.clone(),
+                    }),
+                    sent: 0,
+                    held_back: 0,
+                });
+                self.in_flight.len() - 1
+            }
+        };
+        let credits = &mut self.in_flight[index];
+        credits.sent += 1;
+        if let Channel::Local(_) = **channel {
+            message.credit = Credit(Some(credits.line.clone()));
+        }
+        message
+    }
+}
+
+/// Returns true if `channel` leads to the Node that is registered by `alias` on another process
+fn is_remote_node<A: ArconType>(channel: &Channel<A>, alias: &str) -> bool {
+    match channel {
+        Channel::Remote(ActorPath::Named(path), _) => {
+            path.path_ref().last().map(String::as_str) == Some(alias)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::Application,
+        data::{ArconElement, ArconEvent, NodeID, Watermark},
+        stream::{
+            channel::strategy::{
+                broadcast::Broadcast, forward::Forward, tests::*, ChannelStrategy,
+            },
+            node::debug::DebugNode,
+        },
+        test_utils::wait_until,
+    };
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Sends a message with a single element and a watermark on every channel of `strategy`
+    fn send_messages(
+        strategy: &mut ChannelStrategy<Input>,
+        flow_control: &mut FlowControl<Input>,
+        messages: u32,
+        source: &dyn Dispatcher,
+    ) {
+        for id in 0..messages {
+            let elem = ArconElement::new(Input { id });
+            let _ = strategy.push(ArconEvent::Element(elem)).unwrap();
+            for (channel, msg) in strategy
+                .push(ArconEvent::Watermark(Watermark::new(0)))
+                .unwrap()
+            {
+                flow_control.send(channel, msg, source).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn credit_exhaustion_test() {
+        let mut app = Application::default();
+        let pool_info = app.get_pool_info();
+        let system = app.data_system();
+
+        // The receiver is not started and drains none of the messages yet
+        let comp = system.create(DebugNode::<Input>::new);
+        let actor_ref: ActorRefStrong<ArconMessage<Input>> =
+            comp.actor_ref().hold().expect("failed to fetch");
+        let mut strategy = ChannelStrategy::Forward(Forward::new(
+            Channel::Local(actor_ref),
+            NodeID::new(1),
+            pool_info,
+        ));
+        let mut flow_control = FlowControl::new(2);
+
+        comp.on_definition(|cd| {
+            send_messages(&mut strategy, &mut flow_control, 3, cd);
+        });
+        assert!(flow_control.is_backpressured());
+
+        // The receiver grants the credits back as it handles the messages
+        system
+            .start_notify(&comp)
+            .wait_timeout(TIMEOUT)
+            .expect("DebugNode never started");
+        assert!(wait_until(TIMEOUT, || {
+            comp.on_definition(|cd| flow_control.flush(cd).unwrap());
+            !flow_control.is_backpressured()
+        }));
+        assert!(wait_until(TIMEOUT, || {
+            comp.on_definition(|cd| cd.data.len() == 3)
+        }));
+        assert!(flow_control.backpressure_ratio() > 0.0);
+        app.shutdown();
+    }
+
+    #[test]
+    fn broadcast_credits_test() {
+        let mut app = Application::default();
+        let pool_info = app.get_pool_info();
+        let system = app.data_system();
+
+        // Only the fast receiver drains its messages
+        let fast = system.create(DebugNode::<Input>::new);
+        let slow = system.create(DebugNode::<Input>::new);
+        system
+            .start_notify(&fast)
+            .wait_timeout(TIMEOUT)
+            .expect("DebugNode never started");
+        let channels = vec![
+            Channel::Local(fast.actor_ref().hold().expect("failed to fetch")),
+            Channel::Local(slow.actor_ref().hold().expect("failed to fetch")),
+        ];
+        let mut strategy =
+            ChannelStrategy::Broadcast(Broadcast::new(channels, NodeID::new(1), pool_info));
+        let mut flow_control = FlowControl::new(2);
+
+        fast.on_definition(|cd| {
+            send_messages(&mut strategy, &mut flow_control, 3, cd);
+        });
+        assert!(wait_until(TIMEOUT, || {
+            fast.on_definition(|cd| cd.data.len() >= 2)
+        }));
+
+        // The fast receiver does not grant the credits of the slow one
+        fast.on_definition(|cd| flow_control.flush(cd).unwrap());
+        assert!(flow_control.is_backpressured());
+
+        system
+            .start_notify(&slow)
+            .wait_timeout(TIMEOUT)
+            .expect("DebugNode never started");
+        assert!(wait_until(TIMEOUT, || {
+            fast.on_definition(|cd| flow_control.flush(cd).unwrap());
+            !flow_control.is_backpressured()
+        }));
+        assert!(wait_until(TIMEOUT, || {
+            slow.on_definition(|cd| cd.data.len() == 3)
+        }));
+        app.shutdown();
+    }
+}
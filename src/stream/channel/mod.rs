@@ -1,11 +1,16 @@
 // Copyright (c) 2020, KTH Royal Institute of Technology.
 // SPDX-License-Identifier: AGPL-3.0-only
 
+/// Credit-based flow control between components
+pub(crate) mod flow_control;
 /// Available Channel Strategies
 pub mod strategy;
 
-use crate::data::{flight_serde::FlightSerde, ArconMessage, ArconType};
-use kompact::prelude::{ActorPath, ActorRefStrong};
+use crate::data::{flight_serde::FlightSerde, ArconMessage, ArconType, NodeID};
+use kompact::prelude::{
+    ActorPath, ActorRefStrong, BufMut, ComponentDefinition, SerError, Serialisable,
+};
+use std::any::Any;
 
 /// A Channel represents a connection to another Component
 #[derive(Clone)]
@@ -13,6 +18,51 @@ pub enum Channel<A: ArconType> {
     /// A typed local queue
     Local(ActorRefStrong<ArconMessage<A>>),
     /// Remote based queue containing a remote ActorPath identifier and an Arcon Serialiser
-    #[allow(dead_code)]
     Remote(ActorPath, FlightSerde),
 }
+
+/// Returns the alias that a Node is registered by in the data system of a distributed Application
+pub(crate) fn node_alias(node_id: NodeID) -> String {
+    format!("node_{}", node_id.id)
+}
+
+/// The component on whose behalf messages are sent over remote channels
+///
+/// Implemented for every [ComponentDefinition]. Being object safe, it lets type-erased
+/// outputs such as side outputs send through the Node that owns them.
+pub trait Dispatcher {
+    /// Serialises `message` and sends it to `path`
+    fn tell_serialised(
+        &self,
+        path: &ActorPath,
+        message: Box<dyn Serialisable>,
+    ) -> Result<(), SerError>;
+}
+
+impl<CD: ComponentDefinition> Dispatcher for CD {
+    fn tell_serialised(
+        &self,
+        path: &ActorPath,
+        message: Box<dyn Serialisable>,
+    ) -> Result<(), SerError> {
+        path.tell_serialised(Erased(message), self)
+    }
+}
+
+/// A boxed [Serialisable] that is sent as the message it wraps
+struct Erased(Box<dyn Serialisable>);
+
+impl Serialisable for Erased {
+    fn ser_id(&self) -> u64 {
+        self.0.ser_id()
+    }
+    fn size_hint(&self) -> Option<usize> {
+        self.0.size_hint()
+    }
+    fn serialise(&self, buf: &mut dyn BufMut) -> Result<(), SerError> {
+        self.0.serialise(buf)
+    }
+    fn local(self: Box<Self>) -> Result<Box<dyn Any + Send>, Box<dyn Serialisable>> {
+        self.0.local()
+    }
+}
@@ -75,6 +75,7 @@ where
         let msg = ArconMessage {
             events: reader,
             sender: self.sender_id,
+            credit: Default::default(),
         };
 
         // TODO: Should probably not busy wait here..
@@ -127,6 +128,7 @@ where
                 let msg = ArconMessage {
                     events: reader,
                     sender: self.sender_id,
+                    credit: Default::default(),
                 };
                 if let Err(SerError::BufferError(err)) = send(channel, msg, source) {
                     // TODO: Figure out how to get more space for `tell_serialised`
@@ -140,6 +142,7 @@ where
                 let msg = ArconMessage {
                     events: writer.reader(),
                     sender: self.sender_id,
+                    credit: Default::default(),
                 };
                 if let Err(SerError::BufferError(err)) = send(channel, msg, source) {
                     // TODO: Figure out how to get more space for `tell_serialised`
@@ -202,12 +205,15 @@ mod tests {
         comp.on_definition(|cd| {
             for _i in 0..total_msgs {
                 let elem = ArconElement::new(Input { id: 1 });
-                for (channel, msg) in channel_strategy.push(ArconEvent::Element(elem)) {
+                for (channel, msg) in channel_strategy.push(ArconEvent::Element(elem)).unwrap() {
                     send(&channel, msg, cd).unwrap();
                 }
             }
             // force a flush through a marker
-            for (channel, msg) in channel_strategy.push(ArconEvent::Watermark(Watermark::new(0))) {
+            for (channel, msg) in channel_strategy
+                .push(ArconEvent::Watermark(Watermark::new(0)))
+                .unwrap()
+            {
                 send(&channel, msg, cd).unwrap();
             }
         });
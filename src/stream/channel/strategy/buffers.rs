@@ -0,0 +1,140 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{
+    buffer::event::{BufferPool, BufferWriter, EventBuffer, PoolInfo},
+    data::{ArconEvent, ArconEventWrapper, ArconMessage, ArconType, NodeID},
+    stream::channel::Channel,
+};
+use std::sync::Arc;
+
+/// A buffer per Channel for strategies that batch the elements of each Channel separately
+pub(crate) struct ChannelBuffers<A>
+where
+    A: ArconType,
+{
+    /// A buffer pool of EventBuffer's
+    buffer_pool: BufferPool<ArconEventWrapper<A>>,
+    /// An identifier that is embedded with outgoing messages
+    sender_id: NodeID,
+    buffers: Vec<BufferWriter<ArconEventWrapper<A>>>,
+    channels: Vec<Arc<Channel<A>>>,
+    /// Sent buffers per channel that may not have been released yet, if they are tracked
+    in_flight: Option<Vec<Vec<Arc<EventBuffer<ArconEventWrapper<A>>>>>>,
+    /// Struct holding information regarding the BufferPool
+    _pool_info: PoolInfo,
+}
+
+impl<A> ChannelBuffers<A>
+where
+    A: ArconType,
+{
+    pub fn new(channels: Vec<Channel<A>>, sender_id: NodeID, pool_info: PoolInfo) -> Self {
+        assert!(
+            channels.len() < pool_info.capacity,
+            "Strategy must be initialised with a pool capacity larger than amount of channels"
+        );
+        let mut buffer_pool: BufferPool<ArconEventWrapper<A>> = BufferPool::new(
+            pool_info.capacity,
+            pool_info.buffer_size,
+            pool_info.allocator.clone(),
+        )
+        .expect("failed to initialise BufferPool");
+
+        let buffers = (0..channels.len())
+            .map(|_| {
+                buffer_pool
+                    .try_get()
+                    .expect("failed to fetch initial buffer")
+            })
+            .collect();
+
+        ChannelBuffers {
+            buffer_pool,
+            sender_id,
+            buffers,
+            channels: channels.into_iter().map(Arc::new).collect(),
+            in_flight: None,
+            _pool_info: pool_info,
+        }
+    }
+
+    /// Keep track of the sent buffers that have not been released by their receivers
+    pub fn with_in_flight(mut self) -> Self {
+        self.in_flight = Some(vec![Vec::new(); self.channels.len()]);
+        self
+    }
+
+    #[inline]
+    pub fn num_channels(&self) -> usize {
+        self.channels.len()
+    }
+
+    #[inline]
+    pub fn channel(&self, index: usize) -> &Arc<Channel<A>> {
+        &self.channels[index]
+    }
+
+    /// Adds `event` to the buffer of the channel `index`
+    ///
+    /// Returns the message of the buffer if it was full.
+    #[inline]
+    pub fn push_event(&mut self, index: usize, event: ArconEvent<A>) -> Option<ArconMessage<A>> {
+        match self.buffers[index].push(event.into()) {
+            Some(e) => {
+                let msg = self.message(index);
+                self.buffers[index].push(e);
+                Some(msg)
+            }
+            None => None,
+        }
+    }
+
+    /// Sends off the buffer of the channel `index` and continues with a fresh buffer
+    #[inline]
+    pub fn message(&mut self, index: usize) -> ArconMessage<A> {
+        let msg = ArconMessage {
+            events: self.buffers[index].reader(),
+            sender: self.sender_id,
+            credit: Default::default(),
+        };
+        if let Some(in_flight) = self.in_flight.as_mut() {
+            in_flight[index].push(msg.events.buffer().clone());
+            // Released buffers must be forgotten before the pool may hand them out again
+            self.release_buffers();
+        }
+        self.buffers[index] = self.buffer_pool.get();
+        msg
+    }
+
+    /// Adds a marker to every channel and flushes all buffers
+    pub fn push_marker(&mut self, event: ArconEvent<A>) -> Vec<(Arc<Channel<A>>, ArconMessage<A>)> {
+        let mut outputs = Vec::with_capacity(self.buffers.len());
+        for index in 0..self.buffers.len() {
+            if let Some(msg) = self.push_event(index, event.clone()) {
+                outputs.push((self.channels[index].clone(), msg));
+            }
+            let msg = self.message(index);
+            outputs.push((self.channels[index].clone(), msg));
+        }
+        outputs
+    }
+
+    /// Drops the tracked buffers that have been released by their receivers
+    pub fn release_buffers(&mut self) {
+        if let Some(in_flight) = self.in_flight.as_mut() {
+            for buffers in in_flight.iter_mut() {
+                buffers.retain(|buffer| !buffer.is_free());
+            }
+        }
+    }
+
+    /// Returns the number of tracked buffers of the channel `index` that may not have been released
+    #[inline]
+    pub fn in_flight(&self, index: usize) -> usize {
+        self.in_flight
+            .as_ref()
+            .map(|in_flight| in_flight[index].len())
+            .unwrap_or(0)
+    }
+}
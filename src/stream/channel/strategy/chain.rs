@@ -0,0 +1,286 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{
+    application::conf::logger::ArconLogger,
+    data::{ArconElement, ArconEvent, ArconNever, ArconType},
+    error::{
+        timer::{RescheduleResult, TimerResult},
+        ArconResult, StateResult,
+    },
+    index::{timer::ArconTimer, EmptyState, IndexOps},
+    stream::{
+        channel::{
+            flow_control::{CreditWaker, FlowControl},
+            strategy::ChannelStrategy,
+            Dispatcher,
+        },
+        node::common,
+        operator::{Operator, OperatorContext},
+    },
+    table::ImmutableTable,
+};
+use slog::debug;
+
+/// A type-erased Operator that has been fused into the Nodes of its upstream Operator
+///
+/// The output of the upstream Operator is pushed through the chained Operator rather than
+/// being sent to Nodes of its own, and the output of the chained Operator is sent on through
+/// its own strategy on behalf of the upstream Node.
+pub trait ChainedOutput<A: ArconType>: Send {
+    /// Pushes an event of the upstream Operator through the chained Operator
+    fn push(&mut self, event: ArconEvent<A>, source: &dyn Dispatcher) -> ArconResult<()>;
+    /// Sends the messages that were held back for lack of credits
+    fn flush(&mut self, source: &dyn Dispatcher) -> ArconResult<()>;
+    /// Returns true if messages are held back for lack of credits
+    fn is_backpressured(&self) -> bool;
+    /// Wakes `waker` whenever a channel of the chained Operator is granted a credit
+    fn set_credit_waker(&mut self, waker: &CreditWaker);
+    /// Returns number of outgoing channels
+    fn num_channels(&self) -> usize;
+}
+
+/// A stateless Operator that runs within the Nodes of its upstream Operator
+///
+/// The Operator has neither state nor timers, so markers are forwarded once the Operator
+/// has handled them. Epoch commits are not delivered, as they reach Operators through
+/// their NodeManager.
+pub struct ChainedOperator<OP>
+where
+    OP: Operator<TimerState = ArconNever, OperatorState = EmptyState>,
+{
+    descriptor: String,
+    operator: OP,
+    context: OperatorContext<ArconNever, EmptyState>,
+    /// Set once [Operator::on_start] has been run
+    started: bool,
+    /// Strategy of the outgoing edges of the chained Operator
+    strategy: ChannelStrategy<OP::OUT>,
+    flow_control: FlowControl<OP::OUT>,
+}
+
+impl<OP> ChainedOperator<OP>
+where
+    OP: Operator<TimerState = ArconNever, OperatorState = EmptyState>,
+{
+    pub(crate) fn new(
+        descriptor: String,
+        operator: OP,
+        instance: u32,
+        strategy: ChannelStrategy<OP::OUT>,
+        credits: usize,
+        logger: ArconLogger,
+    ) -> Self {
+        let mut context = OperatorContext::new(
+            Box::new(WatermarkTimer::default()),
+            Box::new(WatermarkTimer::default()),
+            EmptyState,
+            logger,
+            #[cfg(feature = "metrics")]
+            descriptor.clone(),
+        );
+        context.instance = instance;
+        ChainedOperator {
+            descriptor,
+            operator,
+            context,
+            started: false,
+            strategy,
+            flow_control: FlowControl::new(credits),
+        }
+    }
+
+    fn push_outputs<I>(&mut self, outputs: I, source: &dyn Dispatcher) -> ArconResult<()>
+    where
+        I: IntoIterator<Item = ArconElement<OP::OUT>>,
+    {
+        for element in outputs {
+            self.push_event(ArconEvent::Element(element), source)?;
+        }
+        // Chained Operators have no side outputs to emit to
+        self.context.side_outputs.clear();
+        Ok(())
+    }
+
+    fn push_event(
+        &mut self,
+        event: ArconEvent<OP::OUT>,
+        source: &dyn Dispatcher,
+    ) -> ArconResult<()> {
+        common::add_outgoing_event(event, &mut self.strategy, &mut self.flow_control, source)
+    }
+}
+
+impl<OP> ChainedOutput<OP::IN> for ChainedOperator<OP>
+where
+    OP: Operator<TimerState = ArconNever, OperatorState = EmptyState>,
+{
+    fn push(&mut self, event: ArconEvent<OP::IN>, source: &dyn Dispatcher) -> ArconResult<()> {
+        if !self.started {
+            debug!(
+                self.context.logger,
+                "Started chained Operator {}", self.descriptor
+            );
+            self.started = true;
+            self.operator.on_start(&mut self.context)?;
+        }
+        match event {
+            ArconEvent::Element(element) => {
+                let outputs = self.operator.handle_element(element, &mut self.context)?;
+                self.push_outputs(outputs, source)
+            }
+            ArconEvent::Watermark(w) => {
+                // No timers can be scheduled, so advancing the timer only moves its time
+                self.context.timer.advance_to(w.timestamp)?;
+                if let Some(outputs) = self
+                    .operator
+                    .handle_watermark(w.timestamp, &mut self.context)?
+                {
+                    self.push_outputs(outputs, source)?;
+                }
+                self.push_event(ArconEvent::Watermark(w), source)
+            }
+            ArconEvent::Epoch(e) => {
+                self.operator.handle_epoch(e.epoch, &mut self.context)?;
+                self.push_event(ArconEvent::Epoch(e), source)
+            }
+            ArconEvent::Death(s) => self.push_event(ArconEvent::Death(s), source),
+        }
+    }
+
+    fn flush(&mut self, source: &dyn Dispatcher) -> ArconResult<()> {
+        common::flush_outgoing(&mut self.strategy, &mut self.flow_control, source)
+    }
+
+    fn is_backpressured(&self) -> bool {
+        common::is_backpressured(&self.strategy, &self.flow_control)
+    }
+
+    fn set_credit_waker(&mut self, waker: &CreditWaker) {
+        self.flow_control.set_waker(waker);
+        self.strategy.set_credit_waker(waker);
+    }
+
+    fn num_channels(&self) -> usize {
+        self.strategy.num_channels()
+    }
+}
+
+/// Timer of a chained Operator
+///
+/// Operators without timer state cannot schedule timers, so the timer only keeps
+/// track of the current watermark.
+#[derive(Default)]
+struct WatermarkTimer {
+    time: u64,
+}
+
+impl IndexOps for WatermarkTimer {
+    fn persist(&mut self) -> ArconResult<()> {
+        Ok(())
+    }
+    fn set_key(&mut self, _: u64) {}
+    fn table(&mut self) -> ArconResult<Option<ImmutableTable>> {
+        Ok(None)
+    }
+}
+
+impl ArconTimer for WatermarkTimer {
+    type Key = u64;
+    type Value = ArconNever;
+
+    fn schedule_at(&mut self, _: u64, _: u64, entry: ArconNever) -> TimerResult<ArconNever> {
+        match entry {}
+    }
+    fn advance_keyed(&mut self, ts: u64) -> StateResult<Vec<(u64, ArconNever)>> {
+        self.time = ts;
+        Ok(Vec::new())
+    }
+    fn get_time(&self) -> StateResult<u64> {
+        Ok(self.time)
+    }
+    fn cancel(&mut self, _: &u64) -> StateResult<Option<ArconNever>> {
+        Ok(None)
+    }
+    fn reschedule(&mut self, _: u64, _: u64) -> RescheduleResult<ArconNever> {
+        Ok(None)
+    }
+    fn pending(&self, _: &u64) -> StateResult<Option<u64>> {
+        Ok(None)
+    }
+    fn is_empty(&self) -> StateResult<bool> {
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::Application,
+        data::{ArconMessage, NodeID, Watermark},
+        stream::{
+            channel::{
+                strategy::{forward::Forward, tests::*},
+                Channel,
+            },
+            node::debug::DebugNode,
+            operator::function::FlatMap,
+        },
+        test_utils::wait_until,
+    };
+    use kompact::prelude::*;
+    use std::time::Duration;
+
+    #[test]
+    fn chained_operator_test() {
+        let mut app = Application::default();
+        let pool_info = app.get_pool_info();
+        let logger = app.arcon_logger.clone();
+        let system = app.data_system();
+
+        let comp = system.create(DebugNode::<Input>::new);
+        system.start(&comp);
+        let actor_ref: ActorRefStrong<ArconMessage<Input>> =
+            comp.actor_ref().hold().expect("failed to fetch");
+        let strategy = ChannelStrategy::Forward(Forward::new(
+            Channel::Local(actor_ref),
+            NodeID::new(1),
+            pool_info,
+        ));
+
+        // keeps even ids and doubles them
+        let operator = FlatMap::new(|input: Input| {
+            if input.id % 2 == 0 {
+                Some(Input { id: input.id * 2 })
+            } else {
+                None
+            }
+        });
+        let chained =
+            ChainedOperator::new(String::from("chained"), operator, 0, strategy, 0, logger);
+        let mut chain = ChannelStrategy::Chained(Box::new(chained));
+        assert_eq!(chain.num_channels(), 1);
+
+        if let ChannelStrategy::Chained(chained) = &mut chain {
+            comp.on_definition(|cd| {
+                for id in 0..10 {
+                    let elem = ArconElement::new(Input { id });
+                    chained.push(ArconEvent::Element(elem), cd).unwrap();
+                }
+                chained
+                    .push(ArconEvent::Watermark(Watermark::new(0)), cd)
+                    .unwrap();
+            });
+        }
+
+        assert!(wait_until(Duration::from_secs(5), || {
+            comp.on_definition(|cd| cd.watermarks.len() == 1)
+        }));
+        comp.on_definition(|cd| {
+            let ids: Vec<u32> = cd.data.iter().map(|elem| elem.data.id).collect();
+            assert_eq!(ids, vec![0, 4, 8, 12, 16]);
+        });
+        app.shutdown();
+    }
+}
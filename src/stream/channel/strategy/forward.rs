@@ -90,6 +90,7 @@ where
         let msg = ArconMessage {
             events: reader,
             sender: self.sender_id,
+            credit: Default::default(),
         };
         // TODO: Should probably not busy wait here..
         self.curr_buffer = self.buffer_pool.get();
@@ -128,10 +129,13 @@ mod tests {
         comp.on_definition(|cd| {
             for _i in 0..total_msgs {
                 let elem = ArconElement::new(Input { id: 1 });
-                let _ = channel_strategy.push(ArconEvent::Element(elem));
+                let _ = channel_strategy.push(ArconEvent::Element(elem)).unwrap();
             }
             // force a flush through a marker
-            for (channel, msg) in channel_strategy.push(ArconEvent::Watermark(Watermark::new(0))) {
+            for (channel, msg) in channel_strategy
+                .push(ArconEvent::Watermark(Watermark::new(0)))
+                .unwrap()
+            {
                 let _ = send(&channel, msg, cd);
             }
         });
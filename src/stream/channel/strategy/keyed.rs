@@ -3,26 +3,36 @@
 
 use crate::{
     buffer::event::{BufferPool, BufferWriter, PoolInfo},
-    data::{ArconEvent, ArconEventWrapper, ArconMessage, ArconType, NodeID},
+    data::{
+        partition::{KeyGroupPartitioner, Partitioner},
+        ArconEvent, ArconEventWrapper, ArconMessage, ArconType, NodeID,
+    },
+    dataflow::conf::{default_key_extractor, KeyExtractor},
+    error::{ArconResult, Error},
+    manager::scaling::ManagedParallelism,
     stream::channel::Channel,
 };
 use std::sync::Arc;
 
 /// A Channel Strategy for Keyed Data Streams
 ///
-/// Data is split onto a contiguous key space containing N key ranges.
+/// Data is split onto a contiguous key space containing N key ranges,
+/// unless the strategy has been created with another [Partitioner].
 pub struct Keyed<A>
 where
     A: ArconType,
 {
     /// A buffer pool of EventBuffer's
     buffer_pool: BufferPool<ArconEventWrapper<A>>,
-    /// The highest possible key value
-    ///
-    /// This should not be set too low or ridiculously high
-    max_key: u64,
-    /// Number of ranges on the contiguous key space
+    /// Number of channels that elements are partitioned onto
     key_ranges: u64,
+    /// Set if the downstream Operator has a managed parallelism
+    ///
+    /// Only the first `key_ranges` channels own a key range, the remaining ones
+    /// only receive markers such as watermarks and epochs.
+    managed: Option<ManagedParallelism>,
+    /// Decides which channel an element is sent to
+    partitioner: Arc<dyn Partitioner<A>>,
     /// An identifier that is embedded with outgoing messages
     sender_id: NodeID,
     buffers: Vec<BufferWriter<ArconEventWrapper<A>>>,
@@ -35,12 +45,44 @@ impl<A> Keyed<A>
 where
     A: ArconType,
 {
-    /// Creates a Keyed strategy
+    /// Creates a Keyed strategy that partitions on [ArconType::get_key]
     pub fn new(
         max_key: u64,
         channels: Vec<Channel<A>>,
         sender_id: NodeID,
         pool_info: PoolInfo,
+    ) -> Keyed<A> {
+        Self::with_key_extractor(
+            max_key,
+            channels,
+            sender_id,
+            pool_info,
+            default_key_extractor(),
+        )
+    }
+
+    /// Creates a Keyed strategy that partitions on the key returned by `key_extractor`
+    pub fn with_key_extractor(
+        max_key: u64,
+        channels: Vec<Channel<A>>,
+        sender_id: NodeID,
+        pool_info: PoolInfo,
+        key_extractor: KeyExtractor<A>,
+    ) -> Keyed<A> {
+        Self::with_partitioner(
+            channels,
+            sender_id,
+            pool_info,
+            Arc::new(KeyGroupPartitioner::new(key_extractor, max_key)),
+        )
+    }
+
+    /// Creates a Keyed strategy that sends elements to the channels chosen by `partitioner`
+    pub fn with_partitioner(
+        channels: Vec<Channel<A>>,
+        sender_id: NodeID,
+        pool_info: PoolInfo,
+        partitioner: Arc<dyn Partitioner<A>>,
     ) -> Keyed<A> {
         let channels_len: u64 = channels.len() as u64;
         assert!(
@@ -65,13 +107,26 @@ where
         Keyed {
             buffer_pool,
             key_ranges: channels_len,
-            max_key,
+            managed: None,
+            partitioner,
             sender_id,
             channels: channels.into_iter().map(Arc::new).collect::<Vec<_>>(),
             buffers,
             _pool_info: pool_info,
         }
     }
+    /// Route elements onto the instances that are active according to `parallelism`
+    pub(crate) fn with_managed_parallelism(mut self, parallelism: ManagedParallelism) -> Self {
+        assert_eq!(
+            parallelism.max(),
+            self.channels.len(),
+            "Expected a channel per deployed instance"
+        );
+        self.key_ranges = parallelism.active_after(0) as u64;
+        self.managed = Some(parallelism);
+        self
+    }
+
     #[inline]
     fn push_event(&mut self, index: usize, event: ArconEvent<A>) -> Option<ArconMessage<A>> {
         let writer = &mut self.buffers[index];
@@ -80,6 +135,7 @@ where
                 let msg = ArconMessage {
                     events: writer.reader(),
                     sender: self.sender_id,
+                    credit: Default::default(),
                 };
                 // set a new writer
                 *writer = self.buffer_pool.get();
@@ -92,18 +148,33 @@ where
         }
     }
 
+    /// Adds `event` to the buffer of its channel
+    ///
+    /// Returns an error if the [Partitioner] chose a channel that does not exist.
     #[inline]
-    pub fn add(&mut self, event: ArconEvent<A>) -> Vec<(Arc<Channel<A>>, ArconMessage<A>)> {
+    pub fn add(
+        &mut self,
+        event: ArconEvent<A>,
+    ) -> ArconResult<Vec<(Arc<Channel<A>>, ArconMessage<A>)>> {
         match &event {
             ArconEvent::Element(element) => {
-                // Get key placement
-                let key = element.data.get_key() % self.max_key;
-                // Calculate which key range index is responsible for this key
-                let index = (key * self.key_ranges / self.max_key) as usize;
+                // Ask the partitioner which channel is responsible for this element
+                let index = self
+                    .partitioner
+                    .partition(&element.data, self.key_ranges as usize);
+                if index >= self.key_ranges as usize {
+                    return Err(Error::Unsupported {
+                        msg: format!(
+                            "Partitioner returned channel {} while there are {} channels",
+                            index, self.key_ranges
+                        ),
+                    });
+                }
 
-                self.push_event(index, event)
+                Ok(self
+                    .push_event(index, event)
                     .map(move |msg| vec![(self.channels[index].clone(), msg)])
-                    .unwrap_or_else(Vec::new)
+                    .unwrap_or_else(Vec::new))
             }
             _ => {
                 let mut outputs = Vec::with_capacity(self.buffers.len());
@@ -116,6 +187,7 @@ where
                             let msg_two = ArconMessage {
                                 events: writer.reader(),
                                 sender: self.sender_id,
+                                credit: Default::default(),
                             };
                             // set a new writer
                             *writer = self.buffer_pool.get();
@@ -128,6 +200,7 @@ where
                             let msg = ArconMessage {
                                 events: writer.reader(),
                                 sender: self.sender_id,
+                                credit: Default::default(),
                             };
                             // set a new writer
                             *writer = self.buffer_pool.get();
@@ -136,7 +209,13 @@ where
                         }
                     }
                 }
-                outputs
+
+                // Key ranges may only change owner at the epoch the parallelism was scheduled for
+                if let (ArconEvent::Epoch(epoch), Some(managed)) = (&event, &self.managed) {
+                    self.key_ranges = managed.active_after(epoch.epoch) as u64;
+                }
+
+                Ok(outputs)
             }
         }
     }
@@ -203,10 +282,15 @@ mod tests {
         let comp = &comps[0];
         comp.on_definition(|cd| {
             for input in inputs {
-                let _ = channel_strategy.push(input);
+                for (channel, msg) in channel_strategy.push(input).unwrap() {
+                    let _ = send(&channel, msg, cd);
+                }
             }
             // force a flush through a marker
-            for (channel, msg) in channel_strategy.push(ArconEvent::Watermark(Watermark::new(0))) {
+            for (channel, msg) in channel_strategy
+                .push(ArconEvent::Watermark(Watermark::new(0)))
+                .unwrap()
+            {
                 let _ = send(&channel, msg, cd);
             }
         });
@@ -221,4 +305,203 @@ mod tests {
         }
         app.shutdown();
     }
+
+    #[test]
+    fn keyby_extractor_test() {
+        let mut app = Application::default();
+        let pool_info = app.get_pool_info();
+        let system = app.data_system();
+
+        let parallelism: u32 = 4;
+        let total_msgs = 100;
+
+        let mut channels: Vec<Channel<Input>> = Vec::new();
+        let mut comps: Vec<Arc<crate::prelude::Component<DebugNode<Input>>>> = Vec::new();
+
+        for _i in 0..parallelism {
+            let comp = system.create(DebugNode::<Input>::new);
+            system.start(&comp);
+            let actor_ref: ActorRefStrong<ArconMessage<Input>> =
+                comp.actor_ref().hold().expect("failed to fetch");
+            channels.push(Channel::Local(actor_ref));
+            comps.push(comp);
+        }
+
+        // Key every element on the same value regardless of its id
+        let mut channel_strategy = ChannelStrategy::Keyed(Keyed::with_key_extractor(
+            256,
+            channels,
+            NodeID::new(1),
+            pool_info,
+            Arc::new(|_: &Input| 0),
+        ));
+
+        let comp = &comps[0];
+        comp.on_definition(|cd| {
+            for id in 0..total_msgs {
+                let elem = ArconElement::new(Input { id });
+                for (channel, msg) in channel_strategy.push(ArconEvent::Element(elem)).unwrap() {
+                    let _ = send(&channel, msg, cd);
+                }
+            }
+            for (channel, msg) in channel_strategy
+                .push(ArconEvent::Watermark(Watermark::new(0)))
+                .unwrap()
+            {
+                let _ = send(&channel, msg, cd);
+            }
+        });
+
+        std::thread::sleep(std::time::Duration::from_secs(1));
+
+        // All elements should end up at the first component
+        let received: Vec<usize> = comps
+            .iter()
+            .map(|comp| comp.on_definition(|cd| cd.data.len()))
+            .collect();
+        assert_eq!(received[0], total_msgs as usize);
+        assert_eq!(received.iter().sum::<usize>(), total_msgs as usize);
+        app.shutdown();
+    }
+
+    /// Sends elements with an even id to the last channel and the others to the first
+    struct EvenOdd;
+
+    impl Partitioner<Input> for EvenOdd {
+        fn partition(&self, elem: &Input, num_channels: usize) -> usize {
+            if elem.id % 2 == 0 {
+                num_channels - 1
+            } else {
+                0
+            }
+        }
+    }
+
+    #[test]
+    fn keyby_partitioner_test() {
+        let mut app = Application::default();
+        let pool_info = app.get_pool_info();
+        let system = app.data_system();
+
+        let parallelism: u32 = 3;
+        let total_msgs = 100;
+
+        let mut channels: Vec<Channel<Input>> = Vec::new();
+        let mut comps: Vec<Arc<crate::prelude::Component<DebugNode<Input>>>> = Vec::new();
+
+        for _i in 0..parallelism {
+            let comp = system.create(DebugNode::<Input>::new);
+            system.start(&comp);
+            let actor_ref: ActorRefStrong<ArconMessage<Input>> =
+                comp.actor_ref().hold().expect("failed to fetch");
+            channels.push(Channel::Local(actor_ref));
+            comps.push(comp);
+        }
+
+        let mut channel_strategy = ChannelStrategy::Keyed(Keyed::with_partitioner(
+            channels,
+            NodeID::new(1),
+            pool_info,
+            Arc::new(EvenOdd),
+        ));
+
+        let comp = &comps[0];
+        comp.on_definition(|cd| {
+            for id in 0..total_msgs {
+                let elem = ArconElement::new(Input { id });
+                for (channel, msg) in channel_strategy.push(ArconEvent::Element(elem)).unwrap() {
+                    let _ = send(&channel, msg, cd);
+                }
+            }
+            for (channel, msg) in channel_strategy
+                .push(ArconEvent::Watermark(Watermark::new(0)))
+                .unwrap()
+            {
+                let _ = send(&channel, msg, cd);
+            }
+        });
+
+        std::thread::sleep(std::time::Duration::from_secs(1));
+
+        let received: Vec<usize> = comps
+            .iter()
+            .map(|comp| comp.on_definition(|cd| cd.data.len()))
+            .collect();
+        assert_eq!(received, vec![50, 0, 50]);
+        app.shutdown();
+    }
+
+    /// Sends every element past the last channel
+    struct OutOfRange;
+
+    impl Partitioner<Input> for OutOfRange {
+        fn partition(&self, _: &Input, num_channels: usize) -> usize {
+            num_channels
+        }
+    }
+
+    #[test]
+    fn keyby_partition_out_of_range_test() {
+        let mut app = Application::default();
+        let pool_info = app.get_pool_info();
+        let system = app.data_system();
+
+        let comp = system.create(DebugNode::<Input>::new);
+        let actor_ref: ActorRefStrong<ArconMessage<Input>> =
+            comp.actor_ref().hold().expect("failed to fetch");
+        let mut keyed = Keyed::with_partitioner(
+            vec![Channel::Local(actor_ref)],
+            NodeID::new(1),
+            pool_info,
+            Arc::new(OutOfRange),
+        );
+
+        let elem = ArconElement::new(Input { id: 1 });
+        let result = keyed.add(ArconEvent::Element(elem));
+        assert!(matches!(result, Err(Error::Unsupported { .. })));
+        app.shutdown();
+    }
+
+    #[test]
+    fn keyby_managed_parallelism_test() {
+        use crate::{data::Epoch, manager::scaling::ManagedParallelism};
+
+        let mut app = Application::default();
+        let pool_info = app.get_pool_info();
+        let system = app.data_system();
+
+        let mut channels: Vec<Channel<Input>> = Vec::new();
+        for _i in 0..4 {
+            let comp = system.create(DebugNode::<Input>::new);
+            let actor_ref: ActorRefStrong<ArconMessage<Input>> =
+                comp.actor_ref().hold().expect("failed to fetch");
+            channels.push(Channel::Local(actor_ref));
+        }
+
+        // two upstream instances that share the parallelism of their downstream Operator
+        let parallelism = ManagedParallelism::new(1, 4);
+        let mut first = Keyed::new(256, channels.clone(), NodeID::new(1), pool_info.clone())
+            .with_managed_parallelism(parallelism.clone());
+        let mut second = Keyed::new(256, channels, NodeID::new(2), pool_info)
+            .with_managed_parallelism(parallelism.clone());
+
+        parallelism.schedule(1, 3);
+        let _ = first.add(ArconEvent::Epoch(Epoch::new(0)));
+        assert_eq!(first.key_ranges, 1);
+        let _ = first.add(ArconEvent::Epoch(Epoch::new(1)));
+        assert_eq!(first.key_ranges, 3);
+
+        // an instance that lags behind applies the parallelism at the same epoch
+        parallelism.schedule(3, 2);
+        assert_eq!(second.key_ranges, 1);
+        let _ = second.add(ArconEvent::Epoch(Epoch::new(0)));
+        assert_eq!(second.key_ranges, 1);
+        let _ = second.add(ArconEvent::Epoch(Epoch::new(1)));
+        assert_eq!(second.key_ranges, 3);
+        let _ = second.add(ArconEvent::Epoch(Epoch::new(2)));
+        assert_eq!(second.key_ranges, 3);
+        let _ = second.add(ArconEvent::Epoch(Epoch::new(3)));
+        assert_eq!(second.key_ranges, 2);
+        app.shutdown();
+    }
 }
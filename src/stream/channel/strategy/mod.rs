@@ -8,15 +8,20 @@ use crate::{
         flight_serde::{reliable_remote::ReliableSerde, FlightSerde},
         ArconEvent, ArconMessage, ArconType,
     },
-    stream::channel::Channel,
+    error::ArconResult,
+    stream::channel::{flow_control::CreditWaker, Channel, Dispatcher},
 };
-use kompact::prelude::{ComponentDefinition, SerError};
+use kompact::prelude::SerError;
 use std::sync::Arc;
 
 #[allow(dead_code)]
 pub mod broadcast;
+mod buffers;
+pub mod chain;
 pub mod forward;
 pub mod keyed;
+pub mod rebalance;
+pub mod round_robin;
 
 /// A `ChannelStrategy` defines a strategy of how messages are sent downstream
 ///
@@ -32,6 +37,16 @@ where
     Broadcast(broadcast::Broadcast<A>),
     /// Partition data to a set of `Channels` based on keyed hash
     Keyed(keyed::Keyed<A>),
+    /// Spread data evenly over a Vec of `Channels`
+    RoundRobin(round_robin::RoundRobin<A>),
+    /// Send data to the least loaded of a Vec of `Channels`
+    Rebalance(rebalance::Rebalance<A>),
+    /// Fans out messages to several strategies, one per outgoing edge
+    Multiple(Vec<ChannelStrategy<A>>),
+    /// Pushes events through an Operator that has been fused into the Node
+    ///
+    /// The chained Operator sends its own messages rather than returning them from [ChannelStrategy::push].
+    Chained(Box<dyn chain::ChainedOutput<A>>),
     /// A strategy that prints to the console
     Console,
     /// A strategy that simply does nothing
@@ -49,16 +64,32 @@ where
     pub fn push(
         &mut self,
         event: ArconEvent<A>,
-    ) -> impl IntoIterator<Item = (Arc<Channel<A>>, ArconMessage<A>)> {
+    ) -> ArconResult<Vec<(Arc<Channel<A>>, ArconMessage<A>)>> {
         match self {
-            ChannelStrategy::Forward(s) => s.add(event),
+            ChannelStrategy::Forward(s) => Ok(s.add(event)),
             ChannelStrategy::Keyed(s) => s.add(event),
-            ChannelStrategy::Broadcast(s) => s.add(event),
+            ChannelStrategy::Broadcast(s) => Ok(s.add(event)),
+            ChannelStrategy::RoundRobin(s) => Ok(s.add(event)),
+            ChannelStrategy::Rebalance(s) => Ok(s.add(event)),
+            ChannelStrategy::Multiple(strategies) => {
+                let mut messages = Vec::new();
+                // The last strategy takes the event itself, the others a copy
+                if let Some((last, rest)) = strategies.split_last_mut() {
+                    for strategy in rest {
+                        messages.extend(strategy.push(event.clone())?);
+                    }
+                    messages.extend(last.push(event)?);
+                }
+                Ok(messages)
+            }
+            ChannelStrategy::Chained(_) => {
+                unreachable!("Chained Operators are pushed to through add_outgoing_event")
+            }
             ChannelStrategy::Console => {
                 println!("{:?}", event);
-                Vec::new()
+                Ok(Vec::new())
             }
-            ChannelStrategy::Mute => Vec::new(),
+            ChannelStrategy::Mute => Ok(Vec::new()),
         }
     }
 
@@ -70,10 +101,55 @@ where
             ChannelStrategy::Forward(_) => 1,
             ChannelStrategy::Broadcast(s) => s.num_channels(),
             ChannelStrategy::Keyed(s) => s.num_channels(),
+            ChannelStrategy::RoundRobin(s) => s.num_channels(),
+            ChannelStrategy::Rebalance(s) => s.num_channels(),
+            ChannelStrategy::Multiple(strategies) => {
+                strategies.iter().map(|s| s.num_channels()).sum()
+            }
+            ChannelStrategy::Chained(s) => s.num_channels(),
             ChannelStrategy::Console => 0,
             ChannelStrategy::Mute => 0,
         }
     }
+
+    /// Returns true if a chained Operator holds back messages for lack of credits
+    #[inline]
+    pub(crate) fn is_backpressured(&self) -> bool {
+        match self {
+            ChannelStrategy::Chained(s) => s.is_backpressured(),
+            ChannelStrategy::Multiple(strategies) => {
+                strategies.iter().any(|s| s.is_backpressured())
+            }
+            _ => false,
+        }
+    }
+
+    /// Wakes `waker` whenever a chained Operator is granted a credit
+    pub(crate) fn set_credit_waker(&mut self, waker: &CreditWaker) {
+        match self {
+            ChannelStrategy::Chained(s) => s.set_credit_waker(waker),
+            ChannelStrategy::Multiple(strategies) => {
+                for strategy in strategies.iter_mut() {
+                    strategy.set_credit_waker(waker);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Sends the messages that chained Operators have held back for lack of credits
+    pub(crate) fn flush_chained(&mut self, source: &dyn Dispatcher) -> ArconResult<()> {
+        match self {
+            ChannelStrategy::Chained(s) => s.flush(source),
+            ChannelStrategy::Multiple(strategies) => {
+                for strategy in strategies.iter_mut() {
+                    strategy.flush_chained(source)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 /// `send` pushes an ArconMessage onto a Component queue
@@ -83,7 +159,7 @@ where
 pub(crate) fn send<A: ArconType>(
     channel: &Channel<A>,
     message: ArconMessage<A>,
-    source: &impl ComponentDefinition,
+    source: &dyn Dispatcher,
 ) -> Result<(), SerError> {
     match channel {
         Channel::Local(actor_ref) => {
@@ -93,19 +169,26 @@ pub(crate) fn send<A: ArconType>(
         #[cfg(feature = "unsafe_flight")]
         Channel::Remote(actor_path, FlightSerde::Unsafe) => {
             let unsafe_msg = UnsafeSerde(message.into());
-            actor_path.tell_serialised(unsafe_msg, source)
+            source.tell_serialised(actor_path, Box::new(unsafe_msg))
         }
         Channel::Remote(actor_path, FlightSerde::Reliable) => {
             let reliable_msg = ReliableSerde(message.into());
-            actor_path.tell_serialised(reliable_msg, source)
+            source.tell_serialised(actor_path, Box::new(reliable_msg))
         }
     }
 }
 
 #[cfg(test)]
 pub mod tests {
+    use super::{forward::Forward, Channel, *};
+    use crate::{
+        application::Application,
+        data::{ArconElement, Watermark},
+        stream::node::debug::DebugNode,
+    };
     #[cfg(feature = "unsafe_flight")]
     use abomonation_derive::*;
+    use kompact::prelude::*;
 
     #[cfg_attr(feature = "arcon_serde", derive(serde::Serialize, serde::Deserialize))]
     #[cfg_attr(feature = "unsafe_flight", derive(Abomonation))]
@@ -115,4 +198,58 @@ pub mod tests {
         #[prost(uint32, tag = "1")]
         pub id: u32,
     }
+
+    #[test]
+    fn multiple_test() {
+        let mut app = Application::default();
+        let pool_info = app.get_pool_info();
+        let system = app.data_system();
+
+        let total_msgs: u64 = 10;
+        let mut comps = Vec::new();
+        let mut strategies = Vec::new();
+
+        for _i in 0..2 {
+            let comp = system.create(DebugNode::<Input>::new);
+            system.start(&comp);
+            let actor_ref: ActorRefStrong<ArconMessage<Input>> =
+                comp.actor_ref().hold().expect("failed to fetch");
+            strategies.push(ChannelStrategy::Forward(Forward::new(
+                Channel::Local(actor_ref),
+                1.into(),
+                pool_info.clone(),
+            )));
+            comps.push(comp);
+        }
+
+        let mut channel_strategy: ChannelStrategy<Input> = ChannelStrategy::Multiple(strategies);
+        assert_eq!(channel_strategy.num_channels(), 2);
+
+        let comp = &comps[0];
+        comp.on_definition(|cd| {
+            for _i in 0..total_msgs {
+                let elem = ArconElement::new(Input { id: 1 });
+                for (channel, msg) in channel_strategy.push(ArconEvent::Element(elem)).unwrap() {
+                    send(&channel, msg, cd).unwrap();
+                }
+            }
+            // force a flush through a marker
+            for (channel, msg) in channel_strategy
+                .push(ArconEvent::Watermark(Watermark::new(0)))
+                .unwrap()
+            {
+                send(&channel, msg, cd).unwrap();
+            }
+        });
+
+        std::thread::sleep(std::time::Duration::from_secs(1));
+
+        // Both outgoing edges should have received every element
+        for comp in comps {
+            comp.on_definition(|cd| {
+                assert_eq!(cd.data.len() as u64, total_msgs);
+            });
+        }
+        let _ = app.shutdown();
+    }
 }
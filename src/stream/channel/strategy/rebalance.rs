@@ -0,0 +1,195 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use super::buffers::ChannelBuffers;
+use crate::{
+    buffer::event::PoolInfo,
+    data::{ArconEvent, ArconMessage, ArconType, NodeID},
+    stream::channel::Channel,
+};
+use std::sync::Arc;
+
+/// A Channel Strategy that sends elements to the least loaded of a set of `Channels`
+///
+/// Elements are batched per channel and once a batch has been sent off, the next batch
+/// goes to the channel with the fewest sent buffers that its receiver has not released yet.
+/// A downstream that falls behind thus receives less of the stream, while equally loaded
+/// downstreams are picked in round-robin order.
+///
+/// Buffers sent to remote channels are released once they have been serialised, so the
+/// load of a remote channel is taken to be that of the least loaded local channel. Remote
+/// channels then receive the same share as idle local channels, but never a larger one.
+pub struct Rebalance<A>
+where
+    A: ArconType,
+{
+    buffers: ChannelBuffers<A>,
+    /// Index of the channel that receives the current batch of elements
+    current: usize,
+}
+
+impl<A> Rebalance<A>
+where
+    A: ArconType,
+{
+    /// Creates a Rebalance strategy
+    pub fn new(channels: Vec<Channel<A>>, sender_id: NodeID, pool_info: PoolInfo) -> Self {
+        Rebalance {
+            buffers: ChannelBuffers::new(channels, sender_id, pool_info).with_in_flight(),
+            current: 0,
+        }
+    }
+
+    /// Returns the number of buffers in flight on the channel `index`
+    fn load(&self, index: usize, remote_load: usize) -> usize {
+        match **self.buffers.channel(index) {
+            Channel::Local(_) => self.buffers.in_flight(index),
+            Channel::Remote(..) => remote_load,
+        }
+    }
+
+    /// Returns the index of the channel with the fewest buffers in flight
+    ///
+    /// Ties are resolved in favour of the channels that follow the current one.
+    fn least_loaded(&mut self) -> usize {
+        self.buffers.release_buffers();
+        let channels = self.buffers.num_channels();
+        let remote_load = (0..channels)
+            .filter(|index| matches!(**self.buffers.channel(*index), Channel::Local(_)))
+            .map(|index| self.buffers.in_flight(index))
+            .min()
+            .unwrap_or(0);
+        (1..=channels)
+            .map(|offset| (self.current + offset) % channels)
+            .min_by_key(|index| self.load(*index, remote_load))
+            .unwrap_or(self.current)
+    }
+
+    #[inline]
+    pub fn add(&mut self, event: ArconEvent<A>) -> Vec<(Arc<Channel<A>>, ArconMessage<A>)> {
+        match &event {
+            ArconEvent::Element(_) => {
+                let index = self.current;
+                match self.buffers.push_event(index, event) {
+                    Some(msg) => {
+                        self.current = self.least_loaded();
+                        vec![(self.buffers.channel(index).clone(), msg)]
+                    }
+                    None => Vec::new(),
+                }
+            }
+            // Markers are sent on every channel, which flushes all buffers
+            _ => self.buffers.push_marker(event),
+        }
+    }
+
+    #[inline]
+    pub fn num_channels(&self) -> usize {
+        self.buffers.num_channels()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Channel, *};
+    use crate::{
+        application::Application,
+        data::flight_serde::FlightSerde,
+        data::{ArconElement, ArconEvent, NodeID},
+        stream::{channel::strategy::tests::*, node::debug::DebugNode},
+    };
+    use kompact::prelude::*;
+
+    #[test]
+    fn rebalance_avoids_slow_channel_test() {
+        let mut app = Application::default();
+        let pool_info = app.get_pool_info();
+        // small batches so that many of them are sent off
+        let pool_info = PoolInfo::new(
+            4,
+            pool_info.capacity,
+            pool_info.limit,
+            pool_info.allocator.clone(),
+        );
+        let system = app.data_system();
+
+        let mut channels: Vec<Channel<Input>> = Vec::new();
+        for _i in 0..3 {
+            let comp = system.create(DebugNode::<Input>::new);
+            let actor_ref: ActorRefStrong<ArconMessage<Input>> =
+                comp.actor_ref().hold().expect("failed to fetch");
+            channels.push(Channel::Local(actor_ref));
+        }
+
+        let mut strategy = Rebalance::new(channels, NodeID::new(1), pool_info);
+        let slow_channel = strategy.buffers.channel(0).clone();
+
+        // The messages of the first channel are never processed, while the others are released at once
+        let mut unprocessed = Vec::new();
+        let mut processed = 0;
+        for id in 0..100 {
+            let elem = ArconElement::new(Input { id });
+            for (channel, msg) in strategy.add(ArconEvent::Element(elem)) {
+                if Arc::ptr_eq(&channel, &slow_channel) {
+                    unprocessed.push(msg);
+                } else {
+                    processed += 1;
+                }
+            }
+        }
+
+        assert_eq!(unprocessed.len(), 1);
+        assert!(processed > 10);
+        app.shutdown();
+    }
+
+    #[test]
+    fn rebalance_remote_channel_test() {
+        let mut app = Application::default();
+        let pool_info = app.get_pool_info();
+        let pool_info = PoolInfo::new(
+            4,
+            pool_info.capacity,
+            pool_info.limit,
+            pool_info.allocator.clone(),
+        );
+        let system = app.data_system();
+
+        let mut channels: Vec<Channel<Input>> = Vec::new();
+        for _i in 0..2 {
+            let comp = system.create(DebugNode::<Input>::new);
+            let actor_ref: ActorRefStrong<ArconMessage<Input>> =
+                comp.actor_ref().hold().expect("failed to fetch");
+            channels.push(Channel::Local(actor_ref));
+        }
+        let addr = "127.0.0.1:1".parse().unwrap();
+        let path = NamedPath::with_socket(Transport::Tcp, addr, vec!["node_0".to_string()]);
+        channels.push(Channel::Remote(path.into(), FlightSerde::Reliable));
+
+        let mut strategy = Rebalance::new(channels, NodeID::new(1), pool_info);
+        let slow_channel = strategy.buffers.channel(0).clone();
+        let remote_channel = strategy.buffers.channel(2).clone();
+
+        // The remote channel gets the share of an idle local channel rather than all of the stream
+        let mut unprocessed = Vec::new();
+        let mut remote = 0;
+        let mut local = 0;
+        for id in 0..100 {
+            let elem = ArconElement::new(Input { id });
+            for (channel, msg) in strategy.add(ArconEvent::Element(elem)) {
+                if Arc::ptr_eq(&channel, &slow_channel) {
+                    unprocessed.push(msg);
+                } else if Arc::ptr_eq(&channel, &remote_channel) {
+                    remote += 1;
+                } else {
+                    local += 1;
+                }
+            }
+        }
+
+        assert_eq!(unprocessed.len(), 1);
+        assert!(remote > 5);
+        assert!((local as i32 - remote as i32).abs() <= 1);
+        app.shutdown();
+    }
+}
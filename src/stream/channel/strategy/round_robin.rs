@@ -0,0 +1,122 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use super::buffers::ChannelBuffers;
+use crate::{
+    buffer::event::PoolInfo,
+    data::{ArconEvent, ArconMessage, ArconType, NodeID},
+    stream::channel::Channel,
+};
+use std::sync::Arc;
+
+/// A Channel Strategy that spreads elements evenly over a set of `Channels`
+///
+/// Each element is sent to the channel following the one of the previous element.
+pub struct RoundRobin<A>
+where
+    A: ArconType,
+{
+    buffers: ChannelBuffers<A>,
+    /// Index of the channel that receives the next element
+    next: usize,
+}
+
+impl<A> RoundRobin<A>
+where
+    A: ArconType,
+{
+    /// Creates a RoundRobin strategy
+    pub fn new(channels: Vec<Channel<A>>, sender_id: NodeID, pool_info: PoolInfo) -> Self {
+        RoundRobin {
+            buffers: ChannelBuffers::new(channels, sender_id, pool_info),
+            next: 0,
+        }
+    }
+
+    #[inline]
+    pub fn add(&mut self, event: ArconEvent<A>) -> Vec<(Arc<Channel<A>>, ArconMessage<A>)> {
+        match &event {
+            ArconEvent::Element(_) => {
+                let index = self.next;
+                self.next = (self.next + 1) % self.buffers.num_channels();
+                match self.buffers.push_event(index, event) {
+                    Some(msg) => vec![(self.buffers.channel(index).clone(), msg)],
+                    None => Vec::new(),
+                }
+            }
+            // Markers are sent on every channel, which flushes all buffers
+            _ => self.buffers.push_marker(event),
+        }
+    }
+
+    #[inline]
+    pub fn num_channels(&self) -> usize {
+        self.buffers.num_channels()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Channel, *};
+    use crate::{
+        application::Application,
+        data::{ArconElement, ArconEvent, NodeID, Watermark},
+        stream::{
+            channel::strategy::{send, tests::*, ChannelStrategy},
+            node::debug::DebugNode,
+        },
+        test_utils::wait_until,
+    };
+    use kompact::prelude::*;
+    use std::{sync::Arc, time::Duration};
+
+    #[test]
+    fn round_robin_test() {
+        let mut app = Application::default();
+        let pool_info = app.get_pool_info();
+        let system = app.data_system();
+
+        let parallelism = 4;
+        let total_msgs = 100;
+
+        let mut channels: Vec<Channel<Input>> = Vec::new();
+        let mut comps: Vec<Arc<crate::prelude::Component<DebugNode<Input>>>> = Vec::new();
+
+        for _i in 0..parallelism {
+            let comp = system.create(DebugNode::<Input>::new);
+            system.start(&comp);
+            let actor_ref: ActorRefStrong<ArconMessage<Input>> =
+                comp.actor_ref().hold().expect("failed to fetch");
+            channels.push(Channel::Local(actor_ref));
+            comps.push(comp);
+        }
+
+        let mut channel_strategy =
+            ChannelStrategy::RoundRobin(RoundRobin::new(channels, NodeID::new(1), pool_info));
+
+        let comp = &comps[0];
+        comp.on_definition(|cd| {
+            for id in 0..total_msgs {
+                let elem = ArconElement::new(Input { id });
+                for (channel, msg) in channel_strategy.push(ArconEvent::Element(elem)).unwrap() {
+                    let _ = send(&channel, msg, cd);
+                }
+            }
+            for (channel, msg) in channel_strategy
+                .push(ArconEvent::Watermark(Watermark::new(0)))
+                .unwrap()
+            {
+                let _ = send(&channel, msg, cd);
+            }
+        });
+
+        // Every component receives an equal share of the elements
+        let share = (total_msgs / parallelism) as usize;
+        for comp in comps {
+            assert!(wait_until(Duration::from_secs(10), || {
+                comp.on_definition(|cd| cd.data.len() == share)
+            }));
+        }
+        app.shutdown();
+    }
+}
@@ -1,33 +1,63 @@
 use crate::data::{ArconEvent, ArconType};
 use crate::error::{ArconResult, Error};
 use crate::reportable_error;
-use crate::stream::channel::strategy::{send, ChannelStrategy};
-use kompact::prelude::{ComponentDefinition, SerError};
+use crate::stream::channel::{flow_control::FlowControl, strategy::ChannelStrategy, Dispatcher};
+use kompact::prelude::SerError;
 
 // Common helper function for adding events to a ChannelStrategy and possibly
 // dispatching Arcon messages.
 #[inline]
-pub fn add_outgoing_event<OUT: ArconType>(
+pub(crate) fn add_outgoing_event<OUT: ArconType>(
     event: ArconEvent<OUT>,
     strategy: &mut ChannelStrategy<OUT>,
-    cd: &impl ComponentDefinition,
+    flow_control: &mut FlowControl<OUT>,
+    cd: &dyn Dispatcher,
 ) -> ArconResult<()> {
-    for (channel, msg) in strategy.push(event) {
-        match send(&channel, msg, cd) {
-            Err(SerError::BufferError(msg)) | Err(SerError::NoBuffersAvailable(msg)) => {
-                // TODO: actually handle it
-                return Err(Error::Unsupported { msg });
-            }
-            Err(SerError::InvalidData(msg))
-            | Err(SerError::InvalidType(msg))
-            | Err(SerError::Unknown(msg)) => {
-                return reportable_error!("{}", msg);
-            }
-            Err(SerError::NoClone) => {
-                return reportable_error!("Got Kompact's SerError::NoClone");
-            }
-            Ok(_) => (),
+    if let ChannelStrategy::Chained(chain) = strategy {
+        return chain.push(event, cd);
+    }
+    for (channel, msg) in strategy.push(event)? {
+        if let Err(error) = flow_control.send(channel, msg, cd) {
+            return send_error(error);
         }
     }
     Ok(())
 }
+
+// Sends the messages that were held back by the flow control of a component
+// or its chained Operators for channels that have been granted credits since.
+#[inline]
+pub(crate) fn flush_outgoing<OUT: ArconType>(
+    strategy: &mut ChannelStrategy<OUT>,
+    flow_control: &mut FlowControl<OUT>,
+    cd: &dyn Dispatcher,
+) -> ArconResult<()> {
+    if let Err(error) = flow_control.flush(cd) {
+        return send_error(error);
+    }
+    strategy.flush_chained(cd)
+}
+
+// Returns true if a component or one of its chained Operators holds back messages.
+#[inline]
+pub(crate) fn is_backpressured<OUT: ArconType>(
+    strategy: &ChannelStrategy<OUT>,
+    flow_control: &FlowControl<OUT>,
+) -> bool {
+    flow_control.is_backpressured() || strategy.is_backpressured()
+}
+
+fn send_error(error: SerError) -> ArconResult<()> {
+    match error {
+        SerError::BufferError(msg) | SerError::NoBuffersAvailable(msg) => {
+            // TODO: actually handle it
+            Err(Error::Unsupported { msg })
+        }
+        SerError::InvalidData(msg) | SerError::InvalidType(msg) | SerError::Unknown(msg) => {
+            reportable_error!("{}", msg)
+        }
+        SerError::NoClone => {
+            reportable_error!("Got Kompact's SerError::NoClone")
+        }
+    }
+}
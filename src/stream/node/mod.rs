@@ -5,6 +5,8 @@
 pub mod common;
 /// Debug version of [Node]
 pub mod debug;
+/// Side outputs of nodes
+pub mod side_output;
 /// SourceNode components that drives the execution of sources
 pub mod source;
 
@@ -20,23 +22,40 @@ use crate::application::conf::logger::ArconLogger;
 #[cfg(feature = "unsafe_flight")]
 use crate::data::flight_serde::unsafe_remote::UnsafeSerde;
 use crate::{
-    data::{flight_serde::reliable_remote::ReliableSerde, RawArconMessage, *},
+    application::conf::processing_timer_interval_default,
+    data::{
+        flight_serde::reliable_remote::ReliableSerde, partition::key_owner, RawArconMessage, *,
+    },
+    dataflow::conf::KeyExtractor,
     error::{ArconResult, *},
     index::{AppenderIndex, ArconState, EagerAppender, IndexOps},
-    manager::node::{NodeManagerEvent::Checkpoint, *},
+    manager::{
+        node::{NodeManagerEvent::Checkpoint, *},
+        scaling::ManagedParallelism,
+    },
     reportable_error,
     stream::{
-        channel::strategy::ChannelStrategy,
-        operator::{Operator, OperatorContext},
+        channel::{
+            flow_control::{CreditGrant, CreditWaker, FlowControl},
+            node_alias,
+            strategy::ChannelStrategy,
+            Dispatcher,
+        },
+        operator::{side_output::OutputTag, Operator, OperatorContext},
     },
 };
 use arcon_macros::ArconState;
-use arcon_state::Backend;
+use arcon_state::{backend::serialization::protobuf, Backend};
 use fxhash::*;
 use kompact::prelude::*;
+use side_output::{ControlEvent, SideOutput};
 use std::{
     cell::{RefCell, UnsafeCell},
+    collections::VecDeque,
+    fs,
+    path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 
 /// Type alias for a Node description
@@ -75,6 +94,9 @@ pub struct NodeState<OP: Operator + 'static, B: Backend> {
     /// Identifier for the Node
     #[ephemeral]
     id: NodeID,
+    /// Number of elements that arrived after the watermark had passed their timestamp
+    #[ephemeral]
+    late_records: u64,
 }
 
 impl<OP: Operator + 'static, B: Backend> NodeState<OP, B> {
@@ -95,10 +117,67 @@ impl<OP: Operator + 'static, B: Backend> NodeState<OP, B> {
             current_epoch: Epoch::new(0),
             in_channels,
             id,
+            late_records: 0,
         }
     }
 }
 
+/// In-flight messages of a Node that are part of an unaligned checkpoint
+#[derive(prost::Message, Clone)]
+pub(crate) struct ChannelState<A: ArconType> {
+    #[prost(message, repeated, tag = "1")]
+    pub messages: Vec<RawArconMessage<A>>,
+}
+
+/// Returns the file that the channel state of `node` is stored in within `dir`
+pub(crate) fn channel_state_path(dir: &Path, node: NodeID) -> PathBuf {
+    dir.join(format!("node_{}", node.id))
+}
+
+/// An unaligned checkpoint of a Node that is in progress
+struct UnalignedEpoch<A: ArconType> {
+    /// Epoch of the checkpoint
+    epoch: Epoch,
+    /// Channels that have yet to deliver the epoch
+    pending: FxHashSet<NodeID>,
+    /// Set once the NodeManager has captured the state of the checkpoint
+    captured: bool,
+    /// Messages of pending channels that were processed after the state was captured
+    channel_state: Vec<RawArconMessage<A>>,
+    /// Channels that delivered the following epoch before this checkpoint completed
+    deferred: Vec<(NodeID, Epoch)>,
+}
+
+impl<A: ArconType> UnalignedEpoch<A> {
+    /// Records the events of `message` that precede the epoch marker of its channel
+    fn record(&mut self, message: &RawArconMessage<A>) {
+        let events: Vec<ArconEventWrapper<A>> = message
+            .events
+            .iter()
+            .take_while(|event| !matches!(event.unwrap_ref(), ArconEvent::Epoch(_)))
+            .cloned()
+            .collect();
+        if !events.is_empty() {
+            self.channel_state.push(RawArconMessage {
+                events,
+                sender: message.sender,
+            });
+        }
+    }
+}
+
+/// Instance of an Operator whose parallelism is managed, see [ManagedParallelism]
+struct ManagedInstance {
+    parallelism: ManagedParallelism,
+    /// Index of the instance among the deployed instances of the Operator
+    instance: usize,
+    /// Number of instances that own a key range in the current epoch
+    active: usize,
+    max_key: u64,
+    /// Set while the timers that other instances handed off wait to be taken over
+    take_over: bool,
+}
+
 /// A Node is a [kompact] component that drives the execution of streaming operators
 #[derive(ComponentDefinition)]
 pub struct Node<OP, B>
@@ -114,18 +193,60 @@ where
     descriptor: NodeDescriptor,
     /// Channel Strategy used by the Node
     channel_strategy: UnsafeCell<ChannelStrategy<OP::OUT>>,
+    /// Flow control of the outgoing channels
+    flow_control: RefCell<FlowControl<OP::OUT>>,
+    /// Messages that arrived while the Node was backpressured
+    ///
+    /// They hold on to the credits of their channels, which backpressures the upstream Nodes.
+    stashed: VecDeque<MessageContainer<OP::IN>>,
+    /// Woken whenever an output of the Node is granted a credit
+    credit_waker: CreditWaker,
+    /// Set while the Node waits to be granted credits
+    awaiting_credits: bool,
+    /// Paths through which Nodes on other processes are granted credits back
+    remote_senders: FxHashMap<NodeID, ActorPath>,
+    /// Side outputs that are consumed downstream
+    side_outputs: RefCell<Vec<Box<dyn SideOutput>>>,
     /// User-defined Operator
     operator: OP,
+    /// Function used to extract the state key of incoming elements
+    key_extractor: KeyExtractor<OP::IN>,
     /// Context for the Operator of this Node
     operator_context: RefCell<OperatorContext<OP::TimerState, OP::OperatorState>>,
     /// Internal Node State
     node_state: NodeState<OP, B>,
+    /// Interval in milliseconds at which processing-time timers are advanced
+    processing_timer_interval: u64,
+    /// Handle to the periodic Kompact timer that drives processing time while timers are pending
+    processing_timeout: Option<ScheduledTimer>,
+    /// Interval in milliseconds at which the inbound throughput is reported to the NodeManager
+    throughput_interval: Option<u64>,
+    /// Handle to the periodic Kompact timer that drives throughput reports
+    throughput_timeout: Option<ScheduledTimer>,
+    /// Number of events received since the last throughput report
+    inbound_events: u64,
+    /// Set if the Node takes unaligned checkpoints
+    unaligned_checkpoints: bool,
+    /// Unaligned checkpoint that is in progress
+    alignment: Option<UnalignedEpoch<OP::IN>>,
+    /// Epoch that the NodeManager asked to start while another checkpoint was in progress
+    requested_epoch: Option<Epoch>,
+    /// Channel state of the checkpoint the Node was restored from, replayed on start
+    restored_channel_state: Vec<RawArconMessage<OP::IN>>,
+    /// Set if the Operator of the Node has a managed parallelism
+    managed: Option<ManagedInstance>,
     #[cfg(all(feature = "hardware_counters", target_os = "linux", not(test)))]
     /// Configured hardware counters
     perf_events: PerfEvents,
     #[cfg(feature = "metrics")]
     /// Struct holding metrics information
     node_metrics: NodeMetrics,
+    #[cfg(feature = "metrics")]
+    /// Time at which the first epoch marker of the ongoing alignment arrived
+    alignment_start: Option<Instant>,
+    #[cfg(feature = "metrics")]
+    /// Size of the messages buffered or recorded during the ongoing alignment
+    buffered_bytes: usize,
 }
 
 impl<OP, B> Node<OP, B>
@@ -137,9 +258,10 @@ where
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         descriptor: NodeDescriptor,
-        channel_strategy: ChannelStrategy<OP::OUT>,
+        mut channel_strategy: ChannelStrategy<OP::OUT>,
         operator: OP,
         operator_state: OP::OperatorState,
+        key_extractor: KeyExtractor<OP::IN>,
         node_state: NodeState<OP, B>,
         backend: Arc<B>,
         logger: ArconLogger,
@@ -148,10 +270,20 @@ where
         perf_events: PerfEvents,
     ) -> Self {
         let timer_id = format!("_{}_timer", descriptor);
-        let timer = crate::index::timer::Timer::new(timer_id, backend);
+        let timer = crate::index::timer::Timer::new(timer_id, backend.clone());
+
+        let processing_timer_id = format!("_{}_processing_timer", descriptor);
+        let mut processing_timer = crate::index::timer::Timer::new(processing_timer_id, backend);
+        // A fresh processing timer starts at the current wall-clock time
+        if processing_timer.current_time().unwrap_or(0) == 0 {
+            processing_timer
+                .set_time(crate::util::get_system_time())
+                .expect("failed to initialise processing timer");
+        }
 
         let operator_context = OperatorContext::new(
             Box::new(timer),
+            Box::new(processing_timer),
             operator_state,
             logger,
             #[cfg(feature = "metrics")]
@@ -163,7 +295,11 @@ where
             register_gauge!("inbound_throughput", "node" => descriptor.clone());
             register_counter!("epoch_counter", "node" => descriptor.clone());
             register_counter!("watermark_counter", "node" => descriptor.clone());
+            register_counter!("late_records", "node" => descriptor.clone());
             register_histogram!("batch_execution_time","execution time per events batch","node" => descriptor.clone());
+            register_histogram!("epoch_alignment_time_ms", "node" => descriptor.clone());
+            register_gauge!("alignment_buffered_bytes", "node" => descriptor.clone());
+            register_gauge!("backpressure_ratio", "node" => descriptor.clone());
         }
 
         #[cfg(all(feature = "hardware_counters", target_os = "linux", not(test)))]
@@ -173,18 +309,268 @@ where
             }
         }
 
+        let credit_waker = CreditWaker::default();
+        channel_strategy.set_credit_waker(&credit_waker);
+
         Node {
             ctx: ComponentContext::uninitialised(),
             node_manager_port: RequiredPort::uninitialised(),
             descriptor,
             channel_strategy: UnsafeCell::new(channel_strategy),
+            flow_control: RefCell::new(FlowControl::new(0)),
+            stashed: VecDeque::new(),
+            credit_waker,
+            awaiting_credits: false,
+            remote_senders: FxHashMap::default(),
+            side_outputs: RefCell::new(Vec::new()),
             operator,
+            key_extractor,
             operator_context: RefCell::new(operator_context),
             node_state,
+            processing_timer_interval: processing_timer_interval_default(),
+            processing_timeout: None,
+            throughput_interval: None,
+            throughput_timeout: None,
+            inbound_events: 0,
+            unaligned_checkpoints: false,
+            alignment: None,
+            requested_epoch: None,
+            restored_channel_state: Vec::new(),
+            managed: None,
             #[cfg(all(feature = "hardware_counters", target_os = "linux", not(test)))]
             perf_events,
             #[cfg(feature = "metrics")]
             node_metrics: NodeMetrics::new(),
+            #[cfg(feature = "metrics")]
+            alignment_start: None,
+            #[cfg(feature = "metrics")]
+            buffered_bytes: 0,
+        }
+    }
+
+    /// Sets the index of the instance of the Operator that the Node runs
+    pub(crate) fn with_instance(self, instance: u32) -> Self {
+        self.operator_context.borrow_mut().instance = instance;
+        self
+    }
+
+    /// Sets the side outputs of the Node
+    pub(crate) fn with_side_outputs(self, mut side_outputs: Vec<Box<dyn SideOutput>>) -> Self {
+        for output in side_outputs.iter_mut() {
+            output.set_credit_waker(&self.credit_waker);
+        }
+        self.side_outputs.replace(side_outputs);
+        self
+    }
+
+    /// Sets the interval in milliseconds at which processing-time timers are advanced
+    pub(crate) fn with_processing_timer_interval(mut self, interval: u64) -> Self {
+        self.processing_timer_interval = interval;
+        self
+    }
+
+    /// Grant every outgoing channel `credits` messages in flight, zero disables flow control
+    pub(crate) fn with_channel_credits(self, credits: usize) -> Self {
+        let mut flow_control = FlowControl::new(credits);
+        flow_control.set_waker(&self.credit_waker);
+        self.flow_control.replace(flow_control);
+        self
+    }
+
+    /// Report the inbound throughput to the NodeManager every `interval` milliseconds
+    pub(crate) fn with_throughput_reports(mut self, interval: u64) -> Self {
+        self.throughput_interval = Some(interval);
+        self
+    }
+
+    /// Run the Node as instance `instance` of an Operator with a managed parallelism
+    ///
+    /// Key ranges change owner at the epoch a new parallelism is scheduled for. The keyed state
+    /// lives in the backend that the instances share, so the new owner of a key reads what the
+    /// previous one persisted at the epoch. Timers are per instance and are handed off instead.
+    pub(crate) fn with_managed_parallelism(
+        mut self,
+        parallelism: ManagedParallelism,
+        instance: usize,
+        max_key: u64,
+    ) -> Self {
+        self.managed = Some(ManagedInstance {
+            active: parallelism.active_after(self.node_state.current_epoch.epoch),
+            parallelism,
+            instance,
+            max_key,
+            // timers may have been handed off right before the checkpoint the Node was restored from
+            take_over: true,
+        });
+        self
+    }
+
+    /// Continue from `epoch` after the backend of the Node has been restored
+    ///
+    /// Messages that were buffered for blocked channels arrived after the checkpoint
+    /// was taken and are replayed by the sources, so they are discarded.
+    pub(crate) fn with_epoch(mut self, epoch: Epoch) -> ArconResult<Self> {
+        self.node_state.current_epoch = epoch;
+        self.operator_context.borrow_mut().restored_epoch = epoch.epoch.checked_sub(1);
+        let _ = self.node_state.message_buffer().consume()?;
+        Ok(self)
+    }
+
+    /// Take unaligned checkpoints instead of aligning the epochs of the input channels
+    pub(crate) fn with_unaligned_checkpoints(mut self) -> Self {
+        self.unaligned_checkpoints = true;
+        self
+    }
+
+    /// Replay the channel state of an unaligned checkpoint in `dir` once the Node starts
+    ///
+    /// The messages were in flight when the checkpoint was taken and are not replayed by the sources.
+    pub(crate) fn with_channel_state(mut self, dir: &Path) -> ArconResult<Self> {
+        let path = channel_state_path(dir, self.node_state.id);
+        if path.exists() {
+            let state: ChannelState<OP::IN> = protobuf::deserialize(&fs::read(path)?)?;
+            self.restored_channel_state = state.messages;
+        }
+        Ok(self)
+    }
+
+    /// Returns the number of late elements this Node has received
+    #[cfg(test)]
+    pub(crate) fn late_records(&self) -> u64 {
+        self.node_state.late_records
+    }
+
+    /// Handles `message` unless the Node is backpressured, in which case it is stashed
+    #[inline]
+    fn receive(&mut self, message: MessageContainer<OP::IN>) {
+        if self.stashed.is_empty() && !self.is_backpressured() {
+            if let Err(err) = self.handle_received(message) {
+                error!(
+                    self.operator_context.borrow().logger,
+                    "Failed to handle message: {}", err
+                );
+            }
+        } else {
+            self.stashed.push_back(message);
+        }
+        self.await_credits();
+        self.await_processing_timers();
+    }
+
+    /// Returns true if the Node, one of its chained Operators or side outputs holds back messages
+    #[inline]
+    fn is_backpressured(&self) -> bool {
+        let strategy = unsafe { &*self.channel_strategy.get() };
+        common::is_backpressured(strategy, &self.flow_control.borrow())
+            || self
+                .side_outputs
+                .borrow()
+                .iter()
+                .any(|o| o.is_backpressured())
+    }
+
+    /// Resumes the Node once it has been granted credits, if it is backpressured
+    fn await_credits(&mut self) {
+        if !self.awaiting_credits && self.is_backpressured() {
+            self.awaiting_credits = true;
+            let waker = self.credit_waker.clone();
+            self.spawn_local(move |mut async_self| async move {
+                waker.granted().await;
+                async_self.awaiting_credits = false;
+                async_self.on_credits();
+                Handled::Ok
+            });
+        }
+    }
+
+    /// Starts driving processing time once the Operator has registered a processing-time timer
+    fn await_processing_timers(&mut self) {
+        let scheduled = std::mem::take(
+            &mut self
+                .operator_context
+                .borrow_mut()
+                .processing_timer_scheduled,
+        );
+        if self.processing_timeout.is_none() && scheduled {
+            let interval = Duration::from_millis(self.processing_timer_interval);
+            let timeout =
+                self.schedule_periodic(interval, interval, Self::handle_processing_timeout);
+            self.processing_timeout = Some(timeout);
+        }
+    }
+
+    /// Sends the held back messages and handles the stashed ones until the Node is backpressured again
+    fn resume(&mut self) -> ArconResult<()> {
+        let strategy = unsafe { &mut *self.channel_strategy.get() };
+        common::flush_outgoing(strategy, &mut self.flow_control.borrow_mut(), self)?;
+        for output in self.side_outputs.borrow_mut().iter_mut() {
+            output.flush(self)?;
+        }
+        while !self.is_backpressured() {
+            match self.stashed.pop_front() {
+                Some(message) => self.handle_received(message)?,
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends the held back messages and handles the stashed ones after credits have been granted
+    fn on_credits(&mut self) {
+        if let Err(error) = self.resume() {
+            error!(
+                self.operator_context.borrow().logger,
+                "Failed to resume backpressured Node with error {:?}", error
+            );
+        }
+        self.await_credits();
+        self.await_processing_timers();
+    }
+
+    /// Handles a message that the Node has received and grants its credit back
+    ///
+    /// Local messages carry their credit, which is granted back once they are dropped.
+    fn handle_received(&mut self, message: MessageContainer<OP::IN>) -> ArconResult<()> {
+        let sender = *message.sender();
+        let remote = matches!(message, MessageContainer::Raw(_));
+        let result = self.handle_message(message);
+        if remote {
+            self.grant_remote_credit(sender);
+        }
+        result
+    }
+
+    /// Grants a credit back to `sender` if it runs on another process
+    fn grant_remote_credit(&self, sender: NodeID) {
+        if let Some(path) = self.remote_senders.get(&sender) {
+            let grant = CreditGrant {
+                receiver: self.node_state.id.id,
+            };
+            if let Err(error) = self.tell_serialised(path, Box::new(grant)) {
+                error!(
+                    self.operator_context.borrow().logger,
+                    "Failed to grant credit to {:?} with error {:?}", sender, error
+                );
+            }
+        }
+    }
+
+    /// Grants a credit to the outputs that lead to the Node `receiver` on another process
+    ///
+    /// The grant wakes the Node if it is backpressured.
+    fn handle_credit_grant(&mut self, grant: CreditGrant) {
+        let receiver = NodeID::new(grant.receiver);
+        let granted = self.flow_control.borrow_mut().grant(receiver)
+            || self
+                .side_outputs
+                .borrow_mut()
+                .iter_mut()
+                .any(|output| output.grant(receiver));
+        if !granted {
+            error!(
+                self.operator_context.borrow().logger,
+                "Received credit from {:?}, which is not a receiver of the Node", receiver
+            );
         }
     }
 
@@ -205,10 +591,12 @@ where
             return Ok(());
         }
 
-        if self.sender_blocked(message.sender()) {
-            self.node_state.message_buffer().append(message.raw())?;
-            return Ok(());
-        }
+        self.inbound_events += message.total_events();
+
+        let message = match self.buffer_or_record(message)? {
+            Some(message) => message,
+            None => return Ok(()),
+        };
 
         #[cfg(all(feature = "hardware_counters", target_os = "linux", not(test)))]
         let (mut group, counters) = {
@@ -264,6 +652,215 @@ where
         self.node_state.blocked_channels().contains(sender)
     }
 
+    /// Buffers `message` if it may not be processed yet, otherwise it is returned
+    ///
+    /// The backend of a Node is shared with the other instances of its Operator, so while the
+    /// state of an unaligned checkpoint is being captured every message is buffered. Once it
+    /// has been captured, messages of channels that are pending in the checkpoint are recorded
+    /// as channel state before they are processed.
+    #[inline]
+    fn buffer_or_record(
+        &mut self,
+        message: MessageContainer<OP::IN>,
+    ) -> ArconResult<Option<MessageContainer<OP::IN>>> {
+        let capturing = matches!(&self.alignment, Some(alignment) if !alignment.captured);
+        if capturing || self.sender_blocked(message.sender()) {
+            let raw = message.raw();
+            #[cfg(feature = "metrics")]
+            self.add_buffered_bytes(prost::Message::encoded_len(&raw));
+            self.node_state.message_buffer().append(raw)?;
+            return Ok(None);
+        }
+
+        match self.alignment.as_mut() {
+            Some(alignment) if alignment.pending.contains(message.sender()) => {
+                let raw = message.raw();
+                alignment.record(&raw);
+                #[cfg(feature = "metrics")]
+                self.add_buffered_bytes(prost::Message::encoded_len(&raw));
+                Ok(Some(MessageContainer::Raw(raw)))
+            }
+            _ => Ok(Some(message)),
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    fn add_buffered_bytes(&mut self, bytes: usize) {
+        self.buffered_bytes += bytes;
+        gauge!("alignment_buffered_bytes", self.buffered_bytes as f64, "node" => self.descriptor.clone());
+    }
+
+    /// Records the duration of the ongoing alignment and resets its metrics
+    #[cfg(feature = "metrics")]
+    fn complete_alignment_metrics(&mut self) {
+        if let Some(start) = self.alignment_start.take() {
+            let elapsed = start.elapsed();
+            histogram!("epoch_alignment_time_ms", elapsed.as_millis() as f64, "node" => self.descriptor.clone());
+        }
+        self.buffered_bytes = 0;
+        gauge!("alignment_buffered_bytes", 0.0, "node" => self.descriptor.clone());
+    }
+
+    /// Persists the state of the Node, requests a checkpoint and forwards the current epoch
+    fn checkpoint_epoch(&mut self) -> ArconResult<()> {
+        // persist internal node state for this node
+        self.node_state.persist()?;
+
+        // persist possible operator state and its timers..
+        let mut context = self.operator_context.borrow_mut();
+        self.operator
+            .handle_epoch(self.node_state.current_epoch.epoch, &mut context)?;
+        context.state.persist()?;
+        context.persist_timers()?;
+
+        // Hand off the timers of the keys that change owner after this epoch
+        let epoch = self.node_state.current_epoch.epoch;
+        if let Some(managed) = self.managed.as_mut() {
+            let active = managed.parallelism.active_after(epoch);
+            if active != managed.active {
+                let (instance, max_key) = (managed.instance, managed.max_key);
+                context.state.clear_cache();
+                context.hand_off_timers(instance, &|key| key_owner(key, active as u64, max_key))?;
+                managed.active = active;
+                managed.take_over = true;
+            }
+        }
+        drop(context);
+
+        // Create checkpoint request and send it off to the NodeManager
+        let request = CheckpointRequest::new(self.node_state.id, self.node_state.current_epoch);
+        self.node_manager_port.trigger(Checkpoint(request));
+
+        // Forward the Epoch
+        self.add_outgoing_event(ArconEvent::Epoch(self.node_state.current_epoch))?;
+        self.add_side_output_event(ControlEvent::Epoch(self.node_state.current_epoch))?;
+
+        // Update current epoch
+        self.node_state.current_epoch.epoch += 1;
+        Ok(())
+    }
+
+    /// Handles an epoch marker when the Node takes unaligned checkpoints
+    ///
+    /// The first marker of an epoch starts its checkpoint right away. Messages of the channels
+    /// that have yet to deliver the marker are processed and recorded as channel state.
+    fn handle_unaligned_epoch(&mut self, sender: NodeID, epoch: Epoch) -> ArconResult<()> {
+        if let Some(alignment) = self.alignment.as_mut() {
+            if alignment.epoch == epoch {
+                alignment.pending.remove(&sender);
+                return self.complete_unaligned_epoch();
+            }
+        }
+        if epoch < self.node_state.current_epoch {
+            return Ok(());
+        }
+        if let Some(alignment) = self.alignment.as_mut() {
+            // the following epoch waits for the ongoing checkpoint to complete
+            alignment.deferred.push((sender, epoch));
+            self.node_state.blocked_channels().insert(sender);
+            return Ok(());
+        }
+        self.start_unaligned_epoch(Some(sender))
+    }
+
+    /// Starts the current epoch on request of the NodeManager
+    ///
+    /// Another instance of the Operator has started the epoch, and the shared state is only
+    /// captured once every instance has done so. Starting without waiting for a marker keeps
+    /// the instances that started first from waiting on the channels of this one.
+    fn handle_epoch_request(&mut self, epoch: Epoch) -> ArconResult<()> {
+        if epoch != self.node_state.current_epoch {
+            // the epoch has already been started by a marker
+            return Ok(());
+        }
+        if self.alignment.is_some() {
+            self.requested_epoch = Some(epoch);
+            return Ok(());
+        }
+        self.start_unaligned_epoch(None)
+    }
+
+    /// Starts an unaligned checkpoint of the current epoch, of which `sender` delivered the marker
+    fn start_unaligned_epoch(&mut self, sender: Option<NodeID>) -> ArconResult<()> {
+        #[cfg(feature = "metrics")]
+        {
+            self.alignment_start = Some(Instant::now());
+        }
+        let pending = self
+            .node_state
+            .in_channels
+            .iter()
+            .filter(|id| Some(**id) != sender)
+            .copied()
+            .collect();
+        self.alignment = Some(UnalignedEpoch {
+            epoch: self.node_state.current_epoch,
+            pending,
+            captured: false,
+            channel_state: Vec::new(),
+            deferred: Vec::new(),
+        });
+        self.checkpoint_epoch()
+    }
+
+    /// Processes the messages that arrived while the state of an unaligned checkpoint was captured
+    fn resume_unaligned_epoch(&mut self) -> ArconResult<()> {
+        match self.alignment.as_mut() {
+            Some(alignment) if !alignment.captured => alignment.captured = true,
+            _ => return Ok(()),
+        }
+        for message in self.node_state.message_buffer().consume()? {
+            if let Some(message) = self.buffer_or_record(MessageContainer::Raw(message))? {
+                let raw = message.raw();
+                self.handle_events(raw.sender, raw.events)?;
+            }
+        }
+        self.complete_unaligned_epoch()
+    }
+
+    /// Hands the channel state to the NodeManager once every channel has delivered the epoch
+    fn complete_unaligned_epoch(&mut self) -> ArconResult<()> {
+        let complete = matches!(&self.alignment, Some(a) if a.captured && a.pending.is_empty());
+        if !complete {
+            return Ok(());
+        }
+        let alignment = self.alignment.take().unwrap();
+
+        let channel_state = ChannelState {
+            messages: alignment.channel_state,
+        };
+        self.node_manager_port
+            .trigger(NodeManagerEvent::ChannelState(
+                self.node_state.id,
+                alignment.epoch,
+                protobuf::serialize(&channel_state)?,
+            ));
+
+        #[cfg(feature = "metrics")]
+        {
+            increment_counter!("epoch_counter", "node" => self.descriptor.clone());
+            let ratio = self.flow_control.borrow_mut().backpressure_ratio();
+            gauge!("backpressure_ratio", ratio, "node" => self.descriptor.clone());
+            self.complete_alignment_metrics();
+        }
+
+        // channels that delivered the following epoch start its checkpoint
+        self.node_state.blocked_channels().clear();
+        for (sender, epoch) in alignment.deferred {
+            self.handle_unaligned_epoch(sender, epoch)?;
+        }
+        if let Some(epoch) = self.requested_epoch.take() {
+            self.handle_epoch_request(epoch)?;
+        }
+        for message in self.node_state.message_buffer().consume()? {
+            if let Some(message) = self.buffer_or_record(MessageContainer::Raw(message))? {
+                let raw = message.raw();
+                self.handle_events(raw.sender, raw.events)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Iterate over a batch of ArconEvent's
     #[inline]
     fn handle_events<I>(&mut self, sender: NodeID, events: I) -> ArconResult<()>
@@ -279,16 +876,24 @@ where
                     };
 
                     if e.timestamp <= watermark.timestamp {
+                        self.node_state.late_records += 1;
+                        #[cfg(feature = "metrics")]
+                        increment_counter!("late_records", "node" => self.descriptor.clone());
+
+                        let mut context = self.operator_context.borrow_mut();
+                        context.output_to(&OutputTag::late(), e);
+                        self.flush_side_outputs(&mut context)?;
                         continue 'event_loop;
                     }
 
                     // Set key for the current element
                     // TODO: Should use a pre-defined key for Non-Keyed Streams.
                     let mut context = self.operator_context.borrow_mut();
-                    context.state().set_key(e.data.get_key());
+                    context.set_key((self.key_extractor)(&e.data));
                     for elem in self.operator.handle_element(e, &mut context)? {
                         self.add_outgoing_event(ArconEvent::Element(elem))?;
                     }
+                    self.flush_side_outputs(&mut context)?;
                 }
                 ArconEvent::Watermark(w) => {
                     let watermark = match self.node_state.watermarks().get(&sender) {
@@ -316,15 +921,13 @@ where
                     if new_watermark.timestamp > self.node_state.current_watermark.timestamp {
                         self.node_state.current_watermark = new_watermark;
 
-                        let timeouts = self
-                            .operator_context
-                            .borrow_mut()
-                            .timer
-                            .advance_to(new_watermark.timestamp)?;
-                        for timeout in timeouts {
-                            if let Some(elems) = self
-                                .operator
-                                .handle_timeout(timeout, &mut self.operator_context.borrow_mut())?
+                        let mut context = self.operator_context.borrow_mut();
+                        context.state.set_watermark(new_watermark.timestamp);
+                        let timeouts = context.timer.advance_keyed(new_watermark.timestamp)?;
+                        for (key, timeout) in timeouts {
+                            context.set_key(key);
+                            if let Some(elems) =
+                                self.operator.handle_timeout(timeout, &mut context)?
                             {
                                 for elem in elems {
                                     self.add_outgoing_event(ArconEvent::Element(elem))?;
@@ -332,47 +935,54 @@ where
                             }
                         }
 
+                        if let Some(elems) = self
+                            .operator
+                            .handle_watermark(new_watermark.timestamp, &mut context)?
+                        {
+                            for elem in elems {
+                                self.add_outgoing_event(ArconEvent::Element(elem))?;
+                            }
+                        }
+                        self.flush_side_outputs(&mut context)?;
+                        drop(context);
+
                         #[cfg(feature = "metrics")]
                         increment_counter!("watermark_counter", "node" => self.descriptor.clone());
 
                         // Forward the watermark
                         self.add_outgoing_event(ArconEvent::Watermark(new_watermark))?;
+                        self.add_side_output_event(ControlEvent::Watermark(new_watermark))?;
                     }
                 }
                 ArconEvent::Epoch(e) => {
                     debug!(self.operator_context.borrow().logger, "Got Epoch {:?}", e);
+                    if self.unaligned_checkpoints {
+                        self.handle_unaligned_epoch(sender, e)?;
+                        continue 'event_loop;
+                    }
                     if e < self.node_state.current_epoch {
                         continue 'event_loop;
                     }
 
+                    #[cfg(feature = "metrics")]
+                    {
+                        if self.node_state.blocked_channels().is_empty() {
+                            self.alignment_start = Some(Instant::now());
+                        }
+                    }
+
                     // Add the sender to the blocked set.
                     self.node_state.blocked_channels().insert(sender);
 
                     // If all senders blocked we can transition to new Epoch
                     if self.node_state.blocked_channels().len() == self.node_state.in_channels.len()
                     {
-                        // persist internal node state for this node
-                        self.node_state.persist()?;
-
-                        // persist possible operator state..
-                        self.operator_context.borrow_mut().state.persist()?;
-
-                        // Create checkpoint request and send it off to the NodeManager
-                        let request = CheckpointRequest::new(
-                            self.node_state.id,
-                            self.node_state.current_epoch,
-                        );
-                        self.node_manager_port.trigger(Checkpoint(request));
-
-                        // Forward the Epoch
-                        self.add_outgoing_event(ArconEvent::Epoch(self.node_state.current_epoch))?;
-
-                        // Update current epoch
-                        self.node_state.current_epoch.epoch += 1;
+                        self.checkpoint_epoch()?;
                     }
                 }
                 ArconEvent::Death(s) => {
                     // We are instructed to shutdown....
+                    self.add_side_output_event(ControlEvent::Death(s.clone()))?;
                     self.add_outgoing_event(ArconEvent::Death(s))?;
                     self.ctx.suicide(); // TODO: is suicide enough?
                 }
@@ -385,13 +995,124 @@ where
     #[inline]
     fn add_outgoing_event(&self, event: ArconEvent<OP::OUT>) -> ArconResult<()> {
         let strategy = unsafe { &mut *self.channel_strategy.get() };
-        common::add_outgoing_event(event, strategy, self)
+        let mut flow_control = self.flow_control.borrow_mut();
+        common::add_outgoing_event(event, strategy, &mut flow_control, self)
+    }
+
+    /// Pushes the elements the Operator emitted to side outputs downstream
+    ///
+    /// Elements of side outputs that are not consumed are dropped.
+    #[inline]
+    fn flush_side_outputs(
+        &self,
+        context: &mut OperatorContext<OP::TimerState, OP::OperatorState>,
+    ) -> ArconResult<()> {
+        if context.side_outputs.is_empty() {
+            return Ok(());
+        }
+        let mut side_outputs = self.side_outputs.borrow_mut();
+        for (key, element) in context.side_outputs.drain(..) {
+            if let Some(output) = side_outputs.iter_mut().find(|o| *o.key() == key) {
+                output.push_element(element, self)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn add_side_output_event(&self, event: ControlEvent) -> ArconResult<()> {
+        for output in self.side_outputs.borrow_mut().iter_mut() {
+            output.push_control(event.clone(), self)?;
+        }
+        Ok(())
+    }
+
+    /// Advances the processing-time timers to the current wall-clock time
+    ///
+    /// Fired timeouts are handled by [Operator::handle_timeout].
+    fn advance_processing_time(&mut self) -> ArconResult<()> {
+        let mut context = self.operator_context.borrow_mut();
+        let now = context.current_processing_time();
+        let timeouts = context.processing_timer.advance_keyed(now)?;
+        for (key, timeout) in timeouts {
+            context.set_key(key);
+            if let Some(elems) = self.operator.handle_timeout(timeout, &mut context)? {
+                for elem in elems {
+                    self.add_outgoing_event(ArconEvent::Element(elem))?;
+                }
+            }
+        }
+        self.flush_side_outputs(&mut context)
+    }
+
+    fn handle_processing_timeout(&mut self, timeout_id: ScheduledTimer) -> Handled {
+        match self.processing_timeout {
+            Some(ref timeout) if *timeout == timeout_id => {
+                if let Err(error) = self.advance_processing_time() {
+                    error!(
+                        self.operator_context.borrow().logger,
+                        "Failed to advance processing time with error {:?}", error
+                    );
+                }
+                self.await_credits();
+
+                // stop driving processing time until the next timer is registered
+                let idle = {
+                    let context = self.operator_context.borrow();
+                    !context.processing_timer_scheduled
+                        && context.processing_timer.is_empty().unwrap_or(false)
+                };
+                if idle {
+                    if let Some(timeout) = self.processing_timeout.take() {
+                        self.cancel_timer(timeout);
+                    }
+                }
+            }
+            _ => (), // just ignore outdated timeouts
+        }
+        Handled::Ok
+    }
+
+    fn handle_throughput_timeout(&mut self, timeout_id: ScheduledTimer) -> Handled {
+        match (self.throughput_interval, &self.throughput_timeout) {
+            (Some(interval), Some(timeout)) if *timeout == timeout_id => {
+                let throughput = self.inbound_events as f64 * 1000.0 / interval as f64;
+                self.inbound_events = 0;
+                self.node_manager_port
+                    .trigger(NodeManagerEvent::Throughput(self.node_state.id, throughput));
+            }
+            _ => (), // just ignore outdated timeouts
+        }
+        Handled::Ok
+    }
+
+    /// Takes over the timers that other instances handed off to this one, if any are pending
+    fn take_over_timers(&mut self) -> ArconResult<()> {
+        if let Some(managed) = self.managed.as_mut() {
+            if managed.take_over {
+                managed.take_over = false;
+                self.operator_context
+                    .borrow_mut()
+                    .take_over_timers(managed.instance, managed.parallelism.max())?;
+            }
+        }
+        Ok(())
     }
 
     #[inline]
     fn complete_epoch(&mut self) -> ArconResult<()> {
         #[cfg(feature = "metrics")]
-        increment_counter!("epoch_counter", "node" => self.descriptor.clone());
+        {
+            increment_counter!("epoch_counter", "node" => self.descriptor.clone());
+            let ratio = self.flow_control.borrow_mut().backpressure_ratio();
+            gauge!("backpressure_ratio", ratio, "node" => self.descriptor.clone());
+        }
+
+        #[cfg(feature = "metrics")]
+        self.complete_alignment_metrics();
+
+        // Timers of the keys handed off at this epoch are in the backend once every instance has checkpointed
+        self.take_over_timers()?;
 
         // flush the blocked_channels list
         self.node_state.blocked_channels().clear();
@@ -416,17 +1137,66 @@ where
             "Started Arcon Node {} with Node ID {:?}", self.descriptor, self.node_state.id
         );
 
-        if self
+        if let Err(error) = self
             .operator
             .on_start(&mut self.operator_context.borrow_mut())
-            .is_err()
         {
             error!(
                 self.operator_context.borrow().logger,
-                "Failed to run startup code"
+                "Failed to run startup code with error {:?}", error
+            );
+        }
+
+        if let Err(error) = self.take_over_timers() {
+            error!(
+                self.operator_context.borrow().logger,
+                "Failed to take over timers with error {:?}", error
             );
         }
 
+        for message in std::mem::take(&mut self.restored_channel_state) {
+            if let Err(error) = self.handle_events(message.sender, message.events) {
+                error!(
+                    self.operator_context.borrow().logger,
+                    "Failed to replay channel state with error {:?}", error
+                );
+            }
+        }
+
+        // timers restored from a snapshot are driven from the start
+        let restored_timers = match self.operator_context.borrow().processing_timer.is_empty() {
+            Ok(empty) => !empty,
+            Err(error) => {
+                error!(
+                    self.operator_context.borrow().logger,
+                    "Failed to read processing-time timers with error {:?}", error
+                );
+                false
+            }
+        };
+        if restored_timers {
+            self.operator_context
+                .borrow_mut()
+                .processing_timer_scheduled = true;
+        }
+        self.await_processing_timers();
+
+        if let Some(interval) = self.throughput_interval {
+            let interval = std::time::Duration::from_millis(interval);
+            let timeout =
+                self.schedule_periodic(interval, interval, Self::handle_throughput_timeout);
+            self.throughput_timeout = Some(timeout);
+        }
+
+        Handled::Ok
+    }
+    fn on_stop(&mut self) -> Handled {
+        if let Some(timeout) = self.processing_timeout.take() {
+            self.cancel_timer(timeout);
+        }
+        if let Some(timeout) = self.throughput_timeout.take() {
+            self.cancel_timer(timeout);
+        }
         Handled::Ok
     }
 }
@@ -439,12 +1209,38 @@ where
     fn handle(&mut self, event: NodeEvent) -> Handled {
         match event {
             NodeEvent::CheckpointResponse(_) => {
-                if let Err(error) = self.complete_epoch() {
+                let result = if self.unaligned_checkpoints {
+                    self.resume_unaligned_epoch()
+                } else {
+                    self.complete_epoch()
+                };
+                if let Err(error) = result {
                     error!(
                         self.operator_context.borrow().logger,
                         "Failed to complete epoch with error {:?}", error
                     );
                 }
+                self.await_credits();
+                self.await_processing_timers();
+            }
+            NodeEvent::StartEpoch(epoch) => {
+                if let Err(error) = self.handle_epoch_request(epoch) {
+                    error!(
+                        self.operator_context.borrow().logger,
+                        "Failed to start epoch {:?} with error {:?}", epoch, error
+                    );
+                }
+            }
+            NodeEvent::EpochCommit(epoch) => {
+                let mut context = self.operator_context.borrow_mut();
+                if let Err(error) = self.operator.handle_epoch_commit(epoch.epoch, &mut context) {
+                    error!(
+                        context.logger,
+                        "Failed to commit epoch {:?} with error {:?}", epoch, error
+                    );
+                }
+                drop(context);
+                self.await_processing_timers();
             }
         }
         Handled::Ok
@@ -474,15 +1270,22 @@ where
     type Message = ArconMessage<OP::IN>;
 
     fn receive_local(&mut self, msg: Self::Message) -> Handled {
-        if let Err(err) = self.handle_message(MessageContainer::Local(msg)) {
-            error!(
-                self.operator_context.borrow().logger,
-                "Failed to handle message: {}", err
-            );
-        }
+        self.receive(MessageContainer::Local(msg));
         Handled::Ok
     }
     fn receive_network(&mut self, msg: NetMessage) -> Handled {
+        if *msg.ser_id() == CreditGrant::SER_ID {
+            match msg.try_deserialise::<CreditGrant, CreditGrant>() {
+                Ok(grant) => self.handle_credit_grant(grant),
+                Err(e) => error!(
+                    self.operator_context.borrow().logger,
+                    "Failed to unpack CreditGrant: {:?}", e
+                ),
+            }
+            return Handled::Ok;
+        }
+        // credits are granted back to the alias of the sender on its process
+        let sender_system = msg.sender.system().clone();
         let arcon_msg = match *msg.ser_id() {
             id if id == OP::IN::RELIABLE_SER_ID => msg
                 .try_deserialise::<RawArconMessage<OP::IN>, ReliableSerde<OP::IN>>()
@@ -500,12 +1303,9 @@ where
 
         match arcon_msg {
             Ok(m) => {
-                if let Err(err) = self.handle_message(MessageContainer::Raw(m)) {
-                    error!(
-                        self.operator_context.borrow().logger,
-                        "Failed to handle node message: {}", err
-                    );
-                }
+                let path = NamedPath::with_system(sender_system, vec![node_alias(m.sender)]);
+                self.remote_senders.insert(m.sender, path.into());
+                self.receive(MessageContainer::Raw(m))
             }
             Err(e) => error!(
                 self.operator_context.borrow().logger,
@@ -525,18 +1325,33 @@ mod tests {
     #[cfg(not(test))]
     use crate::metrics::perf_event::HardwareCounter;
     use crate::{
-        application::*,
-        dataflow::api::OperatorBuilder,
+        application::{
+            restore::{channel_state_id, checkpoint_name, is_complete},
+            *,
+        },
+        dataflow::{api::OperatorBuilder, conf::default_key_extractor},
         index::EmptyState,
         stream::{
             channel::{strategy::forward::Forward, Channel},
             node::debug::DebugNode,
             operator::function::Filter,
+            time::ArconTime,
         },
     };
-    use std::{sync::Arc, thread, time};
+    use std::{path::PathBuf, sync::Arc, thread, time};
 
     fn node_test_setup() -> (ActorRef<ArconMessage<i32>>, Arc<Component<DebugNode<i32>>>) {
+        let (node_ref, sink, _) = node_test_setup_with(false);
+        (node_ref, sink)
+    }
+
+    fn node_test_setup_with(
+        unaligned: bool,
+    ) -> (
+        ActorRef<ArconMessage<i32>>,
+        Arc<Component<DebugNode<i32>>>,
+        PathBuf,
+    ) {
         fn filter_fn(x: &i32) -> bool {
             *x >= 0
         }
@@ -547,90 +1362,107 @@ mod tests {
             conf: Default::default(),
         };
 
-        fn setup<OP: Operator<IN = i32, OUT = i32> + 'static, B: Backend>(
-            builder: OperatorBuilder<OP, B>,
-        ) -> (ActorRef<ArconMessage<i32>>, Arc<Component<DebugNode<i32>>>) {
-            // Returns a filter Node with input channels: sender1..sender3
-            // And a debug sink receiving its results
-            let mut app = Application::default();
-            let pool_info = app.get_pool_info();
-            let epoch_manager_ref = app.epoch_manager();
-
-            let sink = app.data_system().create(DebugNode::<i32>::new);
-
-            app.data_system()
-                .start_notify(&sink)
-                .wait_timeout(std::time::Duration::from_millis(100))
-                .expect("started");
-
-            // Construct Channel to the Debug sink
-            let actor_ref: ActorRefStrong<ArconMessage<i32>> =
-                sink.actor_ref().hold().expect("Failed to fetch");
-            let channel = Channel::Local(actor_ref);
-            let channel_strategy: ChannelStrategy<i32> =
-                ChannelStrategy::Forward(Forward::new(channel, NodeID::new(0), pool_info));
-
-            // Set up  NodeManager
-            let backend = Arc::new(crate::test_utils::temp_backend::<B>());
-            let descriptor = String::from("node_");
-            let in_channels = vec![1.into(), 2.into(), 3.into()];
-
-            let operator = builder.operator.clone();
-            let operator_state = builder.state.clone();
+        let (node, sink, checkpoints_dir) = setup(builder, unaligned);
+        (node.actor_ref(), sink, checkpoints_dir)
+    }
 
-            #[cfg(not(test))]
-            let mut perf_events = PerfEvents::new();
-
-            let nm = NodeManager::<OP, B>::new(
-                descriptor.clone(),
-                app.data_system.clone(),
-                epoch_manager_ref,
-                in_channels.clone(),
-                backend.clone(),
-                app.arcon_logger.clone(),
-                builder,
-            );
-            let node_manager_comp = app.ctrl_system().create(|| nm);
-
-            app.ctrl_system()
-                .start_notify(&node_manager_comp)
-                .wait_timeout(std::time::Duration::from_millis(100))
-                .expect("started");
-
-            let node = Node::<OP, _>::new(
-                descriptor,
-                channel_strategy,
-                operator(),
-                operator_state(backend.clone()),
-                NodeState::new(NodeID::new(0), in_channels, backend.clone()),
-                backend,
-                app.arcon_logger.clone(),
-                #[cfg(not(test))]
-                perf_events,
-            );
+    fn setup<OP: Operator<IN = i32, OUT = i32> + 'static, B: Backend>(
+        builder: OperatorBuilder<OP, B>,
+        unaligned: bool,
+    ) -> (
+        Arc<Component<Node<OP, B>>>,
+        Arc<Component<DebugNode<i32>>>,
+        PathBuf,
+    ) {
+        // Returns a filter Node with input channels: sender1..sender3
+        // And a debug sink receiving its results
+        let mut app = Application::default();
+        let checkpoints_dir = app.conf.checkpoints_dir();
+        let pool_info = app.get_pool_info();
+        let epoch_manager_ref = app.epoch_manager();
+
+        let sink = app.data_system().create(DebugNode::<i32>::new);
+
+        app.data_system()
+            .start_notify(&sink)
+            .wait_timeout(std::time::Duration::from_millis(100))
+            .expect("started");
+
+        // Construct Channel to the Debug sink
+        let actor_ref: ActorRefStrong<ArconMessage<i32>> =
+            sink.actor_ref().hold().expect("Failed to fetch");
+        let channel = Channel::Local(actor_ref);
+        let channel_strategy: ChannelStrategy<i32> =
+            ChannelStrategy::Forward(Forward::new(channel, NodeID::new(0), pool_info));
+
+        // Set up  NodeManager
+        let backend = Arc::new(crate::test_utils::temp_backend::<B>());
+        let descriptor = String::from("node_");
+        let in_channels = vec![1.into(), 2.into(), 3.into()];
+
+        let operator = builder.operator.clone();
+        let operator_state = builder.state.clone();
 
-            let filter_comp = app.data_system().create(|| node);
-            let required_ref = filter_comp.on_definition(|cd| cd.node_manager_port.share());
+        #[cfg(not(test))]
+        let mut perf_events = PerfEvents::new();
 
-            biconnect_components::<NodeManagerPort, _, _>(&node_manager_comp, &filter_comp)
-                .expect("connection");
+        let nm = NodeManager::<OP, B>::new(
+            descriptor.clone(),
+            app.data_system.clone(),
+            epoch_manager_ref,
+            in_channels.clone(),
+            backend.clone(),
+            app.arcon_logger.clone(),
+            builder,
+        );
+        let nm = if unaligned {
+            nm.with_unaligned_checkpoints()
+        } else {
+            nm
+        };
+        let node_manager_comp = app.ctrl_system().create(|| nm);
 
-            app.data_system()
-                .start_notify(&filter_comp)
-                .wait_timeout(std::time::Duration::from_millis(100))
-                .expect("started");
+        app.ctrl_system()
+            .start_notify(&node_manager_comp)
+            .wait_timeout(std::time::Duration::from_millis(100))
+            .expect("started");
 
-            let filter_ref = filter_comp.actor_ref();
+        let node = Node::<OP, _>::new(
+            descriptor,
+            channel_strategy,
+            operator(),
+            operator_state(backend.clone()),
+            default_key_extractor(),
+            NodeState::new(NodeID::new(0), in_channels, backend.clone()),
+            backend,
+            app.arcon_logger.clone(),
+            #[cfg(not(test))]
+            perf_events,
+        );
+        let node = if unaligned {
+            node.with_unaligned_checkpoints()
+        } else {
+            node
+        };
 
-            node_manager_comp.on_definition(|cd| {
-                // Insert the created Node into the NodeManager
-                cd.nodes.insert(NodeID::new(0), (filter_comp, required_ref));
-            });
+        let filter_comp = app.data_system().create(|| node);
+        let required_ref = filter_comp.on_definition(|cd| cd.node_manager_port.share());
 
-            (filter_ref, sink)
-        }
+        biconnect_components::<NodeManagerPort, _, _>(&node_manager_comp, &filter_comp)
+            .expect("connection");
+
+        app.data_system()
+            .start_notify(&filter_comp)
+            .wait_timeout(std::time::Duration::from_millis(100))
+            .expect("started");
 
-        setup(builder)
+        node_manager_comp.on_definition(|cd| {
+            // Insert the created Node into the NodeManager
+            cd.nodes
+                .insert(NodeID::new(0), (filter_comp.clone(), required_ref));
+        });
+
+        (filter_comp, sink, checkpoints_dir)
     }
 
     fn watermark(time: u64, sender: u32) -> ArconMessage<i32> {
@@ -699,6 +1531,37 @@ mod tests {
         });
     }
 
+    #[test]
+    fn node_late_elements() {
+        fn filter_fn(x: &i32) -> bool {
+            *x >= 0
+        }
+        let builder = OperatorBuilder::<_> {
+            operator: Arc::new(|| Filter::new(&filter_fn)),
+            state: Arc::new(|_backend| EmptyState),
+            conf: Default::default(),
+        };
+        let (node, sink, _) = setup(builder, false);
+        let node_ref = node.actor_ref();
+
+        node_ref.tell(watermark(5, 1));
+        // at or below the watermark of its sender
+        node_ref.tell(element(1, 5, 1));
+        node_ref.tell(element(2, 6, 1));
+        // the watermark of sender 2 has not advanced
+        node_ref.tell(element(3, 1, 2));
+
+        assert!(crate::test_utils::wait_until(
+            time::Duration::from_secs(10),
+            || sink.on_definition(|cd| cd.data.len() == 2)
+        ));
+        node.on_definition(|cd| assert_eq!(cd.late_records(), 1));
+        sink.on_definition(|cd| {
+            let data: Vec<i32> = cd.data.iter().map(|e| e.data).collect();
+            assert_eq!(data, vec![2, 3]);
+        });
+    }
+
     #[test]
     fn node_epoch_block() {
         let (node_ref, sink) = node_test_setup();
@@ -722,6 +1585,55 @@ mod tests {
         });
     }
 
+    #[test]
+    fn node_unaligned_epoch() {
+        let (node_ref, sink, checkpoints_dir) = node_test_setup_with(true);
+        node_ref.tell(element(1, 1, 1));
+        // starts the checkpoint and is forwarded right away
+        node_ref.tell(epoch(0, 1));
+        // not blocked
+        node_ref.tell(element(2, 1, 1));
+        // in flight on channels that have yet to deliver the epoch
+        node_ref.tell(element(3, 1, 2));
+        node_ref.tell(epoch(0, 2));
+        node_ref.tell(element(4, 1, 3));
+        node_ref.tell(epoch(0, 3));
+        // after the epoch on every channel
+        node_ref.tell(element(5, 1, 3));
+
+        let timeout = time::Duration::from_secs(5);
+        assert!(crate::test_utils::wait_until(timeout, || {
+            sink.on_definition(|cd| cd.data.len() == 5 && cd.epochs.len() == 1)
+        }));
+        sink.on_definition(|cd| {
+            let data: Vec<i32> = cd.data.iter().map(|e| e.data).collect();
+            assert_eq!(data, vec![1, 2, 3, 4, 5]);
+        });
+
+        // only the overtaken messages are persisted as channel state
+        let channels_dir = checkpoints_dir.join(checkpoint_name(&channel_state_id("node_"), 0));
+        assert!(crate::test_utils::wait_until(timeout, || is_complete(
+            &channels_dir
+        )));
+        let bytes = std::fs::read(channel_state_path(&channels_dir, NodeID::new(0))).unwrap();
+        let state: ChannelState<i32> = protobuf::deserialize(&bytes).unwrap();
+        let recorded: Vec<(u32, i32)> = state
+            .messages
+            .into_iter()
+            .flat_map(|message| {
+                let sender = message.sender.id;
+                message
+                    .events
+                    .into_iter()
+                    .map(move |event| match event.unwrap() {
+                        ArconEvent::Element(e) => (sender, e.data),
+                        _ => panic!("channel state holds only elements"),
+                    })
+            })
+            .collect();
+        assert_eq!(recorded, vec![(2, 3), (3, 4)]);
+    }
+
     #[test]
     fn node_epoch_no_continue() {
         let (node_ref, sink) = node_test_setup();
@@ -783,4 +1695,88 @@ mod tests {
             assert_eq!(data_len, 6);
         });
     }
+
+    #[derive(prost::Message, Clone)]
+    struct Flush {
+        #[prost(int32, tag = "1")]
+        value: i32,
+    }
+
+    // Holds back every element until a processing-time timer releases it
+    struct ProcessingTimeDelay;
+
+    impl Operator for ProcessingTimeDelay {
+        type IN = i32;
+        type OUT = i32;
+        type TimerState = Flush;
+        type OperatorState = EmptyState;
+        type ElementIterator = Option<ArconElement<Self::OUT>>;
+
+        fn handle_element(
+            &mut self,
+            element: ArconElement<i32>,
+            ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+        ) -> ArconResult<Self::ElementIterator> {
+            let value = element.data;
+            let delay = time::Duration::from_millis(50);
+            let _ = ctx.schedule_processing_after(value as u64, delay, Flush { value })?;
+            Ok(None)
+        }
+
+        fn handle_timeout(
+            &mut self,
+            timeout: Self::TimerState,
+            _ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+        ) -> ArconResult<Option<Self::ElementIterator>> {
+            Ok(Some(Some(ArconElement::new(timeout.value))))
+        }
+    }
+
+    #[test]
+    fn node_processing_time_timers() {
+        let mut app = Application::default()
+            .with_debug_node()
+            .iterator(0..10, |conf| {
+                conf.set_arcon_time(ArconTime::Process);
+            })
+            .operator(OperatorBuilder {
+                operator: Arc::new(|| ProcessingTimeDelay),
+                state: Arc::new(|_| EmptyState),
+                conf: Default::default(),
+            })
+            .build();
+
+        app.start();
+        wait(1);
+
+        let debug_node = app.get_debug_node::<i32>().unwrap();
+        debug_node.on_definition(|cd| {
+            let sum: i32 = cd.data.iter().map(|elem| elem.data).sum();
+            assert_eq!(cd.data.len(), 10);
+            assert_eq!(sum, 45);
+        });
+    }
+
+    #[test]
+    fn node_processing_timer_on_demand() {
+        let builder = OperatorBuilder::<_> {
+            operator: Arc::new(|| ProcessingTimeDelay),
+            state: Arc::new(|_backend| EmptyState),
+            conf: Default::default(),
+        };
+        let (node, sink, _) = setup(builder, false);
+        // no processing-time timer is registered yet
+        node.on_definition(|cd| assert!(cd.processing_timeout.is_none()));
+
+        node.actor_ref().tell(element(1, 1, 1));
+        assert!(crate::test_utils::wait_until(
+            time::Duration::from_secs(10),
+            || sink.on_definition(|cd| cd.data.len() == 1)
+        ));
+        // processing time is no longer driven once the timer has fired
+        assert!(crate::test_utils::wait_until(
+            time::Duration::from_secs(10),
+            || node.on_definition(|cd| cd.processing_timeout.is_none())
+        ));
+    }
 }
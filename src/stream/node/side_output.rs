@@ -0,0 +1,121 @@
+// Copyright (c) 2020, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use super::common;
+use crate::{
+    data::{ArconElement, ArconEvent, ArconType, Epoch, NodeID, Watermark},
+    error::ArconResult,
+    reportable_error,
+    stream::{
+        channel::{
+            flow_control::{CreditWaker, FlowControl},
+            strategy::ChannelStrategy,
+            Dispatcher,
+        },
+        operator::side_output::SideOutputKey,
+    },
+};
+use std::any::Any;
+
+/// Markers that a Node forwards to its side outputs next to the regular output
+#[derive(Clone)]
+pub enum ControlEvent {
+    Watermark(Watermark),
+    Epoch(Epoch),
+    Death(String),
+}
+
+/// A type-erased side output of a Node
+///
+/// Events are sent through the same flow control as the regular output of the Node.
+pub trait SideOutput: Send {
+    /// Returns the id and element type of the [OutputTag](crate::stream::operator::side_output::OutputTag)
+    fn key(&self) -> &SideOutputKey;
+    /// Push an element that has been emitted through `OperatorContext::output_to`
+    ///
+    /// The element is an `ArconElement` of the type of [SideOutput::key].
+    fn push_element(
+        &mut self,
+        element: Box<dyn Any + Send>,
+        source: &dyn Dispatcher,
+    ) -> ArconResult<()>;
+    /// Forward a marker of the regular output to the side output
+    fn push_control(&mut self, event: ControlEvent, source: &dyn Dispatcher) -> ArconResult<()>;
+    /// Sends the messages that were held back for lack of credits
+    fn flush(&mut self, source: &dyn Dispatcher) -> ArconResult<()>;
+    /// Returns true if messages are held back for lack of credits
+    fn is_backpressured(&self) -> bool;
+    /// Wakes `waker` whenever a channel of the side output is granted a credit
+    fn set_credit_waker(&mut self, waker: &CreditWaker);
+    /// Grants a credit to the channel of the Node `receiver` on another process
+    ///
+    /// Returns false if none of the channels leads to the Node.
+    fn grant(&mut self, receiver: NodeID) -> bool;
+}
+
+/// Side output that partitions its elements through a [ChannelStrategy]
+pub struct SideOutputChannel<T: ArconType> {
+    key: SideOutputKey,
+    strategy: ChannelStrategy<T>,
+    flow_control: FlowControl<T>,
+}
+
+impl<T: ArconType> SideOutputChannel<T> {
+    pub(crate) fn new(key: SideOutputKey, strategy: ChannelStrategy<T>, credits: usize) -> Self {
+        debug_assert!(key.is::<T>());
+        Self {
+            key,
+            strategy,
+            flow_control: FlowControl::new(credits),
+        }
+    }
+
+    fn push(&mut self, event: ArconEvent<T>, source: &dyn Dispatcher) -> ArconResult<()> {
+        common::add_outgoing_event(event, &mut self.strategy, &mut self.flow_control, source)
+    }
+}
+
+impl<T: ArconType> SideOutput for SideOutputChannel<T> {
+    fn key(&self) -> &SideOutputKey {
+        &self.key
+    }
+
+    fn push_element(
+        &mut self,
+        element: Box<dyn Any + Send>,
+        source: &dyn Dispatcher,
+    ) -> ArconResult<()> {
+        // The Node only routes elements of the tag type to this side output
+        match element.downcast::<ArconElement<T>>() {
+            Ok(element) => self.push(ArconEvent::Element(*element), source),
+            Err(_) => {
+                reportable_error!("Element of unexpected type for side output {:?}", self.key)
+            }
+        }
+    }
+
+    fn push_control(&mut self, event: ControlEvent, source: &dyn Dispatcher) -> ArconResult<()> {
+        match event {
+            ControlEvent::Watermark(w) => self.push(ArconEvent::Watermark(w), source),
+            ControlEvent::Epoch(e) => self.push(ArconEvent::Epoch(e), source),
+            ControlEvent::Death(s) => self.push(ArconEvent::Death(s), source),
+        }
+    }
+
+    fn flush(&mut self, source: &dyn Dispatcher) -> ArconResult<()> {
+        common::flush_outgoing(&mut self.strategy, &mut self.flow_control, source)
+    }
+
+    fn is_backpressured(&self) -> bool {
+        common::is_backpressured(&self.strategy, &self.flow_control)
+    }
+
+    fn set_credit_waker(&mut self, waker: &CreditWaker) {
+        self.flow_control.set_waker(waker);
+        self.strategy.set_credit_waker(waker);
+    }
+
+    fn grant(&mut self, receiver: NodeID) -> bool {
+        self.flow_control.grant(receiver)
+    }
+}
@@ -8,12 +8,15 @@ use crate::metrics::runtime_metrics::SourceMetrics;
 
 use crate::{
     application::conf::logger::ArconLogger,
-    data::{ArconElement, ArconEvent, Epoch, Watermark},
+    data::{ArconElement, ArconEvent, Epoch, NodeID, Watermark},
     error::{source::SourceError, ArconResult, Error},
     manager::source::{SourceManagerEvent, SourceManagerPort},
     prelude::SourceConf,
     stream::{
-        channel::strategy::ChannelStrategy,
+        channel::{
+            flow_control::{CreditGrant, CreditWaker, FlowControl},
+            strategy::ChannelStrategy,
+        },
         source::{Poll, Source},
         time::ArconTime,
     },
@@ -29,6 +32,12 @@ pub enum SourceEvent {
     Epoch(Epoch),
     Watermark(ArconTime),
     Start,
+    /// Stop polling the source, while still forwarding epochs and watermarks
+    Stop,
+    /// Forward the epoch and stop polling the source right after it
+    StopAfter(Epoch),
+    /// Stop polling the source and forward a final watermark that fires every event-time timer
+    Drain,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -51,10 +60,19 @@ where
     loopback_receive: ProvidedPort<LoopbackPort>,
     watermark: u64,
     ended: bool,
+    stopped: bool,
     channel_strategy: RefCell<ChannelStrategy<S::Item>>,
+    /// Flow control of the outgoing channels
+    flow_control: RefCell<FlowControl<S::Item>>,
+    /// Woken whenever an output of the source is granted a credit
+    credit_waker: CreditWaker,
+    /// Set while the source waits to be granted credits
+    awaiting_credits: bool,
     conf: SourceConf<S::Item>,
     source_index: usize,
     source: S,
+    /// Number of records consumed from the source
+    offset: u64,
     logger: ArconLogger,
 
     #[cfg(feature = "metrics")]
@@ -70,7 +88,7 @@ where
         source_index: usize,
         source: S,
         conf: SourceConf<S::Item>,
-        channel_strategy: ChannelStrategy<S::Item>,
+        mut channel_strategy: ChannelStrategy<S::Item>,
         logger: ArconLogger,
     ) -> Self {
         let borrowed_source_name: &str = &conf.name.clone();
@@ -79,19 +97,28 @@ where
         {
             register_gauge!("incoming_message_rate", "source" => conf.name.clone());
             register_counter!("error_counter", "source" => conf.name.clone());
+            register_gauge!("backpressure_ratio", "source" => conf.name.clone());
         }
 
+        let credit_waker = CreditWaker::default();
+        channel_strategy.set_credit_waker(&credit_waker);
+
         Self {
             ctx: ComponentContext::uninitialised(),
             manager_port: RequiredPort::uninitialised(),
             loopback_send: RequiredPort::uninitialised(),
             loopback_receive: ProvidedPort::uninitialised(),
             channel_strategy: RefCell::new(channel_strategy),
+            flow_control: RefCell::new(FlowControl::new(0)),
+            credit_waker,
+            awaiting_credits: false,
             ended: false,
+            stopped: false,
             watermark: 0,
             conf,
             source_index,
             source,
+            offset: 0,
             logger,
 
             #[cfg(feature = "metrics")]
@@ -100,11 +127,27 @@ where
             descriptor: String::from(borrowed_source_name),
         }
     }
+    /// Resume the source from `offset`
+    pub(crate) fn with_offset(mut self, offset: u64) -> Self {
+        self.source.set_offset(offset as usize);
+        self.offset = offset;
+        self
+    }
+
+    /// Grant every outgoing channel `credits` messages in flight, zero disables flow control
+    pub(crate) fn with_channel_credits(self, credits: usize) -> Self {
+        let mut flow_control = FlowControl::new(credits);
+        flow_control.set_waker(&self.credit_waker);
+        self.flow_control.replace(flow_control);
+        self
+    }
+
     pub fn process(&mut self) -> ArconResult<usize> {
         let mut counter = 0;
 
         loop {
-            if counter >= self.conf.batch_size {
+            // pause once the channels are out of credits
+            if counter >= self.conf.batch_size || self.is_backpressured() {
                 return Ok(counter);
             }
 
@@ -127,6 +170,7 @@ where
                         }
                     }
                     counter += 1;
+                    self.offset += 1;
                 }
                 Ok(Poll::Pending) => {
                     // nothing to collect, reschedule...
@@ -144,6 +188,7 @@ where
                     match self.handle_source_error(error) {
                         Ok(_) => {
                             counter += 1;
+                            self.offset += 1;
                         }
                         Err(err) => {
                             return Err(err);
@@ -185,7 +230,52 @@ where
     #[inline(always)]
     fn send_event(&mut self, event: ArconEvent<S::Item>) -> ArconResult<()> {
         let mut strategy = self.channel_strategy.borrow_mut();
-        super::common::add_outgoing_event(event, &mut strategy, self)
+        let mut flow_control = self.flow_control.borrow_mut();
+        super::common::add_outgoing_event(event, &mut strategy, &mut flow_control, self)
+    }
+
+    /// Returns true if the source or one of its chained Operators holds back messages
+    #[inline]
+    fn is_backpressured(&self) -> bool {
+        super::common::is_backpressured(
+            &self.channel_strategy.borrow(),
+            &self.flow_control.borrow(),
+        )
+    }
+
+    /// Sends the messages that were held back for channels that have been granted credits
+    fn flush_outgoing(&self) -> ArconResult<()> {
+        let mut strategy = self.channel_strategy.borrow_mut();
+        let mut flow_control = self.flow_control.borrow_mut();
+        super::common::flush_outgoing(&mut strategy, &mut flow_control, self)
+    }
+
+    /// Resumes the source once it has been granted credits, if it is backpressured
+    fn await_credits(&mut self) {
+        if !self.awaiting_credits && self.is_backpressured() {
+            self.awaiting_credits = true;
+            let waker = self.credit_waker.clone();
+            self.spawn_local(move |mut async_self| async move {
+                waker.granted().await;
+                async_self.awaiting_credits = false;
+                async_self.on_credits();
+                Handled::Ok
+            });
+        }
+    }
+
+    /// Continues polling the source, or sending its held back markers, once credits have been granted
+    fn on_credits(&mut self) {
+        if self.stopped || self.ended {
+            // only the markers that were held back are left to send
+            let result = self.flush_outgoing();
+            if let Err(error) = result {
+                self.source_shutdown(error);
+            }
+            self.await_credits();
+        } else {
+            self.loopback_send.trigger(ProcessSource);
+        }
     }
 
     #[inline(always)]
@@ -196,6 +286,12 @@ where
     pub fn handle_source_event(&mut self, event: SourceEvent) -> ArconResult<()> {
         match event {
             SourceEvent::Epoch(epoch) => {
+                // Every record before the marker belongs to the epoch
+                self.manager_port.trigger(SourceManagerEvent::Offset(
+                    self.source_index,
+                    epoch,
+                    self.offset,
+                ));
                 self.send_event(ArconEvent::Epoch(epoch))?;
             }
             SourceEvent::Watermark(time) => {
@@ -212,10 +308,28 @@ where
 
                 // send watermark downstream
                 self.send_event(ArconEvent::Watermark(wm))?;
+
+                #[cfg(feature = "metrics")]
+                {
+                    let ratio = self.flow_control.borrow_mut().backpressure_ratio();
+                    gauge!("backpressure_ratio", ratio, "source" => self.descriptor.clone());
+                }
             }
             SourceEvent::Start => {
                 self.loopback_send.trigger(ProcessSource);
             }
+            SourceEvent::Stop => {
+                self.stopped = true;
+            }
+            SourceEvent::StopAfter(epoch) => {
+                self.handle_source_event(SourceEvent::Epoch(epoch))?;
+                self.stopped = true;
+            }
+            SourceEvent::Drain => {
+                self.stopped = true;
+                self.update_watermark(u64::MAX);
+                self.send_event(ArconEvent::Watermark(Watermark::new(u64::MAX)))?;
+            }
         }
         Ok(())
     }
@@ -247,6 +361,18 @@ where
     S: Source,
 {
     fn handle(&mut self, _event: ProcessSource) -> Handled {
+        if self.stopped {
+            return Handled::Ok;
+        }
+        let result = self.flush_outgoing();
+        if let Err(error) = result {
+            self.source_shutdown(error);
+        }
+        if self.is_backpressured() {
+            // poll again once the channels have been granted credits
+            self.await_credits();
+            return Handled::Ok;
+        }
         match self.process() {
             #[cfg(not(feature = "metrics"))]
             Ok(_) => (),
@@ -264,6 +390,9 @@ where
         }
         if self.ended {
             self.manager_port.trigger(SourceManagerEvent::End);
+            self.await_credits();
+        } else if self.is_backpressured() {
+            self.await_credits();
         } else {
             self.loopback_send.trigger(ProcessSource);
         }
@@ -299,9 +428,24 @@ where
         if let Err(err) = self.handle_source_event(msg) {
             self.source_shutdown(err);
         }
+        self.await_credits();
         Handled::Ok
     }
-    fn receive_network(&mut self, _: NetMessage) -> Handled {
+    fn receive_network(&mut self, msg: NetMessage) -> Handled {
+        // Nodes on other processes grant credits back to the source, which wakes it
+        match msg.try_deserialise::<CreditGrant, CreditGrant>() {
+            Ok(grant) => {
+                let receiver = NodeID::new(grant.receiver);
+                if !self.flow_control.borrow_mut().grant(receiver) {
+                    error!(
+                        self.logger,
+                        "Received credit from {:?}, which is not a receiver of the source",
+                        receiver
+                    );
+                }
+            }
+            Err(error) => error!(self.logger, "Failed to unpack CreditGrant: {:?}", error),
+        }
         Handled::Ok
     }
 }
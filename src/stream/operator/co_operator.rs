@@ -0,0 +1,167 @@
+// Copyright (c) 2020, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{
+    data::{ArconElement, ArconType, CoElement, CoInput},
+    error::*,
+    index::ArconState,
+    stream::operator::{Operator, OperatorContext},
+};
+use prost::Message;
+
+/// Defines the methods a two-input `CoOperator` must implement
+///
+/// A CoOperator consumes the two streams of a [ConnectedStreams](crate::prelude::ConnectedStreams).
+/// Watermarks and epochs are aligned across both inputs before they reach the operator.
+pub trait CoOperator: Send + Sized {
+    /// The type of elements of the first input stream
+    type IN1: ArconType;
+    /// The type of elements of the second input stream
+    type IN2: ArconType;
+    /// The type of output elements this operator produces
+    type OUT: ArconType;
+    /// Storage state type for timer facilities
+    type TimerState: Message + Clone + Default;
+    /// State type for the CoOperator
+    type OperatorState: ArconState;
+    /// Iterator that produces outgoing elements
+    type ElementIterator: IntoIterator<Item = ArconElement<Self::OUT>> + 'static;
+
+    /// Determines what the `CoOperator` runs before beginning to process Elements
+    fn on_start(
+        &mut self,
+        _ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<()> {
+        Ok(())
+    }
+
+    /// Determines how the `CoOperator` processes Elements of the first stream
+    fn handle_element1(
+        &mut self,
+        element: ArconElement<Self::IN1>,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator>;
+
+    /// Determines how the `CoOperator` processes Elements of the second stream
+    fn handle_element2(
+        &mut self,
+        element: ArconElement<Self::IN2>,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator>;
+
+    /// Determines how the `CoOperator` handles timeouts it registered earlier when they are triggered
+    fn handle_timeout(
+        &mut self,
+        timeout: Self::TimerState,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Option<Self::ElementIterator>>;
+
+    /// Determines how the `CoOperator` reacts to an advanced watermark
+    ///
+    /// The watermark is the minimum of the watermarks of both inputs and is
+    /// delivered after all timeouts up to it have been handled.
+    fn handle_watermark(
+        &mut self,
+        _watermark: u64,
+        _ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Option<Self::ElementIterator>> {
+        Ok(None)
+    }
+
+    /// Determines what the `CoOperator` does when a checkpoint of `epoch` is taken
+    ///
+    /// Called once the epoch barrier has arrived on both inputs, before the state of the
+    /// `CoOperator` is persisted.
+    fn handle_epoch(
+        &mut self,
+        _epoch: u64,
+        _ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<()> {
+        Ok(())
+    }
+
+    /// Determines what the `CoOperator` does once `epoch` has been committed
+    ///
+    /// See [Operator::handle_epoch_commit].
+    fn handle_epoch_commit(
+        &mut self,
+        _epoch: u64,
+        _ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<()> {
+        Ok(())
+    }
+}
+
+/// Runs a [CoOperator] as a regular [Operator] over [CoElement]s
+pub struct CoOperatorAdapter<C: CoOperator> {
+    co_operator: C,
+}
+
+impl<C: CoOperator> CoOperatorAdapter<C> {
+    pub fn new(co_operator: C) -> Self {
+        Self { co_operator }
+    }
+}
+
+impl<C: CoOperator> Operator for CoOperatorAdapter<C> {
+    type IN = CoElement<C::IN1, C::IN2>;
+    type OUT = C::OUT;
+    type TimerState = C::TimerState;
+    type OperatorState = C::OperatorState;
+    type ElementIterator = C::ElementIterator;
+
+    fn on_start(
+        &mut self,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<()> {
+        self.co_operator.on_start(ctx)
+    }
+
+    fn handle_element(
+        &mut self,
+        element: ArconElement<Self::IN>,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator> {
+        let timestamp = element.timestamp;
+        match element.data.unwrap() {
+            CoInput::First(data) => self
+                .co_operator
+                .handle_element1(ArconElement::with_timestamp(data, timestamp), ctx),
+            CoInput::Second(data) => self
+                .co_operator
+                .handle_element2(ArconElement::with_timestamp(data, timestamp), ctx),
+        }
+    }
+
+    fn handle_timeout(
+        &mut self,
+        timeout: Self::TimerState,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Option<Self::ElementIterator>> {
+        self.co_operator.handle_timeout(timeout, ctx)
+    }
+
+    fn handle_watermark(
+        &mut self,
+        watermark: u64,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Option<Self::ElementIterator>> {
+        self.co_operator.handle_watermark(watermark, ctx)
+    }
+
+    fn handle_epoch(
+        &mut self,
+        epoch: u64,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<()> {
+        self.co_operator.handle_epoch(epoch, ctx)
+    }
+
+    fn handle_epoch_commit(
+        &mut self,
+        epoch: u64,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<()> {
+        self.co_operator.handle_epoch_commit(epoch, ctx)
+    }
+}
@@ -0,0 +1,659 @@
+// Copyright (c) 2020, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{
+    data::{ArconElement, ArconType},
+    dataflow::{
+        api::Assigner,
+        conf::{IntervalJoinConf, StreamKind, WindowConf},
+    },
+    error::*,
+    index::{EagerHashTable, IndexOps},
+    stream::operator::{
+        co_operator::CoOperator,
+        window::{assigner::WindowEvent, WindowContext},
+        OperatorContext,
+    },
+    table::ImmutableTable,
+    util::ArconFnBounds,
+};
+use arcon_macros::ArconState;
+use arcon_state::{backend::handles::ActiveHandle, data::Value, Backend, Handle, VecState};
+use kompact::prelude::error;
+use prost::Message;
+use std::{marker::PhantomData, sync::Arc};
+
+/// Buffer of elements waiting to be joined
+///
+/// Elements are kept in a backend-managed VecState scoped by the key and index of a [WindowContext].
+pub struct JoinBuffer<V, B>
+where
+    V: Value,
+    B: Backend,
+{
+    handle: ActiveHandle<B, VecState<V>, u64, u64>,
+}
+
+impl<V, B> JoinBuffer<V, B>
+where
+    V: Value,
+    B: Backend,
+{
+    pub fn new(id: impl Into<String>, backend: Arc<B>) -> Self {
+        let mut handle = Handle::vec(id.into()).with_item_key(0).with_namespace(0);
+
+        backend.register_vec_handle(&mut handle);
+
+        let handle = handle.activate(backend);
+
+        Self { handle }
+    }
+
+    #[inline]
+    fn scope(&mut self, ctx: WindowContext) {
+        self.handle.set_item_key(ctx.key);
+        self.handle.set_namespace(ctx.index);
+    }
+
+    /// Buffer an element
+    pub fn append(&mut self, ctx: WindowContext, value: V) -> ArconResult<()> {
+        self.scope(ctx);
+        self.handle.append(value)?;
+        Ok(())
+    }
+
+    /// Returns all buffered elements
+    pub fn get(&mut self, ctx: WindowContext) -> ArconResult<Vec<V>> {
+        self.scope(ctx);
+        Ok(self.handle.get()?)
+    }
+
+    /// Removes all buffered elements
+    pub fn clear(&mut self, ctx: WindowContext) -> ArconResult<()> {
+        self.scope(ctx);
+        self.handle.clear()?;
+        Ok(())
+    }
+}
+
+impl<V, B> IndexOps for JoinBuffer<V, B>
+where
+    V: Value,
+    B: Backend,
+{
+    fn persist(&mut self) -> ArconResult<()> {
+        Ok(())
+    }
+    fn set_key(&mut self, _: u64) {}
+    fn table(&mut self) -> ArconResult<Option<ImmutableTable>> {
+        Ok(None)
+    }
+}
+
+#[derive(ArconState)]
+pub struct WindowJoinState<IN1: ArconType, IN2: ArconType, B: Backend> {
+    left: JoinBuffer<IN1, B>,
+    right: JoinBuffer<IN2, B>,
+    active_windows: EagerHashTable<WindowContext, (), B>,
+}
+
+impl<IN1: ArconType, IN2: ArconType, B: Backend> WindowJoinState<IN1, IN2, B> {
+    pub fn new(backend: Arc<B>) -> Self {
+        Self {
+            left: JoinBuffer::new("_window_join_left", backend.clone()),
+            right: JoinBuffer::new("_window_join_right", backend.clone()),
+            active_windows: EagerHashTable::new("_window_join_active_windows", backend),
+        }
+    }
+}
+
+/// Event-time Window Join
+///
+/// Buffers the elements of both inputs per key and window. When a window closes,
+/// every pair of elements from the two sides is passed to the join function.
+/// Windows are aligned to the epoch so that both inputs agree on their boundaries.
+pub struct WindowJoin<IN1, IN2, OUT, F, B>
+where
+    IN1: ArconType,
+    IN2: ArconType,
+    OUT: ArconType,
+    F: Fn(&IN1, &IN2) -> OUT + ArconFnBounds,
+    B: Backend,
+{
+    window_length: u64,
+    window_slide: u64,
+    late_arrival_time: u64,
+    keyed: bool,
+    join_fn: F,
+    _marker: PhantomData<(IN1, IN2, OUT, B)>,
+}
+
+impl<IN1, IN2, OUT, F, B> WindowJoin<IN1, IN2, OUT, F, B>
+where
+    IN1: ArconType,
+    IN2: ArconType,
+    OUT: ArconType,
+    F: Fn(&IN1, &IN2) -> OUT + ArconFnBounds,
+    B: Backend,
+{
+    pub fn new(conf: WindowConf, join_fn: F) -> Self {
+        let keyed = matches!(conf.kind, StreamKind::Keyed);
+        let (length, slide, late) = match conf.assigner {
+            Assigner::Sliding {
+                length,
+                slide,
+                late_arrival,
+            } => (length.0, slide.0, late_arrival.0),
+            Assigner::Tumbling {
+                length,
+                late_arrival,
+            } => (length.0, length.0, late_arrival.0),
+            Assigner::Session { .. } | Assigner::Global | Assigner::Count { .. } => {
+                panic!("Only sliding and tumbling windows are supported for joins")
+            }
+        };
+
+        // Sanity check on slide and length
+        if length < slide {
+            panic!("Window Length lower than slide!");
+        }
+        if length % slide != 0 {
+            panic!("Window Length not divisible by slide!");
+        }
+
+        Self {
+            window_length: length,
+            window_slide: slide,
+            late_arrival_time: late,
+            keyed,
+            join_fn,
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn window_end(&self, index: u64) -> u64 {
+        index * self.window_slide + self.window_length
+    }
+
+    /// Returns the indexes of the windows an element with timestamp `ts` belongs to
+    /// and that have not been triggered yet.
+    #[inline]
+    fn assign_windows(&self, ts: u64, time: u64) -> Vec<u64> {
+        let last = ts / self.window_slide;
+        let first = if ts >= self.window_length {
+            (ts - self.window_length) / self.window_slide + 1
+        } else {
+            0
+        };
+        (first..=last)
+            .filter(|index| self.window_end(*index) + self.late_arrival_time > time)
+            .collect()
+    }
+
+    #[inline]
+    fn register_window(
+        &mut self,
+        window_ctx: WindowContext,
+        ctx: &mut OperatorContext<WindowEvent, WindowJoinState<IN1, IN2, B>>,
+    ) -> ArconResult<()> {
+        if ctx.state().active_windows().contains(&window_ctx)? {
+            return Ok(());
+        }
+        ctx.state().active_windows().put(window_ctx, ())?;
+
+        let ts = self.window_end(window_ctx.index);
+        let request = ctx.schedule_at(
+            window_ctx,
+            ts + self.late_arrival_time,
+            WindowEvent::new(window_ctx.key, window_ctx.index, ts),
+        )?;
+
+        if let Err(expired) = request {
+            // For now just log the error..
+            error!(ctx.log(), "{}", expired);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn get_key(&self, ctx: &OperatorContext<WindowEvent, WindowJoinState<IN1, IN2, B>>) -> u64 {
+        if !self.keyed {
+            return 0;
+        }
+        ctx.current_key()
+    }
+}
+
+impl<IN1, IN2, OUT, F, B> CoOperator for WindowJoin<IN1, IN2, OUT, F, B>
+where
+    IN1: ArconType,
+    IN2: ArconType,
+    OUT: ArconType,
+    F: Fn(&IN1, &IN2) -> OUT + ArconFnBounds,
+    B: Backend,
+{
+    type IN1 = IN1;
+    type IN2 = IN2;
+    type OUT = OUT;
+    type TimerState = WindowEvent;
+    type OperatorState = WindowJoinState<IN1, IN2, B>;
+    type ElementIterator = Vec<ArconElement<OUT>>;
+
+    fn handle_element1(
+        &mut self,
+        element: ArconElement<IN1>,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator> {
+        let key = self.get_key(ctx);
+        for index in self.assign_windows(element.timestamp, ctx.current_time()?) {
+            let window_ctx = WindowContext::new(key, index);
+            ctx.state()
+                .left()
+                .append(window_ctx, element.data.clone())?;
+            self.register_window(window_ctx, ctx)?;
+        }
+        Ok(Vec::new())
+    }
+
+    fn handle_element2(
+        &mut self,
+        element: ArconElement<IN2>,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator> {
+        let key = self.get_key(ctx);
+        for index in self.assign_windows(element.timestamp, ctx.current_time()?) {
+            let window_ctx = WindowContext::new(key, index);
+            ctx.state()
+                .right()
+                .append(window_ctx, element.data.clone())?;
+            self.register_window(window_ctx, ctx)?;
+        }
+        Ok(Vec::new())
+    }
+
+    fn handle_timeout(
+        &mut self,
+        timeout: Self::TimerState,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Option<Self::ElementIterator>> {
+        let WindowEvent {
+            key,
+            index,
+            timestamp,
+            ..
+        } = timeout;
+
+        let window_ctx = WindowContext::new(key, index);
+
+        let state = ctx.state();
+        let left = state.left().get(window_ctx)?;
+        let right = state.right().get(window_ctx)?;
+        state.left().clear(window_ctx)?;
+        state.right().clear(window_ctx)?;
+        state.active_windows().remove(&window_ctx)?;
+
+        let mut output = Vec::with_capacity(left.len() * right.len());
+        for l in &left {
+            for r in &right {
+                output.push(ArconElement::with_timestamp(
+                    (self.join_fn)(l, r),
+                    timestamp,
+                ));
+            }
+        }
+
+        Ok(Some(output))
+    }
+}
+
+/// Cleanup of a timestamp bucket of an [IntervalJoin]
+#[cfg_attr(feature = "arcon_serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Message, PartialEq, Clone)]
+pub struct IntervalJoinEvent {
+    #[prost(uint64, tag = "1")]
+    key: u64,
+    #[prost(uint64, tag = "2")]
+    bucket: u64,
+}
+
+#[derive(ArconState)]
+pub struct IntervalJoinState<IN1: ArconType, IN2: ArconType, B: Backend> {
+    left: JoinBuffer<ArconElement<IN1>, B>,
+    right: JoinBuffer<ArconElement<IN2>, B>,
+}
+
+impl<IN1: ArconType, IN2: ArconType, B: Backend> IntervalJoinState<IN1, IN2, B> {
+    pub fn new(backend: Arc<B>) -> Self {
+        Self {
+            left: JoinBuffer::new("_interval_join_left", backend.clone()),
+            right: JoinBuffer::new("_interval_join_right", backend),
+        }
+    }
+}
+
+/// Event-time Interval Join
+///
+/// Joins an element `a` of the first stream with every element `b` of the second
+/// stream with the same key where `a.ts - lower <= b.ts <= a.ts + upper`.
+/// The joined element is assigned the larger timestamp of the two.
+///
+/// Elements are buffered in buckets of their timestamp, which are as wide as the join
+/// interval. An element is thereby matched against at most two buckets of the other input,
+/// and a bucket is dropped as a whole once the watermark guarantees that no more matches can arrive.
+pub struct IntervalJoin<IN1, IN2, OUT, F, B>
+where
+    IN1: ArconType,
+    IN2: ArconType,
+    OUT: ArconType,
+    F: Fn(&IN1, &IN2) -> OUT + ArconFnBounds,
+    B: Backend,
+{
+    lower: u64,
+    upper: u64,
+    /// Width of the timestamp buckets
+    bucket_width: u64,
+    keyed: bool,
+    join_fn: F,
+    _marker: PhantomData<(IN1, IN2, OUT, B)>,
+}
+
+impl<IN1, IN2, OUT, F, B> IntervalJoin<IN1, IN2, OUT, F, B>
+where
+    IN1: ArconType,
+    IN2: ArconType,
+    OUT: ArconType,
+    F: Fn(&IN1, &IN2) -> OUT + ArconFnBounds,
+    B: Backend,
+{
+    pub fn new(conf: IntervalJoinConf, join_fn: F) -> Self {
+        Self {
+            lower: conf.lower.0,
+            upper: conf.upper.0,
+            bucket_width: conf.lower.0 + conf.upper.0 + 1,
+            keyed: matches!(conf.kind, StreamKind::Keyed),
+            join_fn,
+            _marker: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn join(&self, left: &ArconElement<IN1>, right: &ArconElement<IN2>) -> ArconElement<OUT> {
+        ArconElement::with_timestamp(
+            (self.join_fn)(&left.data, &right.data),
+            std::cmp::max(left.timestamp, right.timestamp),
+        )
+    }
+
+    #[inline]
+    fn bucket(&self, timestamp: u64) -> u64 {
+        timestamp / self.bucket_width
+    }
+
+    /// Returns the buckets that hold the timestamps in `[lower, upper]`
+    #[inline]
+    fn buckets(&self, lower: u64, upper: u64) -> std::ops::RangeInclusive<u64> {
+        self.bucket(lower)..=self.bucket(upper)
+    }
+
+    /// Schedules the cleanup of `bucket` unless it is already scheduled
+    ///
+    /// A bucket is dropped once the watermark has passed the point until which any of
+    /// its elements, on either side, may still be matched.
+    #[inline]
+    fn schedule_cleanup(
+        &mut self,
+        key: u64,
+        bucket: u64,
+        ctx: &mut OperatorContext<IntervalJoinEvent, IntervalJoinState<IN1, IN2, B>>,
+    ) -> ArconResult<()> {
+        let timer_key = WindowContext::new(key, bucket);
+        if ctx.pending_timer(timer_key)?.is_some() {
+            return Ok(());
+        }
+        let time = (bucket + 1) * self.bucket_width + std::cmp::max(self.lower, self.upper);
+        let request = ctx.schedule_at(timer_key, time, IntervalJoinEvent { key, bucket })?;
+
+        if let Err(expired) = request {
+            // For now just log the error..
+            error!(ctx.log(), "{}", expired);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn get_key(
+        &self,
+        ctx: &OperatorContext<IntervalJoinEvent, IntervalJoinState<IN1, IN2, B>>,
+    ) -> u64 {
+        if !self.keyed {
+            return 0;
+        }
+        ctx.current_key()
+    }
+}
+
+impl<IN1, IN2, OUT, F, B> CoOperator for IntervalJoin<IN1, IN2, OUT, F, B>
+where
+    IN1: ArconType,
+    IN2: ArconType,
+    OUT: ArconType,
+    F: Fn(&IN1, &IN2) -> OUT + ArconFnBounds,
+    B: Backend,
+{
+    type IN1 = IN1;
+    type IN2 = IN2;
+    type OUT = OUT;
+    type TimerState = IntervalJoinEvent;
+    type OperatorState = IntervalJoinState<IN1, IN2, B>;
+    type ElementIterator = Vec<ArconElement<OUT>>;
+
+    fn handle_element1(
+        &mut self,
+        element: ArconElement<IN1>,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator> {
+        if element.timestamp < ctx.current_time()? {
+            // Late arrival: early return
+            return Ok(Vec::new());
+        }
+
+        let key = self.get_key(ctx);
+        let lower = element.timestamp.saturating_sub(self.lower);
+        let upper = element.timestamp + self.upper;
+
+        let mut output = Vec::new();
+        for bucket in self.buckets(lower, upper) {
+            let right = ctx.state().right().get(WindowContext::new(key, bucket))?;
+            output.extend(
+                right
+                    .iter()
+                    .filter(|right| right.timestamp >= lower && right.timestamp <= upper)
+                    .map(|right| self.join(&element, right)),
+            );
+        }
+
+        let bucket = self.bucket(element.timestamp);
+        ctx.state()
+            .left()
+            .append(WindowContext::new(key, bucket), element)?;
+        self.schedule_cleanup(key, bucket, ctx)?;
+
+        Ok(output)
+    }
+
+    fn handle_element2(
+        &mut self,
+        element: ArconElement<IN2>,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator> {
+        if element.timestamp < ctx.current_time()? {
+            // Late arrival: early return
+            return Ok(Vec::new());
+        }
+
+        let key = self.get_key(ctx);
+        let lower = element.timestamp.saturating_sub(self.upper);
+        let upper = element.timestamp + self.lower;
+
+        let mut output = Vec::new();
+        for bucket in self.buckets(lower, upper) {
+            let left = ctx.state().left().get(WindowContext::new(key, bucket))?;
+            output.extend(
+                left.iter()
+                    .filter(|left| left.timestamp >= lower && left.timestamp <= upper)
+                    .map(|left| self.join(left, &element)),
+            );
+        }
+
+        let bucket = self.bucket(element.timestamp);
+        ctx.state()
+            .right()
+            .append(WindowContext::new(key, bucket), element)?;
+        self.schedule_cleanup(key, bucket, ctx)?;
+
+        Ok(output)
+    }
+
+    fn handle_timeout(
+        &mut self,
+        timeout: Self::TimerState,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Option<Self::ElementIterator>> {
+        let IntervalJoinEvent { key, bucket } = timeout;
+        let buffer_ctx = WindowContext::new(key, bucket);
+
+        let state = ctx.state();
+        state.left().clear(buffer_ctx)?;
+        state.right().clear(buffer_ctx)?;
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{application::conf::ApplicationConf, index::Timer, stream::time::Time};
+    use arcon_state::Sled;
+
+    fn context<T: Message + Clone + Default, S: crate::index::ArconState>(
+        state: S,
+        backend: Arc<Sled>,
+    ) -> OperatorContext<T, S> {
+        let timer = Timer::new("_join_timer", backend.clone());
+        let processing_timer = Timer::new("_join_processing_timer", backend);
+        OperatorContext::new(
+            Box::new(timer),
+            Box::new(processing_timer),
+            state,
+            ApplicationConf::default().arcon_logger(),
+            #[cfg(feature = "metrics")]
+            String::from("join"),
+        )
+    }
+
+    #[test]
+    fn window_join_test() {
+        let backend = Arc::new(crate::test_utils::temp_backend::<Sled>());
+        let mut ctx = context(WindowJoinState::new(backend.clone()), backend);
+
+        let conf = WindowConf {
+            assigner: Assigner::Tumbling {
+                length: Time::seconds(10),
+                late_arrival: Time::seconds(0),
+            },
+            kind: StreamKind::Keyed,
+        };
+        let mut join = WindowJoin::<u64, u64, u64, _, Sled>::new(conf, |a: &u64, b: &u64| a + b);
+
+        ctx.set_key(1);
+        join.handle_element1(ArconElement::with_timestamp(1, 2), &mut ctx)
+            .unwrap();
+        join.handle_element2(ArconElement::with_timestamp(10, 5), &mut ctx)
+            .unwrap();
+        join.handle_element2(ArconElement::with_timestamp(20, 8), &mut ctx)
+            .unwrap();
+        // Falls into the next window
+        join.handle_element2(ArconElement::with_timestamp(30, 12), &mut ctx)
+            .unwrap();
+
+        // Other key
+        ctx.set_key(2);
+        join.handle_element1(ArconElement::with_timestamp(100, 3), &mut ctx)
+            .unwrap();
+
+        let timeouts = ctx.timer.advance_to(11).unwrap();
+        let mut results = Vec::new();
+        for timeout in timeouts {
+            let output = join.handle_timeout(timeout, &mut ctx).unwrap().unwrap();
+            results.extend(output.into_iter().map(|e| (e.data, e.timestamp)));
+        }
+        results.sort_unstable();
+        assert_eq!(results, vec![(11, 10), (21, 10)]);
+
+        // The window of the last element yields no result as the first stream is empty
+        let timeouts = ctx.timer.advance_to(21).unwrap();
+        assert_eq!(timeouts.len(), 1);
+        for timeout in timeouts {
+            let output = join.handle_timeout(timeout, &mut ctx).unwrap().unwrap();
+            assert!(output.is_empty());
+        }
+    }
+
+    #[test]
+    fn interval_join_test() {
+        let backend = Arc::new(crate::test_utils::temp_backend::<Sled>());
+        let mut ctx = context(IntervalJoinState::new(backend.clone()), backend);
+
+        let conf = IntervalJoinConf {
+            lower: Time::seconds(2),
+            upper: Time::seconds(3),
+            kind: StreamKind::Keyed,
+        };
+        let mut join = IntervalJoin::<u64, u64, u64, _, Sled>::new(conf, |a: &u64, b: &u64| a + b);
+
+        ctx.set_key(1);
+        let out = join
+            .handle_element1(ArconElement::with_timestamp(1, 10), &mut ctx)
+            .unwrap();
+        assert!(out.is_empty());
+
+        // Within [8, 13]
+        let out = join
+            .handle_element2(ArconElement::with_timestamp(10, 8), &mut ctx)
+            .unwrap();
+        assert_eq!(out.len(), 1);
+        assert_eq!((out[0].data, out[0].timestamp), (11, 10));
+
+        let out = join
+            .handle_element2(ArconElement::with_timestamp(20, 13), &mut ctx)
+            .unwrap();
+        assert_eq!((out[0].data, out[0].timestamp), (21, 13));
+
+        // Outside of the interval
+        let out = join
+            .handle_element2(ArconElement::with_timestamp(30, 14), &mut ctx)
+            .unwrap();
+        assert!(out.is_empty());
+
+        // Matches the buffered element at 13 and 14 but not 8
+        let out = join
+            .handle_element1(ArconElement::with_timestamp(2, 12), &mut ctx)
+            .unwrap();
+        assert_eq!(out.len(), 2);
+
+        // Buckets are 6 wide, once the watermark passes 14 no element of
+        // the bucket [6, 11] may be matched anymore and it is dropped
+        for timeout in ctx.timer.advance_to(15).unwrap() {
+            join.handle_timeout(timeout, &mut ctx).unwrap();
+        }
+        let dropped = ctx.state().left().get(WindowContext::new(1, 1)).unwrap();
+        assert!(dropped.is_empty());
+        let left = ctx.state().left().get(WindowContext::new(1, 2)).unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].data, 2);
+        let right = ctx.state().right().get(WindowContext::new(1, 2)).unwrap();
+        assert_eq!(right.len(), 2);
+    }
+}
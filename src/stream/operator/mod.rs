@@ -1,8 +1,14 @@
 // Copyright (c) 2020, KTH Royal Institute of Technology.
 // SPDX-License-Identifier: AGPL-3.0-only
 
+/// Two-input operators
+pub mod co_operator;
 /// Available function operators
 pub mod function;
+/// Available join operators
+pub mod join;
+/// Side outputs of operators
+pub mod side_output;
 /// Available sink operators
 pub mod sink;
 /// Available window operators
@@ -14,10 +20,15 @@ use metrics::{gauge, increment_counter, register_counter, register_gauge};
 use crate::{
     application::conf::logger::ArconLogger,
     data::{ArconElement, ArconType},
-    error::{timer::TimerResult, *},
-    index::{timer::ArconTimer, ArconState},
+    error::{
+        timer::{RescheduleResult, TimerResult},
+        *,
+    },
+    index::{timer::ArconTimer, ArconState, IndexOps},
 };
 use prost::Message;
+use side_output::{OutputTag, SideOutputKey};
+use std::any::Any;
 
 /// Defines the methods an `Operator` must implement
 pub trait Operator: Send + Sized {
@@ -48,11 +59,48 @@ pub trait Operator: Send + Sized {
     ) -> ArconResult<Self::ElementIterator>;
 
     /// Determines how the `Operator` handles timeouts it registered earlier when they are triggered
+    ///
+    /// Both event-time and processing-time timers are delivered here.
     fn handle_timeout(
         &mut self,
         timeout: Self::TimerState,
         ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
     ) -> ArconResult<Option<Self::ElementIterator>>;
+
+    /// Determines how the `Operator` reacts to an advanced watermark
+    ///
+    /// Called after all timeouts up to the watermark have been handled.
+    fn handle_watermark(
+        &mut self,
+        _watermark: u64,
+        _ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Option<Self::ElementIterator>> {
+        Ok(None)
+    }
+
+    /// Determines what the `Operator` does when a checkpoint of `epoch` is taken
+    ///
+    /// Called on the epoch barrier before the state of the `Operator` is persisted.
+    fn handle_epoch(
+        &mut self,
+        _epoch: u64,
+        _ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<()> {
+        Ok(())
+    }
+
+    /// Determines what the `Operator` does once `epoch` has been committed
+    ///
+    /// An epoch is committed once every Operator and source of the Application has completed
+    /// its checkpoint. Notifications may be skipped for earlier epochs, so committing `epoch`
+    /// implies that every epoch before it is committed as well.
+    fn handle_epoch_commit(
+        &mut self,
+        _epoch: u64,
+        _ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<()> {
+        Ok(())
+    }
 }
 
 /// Helper macro to implement an empty ´handle_timeout` function
@@ -69,6 +117,11 @@ macro_rules! ignore_timeout {
     };
 }
 
+/// Handoff map of the event timers, shared by the instances of an Operator through their backend
+const TIMER_HANDOFFS: &str = "_timer_handoffs";
+/// Handoff map of the processing-time timers
+const PROCESSING_TIMER_HANDOFFS: &str = "_processing_timer_handoffs";
+
 /// Context Available to an Arcon Operator
 pub struct OperatorContext<TimerState, OperatorState>
 where
@@ -77,10 +130,24 @@ where
 {
     /// A Timer that can be used to schedule event timers
     pub(crate) timer: Box<dyn ArconTimer<Key = u64, Value = TimerState>>,
+    /// A Timer that can be used to schedule processing-time timers
+    ///
+    /// Its time is the wall-clock time in milliseconds since the UNIX epoch.
+    pub(crate) processing_timer: Box<dyn ArconTimer<Key = u64, Value = TimerState>>,
     /// State of the Operator
     pub(crate) state: OperatorState,
     /// Reference to logger
     pub(crate) logger: ArconLogger,
+    /// Key of the element that is currently being processed
+    current_key: u64,
+    /// Committed epoch that the Operator was restored from
+    pub(crate) restored_epoch: Option<u64>,
+    /// Index of the instance that runs the Operator
+    pub(crate) instance: u32,
+    /// Elements emitted to side outputs, drained by the Node
+    pub(crate) side_outputs: Vec<(SideOutputKey, Box<dyn Any + Send>)>,
+    /// Set once a processing-time timer has been scheduled, taken by the Node
+    pub(crate) processing_timer_scheduled: bool,
     #[cfg(feature = "metrics")]
     name: String,
 }
@@ -93,23 +160,69 @@ where
     #[inline]
     pub(crate) fn new(
         timer: Box<dyn ArconTimer<Key = u64, Value = TimerState>>,
+        processing_timer: Box<dyn ArconTimer<Key = u64, Value = TimerState>>,
         state: OperatorState,
         logger: ArconLogger,
         #[cfg(feature = "metrics")] name: String,
     ) -> Self {
         OperatorContext {
             timer,
+            processing_timer,
             state,
             logger,
+            current_key: 0,
+            restored_epoch: None,
+            instance: 0,
+            side_outputs: Vec::new(),
+            processing_timer_scheduled: false,
             #[cfg(feature = "metrics")]
             name,
         }
     }
+
+    /// Set the active key for the Operator, its state and the timers it schedules
+    #[inline]
+    pub(crate) fn set_key(&mut self, key: u64) {
+        self.current_key = key;
+        self.state.set_key(key);
+        self.timer.set_key(key);
+        self.processing_timer.set_key(key);
+    }
+
+    /// Returns the key of the element that is currently being processed
+    #[inline]
+    pub fn current_key(&self) -> u64 {
+        self.current_key
+    }
+
+    /// Returns the committed epoch that the Operator was restored from
+    ///
+    /// `None` if the Application started from scratch.
+    #[inline]
+    pub fn restored_epoch(&self) -> Option<u64> {
+        self.restored_epoch
+    }
+
+    /// Returns the index of the instance that runs the Operator
+    #[inline]
+    pub fn instance(&self) -> u32 {
+        self.instance
+    }
+
     #[inline]
     pub fn state(&mut self) -> &mut OperatorState {
         &mut self.state
     }
 
+    /// Emit an element to the side output identified by `tag`
+    ///
+    /// The element is dropped if no [Stream](crate::prelude::Stream) consumes the side output.
+    #[inline]
+    pub fn output_to<T: ArconType>(&mut self, tag: &OutputTag<T>, element: ArconElement<T>) {
+        self.side_outputs
+            .push((tag.key().clone(), Box::new(element)));
+    }
+
     /// Enable users to log within an Operator
     ///
     /// `error!(ctx.log(), "Something bad happened!");
@@ -138,6 +251,106 @@ where
         self.timer.schedule_at(key.into(), time, entry)
     }
 
+    /// Cancel the pending event timer registered under `key`
+    ///
+    /// Returns the entry of the timer if one was pending.
+    #[inline]
+    pub fn cancel_timer<I: Into<u64>>(&mut self, key: I) -> StateResult<Option<TimerState>> {
+        self.timer.cancel(&key.into())
+    }
+
+    /// Move the pending event timer registered under `key` to `time`
+    ///
+    /// Returns `Ok(None)` if no timer is pending for `key`. If `time` has already expired,
+    /// the timer is removed and its entry is handed back in a [TimerExpiredError].
+    #[inline]
+    pub fn reschedule_timer<I: Into<u64>>(
+        &mut self,
+        key: I,
+        time: u64,
+    ) -> RescheduleResult<TimerState> {
+        self.timer.reschedule(key.into(), time)
+    }
+
+    /// Returns the time the event timer registered under `key` is scheduled at, if any
+    #[inline]
+    pub fn pending_timer<I: Into<u64>>(&self, key: I) -> StateResult<Option<u64>> {
+        self.timer.pending(&key.into())
+    }
+
+    /// Get current processing time
+    ///
+    /// Processing time is the wall-clock time in milliseconds since the UNIX epoch.
+    #[inline]
+    pub fn current_processing_time(&self) -> u64 {
+        crate::util::get_system_time()
+    }
+
+    /// Schedule a processing-time timer at a specific wall-clock time in milliseconds
+    ///
+    /// The entry is delivered through [Operator::handle_timeout] once the wall clock
+    /// passes `time`. Returns `Err(entry)` if `time` has already passed.
+    #[inline]
+    pub fn schedule_processing_at<I: Into<u64>>(
+        &mut self,
+        key: I,
+        time: u64,
+        entry: TimerState,
+    ) -> TimerResult<TimerState> {
+        self.processing_timer_scheduled = true;
+        self.processing_timer.schedule_at(key.into(), time, entry)
+    }
+
+    /// Schedule a processing-time timer that fires after `delay` of wall-clock time
+    #[inline]
+    pub fn schedule_processing_after<I: Into<u64>>(
+        &mut self,
+        key: I,
+        delay: std::time::Duration,
+        entry: TimerState,
+    ) -> TimerResult<TimerState> {
+        let time = self.current_processing_time() + delay.as_millis() as u64;
+        self.schedule_processing_at(key, time, entry)
+    }
+
+    /// Persist the event-time and processing-time timers
+    #[inline]
+    pub(crate) fn persist_timers(&mut self) -> ArconResult<()> {
+        self.timer.persist()?;
+        self.processing_timer.persist()
+    }
+
+    /// Hand off the timers of the keys that `owner` assigns to other instances than `instance`
+    ///
+    /// See [ArconTimer::hand_off].
+    pub(crate) fn hand_off_timers(
+        &mut self,
+        instance: usize,
+        owner: &dyn Fn(u64) -> usize,
+    ) -> ArconResult<()> {
+        self.timer.hand_off(TIMER_HANDOFFS, instance, owner)?;
+        self.processing_timer
+            .hand_off(PROCESSING_TIMER_HANDOFFS, instance, owner)?;
+        Ok(())
+    }
+
+    /// Take over the timers that any of `instances` handed off to `instance`
+    ///
+    /// See [ArconTimer::take_over].
+    pub(crate) fn take_over_timers(
+        &mut self,
+        instance: usize,
+        instances: usize,
+    ) -> ArconResult<()> {
+        self.timer.take_over(TIMER_HANDOFFS, instance, instances)?;
+        self.processing_timer
+            .take_over(PROCESSING_TIMER_HANDOFFS, instance, instances)?;
+        if !self.processing_timer.is_empty()? {
+            self.processing_timer_scheduled = true;
+        }
+        Ok(())
+    }
+
     #[cfg(feature = "metrics")]
     pub fn register_gauge(&mut self, name: &'static str) {
         register_gauge!(format!("{}_{}", self.name, name));
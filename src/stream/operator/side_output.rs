@@ -0,0 +1,155 @@
+// Copyright (c) 2020, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::data::ArconType;
+use std::{any::TypeId, marker::PhantomData, sync::Arc};
+
+/// Identifier of the side output that receives late elements
+pub const LATE_TAG: &str = "_late";
+
+/// Identifies a typed side output of an [Operator](super::Operator)
+///
+/// Elements are emitted through [OperatorContext::output_to](super::OperatorContext::output_to)
+/// and consumed as a separate [Stream](crate::prelude::Stream) through
+/// [Stream::side_output](crate::prelude::Stream::side_output).
+///
+/// A side output is identified by both the id and the element type of its tag, so elements
+/// only reach the streams that consume a tag of the same id and type.
+pub struct OutputTag<T: ArconType> {
+    key: SideOutputKey,
+    _marker: PhantomData<T>,
+}
+
+impl<T: ArconType> OutputTag<T> {
+    /// Creates a new tag
+    ///
+    /// Tags with equal ids and types refer to the same side output.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            key: SideOutputKey {
+                id: Arc::from(id.into()),
+                type_id: TypeId::of::<T>(),
+            },
+            _marker: PhantomData,
+        }
+    }
+
+    /// Tag of the elements that arrive after the watermark has passed their timestamp
+    ///
+    /// Late elements are of the input type of the operator that dropped them.
+    pub fn late() -> Self {
+        Self::new(LATE_TAG)
+    }
+
+    /// Returns the id of the tag
+    pub fn id(&self) -> &str {
+        &self.key.id
+    }
+
+    pub(crate) fn key(&self) -> &SideOutputKey {
+        &self.key
+    }
+}
+
+impl<T: ArconType> Clone for OutputTag<T> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// The id and element type of an [OutputTag]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SideOutputKey {
+    id: Arc<str>,
+    type_id: TypeId,
+}
+
+impl SideOutputKey {
+    /// Returns true if the side output carries elements of type `T`
+    pub(crate) fn is<T: ArconType>(&self) -> bool {
+        self.type_id == TypeId::of::<T>()
+    }
+
+    pub(crate) fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, test_utils::wait_until};
+    use std::time::Duration;
+
+    // Sends odd numbers to a side output
+    struct OddSplitter {
+        tag: OutputTag<i32>,
+    }
+
+    impl Operator for OddSplitter {
+        type IN = i32;
+        type OUT = i32;
+        type TimerState = ArconNever;
+        type OperatorState = EmptyState;
+        type ElementIterator = Option<ArconElement<Self::OUT>>;
+
+        fn handle_element(
+            &mut self,
+            element: ArconElement<i32>,
+            ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+        ) -> ArconResult<Self::ElementIterator> {
+            if element.data % 2 == 0 {
+                Ok(Some(element))
+            } else {
+                ctx.output_to(&self.tag, element);
+                Ok(None)
+            }
+        }
+        crate::ignore_timeout!();
+    }
+
+    #[test]
+    fn side_output_test() {
+        let stream = Application::default()
+            .with_debug_node()
+            .iterator(0..10, |conf| {
+                conf.set_arcon_time(ArconTime::Process);
+            })
+            .operator(OperatorBuilder {
+                operator: Arc::new(|| OddSplitter {
+                    tag: OutputTag::new("odd"),
+                }),
+                state: Arc::new(|_| EmptyState),
+                conf: Default::default(),
+            });
+
+        let _evens = stream.clone().map(|x| x * 2);
+        let mut app = stream.side_output(OutputTag::<i32>::new("odd")).build();
+
+        app.start();
+
+        let debug_node = app.get_debug_node::<i32>().unwrap();
+        assert!(wait_until(Duration::from_secs(10), || {
+            debug_node.on_definition(|cd| cd.data.len() == 5)
+        }));
+        debug_node.on_definition(|cd| {
+            let sum: i32 = cd.data.iter().map(|elem| elem.data).sum();
+            assert_eq!(sum, 25);
+        });
+        app.shutdown();
+    }
+
+    #[test]
+    #[should_panic(expected = "input type")]
+    fn late_side_output_of_other_type_test() {
+        let _ = Application::default()
+            .iterator(0..10, |conf| {
+                conf.set_arcon_time(ArconTime::Event);
+                conf.set_timestamp_extractor(|x: &i32| *x as u64);
+            })
+            .map(|x| x as u64)
+            .side_output(OutputTag::<u64>::late());
+    }
+}
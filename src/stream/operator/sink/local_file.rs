@@ -1,20 +1,25 @@
 // Copyright (c) 2020, KTH Royal Institute of Technology.
 // SPDX-License-Identifier: AGPL-3.0-only
 
+use super::two_phase::TwoPhaseCommitSink;
 use crate::{
     data::{ArconElement, ArconNever, ArconType},
     error::ArconResult,
     prelude::EmptyState,
     stream::operator::{Operator, OperatorContext},
 };
+use arcon_state::backend::serialization::protobuf;
 use std::{
     cell::RefCell,
-    fs::{File, OpenOptions},
-    io::Write,
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
     marker::PhantomData,
-    path::Path,
+    path::{Path, PathBuf},
 };
 
+const IN_PROGRESS_SUFFIX: &str = ".inprogress";
+const PENDING_SUFFIX: &str = ".pending";
+
 pub struct LocalFileSink<IN>
 where
     IN: ArconType,
@@ -67,6 +72,197 @@ where
     crate::ignore_timeout!();
 }
 
+/// A [TwoPhaseCommitSink] that writes the output of every epoch into its own file
+///
+/// Every instance writes the elements of an epoch to its own hidden staging file, which is
+/// renamed into an `epoch_{epoch}_{instance}` file of the output directory once the epoch has
+/// been committed. Elements are stored as length-delimited protobuf records, which
+/// [read_committed](Self::read_committed) reads back.
+///
+/// On recovery, every instance resolves the staging files that it has written itself. Instance 0
+/// also commits the pending files of the committed epochs of every other instance, so the output
+/// of instances that no longer exist after restoring at a lower parallelism is not lost.
+///
+/// ```no_run
+/// use arcon::prelude::*;
+/// let app = Application::default()
+///     .iterator(0u64..100, |conf| {
+///         conf.set_arcon_time(ArconTime::Process);
+///     })
+///     .operator(OperatorBuilder {
+///         operator: Arc::new(|| TwoPhaseCommit::new(TransactionalFileSink::new("/tmp/output"))),
+///         state: Arc::new(|_| EmptyState),
+///         conf: OperatorConf {
+///             parallelism_strategy: ParallelismStrategy::Static(1),
+///             ..Default::default()
+///         },
+///     })
+///     .build();
+/// ```
+pub struct TransactionalFileSink<IN>
+where
+    IN: ArconType,
+{
+    dir: PathBuf,
+    /// Index of the instance that runs the sink
+    instance: u32,
+    /// Epoch of the open transaction
+    epoch: u64,
+    /// Staging file of the open transaction, created on its first element
+    file: Option<BufWriter<File>>,
+    _marker: PhantomData<IN>,
+}
+
+impl<IN> TransactionalFileSink<IN>
+where
+    IN: ArconType,
+{
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).expect("Failed to create output directory");
+        TransactionalFileSink {
+            dir,
+            instance: 0,
+            epoch: 0,
+            file: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the path of the committed output of `epoch` of this instance
+    pub fn committed_path(&self, epoch: u64) -> PathBuf {
+        self.committed_path_of(epoch, self.instance)
+    }
+
+    fn committed_path_of(&self, epoch: u64, instance: u32) -> PathBuf {
+        self.dir.join(format!("epoch_{}_{}", epoch, instance))
+    }
+
+    /// Reads the elements of a committed output file
+    pub fn read_committed(path: impl AsRef<Path>) -> ArconResult<Vec<IN>> {
+        let bytes = fs::read(path)?;
+        let mut buf = bytes.as_slice();
+        let mut elements = Vec::new();
+        while !buf.is_empty() {
+            elements.push(protobuf::deserialize_from(&mut buf)?);
+        }
+        Ok(elements)
+    }
+
+    fn staging_path(&self, epoch: u64, suffix: &str) -> PathBuf {
+        self.staging_path_of(epoch, self.instance, suffix)
+    }
+
+    fn staging_path_of(&self, epoch: u64, instance: u32, suffix: &str) -> PathBuf {
+        self.dir
+            .join(format!(".epoch_{}_{}{}", epoch, instance, suffix))
+    }
+
+    /// Returns the epochs and instances of all staging files with `suffix`
+    fn staged(&self, suffix: &str) -> ArconResult<Vec<(u64, u32)>> {
+        let mut staged = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let name = entry?.file_name();
+            if let Some((epoch, instance)) = name
+                .to_str()
+                .and_then(|name| name.strip_prefix(".epoch_"))
+                .and_then(|name| name.strip_suffix(suffix))
+                .and_then(|name| name.split_once('_'))
+            {
+                if let (Ok(epoch), Ok(instance)) = (epoch.parse(), instance.parse()) {
+                    staged.push((epoch, instance));
+                }
+            }
+        }
+        staged.sort_unstable();
+        Ok(staged)
+    }
+
+    /// Returns the epochs of the staging files of this instance with `suffix`
+    fn staged_epochs(&self, suffix: &str) -> ArconResult<Vec<u64>> {
+        Ok(self
+            .staged(suffix)?
+            .into_iter()
+            .filter(|(_, instance)| *instance == self.instance)
+            .map(|(epoch, _)| epoch)
+            .collect())
+    }
+}
+
+/// Ignores files that another instance has already resolved during recovery
+fn resolved(result: io::Result<()>) -> ArconResult<()> {
+    match result {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+impl<IN> TwoPhaseCommitSink for TransactionalFileSink<IN>
+where
+    IN: ArconType,
+{
+    type IN = IN;
+
+    fn begin(&mut self, epoch: u64) -> ArconResult<()> {
+        self.epoch = epoch;
+        self.file = None;
+        Ok(())
+    }
+
+    fn write(&mut self, element: ArconElement<IN>) -> ArconResult<()> {
+        if self.file.is_none() {
+            let file = File::create(self.staging_path(self.epoch, IN_PROGRESS_SUFFIX))?;
+            self.file = Some(BufWriter::new(file));
+        }
+        if let Some(file) = &mut self.file {
+            file.write_all(&protobuf::serialize(&element.data)?)?;
+        }
+        Ok(())
+    }
+
+    fn pre_commit(&mut self, epoch: u64) -> ArconResult<()> {
+        if let Some(file) = self.file.take() {
+            let file = file.into_inner().map_err(io::Error::from)?;
+            file.sync_all()?;
+            fs::rename(
+                self.staging_path(epoch, IN_PROGRESS_SUFFIX),
+                self.staging_path(epoch, PENDING_SUFFIX),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn commit(&mut self, epoch: u64) -> ArconResult<()> {
+        for pending in self.staged_epochs(PENDING_SUFFIX)? {
+            if pending <= epoch {
+                fs::rename(
+                    self.staging_path(pending, PENDING_SUFFIX),
+                    self.committed_path(pending),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn recover(&mut self, instance: u32, committed: Option<u64>) -> ArconResult<()> {
+        self.instance = instance;
+        for (pending, owner) in self.staged(PENDING_SUFFIX)? {
+            let path = self.staging_path_of(pending, owner, PENDING_SUFFIX);
+            let is_committed = committed.map(|c| pending <= c).unwrap_or(false);
+            if is_committed && (owner == instance || instance == 0) {
+                // instance 0 may resolve the file at the same time as its owner
+                resolved(fs::rename(path, self.committed_path_of(pending, owner)))?;
+            } else if owner == instance {
+                fs::remove_file(path)?;
+            }
+        }
+        for in_progress in self.staged_epochs(IN_PROGRESS_SUFFIX)? {
+            fs::remove_file(self.staging_path(in_progress, IN_PROGRESS_SUFFIX))?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -107,4 +303,82 @@ mod tests {
         let expected: Vec<i32> = vec![6, 2, 15, 30];
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn transactional_file_sink_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let read = |path: PathBuf| TransactionalFileSink::<i32>::read_committed(path).unwrap();
+
+        let mut sink = TransactionalFileSink::<i32>::new(dir.path());
+        sink.recover(0, None).unwrap();
+        sink.begin(0).unwrap();
+        sink.write(ArconElement::new(1)).unwrap();
+        sink.write(ArconElement::new(2)).unwrap();
+        sink.pre_commit(0).unwrap();
+        sink.begin(1).unwrap();
+        sink.write(ArconElement::new(3)).unwrap();
+        sink.pre_commit(1).unwrap();
+        sink.begin(2).unwrap();
+        sink.write(ArconElement::new(4)).unwrap();
+
+        // nothing is visible before the epochs have been committed
+        assert!(!sink.committed_path(0).exists());
+        sink.commit(0).unwrap();
+        assert_eq!(read(sink.committed_path(0)), vec![1, 2]);
+        assert!(!sink.committed_path(1).exists());
+
+        // epoch 1 was committed before the crash, epoch 2 is replayed after the restore
+        let mut restored = TransactionalFileSink::<i32>::new(dir.path());
+        restored.recover(0, Some(1)).unwrap();
+        assert_eq!(read(restored.committed_path(1)), vec![3]);
+        assert!(!restored.committed_path(2).exists());
+        let remaining: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .filter(|name| name.starts_with('.'))
+            .collect();
+        assert!(remaining.is_empty());
+    }
+
+    #[test]
+    fn transactional_file_sink_instances_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut first = TransactionalFileSink::<i32>::new(dir.path());
+        let mut second = TransactionalFileSink::<i32>::new(dir.path());
+        first.recover(0, None).unwrap();
+        second.recover(1, None).unwrap();
+        for (sink, data) in vec![(&mut first, -1), (&mut second, 7)] {
+            sink.begin(0).unwrap();
+            sink.write(ArconElement::new(data)).unwrap();
+            sink.write(ArconElement::new(data * 2)).unwrap();
+            sink.pre_commit(0).unwrap();
+        }
+
+        // the instances stage and commit the same epoch without clobbering each other
+        first.commit(0).unwrap();
+        assert!(!second.committed_path(0).exists());
+        second.commit(0).unwrap();
+        let read = |path: PathBuf| TransactionalFileSink::<i32>::read_committed(path).unwrap();
+        assert_eq!(read(first.committed_path(0)), vec![-1, -2]);
+        assert_eq!(read(second.committed_path(0)), vec![7, 14]);
+    }
+
+    #[test]
+    fn transactional_file_sink_rescaled_recovery_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut removed = TransactionalFileSink::<i32>::new(dir.path());
+        removed.recover(1, None).unwrap();
+        for epoch in 0..2 {
+            removed.begin(epoch).unwrap();
+            removed.write(ArconElement::new(epoch as i32)).unwrap();
+            removed.pre_commit(epoch).unwrap();
+        }
+
+        // epoch 0 was committed before the Application was restored with a single instance
+        let mut restored = TransactionalFileSink::<i32>::new(dir.path());
+        restored.recover(0, Some(0)).unwrap();
+        let read = |path: PathBuf| TransactionalFileSink::<i32>::read_committed(path).unwrap();
+        assert_eq!(read(removed.committed_path(0)), vec![0]);
+        assert!(!removed.committed_path(1).exists());
+    }
 }
@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 
 pub mod local_file;
+/// Sinks that commit their output together with the epochs of an Application
+pub mod two_phase;
 
 #[cfg(feature = "socket")]
 #[allow(dead_code)]
@@ -0,0 +1,95 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{
+    data::{ArconElement, ArconNever, ArconType},
+    error::ArconResult,
+    prelude::EmptyState,
+    stream::operator::{Operator, OperatorContext},
+};
+
+/// A sink that writes its output in transactions that follow the epochs of an Application
+///
+/// The transaction of an epoch is pre-committed on its epoch barrier and committed once the
+/// epoch has been committed, so that an Application that is restored from a checkpoint does
+/// not emit the output of the replayed epochs twice. Use [TwoPhaseCommit] to run the sink.
+pub trait TwoPhaseCommitSink: Send + Sized {
+    /// The type of elements the sink writes
+    type IN: ArconType;
+
+    /// Opens the transaction that the elements of `epoch` are written to
+    fn begin(&mut self, epoch: u64) -> ArconResult<()>;
+
+    /// Writes an element to the open transaction
+    fn write(&mut self, element: ArconElement<Self::IN>) -> ArconResult<()>;
+
+    /// Prepares the open transaction of `epoch` so that committing it can no longer fail
+    fn pre_commit(&mut self, epoch: u64) -> ArconResult<()>;
+
+    /// Commits the pre-committed transactions of `epoch` and every epoch before it
+    fn commit(&mut self, epoch: u64) -> ArconResult<()>;
+
+    /// Resolves the transactions that `instance` left behind in a previous run of the Application
+    ///
+    /// Called before the first transaction is opened. Pre-committed transactions up to and
+    /// including `committed` are committed and every other transaction is aborted, as its
+    /// epoch is going to be replayed.
+    fn recover(&mut self, instance: u32, committed: Option<u64>) -> ArconResult<()>;
+}
+
+/// Runs a [TwoPhaseCommitSink] as an Operator
+pub struct TwoPhaseCommit<S: TwoPhaseCommitSink> {
+    sink: S,
+}
+
+impl<S: TwoPhaseCommitSink> TwoPhaseCommit<S> {
+    pub fn new(sink: S) -> Self {
+        Self { sink }
+    }
+}
+
+impl<S: TwoPhaseCommitSink> Operator for TwoPhaseCommit<S> {
+    type IN = S::IN;
+    type OUT = ArconNever;
+    type TimerState = ArconNever;
+    type OperatorState = EmptyState;
+    type ElementIterator = std::iter::Empty<ArconElement<Self::OUT>>;
+
+    fn on_start(
+        &mut self,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<()> {
+        let restored = ctx.restored_epoch();
+        self.sink.recover(ctx.instance(), restored)?;
+        self.sink
+            .begin(restored.map(|epoch| epoch + 1).unwrap_or(0))
+    }
+
+    fn handle_element(
+        &mut self,
+        element: ArconElement<Self::IN>,
+        _ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator> {
+        self.sink.write(element)?;
+        Ok(std::iter::empty())
+    }
+
+    fn handle_epoch(
+        &mut self,
+        epoch: u64,
+        _ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<()> {
+        self.sink.pre_commit(epoch)?;
+        self.sink.begin(epoch + 1)
+    }
+
+    fn handle_epoch_commit(
+        &mut self,
+        epoch: u64,
+        _ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<()> {
+        self.sink.commit(epoch)
+    }
+
+    crate::ignore_timeout!();
+}
@@ -1,7 +1,10 @@
 // Copyright (c) 2020, KTH Royal Institute of Technology.
 // SPDX-License-Identifier: AGPL-3.0-only
 
-use super::WindowContext;
+use super::{
+    trigger::{CountTrigger, PurgingTrigger, Trigger, TriggerContext, TriggerResult, TriggerState},
+    WindowContext,
+};
 use crate::dataflow::{
     api::Assigner,
     conf::{StreamKind, WindowConf},
@@ -12,43 +15,108 @@ use crate::{
     error::*,
     index::{EagerHashTable, IndexOps},
     reportable_error,
-    stream::operator::{Operator, OperatorContext},
+    stream::operator::{side_output::OutputTag, Operator, OperatorContext},
 };
 use arcon_macros::ArconState;
 use arcon_state::Backend;
+use fxhash::FxHasher;
 use kompact::prelude::error;
 use prost::Message;
-use std::{marker::PhantomData, sync::Arc};
+use std::{
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    sync::Arc,
+};
 
 type Key = u64;
 type Index = u64;
 type Timestamp = u64;
 
 #[cfg_attr(feature = "arcon_serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Message, PartialEq, Clone)]
+#[derive(Message, PartialEq, Clone, Hash)]
 pub struct WindowEvent {
     #[prost(uint64, tag = "1")]
-    key: Key,
+    pub(crate) key: Key,
     #[prost(uint64, tag = "2")]
-    index: Index,
+    pub(crate) index: Index,
     #[prost(uint64, tag = "3")]
-    timestamp: Timestamp,
+    pub(crate) timestamp: Timestamp,
+    /// Set if the timer was registered by a [Trigger]
+    #[prost(bool, tag = "4")]
+    pub(crate) trigger_timer: bool,
+    /// Set if the timer calls [Trigger::on_watermark] at the end of the window
+    #[prost(bool, tag = "5")]
+    pub(crate) watermark_timer: bool,
 }
 
 impl WindowEvent {
-    fn new(key: Key, index: Index, timestamp: Timestamp) -> WindowEvent {
+    pub(crate) fn new(key: Key, index: Index, timestamp: Timestamp) -> WindowEvent {
         WindowEvent {
             key,
             index,
             timestamp,
+            trigger_timer: false,
+            watermark_timer: false,
+        }
+    }
+
+    fn trigger(key: Key, index: Index, timestamp: Timestamp) -> WindowEvent {
+        WindowEvent {
+            trigger_timer: true,
+            ..WindowEvent::new(key, index, timestamp)
         }
     }
+
+    fn watermark(key: Key, index: Index, timestamp: Timestamp) -> WindowEvent {
+        WindowEvent {
+            watermark_timer: true,
+            ..WindowEvent::new(key, index, timestamp)
+        }
+    }
+
+    // Timer id for events that may be scheduled several times per window
+    fn timer_id(&self) -> u64 {
+        let mut s = FxHasher::default();
+        self.hash(&mut s);
+        s.finish()
+    }
+}
+
+/// An active session window
+///
+/// The `index` is fixed for the lifetime of the session, while `start` and `end`
+/// grow as elements extend the session or other sessions are merged into it.
+#[cfg_attr(feature = "arcon_serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Message, PartialEq, Clone)]
+pub struct Session {
+    #[prost(uint64, tag = "1")]
+    pub(crate) index: Index,
+    #[prost(uint64, tag = "2")]
+    pub(crate) start: Timestamp,
+    #[prost(uint64, tag = "3")]
+    pub(crate) end: Timestamp,
+}
+
+impl Session {
+    fn overlaps(&self, other: &Session) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+}
+
+/// Active session windows of a key
+#[cfg_attr(feature = "arcon_serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Message, PartialEq, Clone)]
+pub struct Sessions {
+    #[prost(message, repeated, tag = "1")]
+    pub(crate) sessions: Vec<Session>,
 }
 
 #[derive(ArconState)]
 pub struct WindowState<I: WindowIndex, B: Backend> {
     window_start: EagerHashTable<Key, Timestamp, B>,
     active_windows: EagerHashTable<WindowContext, (), B>,
+    sessions: EagerHashTable<Key, Sessions, B>,
+    trigger_state: EagerHashTable<WindowContext, TriggerState, B>,
     index: I,
 }
 
@@ -56,13 +124,33 @@ impl<I: WindowIndex, B: Backend> WindowState<I, B> {
     pub fn new(index: I, backend: Arc<B>) -> Self {
         Self {
             window_start: EagerHashTable::new("_window_start", backend.clone()),
-            active_windows: EagerHashTable::new("_active_windows", backend),
+            active_windows: EagerHashTable::new("_active_windows", backend.clone()),
+            sessions: EagerHashTable::new("_sessions", backend.clone()),
+            trigger_state: EagerHashTable::new("_trigger_state", backend),
             index,
         }
     }
 }
 
+// How elements are assigned to windows
+#[derive(Clone, Copy, PartialEq)]
+enum WindowKind {
+    /// Sliding and tumbling windows
+    Time,
+    Session {
+        gap: u64,
+    },
+    /// A single never-ending window per key
+    Global,
+}
+
 /// Window Assigner Based on Event Time
+///
+/// Time and session windows emit their result once the watermark passes their end,
+/// while global windows only emit when their [Trigger] fires. A custom [Trigger] set
+/// through [WindowAssigner::with_trigger] replaces the default firing: windows then only
+/// emit when the trigger fires, and are discarded once the watermark passes their end
+/// plus the late arrival time. Global windows require a [Trigger].
 pub struct WindowAssigner<I, B>
 where
     I: WindowIndex,
@@ -72,6 +160,8 @@ where
     window_length: u64,
     window_slide: u64,
     late_arrival_time: u64,
+    kind: WindowKind,
+    trigger: Option<Arc<dyn Trigger>>,
     keyed: bool,
     _marker: PhantomData<(I, B)>,
 }
@@ -96,9 +186,27 @@ where
                 length,
                 late_arrival,
             } => Self::setup(length.0, length.0, late_arrival.0, keyed),
+            Assigner::Session { gap, late_arrival } => {
+                if gap.0 == 0 {
+                    panic!("Session gap must be greater than zero!");
+                }
+                Self::with_kind(WindowKind::Session { gap: gap.0 }, late_arrival.0, keyed)
+            }
+            Assigner::Global => Self::with_kind(WindowKind::Global, 0, keyed),
+            Assigner::Count { size } => Self::with_kind(WindowKind::Global, 0, keyed)
+                .with_trigger(PurgingTrigger::new(CountTrigger::new(size))),
         }
     }
 
+    /// Set a custom [Trigger] for the windows of this assigner
+    ///
+    /// Replaces the default firing of time and session windows at their end,
+    /// as well as the trigger of count windows.
+    pub fn with_trigger(mut self, trigger: impl Trigger) -> Self {
+        self.trigger = Some(Arc::new(trigger));
+        self
+    }
+
     // Setup method for both sliding and tumbling windows
     fn setup(length: u64, slide: u64, late: u64, keyed: bool) -> Self {
         // Sanity check on slide and length
@@ -112,7 +220,17 @@ where
         WindowAssigner {
             window_length: length,
             window_slide: slide,
+            ..Self::with_kind(WindowKind::Time, late, keyed)
+        }
+    }
+
+    fn with_kind(kind: WindowKind, late: u64, keyed: bool) -> Self {
+        WindowAssigner {
+            window_length: 0,
+            window_slide: 0,
             late_arrival_time: late,
+            kind,
+            trigger: None,
             keyed,
             _marker: Default::default(),
         }
@@ -124,8 +242,8 @@ where
         window_ctx: WindowContext,
         ctx: &mut OperatorContext<WindowEvent, WindowState<I, B>>,
     ) -> ArconResult<()> {
-        let window_start = match ctx.state().window_start().get(&window_ctx.key)? {
-            Some(start) => start,
+        let ts = match self.window_end(window_ctx, ctx)? {
+            Some(end) => end,
             None => {
                 return reportable_error!(
                     "Unexpected failure, could not find window start for existing key"
@@ -133,8 +251,6 @@ where
             }
         };
 
-        let ts = window_start + (window_ctx.index * self.window_slide) + self.window_length;
-
         let request = ctx.schedule_at(
             window_ctx,
             ts + self.late_arrival_time,
@@ -148,43 +264,66 @@ where
         Ok(())
     }
 
-    #[inline]
-    fn get_key(&self, e: &ArconElement<I::IN>) -> u64 {
-        if !self.keyed {
-            return 0;
+    fn schedule_event(
+        &mut self,
+        time: u64,
+        event: WindowEvent,
+        ctx: &mut OperatorContext<WindowEvent, WindowState<I, B>>,
+    ) -> ArconResult<()> {
+        let request = ctx.schedule_at(event.timer_id(), time, event)?;
+
+        if let Err(expired) = request {
+            // For now just log the error..
+            error!(ctx.log(), "{}", expired);
         }
-        e.data.get_key()
+        Ok(())
     }
-}
-
-impl<I, B> Operator for WindowAssigner<I, B>
-where
-    I: WindowIndex,
-    B: Backend,
-{
-    type IN = I::IN;
-    type OUT = I::OUT;
-    type TimerState = WindowEvent;
-    type OperatorState = WindowState<I, B>;
-    type ElementIterator = Option<ArconElement<I::OUT>>;
 
-    fn handle_element(
+    // Schedules the call to Trigger::on_watermark once the watermark passes `end`
+    fn schedule_watermark_trigger(
         &mut self,
-        element: ArconElement<Self::IN>,
-        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
-    ) -> ArconResult<Self::ElementIterator> {
-        let ts = element.timestamp;
-
-        let time = ctx.current_time()?;
-
-        let ts_lower_bound = time.saturating_sub(self.late_arrival_time);
-
-        if ts < ts_lower_bound {
-            // Late arrival: early return
-            return Ok(None);
+        window_ctx: WindowContext,
+        end: Timestamp,
+        ctx: &mut OperatorContext<WindowEvent, WindowState<I, B>>,
+    ) -> ArconResult<()> {
+        if self.trigger.is_none() {
+            return Ok(());
         }
+        let event = WindowEvent::watermark(window_ctx.key, window_ctx.index, end);
+        self.schedule_event(end, event, ctx)
+    }
+
+    // Returns the end timestamp of an active window, global windows have no end
+    fn window_end(
+        &self,
+        window_ctx: WindowContext,
+        ctx: &mut OperatorContext<WindowEvent, WindowState<I, B>>,
+    ) -> ArconResult<Option<Timestamp>> {
+        let end = match self.kind {
+            WindowKind::Time => ctx
+                .state()
+                .window_start()
+                .get(&window_ctx.key)?
+                .map(|start| start + (window_ctx.index * self.window_slide) + self.window_length),
+            WindowKind::Session { .. } => ctx
+                .state()
+                .sessions()
+                .get(&window_ctx.key)?
+                .and_then(|s| s.sessions.into_iter().find(|s| s.index == window_ctx.index))
+                .map(|s| s.end),
+            WindowKind::Global => None,
+        };
+        Ok(end)
+    }
 
-        let key = self.get_key(&element);
+    // Adds the element to all sliding or tumbling windows it belongs to
+    fn assign_time_windows(
+        &mut self,
+        element: &ArconElement<I::IN>,
+        ctx: &mut OperatorContext<WindowEvent, WindowState<I, B>>,
+    ) -> ArconResult<Vec<WindowContext>> {
+        let ts = element.timestamp;
+        let key = self.get_key(ctx);
         let start = match ctx.state().window_start().get(&key)? {
             Some(start) => start,
             None => {
@@ -204,6 +343,8 @@ where
             0
         };
 
+        let mut windows = Vec::new();
+
         // For all windows, insert element....
         for index in floor..=ceil {
             let window_ctx = WindowContext { key, index };
@@ -218,10 +359,278 @@ where
                 ctx.state().active_windows().put(window_ctx, ())?;
 
                 self.new_window_trigger(window_ctx, ctx)?;
+                if let Some(end) = self.window_end(window_ctx, ctx)? {
+                    self.schedule_watermark_trigger(window_ctx, end, ctx)?;
+                }
+            }
+            windows.push(window_ctx);
+        }
+
+        Ok(windows)
+    }
+
+    // Adds the element to the session it falls into, merging all sessions it bridges
+    fn assign_session(
+        &mut self,
+        gap: u64,
+        element: &ArconElement<I::IN>,
+        ctx: &mut OperatorContext<WindowEvent, WindowState<I, B>>,
+    ) -> ArconResult<WindowContext> {
+        let key = self.get_key(ctx);
+        let ts = element.timestamp;
+        let mut session = Session {
+            index: ts,
+            start: ts,
+            end: ts + gap,
+        };
+
+        let sessions = ctx.state().sessions().get(&key)?.unwrap_or_default();
+        let (mut bridged, mut sessions): (Vec<Session>, Vec<Session>) = sessions
+            .sessions
+            .into_iter()
+            .partition(|s| s.overlaps(&session));
+
+        // The earliest bridged session survives and absorbs the others
+        bridged.sort_by_key(|s| s.start);
+        let prev_end = bridged.first().map(|s| s.end);
+        if let Some(first) = bridged.first() {
+            session.index = first.index;
+        }
+        let window_ctx = WindowContext::new(key, session.index);
+
+        for s in bridged {
+            session.start = std::cmp::min(session.start, s.start);
+            session.end = std::cmp::max(session.end, s.end);
+            if s.index != session.index {
+                let merged_ctx = WindowContext::new(key, s.index);
+                ctx.state().index().merge(merged_ctx, window_ctx)?;
+                ctx.state().active_windows().remove(&merged_ctx)?;
+
+                if let Some(merged) = ctx.state().trigger_state().remove(&merged_ctx)? {
+                    self.merge_trigger_state(window_ctx, &merged, ctx)?;
+                }
             }
         }
 
-        Ok(None)
+        ctx.state()
+            .index()
+            .on_element(element.data.clone(), window_ctx)?;
+        ctx.state().active_windows().put(window_ctx, ())?;
+
+        // Timers of merged or extended sessions are left to fire and get ignored
+        if prev_end != Some(session.end) {
+            let end = session.end;
+            self.schedule_event(
+                end + self.late_arrival_time,
+                WindowEvent::new(key, session.index, end),
+                ctx,
+            )?;
+            self.schedule_watermark_trigger(window_ctx, end, ctx)?;
+        }
+
+        sessions.push(session);
+        ctx.state().sessions().put(key, Sessions { sessions })?;
+
+        Ok(window_ctx)
+    }
+
+    // Removes the session that `timeout` refers to, returns false if the timer is stale
+    fn remove_session(
+        &mut self,
+        timeout: &WindowEvent,
+        ctx: &mut OperatorContext<WindowEvent, WindowState<I, B>>,
+    ) -> ArconResult<bool> {
+        let mut sessions = match ctx.state().sessions().get(&timeout.key)? {
+            Some(sessions) => sessions.sessions,
+            None => return Ok(false),
+        };
+
+        let pos = sessions
+            .iter()
+            .position(|s| s.index == timeout.index && s.end == timeout.timestamp);
+
+        match pos {
+            Some(pos) => sessions.remove(pos),
+            None => return Ok(false),
+        };
+
+        if sessions.is_empty() {
+            ctx.state().sessions().remove(&timeout.key)?;
+        } else {
+            ctx.state()
+                .sessions()
+                .put(timeout.key, Sessions { sessions })?;
+        }
+
+        Ok(true)
+    }
+
+    // Adds the element to the global window of its key
+    fn assign_global(
+        &mut self,
+        element: &ArconElement<I::IN>,
+        ctx: &mut OperatorContext<WindowEvent, WindowState<I, B>>,
+    ) -> ArconResult<WindowContext> {
+        let window_ctx = WindowContext::new(self.get_key(ctx), 0);
+        ctx.state()
+            .index()
+            .on_element(element.data.clone(), window_ctx)?;
+        ctx.state().active_windows().put(window_ctx, ())?;
+        Ok(window_ctx)
+    }
+
+    // Lets the trigger combine the state of a merged window into `window_ctx`
+    fn merge_trigger_state(
+        &mut self,
+        window_ctx: WindowContext,
+        merged: &TriggerState,
+        ctx: &mut OperatorContext<WindowEvent, WindowState<I, B>>,
+    ) -> ArconResult<()> {
+        let trigger = match &self.trigger {
+            Some(trigger) => trigger.clone(),
+            None => return Ok(()),
+        };
+
+        let window_end = self.window_end(window_ctx, ctx)?;
+        let state = ctx
+            .state()
+            .trigger_state()
+            .get(&window_ctx)?
+            .unwrap_or_default();
+        let mut trigger_ctx = TriggerContext::new(window_ctx, window_end, state);
+
+        trigger.on_merge(merged, &mut trigger_ctx)?;
+
+        if trigger_ctx.changed {
+            ctx.state()
+                .trigger_state()
+                .put(window_ctx, trigger_ctx.state)?;
+        }
+        Ok(())
+    }
+
+    // Evaluates the trigger of the assigner for a window and applies its result
+    fn evaluate_trigger(
+        &mut self,
+        window_ctx: WindowContext,
+        timestamp: Timestamp,
+        ctx: &mut OperatorContext<WindowEvent, WindowState<I, B>>,
+        f: impl FnOnce(&dyn Trigger, &mut TriggerContext) -> ArconResult<TriggerResult>,
+    ) -> ArconResult<Option<ArconElement<I::OUT>>> {
+        let trigger = match &self.trigger {
+            Some(trigger) => trigger.clone(),
+            None => return Ok(None),
+        };
+
+        let window_end = self.window_end(window_ctx, ctx)?;
+        let state = ctx
+            .state()
+            .trigger_state()
+            .get(&window_ctx)?
+            .unwrap_or_default();
+        let mut trigger_ctx = TriggerContext::new(window_ctx, window_end, state);
+
+        let result = f(trigger.as_ref(), &mut trigger_ctx)?;
+
+        for time in std::mem::take(&mut trigger_ctx.timers) {
+            let event = WindowEvent::trigger(window_ctx.key, window_ctx.index, time);
+            self.schedule_event(time, event, ctx)?;
+        }
+
+        let output = if result.is_fire() {
+            let result = ctx.state().index().result(window_ctx)?;
+            Some(ArconElement::with_timestamp(result, timestamp))
+        } else {
+            None
+        };
+
+        if result.is_purge() {
+            let state = ctx.state();
+            state.index().clear(window_ctx)?;
+            state.active_windows().remove(&window_ctx)?;
+            state.trigger_state().remove(&window_ctx)?;
+        } else if trigger_ctx.changed {
+            ctx.state()
+                .trigger_state()
+                .put(window_ctx, trigger_ctx.state)?;
+        }
+
+        Ok(output)
+    }
+
+    #[inline]
+    fn get_key(&self, ctx: &OperatorContext<WindowEvent, WindowState<I, B>>) -> u64 {
+        if !self.keyed {
+            return 0;
+        }
+        ctx.current_key()
+    }
+}
+
+impl<I, B> Operator for WindowAssigner<I, B>
+where
+    I: WindowIndex,
+    B: Backend,
+{
+    type IN = I::IN;
+    type OUT = I::OUT;
+    type TimerState = WindowEvent;
+    type OperatorState = WindowState<I, B>;
+    type ElementIterator = Vec<ArconElement<I::OUT>>;
+
+    fn on_start(
+        &mut self,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<()> {
+        if let WindowKind::Session { .. } = self.kind {
+            if !ctx.state().index().can_merge() {
+                return Err(Error::Unsupported {
+                    msg: "Session windows require a window index that can merge windows, e.g. IncrementalWindow::merging".into(),
+                });
+            }
+        }
+        // A global window without a trigger would never emit and grow without bound
+        if self.kind == WindowKind::Global && self.trigger.is_none() {
+            return Err(Error::Unsupported {
+                msg: "Global windows require a Trigger, see WindowAssigner::with_trigger".into(),
+            });
+        }
+        Ok(())
+    }
+
+    fn handle_element(
+        &mut self,
+        element: ArconElement<Self::IN>,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator> {
+        let ts = element.timestamp;
+
+        let time = ctx.current_time()?;
+
+        let ts_lower_bound = time.saturating_sub(self.late_arrival_time);
+
+        // Global windows never close, so elements cannot be late
+        if ts < ts_lower_bound && self.kind != WindowKind::Global {
+            // Late arrival: early return
+            ctx.output_to(&OutputTag::late(), element);
+            return Ok(Vec::new());
+        }
+
+        let windows = match self.kind {
+            WindowKind::Time => self.assign_time_windows(&element, ctx)?,
+            WindowKind::Session { gap } => vec![self.assign_session(gap, &element, ctx)?],
+            WindowKind::Global => vec![self.assign_global(&element, ctx)?],
+        };
+
+        let mut output = Vec::new();
+        for window_ctx in windows {
+            let fired = self.evaluate_trigger(window_ctx, ts, ctx, |trigger, trigger_ctx| {
+                trigger.on_element(ts, trigger_ctx)
+            })?;
+            output.extend(fired);
+        }
+
+        Ok(output)
     }
 
     fn handle_timeout(
@@ -233,17 +642,56 @@ where
             key,
             index,
             timestamp,
+            trigger_timer,
+            watermark_timer,
         } = timeout;
 
         let window_ctx = WindowContext::new(key, index);
 
+        if trigger_timer || watermark_timer {
+            if !ctx.state().active_windows().contains(&window_ctx)? {
+                return Ok(None);
+            }
+            if watermark_timer && self.window_end(window_ctx, ctx)? != Some(timestamp) {
+                // Stale timer of a session that has been extended
+                return Ok(None);
+            }
+            let fired =
+                self.evaluate_trigger(window_ctx, timestamp, ctx, |trigger, trigger_ctx| {
+                    if watermark_timer {
+                        trigger.on_watermark(timestamp, trigger_ctx)
+                    } else {
+                        trigger.on_timer(timestamp, trigger_ctx)
+                    }
+                })?;
+            return Ok(Some(fired.into_iter().collect()));
+        }
+
+        if let WindowKind::Session { .. } = self.kind {
+            if !self.remove_session(&timeout, ctx)? {
+                // Stale timer of a session that has been merged or extended
+                return Ok(None);
+            }
+        }
+
         let state = ctx.state();
-        let result = state.index().result(window_ctx)?;
-        state.index().clear(window_ctx)?;
 
-        state.active_windows().remove(&window_ctx)?;
+        if state.active_windows().remove(&window_ctx)?.is_none() {
+            // The window has been purged by its trigger
+            return Ok(None);
+        }
+        state.trigger_state().remove(&window_ctx)?;
+
+        // A custom trigger decides when the window emits, so it is only cleaned up here
+        let output = if self.trigger.is_none() {
+            let result = state.index().result(window_ctx)?;
+            Some(vec![ArconElement::with_timestamp(result, timestamp)])
+        } else {
+            None
+        };
+        state.index().clear(window_ctx)?;
 
-        Ok(Some(Some(ArconElement::with_timestamp(result, timestamp))))
+        Ok(output)
     }
 }
 
@@ -256,6 +704,7 @@ mod tests {
     use crate::{
         application::*,
         data::{ArconMessage, NodeID},
+        dataflow::conf::default_key_extractor,
         index::AppenderWindow,
         manager::node::{NodeManager, NodeManagerPort},
         prelude::OperatorBuilder,
@@ -280,6 +729,18 @@ mod tests {
     ) -> (
         ActorRefStrong<ArconMessage<u64>>,
         Arc<Component<DebugNode<u64>>>,
+    ) {
+        assigner_test_setup(Assigner::Sliding {
+            length: Time::seconds(length),
+            slide: Time::seconds(slide),
+            late_arrival: Time::seconds(late),
+        })
+    }
+    fn assigner_test_setup(
+        assigner: Assigner,
+    ) -> (
+        ActorRefStrong<ArconMessage<u64>>,
+        Arc<Component<DebugNode<u64>>>,
     ) {
         let mut app = Application::default();
         let pool_info = app.get_pool_info();
@@ -313,11 +774,7 @@ mod tests {
         let builder = OperatorBuilder {
             operator: Arc::new(move || {
                 let conf = WindowConf {
-                    assigner: Assigner::Sliding {
-                        length: Time::seconds(length),
-                        slide: Time::seconds(slide),
-                        late_arrival: Time::seconds(late),
-                    },
+                    assigner: assigner.clone(),
                     kind: StreamKind::Keyed,
                 };
                 WindowAssigner::new(conf)
@@ -358,6 +815,7 @@ mod tests {
             channel_strategy,
             operator(),
             operator_state(backend.clone()),
+            default_key_extractor(),
             NodeState::new(NodeID::new(0), in_channels, backend.clone()),
             backend,
             app.arcon_logger.clone(),
@@ -590,4 +1048,186 @@ mod tests {
             assert_eq!(r0, &0);
         });
     }
+    #[test]
+    fn session_window_merge() {
+        let (assigner_ref, sink) = assigner_test_setup(Assigner::Session {
+            gap: Time::seconds(5),
+            late_arrival: Time::seconds(0),
+        });
+        wait(1);
+        let moment = now();
+        // key 1: two sessions, [m, m+8) and [m+10, m+15)
+        assigner_ref.tell(timestamped_keyed_event(moment, 1));
+        assigner_ref.tell(timestamped_keyed_event(moment + 3, 1));
+        assigner_ref.tell(timestamped_keyed_event(moment + 10, 1));
+        // key 2: a single session [m+1, m+6)
+        assigner_ref.tell(timestamped_keyed_event(moment + 1, 2));
+        // bridges both sessions of key 1 into [m, m+15)
+        assigner_ref.tell(timestamped_keyed_event(moment + 7, 1));
+
+        wait(1);
+        assigner_ref.tell(watermark(moment + 20));
+        wait(2);
+        sink.on_definition(|cd| {
+            assert_eq!(cd.data.len(), 2);
+            // session of key 2
+            assert_eq!(cd.data[0].data, 1);
+            assert_eq!(cd.data[0].timestamp, moment + 6);
+            // merged session of key 1
+            assert_eq!(cd.data[1].data, 4);
+            assert_eq!(cd.data[1].timestamp, moment + 15);
+        });
+    }
+
+    #[test]
+    fn count_window_by_key() {
+        let (assigner_ref, sink) = assigner_test_setup(Assigner::Count { size: 2 });
+        wait(1);
+        let moment = now();
+        assigner_ref.tell(timestamped_keyed_event(moment, 1));
+        assigner_ref.tell(timestamped_keyed_event(moment, 1));
+        assigner_ref.tell(timestamped_keyed_event(moment + 1, 2));
+        assigner_ref.tell(timestamped_keyed_event(moment + 2, 1));
+        assigner_ref.tell(timestamped_keyed_event(moment + 3, 2));
+        wait(1);
+        sink.on_definition(|cd| {
+            // the third element of key 1 is still pending in a new window
+            assert_eq!(cd.data.len(), 2);
+            assert_eq!(cd.data[0].data, 2);
+            assert_eq!(cd.data[0].timestamp, moment);
+            assert_eq!(cd.data[1].data, 2);
+            assert_eq!(cd.data[1].timestamp, moment + 3);
+        });
+    }
+
+    // Context for driving a WindowAssigner without a Node
+    fn operator_context<I: WindowIndex>(
+        index: I,
+        backend: Arc<Sled>,
+    ) -> OperatorContext<WindowEvent, WindowState<I, Sled>> {
+        use crate::index::Timer;
+        OperatorContext::new(
+            Box::new(Timer::new("_timer", backend.clone())),
+            Box::new(Timer::new("_processing_timer", backend.clone())),
+            WindowState::new(index, backend),
+            crate::application::conf::ApplicationConf::default().arcon_logger(),
+            #[cfg(feature = "metrics")]
+            String::from("window"),
+        )
+    }
+
+    #[test]
+    fn session_window_requires_merging_index() {
+        use crate::index::IncrementalWindow;
+
+        fn init(i: u64) -> u64 {
+            i
+        }
+        fn aggregation(i: u64, agg: &u64) -> u64 {
+            agg + i
+        }
+
+        let backend = Arc::new(crate::test_utils::temp_backend::<Sled>());
+        let index = IncrementalWindow::new(backend.clone(), &init, &aggregation);
+        let mut ctx = operator_context(index, backend);
+        let mut assigner = WindowAssigner::new(WindowConf {
+            assigner: Assigner::Session {
+                gap: Time::seconds(5),
+                late_arrival: Time::seconds(0),
+            },
+            kind: StreamKind::Keyed,
+        });
+
+        assert!(matches!(
+            assigner.on_start(&mut ctx),
+            Err(Error::Unsupported { .. })
+        ));
+    }
+
+    // Fires every window once the watermark passes its end
+    struct EndOfWindowTrigger;
+
+    impl Trigger for EndOfWindowTrigger {
+        fn on_element(&self, _: u64, _: &mut TriggerContext) -> ArconResult<TriggerResult> {
+            Ok(TriggerResult::Continue)
+        }
+        fn on_watermark(&self, _: u64, _: &mut TriggerContext) -> ArconResult<TriggerResult> {
+            Ok(TriggerResult::Fire)
+        }
+    }
+
+    #[test]
+    fn watermark_trigger_at_window_end() {
+        fn appender_fn(u: &[u64]) -> u64 {
+            u.len() as u64
+        }
+
+        let backend = Arc::new(crate::test_utils::temp_backend::<Sled>());
+        let index = AppenderWindow::new(backend.clone(), &appender_fn);
+        let mut ctx = operator_context(index, backend);
+        let mut assigner = WindowAssigner::new(WindowConf {
+            assigner: Assigner::Tumbling {
+                length: Time::seconds(10),
+                late_arrival: Time::seconds(5),
+            },
+            kind: StreamKind::Keyed,
+        })
+        .with_trigger(EndOfWindowTrigger);
+
+        ctx.set_key(1);
+        for ts in &[6, 8, 18] {
+            let output = assigner
+                .handle_element(ArconElement::with_timestamp(1, *ts), &mut ctx)
+                .unwrap();
+            assert!(output.is_empty());
+        }
+
+        let mut output = Vec::new();
+        for timeout in ctx.timer.advance_to(15).unwrap() {
+            if let Some(fired) = assigner.handle_timeout(timeout, &mut ctx).unwrap() {
+                output.extend(fired.into_iter().map(|e| (e.data, e.timestamp)));
+            }
+        }
+
+        // Windows start at the first timestamp minus the late arrival time. The first
+        // window [1, 11) fires at its end, before its late arrival time has passed,
+        // while the second window [11, 21) is not evaluated yet
+        assert_eq!(output, vec![(2, 11)]);
+
+        // The trigger replaces the default firing, so the first window is only
+        // discarded once its late arrival time has passed
+        for timeout in ctx.timer.advance_to(16).unwrap() {
+            assert!(assigner
+                .handle_timeout(timeout, &mut ctx)
+                .unwrap()
+                .is_none());
+        }
+        assert!(!ctx
+            .state()
+            .active_windows()
+            .contains(&WindowContext::new(1, 0))
+            .unwrap());
+    }
+
+    #[test]
+    fn global_window_requires_trigger() {
+        fn appender_fn(u: &[u64]) -> u64 {
+            u.len() as u64
+        }
+
+        let backend = Arc::new(crate::test_utils::temp_backend::<Sled>());
+        let index = AppenderWindow::new(backend.clone(), &appender_fn);
+        let mut ctx = operator_context(index, backend);
+        let mut assigner = WindowAssigner::new(WindowConf {
+            assigner: Assigner::Global,
+            kind: StreamKind::Keyed,
+        });
+        assert!(matches!(
+            assigner.on_start(&mut ctx),
+            Err(Error::Unsupported { .. })
+        ));
+
+        let mut assigner = assigner.with_trigger(EndOfWindowTrigger);
+        assert!(assigner.on_start(&mut ctx).is_ok());
+    }
 }
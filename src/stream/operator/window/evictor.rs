@@ -0,0 +1,49 @@
+// Copyright (c) 2020, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+/// Removes elements from a window buffer before the window result is computed
+///
+/// Evicted elements are removed from the window state as well, which makes
+/// evictors useful for windows that fire several times without being purged.
+pub trait Evictor<IN>: Send + Sync + 'static {
+    /// Evicts elements from `elements`, which are kept in arrival order
+    fn evict(&self, elements: &mut Vec<IN>);
+}
+
+/// Keeps at most `count` of the most recently added elements
+#[derive(Clone, Copy)]
+pub struct CountEvictor {
+    count: usize,
+}
+
+impl CountEvictor {
+    pub fn new(count: usize) -> Self {
+        Self { count }
+    }
+}
+
+impl<IN> Evictor<IN> for CountEvictor {
+    fn evict(&self, elements: &mut Vec<IN>) {
+        if elements.len() > self.count {
+            let evicted = elements.len() - self.count;
+            elements.drain(..evicted);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_evictor_test() {
+        let evictor = CountEvictor::new(3);
+        let mut elements: Vec<u64> = (0..5).collect();
+        evictor.evict(&mut elements);
+        assert_eq!(elements, vec![2, 3, 4]);
+
+        let mut elements: Vec<u64> = vec![1];
+        evictor.evict(&mut elements);
+        assert_eq!(elements, vec![1]);
+    }
+}
@@ -2,8 +2,14 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 
 pub mod assigner;
+pub mod evictor;
+pub mod trigger;
 
 pub use assigner::{WindowAssigner, WindowState};
+pub use evictor::{CountEvictor, Evictor};
+pub use trigger::{
+    CountTrigger, PurgingTrigger, Trigger, TriggerContext, TriggerResult, TriggerState,
+};
 
 use fxhash::FxHasher;
 use std::hash::{Hash, Hasher};
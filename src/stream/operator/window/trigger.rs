@@ -0,0 +1,252 @@
+// Copyright (c) 2020, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use super::WindowContext;
+use crate::error::ArconResult;
+use arcon_state::error::ArconStateError;
+use prost::Message;
+
+/// Action to take on a window after a [Trigger] has been evaluated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerResult {
+    /// Do nothing
+    Continue,
+    /// Emit the current result of the window and keep its state
+    Fire,
+    /// Discard the window state without emitting
+    Purge,
+    /// Emit the current result of the window and discard its state
+    FireAndPurge,
+}
+
+impl TriggerResult {
+    #[inline]
+    pub fn is_fire(&self) -> bool {
+        matches!(self, TriggerResult::Fire | TriggerResult::FireAndPurge)
+    }
+    #[inline]
+    pub fn is_purge(&self) -> bool {
+        matches!(self, TriggerResult::Purge | TriggerResult::FireAndPurge)
+    }
+}
+
+/// Per-window state that is kept on behalf of a [Trigger]
+///
+/// The state is opaque to the window operator. Triggers keep any [Message] in it
+/// through [TriggerContext::state] and [TriggerContext::set_state].
+#[cfg_attr(feature = "arcon_serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Message, PartialEq, Clone)]
+pub struct TriggerState {
+    #[prost(bytes, tag = "1")]
+    pub(crate) bytes: Vec<u8>,
+}
+
+impl TriggerState {
+    fn decode<S: Message + Default>(&self) -> ArconResult<S> {
+        let state = S::decode(self.bytes.as_slice()).map_err(ArconStateError::from)?;
+        Ok(state)
+    }
+
+    fn encode<S: Message>(state: &S) -> ArconResult<Self> {
+        let mut bytes = Vec::with_capacity(state.encoded_len());
+        state.encode(&mut bytes).map_err(ArconStateError::from)?;
+        Ok(Self { bytes })
+    }
+}
+
+/// Context passed to a [Trigger] when it is evaluated for a window
+pub struct TriggerContext {
+    /// The window the trigger is evaluated for
+    pub window: WindowContext,
+    /// End timestamp of the window, if it has one
+    ///
+    /// Global windows have no end.
+    pub window_end: Option<u64>,
+    pub(crate) state: TriggerState,
+    pub(crate) changed: bool,
+    pub(crate) timers: Vec<u64>,
+}
+
+impl TriggerContext {
+    pub(crate) fn new(window: WindowContext, window_end: Option<u64>, state: TriggerState) -> Self {
+        Self {
+            window,
+            window_end,
+            state,
+            changed: false,
+            timers: Vec::new(),
+        }
+    }
+
+    /// Returns the state of the trigger for this window
+    ///
+    /// A window without state returns the default value of `S`.
+    pub fn state<S: Message + Default>(&self) -> ArconResult<S> {
+        self.state.decode()
+    }
+
+    /// Replaces the state of the trigger for this window
+    pub fn set_state<S: Message>(&mut self, state: &S) -> ArconResult<()> {
+        let state = TriggerState::encode(state)?;
+        if state != self.state {
+            self.state = state;
+            self.changed = true;
+        }
+        Ok(())
+    }
+
+    /// Register an event-time timer for the window
+    ///
+    /// [Trigger::on_timer] is called once the watermark passes `time`.
+    pub fn register_timer(&mut self, time: u64) {
+        self.timers.push(time);
+    }
+}
+
+/// Decides when a window emits its result and when its state is discarded
+pub trait Trigger: Send + Sync + 'static {
+    /// Called for every element that is added to a window
+    fn on_element(&self, timestamp: u64, ctx: &mut TriggerContext) -> ArconResult<TriggerResult>;
+
+    /// Called when a timer registered through [TriggerContext::register_timer] fires
+    fn on_timer(&self, _time: u64, _ctx: &mut TriggerContext) -> ArconResult<TriggerResult> {
+        Ok(TriggerResult::Continue)
+    }
+
+    /// Called once the watermark passes the end of a window
+    ///
+    /// `window_end` is the end timestamp of the window. Global windows have no end
+    /// and are never passed to this function.
+    fn on_watermark(
+        &self,
+        _window_end: u64,
+        _ctx: &mut TriggerContext,
+    ) -> ArconResult<TriggerResult> {
+        Ok(TriggerResult::Continue)
+    }
+
+    /// Combines the state of a window that has been merged into `ctx.window`
+    ///
+    /// Called by merging window assigners such as session windows. The state of
+    /// the surviving window is kept by default.
+    fn on_merge(&self, _merged: &TriggerState, _ctx: &mut TriggerContext) -> ArconResult<()> {
+        Ok(())
+    }
+}
+
+/// Fires every `count` elements of a window
+#[derive(Clone, Copy)]
+pub struct CountTrigger {
+    count: u64,
+}
+
+impl CountTrigger {
+    pub fn new(count: u64) -> Self {
+        assert!(count > 0, "CountTrigger requires a count greater than zero");
+        Self { count }
+    }
+}
+
+impl Trigger for CountTrigger {
+    fn on_element(&self, _timestamp: u64, ctx: &mut TriggerContext) -> ArconResult<TriggerResult> {
+        let count: u64 = ctx.state()? + 1;
+        if count >= self.count {
+            ctx.set_state(&0u64)?;
+            Ok(TriggerResult::Fire)
+        } else {
+            ctx.set_state(&count)?;
+            Ok(TriggerResult::Continue)
+        }
+    }
+
+    fn on_merge(&self, merged: &TriggerState, ctx: &mut TriggerContext) -> ArconResult<()> {
+        let count: u64 = ctx.state()? + merged.decode::<u64>()?;
+        ctx.set_state(&count)
+    }
+}
+
+/// Turns every [TriggerResult::Fire] of the inner trigger into [TriggerResult::FireAndPurge]
+pub struct PurgingTrigger<T: Trigger> {
+    inner: T,
+}
+
+impl<T: Trigger> PurgingTrigger<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    #[inline]
+    fn purging(result: TriggerResult) -> TriggerResult {
+        match result {
+            TriggerResult::Fire => TriggerResult::FireAndPurge,
+            other => other,
+        }
+    }
+}
+
+impl<T: Trigger> Trigger for PurgingTrigger<T> {
+    fn on_element(&self, timestamp: u64, ctx: &mut TriggerContext) -> ArconResult<TriggerResult> {
+        self.inner.on_element(timestamp, ctx).map(Self::purging)
+    }
+    fn on_timer(&self, time: u64, ctx: &mut TriggerContext) -> ArconResult<TriggerResult> {
+        self.inner.on_timer(time, ctx).map(Self::purging)
+    }
+    fn on_watermark(
+        &self,
+        window_end: u64,
+        ctx: &mut TriggerContext,
+    ) -> ArconResult<TriggerResult> {
+        self.inner.on_watermark(window_end, ctx).map(Self::purging)
+    }
+    fn on_merge(&self, merged: &TriggerState, ctx: &mut TriggerContext) -> ArconResult<()> {
+        self.inner.on_merge(merged, ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn purging_count_trigger_test() {
+        let trigger = PurgingTrigger::new(CountTrigger::new(3));
+        let mut ctx = TriggerContext::new(WindowContext::new(0, 0), None, TriggerState::default());
+
+        assert_eq!(
+            trigger.on_element(1, &mut ctx).unwrap(),
+            TriggerResult::Continue
+        );
+        assert_eq!(
+            trigger.on_element(2, &mut ctx).unwrap(),
+            TriggerResult::Continue
+        );
+        assert_eq!(
+            trigger.on_element(3, &mut ctx).unwrap(),
+            TriggerResult::FireAndPurge
+        );
+        assert_eq!(ctx.state::<u64>().unwrap(), 0);
+    }
+
+    #[test]
+    fn count_trigger_merge_test() {
+        let trigger = CountTrigger::new(5);
+        let mut merged =
+            TriggerContext::new(WindowContext::new(0, 1), None, TriggerState::default());
+        trigger.on_element(1, &mut merged).unwrap();
+        trigger.on_element(2, &mut merged).unwrap();
+
+        let mut ctx = TriggerContext::new(WindowContext::new(0, 0), None, TriggerState::default());
+        trigger.on_element(3, &mut ctx).unwrap();
+        trigger.on_merge(&merged.state, &mut ctx).unwrap();
+        assert_eq!(ctx.state::<u64>().unwrap(), 3);
+    }
+
+    #[test]
+    fn unchanged_state_test() {
+        let mut ctx = TriggerContext::new(WindowContext::new(0, 0), None, TriggerState::default());
+        ctx.set_state(&0u64).unwrap();
+        assert!(!ctx.changed);
+        ctx.set_state(&1u64).unwrap();
+        assert!(ctx.changed);
+    }
+}
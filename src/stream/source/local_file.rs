@@ -61,5 +61,10 @@ where
             None => Ok(Ok(Poll::Done)),
         }
     }
-    fn set_offset(&mut self, _: usize) {}
+    fn set_offset(&mut self, offset: usize) {
+        // skip the lines that have already been consumed
+        if offset > 0 {
+            let _ = self.lines.nth(offset - 1);
+        }
+    }
 }
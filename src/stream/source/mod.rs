@@ -30,6 +30,8 @@ pub trait Source: Send + 'static {
     /// Set offset for the source
     ///
     /// May be used by replayable sources to set a certain offset..
+    /// The offset is the number of records that have already been consumed,
+    /// and is set before the first poll when an Application is restored.
     fn set_offset(&mut self, offset: usize);
 }
 
@@ -47,7 +49,12 @@ where
             None => Ok(Ok(Poll::Done)),
         }
     }
-    fn set_offset(&mut self, _: usize) {}
+    fn set_offset(&mut self, offset: usize) {
+        // skip the items that have already been consumed
+        if offset > 0 {
+            let _ = self.nth(offset - 1);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -64,7 +71,10 @@ mod tests {
             sum
         }
         let v: Vec<u32> = vec![1, 2, 3, 4];
-        let sum = sum(v.into_iter());
-        assert_eq!(sum, 10);
+        assert_eq!(sum(v.clone().into_iter()), 10);
+
+        let mut source = v.into_iter();
+        source.set_offset(2);
+        assert_eq!(sum(source), 7);
     }
 }
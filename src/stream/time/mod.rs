@@ -13,7 +13,7 @@ impl Default for ArconTime {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub struct Time(pub u64);
 
 impl Time {
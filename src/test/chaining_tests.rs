@@ -0,0 +1,65 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{prelude::*, test_utils::wait_until};
+use std::time::Duration;
+
+fn chained_app(operator_chaining: bool) -> AssembledApplication {
+    let conf = ApplicationConf {
+        operator_chaining,
+        ..Default::default()
+    };
+    Application::with_conf(conf)
+        .with_debug_node()
+        .iterator(0u64..100, |conf| {
+            conf.set_arcon_time(ArconTime::Process);
+        })
+        .map(|x| x + 1)
+        .filter(|x| x % 2 == 0)
+        .flatmap(|x| vec![x, x * 10])
+        .map_in_place(|x| *x += 1)
+        .build()
+}
+
+/// Runs the application until it has produced `elements` elements
+///
+/// Returns the sorted output along with the number of NodeManagers the application created.
+fn run(operator_chaining: bool, elements: usize) -> (Vec<u64>, usize) {
+    let mut app = chained_app(operator_chaining);
+    let node_managers = app.num_node_managers();
+    app.start();
+    let debug_node = app.get_debug_node::<u64>().unwrap();
+    assert!(wait_until(Duration::from_secs(10), || {
+        debug_node.on_definition(|cd| cd.data.len() >= elements)
+    }));
+    let mut output: Vec<u64> =
+        debug_node.on_definition(|cd| cd.data.iter().map(|elem| elem.data).collect());
+    app.shutdown();
+    output.sort_unstable();
+    (output, node_managers)
+}
+
+#[test]
+fn operator_chaining_test() {
+    let mut expected: Vec<u64> = (0u64..100)
+        .map(|x| x + 1)
+        .filter(|x| x % 2 == 0)
+        .flat_map(|x| vec![x + 1, x * 10 + 1])
+        .collect();
+    expected.sort_unstable();
+
+    // The Operators after the map are fused into its Nodes and get no Nodes of their own,
+    // the map itself is only fused into the source if their parallelism matches
+    let chained_managers = match ParallelismStrategy::default() {
+        ParallelismStrategy::Static(1) => 0,
+        _ => 1,
+    };
+    let (output, node_managers) = run(true, expected.len());
+    assert_eq!(output, expected);
+    assert_eq!(node_managers, chained_managers);
+
+    // Disabling chaining gives every Operator Nodes of its own without changing the output
+    let (output, node_managers) = run(false, expected.len());
+    assert_eq!(output, expected);
+    assert_eq!(node_managers, 4);
+}
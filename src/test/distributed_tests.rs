@@ -0,0 +1,221 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{prelude::*, test_utils::wait_until};
+use std::{
+    net::TcpListener,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    time::Duration,
+};
+
+const ELEMENTS: u64 = 100;
+const PEERS: usize = 2;
+const TIMEOUT: Duration = Duration::from_secs(30);
+/// Number of times the peers are built on fresh ports before a test gives up
+const BIND_ATTEMPTS: usize = 5;
+
+/// Reserves a loopback port for every peer, which stays reserved until its listener is dropped
+fn reserve_peers() -> Vec<TcpListener> {
+    (0..PEERS)
+        .map(|_| TcpListener::bind("127.0.0.1:0").unwrap())
+        .collect()
+}
+
+fn peer_conf(base_dir: &Path, peers: &[String], peer: usize) -> ApplicationConf {
+    ApplicationConf {
+        base_dir: base_dir.to_path_buf(),
+        epoch_interval: 100,
+        execution_mode: ExecutionMode::Distributed(DistributedConf {
+            peers: peers.to_vec(),
+            peer,
+            flight_serde: Default::default(),
+        }),
+        ..Default::default()
+    }
+}
+
+fn map_app(conf: ApplicationConf) -> AssembledApplication {
+    Application::with_conf(conf)
+        .with_debug_node()
+        .iterator(0u64..ELEMENTS, |conf| {
+            conf.set_arcon_time(ArconTime::Process);
+        })
+        .operator(OperatorBuilder {
+            operator: Arc::new(|| Map::new(|x: u64| x + 1)),
+            state: Arc::new(|_| EmptyState),
+            conf: OperatorConf {
+                parallelism_strategy: ParallelismStrategy::Static(PEERS),
+                ..Default::default()
+            },
+        })
+        .build()
+}
+
+/// Sends odd elements to a side output and forwards the even ones
+struct OddSplitter {
+    tag: OutputTag<u64>,
+}
+
+impl Operator for OddSplitter {
+    type IN = u64;
+    type OUT = u64;
+    type TimerState = ArconNever;
+    type OperatorState = EmptyState;
+    type ElementIterator = Option<ArconElement<u64>>;
+
+    fn handle_element(
+        &mut self,
+        element: ArconElement<u64>,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator> {
+        if element.data % 2 == 0 {
+            Ok(Some(element))
+        } else {
+            ctx.output_to(&self.tag, element);
+            Ok(None)
+        }
+    }
+    crate::ignore_timeout!();
+}
+
+fn side_output_app(conf: ApplicationConf) -> AssembledApplication {
+    let stream = Application::with_conf(conf)
+        .with_debug_node()
+        .iterator(0u64..ELEMENTS, |conf| {
+            conf.set_arcon_time(ArconTime::Process);
+        })
+        .operator(OperatorBuilder {
+            operator: Arc::new(|| OddSplitter {
+                tag: OutputTag::new("odd"),
+            }),
+            state: Arc::new(|_| EmptyState),
+            conf: OperatorConf {
+                parallelism_strategy: ParallelismStrategy::Static(PEERS),
+                ..Default::default()
+            },
+        });
+    let _evens = stream.clone().map(|x| x * 2);
+    // the single instance of the map receives the side outputs of both peers
+    stream
+        .side_output(OutputTag::<u64>::new("odd"))
+        .map(|x| x * 10)
+        .build()
+}
+
+/// Builds every peer before any of them is started
+///
+/// The port of a peer is only released right before the peer binds it. Should another process
+/// take it in between, the peers are built again on fresh ports.
+fn start_peers(
+    dirs: &[tempfile::TempDir],
+    app: impl Fn(ApplicationConf) -> AssembledApplication,
+) -> Vec<AssembledApplication> {
+    for _ in 0..BIND_ATTEMPTS {
+        let listeners = reserve_peers();
+        let peers: Vec<String> = listeners
+            .iter()
+            .map(|listener| listener.local_addr().unwrap().to_string())
+            .collect();
+        let built = panic::catch_unwind(AssertUnwindSafe(|| {
+            dirs.iter()
+                .zip(listeners)
+                .enumerate()
+                .map(|(peer, (dir, listener))| {
+                    drop(listener);
+                    app(peer_conf(dir.path(), &peers, peer))
+                })
+                .collect::<Vec<AssembledApplication>>()
+        }));
+        if let Ok(mut apps) = built {
+            for app in apps.iter_mut() {
+                app.start();
+            }
+            return apps;
+        }
+    }
+    panic!("the peers could not bind their ports");
+}
+
+/// Waits until the debug nodes of all peers have received `expected` elements in total
+fn collect_output(apps: &[AssembledApplication], expected: usize) -> Vec<u64> {
+    let output = || {
+        let mut output: Vec<u64> = Vec::new();
+        for app in apps {
+            let debug_node = app.get_debug_node::<u64>().unwrap();
+            debug_node.on_definition(|cd| output.extend(cd.data.iter().map(|elem| elem.data)));
+        }
+        output.sort_unstable();
+        output
+    };
+    assert!(wait_until(TIMEOUT, || output().len() >= expected));
+    output()
+}
+
+fn temp_dirs() -> Vec<tempfile::TempDir> {
+    (0..PEERS).map(|_| tempfile::tempdir().unwrap()).collect()
+}
+
+#[test]
+fn loopback_pipeline_test() {
+    let dirs = temp_dirs();
+    let apps = start_peers(&dirs, map_app);
+
+    // each process receives the output of its own instance of the map
+    let output = collect_output(&apps, ELEMENTS as usize);
+    assert_eq!(output, (1..=ELEMENTS).collect::<Vec<u64>>());
+    for app in &apps {
+        app.get_debug_node::<u64>()
+            .unwrap()
+            .on_definition(|cd| assert!(!cd.data.is_empty(), "a peer received no elements"));
+    }
+
+    for app in apps {
+        app.shutdown();
+    }
+}
+
+#[test]
+fn remote_side_output_test() {
+    let dirs = temp_dirs();
+    let apps = start_peers(&dirs, side_output_app);
+
+    let odds = (0..ELEMENTS).filter(|x| x % 2 == 1).count();
+    let output = collect_output(&apps, odds);
+    let expected: Vec<u64> = (0..ELEMENTS)
+        .filter(|x| x % 2 == 1)
+        .map(|x| x * 10)
+        .collect();
+    assert_eq!(output, expected);
+
+    for app in apps {
+        app.shutdown();
+    }
+}
+
+#[test]
+fn global_epoch_commit_test() {
+    let dirs = temp_dirs();
+    let apps = start_peers(&dirs, map_app);
+    collect_output(&apps, ELEMENTS as usize);
+
+    // the final epoch is only committed once both peers have acked it, and then by both
+    let latest_commits = || -> Vec<Option<u64>> {
+        dirs.iter()
+            .map(|dir| {
+                let checkpoints_dir = dir.path().join("checkpoints");
+                SnapshotCatalog::read(&checkpoints_dir)
+                    .unwrap()
+                    .and_then(|catalog| catalog.epochs.last().map(|committed| committed.epoch))
+            })
+            .collect()
+    };
+    assert!(wait_until(TIMEOUT, || {
+        let commits = latest_commits();
+        commits[0].is_some() && commits.iter().all(|commit| *commit == commits[0])
+    }));
+
+    for app in apps {
+        app.shutdown();
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{prelude::*, test_utils::wait_until};
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::Duration,
+};
+
+const ELEMENTS: u64 = 500;
+const CREDITS: usize = 2;
+const BATCH_SIZE: usize = 4;
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn conf() -> ApplicationConf {
+    ApplicationConf {
+        channel_credits: CREDITS,
+        channel_batch_size: BATCH_SIZE,
+        ..Default::default()
+    }
+}
+
+fn single_instance() -> OperatorConf {
+    OperatorConf {
+        parallelism_strategy: ParallelismStrategy::Static(1),
+        ..Default::default()
+    }
+}
+
+/// Takes a while for each element and counts the elements it has handled
+struct SlowMap {
+    processed: Arc<AtomicU64>,
+}
+
+impl Operator for SlowMap {
+    type IN = u64;
+    type OUT = u64;
+    type TimerState = ArconNever;
+    type OperatorState = EmptyState;
+    type ElementIterator = Option<ArconElement<u64>>;
+
+    fn handle_element(
+        &mut self,
+        element: ArconElement<u64>,
+        _: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator> {
+        thread::sleep(Duration::from_millis(2));
+        self.processed.fetch_add(1, Ordering::SeqCst);
+        Ok(Some(element))
+    }
+    crate::ignore_timeout!();
+}
+
+fn slow_map(processed: Arc<AtomicU64>) -> OperatorBuilder<SlowMap> {
+    OperatorBuilder {
+        operator: Arc::new(move || SlowMap {
+            processed: processed.clone(),
+        }),
+        state: Arc::new(|_| EmptyState),
+        conf: single_instance(),
+    }
+}
+
+/// Sends every element to a side output
+struct SideOutputAll {
+    tag: OutputTag<u64>,
+}
+
+impl Operator for SideOutputAll {
+    type IN = u64;
+    type OUT = u64;
+    type TimerState = ArconNever;
+    type OperatorState = EmptyState;
+    type ElementIterator = Option<ArconElement<u64>>;
+
+    fn handle_element(
+        &mut self,
+        element: ArconElement<u64>,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator> {
+        ctx.output_to(&self.tag, element);
+        Ok(None)
+    }
+    crate::ignore_timeout!();
+}
+
+/// Runs `app` and checks that the source never polls more than `bound` elements
+/// ahead of the slow Operator, which processes every element in the end
+fn assert_paused_source(
+    mut app: AssembledApplication,
+    polled: &AtomicU64,
+    processed: &AtomicU64,
+    bound: u64,
+) {
+    app.start();
+    let debug_node = app.get_debug_node::<u64>().unwrap();
+    let mut samples = 0;
+    assert!(wait_until(TIMEOUT, || {
+        // the source may only have polled ahead by as much as the credits allow
+        let polled = polled.load(Ordering::SeqCst);
+        let processed = processed.load(Ordering::SeqCst);
+        assert!(
+            polled <= processed + bound,
+            "source polled {} elements while {} were processed",
+            polled,
+            processed
+        );
+        samples += 1;
+        debug_node.on_definition(|cd| cd.data.len() as u64 == ELEMENTS)
+    }));
+    assert!(
+        samples > 1,
+        "the pipeline finished before it was backpressured"
+    );
+    debug_node.on_definition(|cd| {
+        let mut data: Vec<u64> = cd.data.iter().map(|elem| elem.data).collect();
+        data.sort_unstable();
+        assert_eq!(data, (0..ELEMENTS).collect::<Vec<u64>>());
+    });
+    app.shutdown();
+}
+
+#[test]
+fn slow_operator_pauses_source_test() {
+    let polled = Arc::new(AtomicU64::new(0));
+    let processed = Arc::new(AtomicU64::new(0));
+    let counter = polled.clone();
+    let app = Application::with_conf(conf())
+        .with_debug_node()
+        .iterator(
+            (0..ELEMENTS).inspect(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }),
+            |conf| {
+                conf.set_arcon_time(ArconTime::Process);
+            },
+        )
+        .operator(slow_map(processed.clone()))
+        .build();
+
+    // messages in flight, one held back and a partially filled buffer
+    let bound = ((CREDITS + 2) * BATCH_SIZE) as u64;
+    assert_paused_source(app, &polled, &processed, bound);
+}
+
+#[test]
+fn slow_side_output_pauses_source_test() {
+    let polled = Arc::new(AtomicU64::new(0));
+    let processed = Arc::new(AtomicU64::new(0));
+    let counter = polled.clone();
+    let app = Application::with_conf(conf())
+        .with_debug_node()
+        .iterator(
+            (0..ELEMENTS).inspect(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }),
+            |conf| {
+                conf.set_arcon_time(ArconTime::Process);
+            },
+        )
+        .operator(OperatorBuilder {
+            operator: Arc::new(|| SideOutputAll {
+                tag: OutputTag::new("all"),
+            }),
+            state: Arc::new(|_| EmptyState),
+            conf: single_instance(),
+        })
+        .side_output(OutputTag::<u64>::new("all"))
+        .operator(slow_map(processed.clone()))
+        .build();
+
+    // the Operator in between stashes its input once its side output is out of credits
+    let bound = (2 * (CREDITS + 2) * BATCH_SIZE) as u64;
+    assert_paused_source(app, &polled, &processed, bound);
+}
@@ -4,3 +4,11 @@
 //#[cfg(test)]
 //mod recovery_tests;
 mod arcon_state;
+mod chaining_tests;
+mod distributed_tests;
+mod flow_control_tests;
+mod multi_input_tests;
+mod rebalance_tests;
+mod restore_tests;
+mod scaling_tests;
+mod split_tests;
@@ -0,0 +1,300 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{prelude::*, test_utils::wait_until};
+use std::{thread, time::Duration};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Emits `range` slowly enough for several epochs to be injected meanwhile
+fn slow(range: std::ops::Range<u64>) -> impl Iterator<Item = u64> + Clone + Send + Sync {
+    range.map(|x| {
+        thread::sleep(Duration::from_millis(2));
+        x
+    })
+}
+
+#[test]
+fn union_test() {
+    let app = Application::default().with_debug_node();
+    let other = app.clone().iterator(100u64..200, |conf| {
+        conf.set_arcon_time(ArconTime::Process);
+    });
+    let mut app = app
+        .iterator(0u64..100, |conf| {
+            conf.set_arcon_time(ArconTime::Process);
+        })
+        .union(other)
+        .map(|x| x * 2)
+        .build();
+
+    app.start();
+    let debug_node = app.get_debug_node::<u64>().unwrap();
+    assert!(wait_until(TIMEOUT, || {
+        debug_node.on_definition(|cd| cd.data.len() == 200)
+    }));
+    debug_node.on_definition(|cd| {
+        let mut data: Vec<u64> = cd.data.iter().map(|elem| elem.data).collect();
+        data.sort_unstable();
+        assert_eq!(data, (0u64..200).map(|x| x * 2).collect::<Vec<u64>>());
+    });
+    app.shutdown();
+}
+
+#[test]
+#[should_panic(expected = "different Applications")]
+fn union_of_different_applications_test() {
+    let other = Application::default().iterator(0u64..100, |conf| {
+        conf.set_arcon_time(ArconTime::Process);
+    });
+    let _ = Application::default()
+        .iterator(0u64..100, |conf| {
+            conf.set_arcon_time(ArconTime::Process);
+        })
+        .union(other);
+}
+
+#[test]
+fn union_alignment_test() {
+    let conf = ApplicationConf {
+        epoch_interval: 25,
+        watermark_interval: 25,
+        ..Default::default()
+    };
+    let app = Application::with_conf(conf).with_debug_node();
+    // The second input runs far ahead in event time
+    let fast = app.clone().iterator(slow(1001..1101), |conf| {
+        conf.set_arcon_time(ArconTime::Event);
+        conf.set_timestamp_extractor(|x: &u64| *x);
+    });
+    let mut app = app
+        .iterator(slow(1..101), |conf| {
+            conf.set_arcon_time(ArconTime::Event);
+            conf.set_timestamp_extractor(|x: &u64| *x);
+        })
+        .union(fast)
+        .operator(OperatorBuilder {
+            operator: Arc::new(|| Map::new(|x| x + 1)),
+            state: Arc::new(|_| EmptyState),
+            // A single instance, so that the debug node sees one aligned stream of markers
+            conf: OperatorConf {
+                parallelism_strategy: ParallelismStrategy::Static(1),
+                ..Default::default()
+            },
+        })
+        .build();
+
+    app.start();
+    let debug_node = app.get_debug_node::<u64>().unwrap();
+    assert!(wait_until(TIMEOUT, || {
+        debug_node.on_definition(|cd| {
+            cd.data.len() == 200 && cd.watermarks.last().map(|w| w.timestamp) == Some(100)
+        })
+    }));
+    debug_node.on_definition(|cd| {
+        // Watermarks follow the slowest input
+        let watermarks: Vec<u64> = cd.watermarks.iter().map(|w| w.timestamp).collect();
+        assert!(watermarks.windows(2).all(|w| w[0] < w[1]));
+        assert!(watermarks.iter().all(|w| *w <= 100));
+        // Each epoch is forwarded once both inputs have delivered it
+        let epochs: Vec<u64> = cd.epochs.iter().map(|e| e.epoch).collect();
+        assert!(!epochs.is_empty());
+        assert!(epochs.windows(2).all(|e| e[1] == e[0] + 1));
+    });
+    app.shutdown();
+}
+
+/// Tags the elements of each input so that they can be told apart downstream
+struct Tag;
+
+impl CoOperator for Tag {
+    type IN1 = u64;
+    type IN2 = u32;
+    type OUT = String;
+    type TimerState = ArconNever;
+    type OperatorState = EmptyState;
+    type ElementIterator = Option<ArconElement<Self::OUT>>;
+
+    fn handle_element1(
+        &mut self,
+        element: ArconElement<u64>,
+        _ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator> {
+        Ok(Some(ArconElement::new(format!("first-{}", element.data))))
+    }
+
+    fn handle_element2(
+        &mut self,
+        element: ArconElement<u32>,
+        _ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator> {
+        Ok(Some(ArconElement::new(format!("second-{}", element.data))))
+    }
+
+    crate::ignore_timeout!();
+}
+
+#[test]
+fn connect_test() {
+    let app = Application::default().with_debug_node();
+    let second = app.clone().iterator(0u32..10, |conf| {
+        conf.set_arcon_time(ArconTime::Process);
+    });
+    let mut app = app
+        .iterator(0u64..10, |conf| {
+            conf.set_arcon_time(ArconTime::Process);
+        })
+        .connect(second)
+        .key_by(|x| *x, |y| *y as u64)
+        .operator(CoOperatorBuilder {
+            operator: Arc::new(|| Tag),
+            state: Arc::new(|_| EmptyState),
+            conf: Default::default(),
+        })
+        .build();
+
+    app.start();
+    let debug_node = app.get_debug_node::<String>().unwrap();
+    assert!(wait_until(TIMEOUT, || {
+        debug_node.on_definition(|cd| cd.data.len() == 20)
+    }));
+    debug_node.on_definition(|cd| {
+        let mut data: Vec<String> = cd.data.iter().map(|elem| elem.data.clone()).collect();
+        data.sort();
+        let mut expected: Vec<String> = (0..10)
+            .flat_map(|x| vec![format!("first-{}", x), format!("second-{}", x)])
+            .collect();
+        expected.sort();
+        assert_eq!(data, expected);
+    });
+    app.shutdown();
+}
+
+#[test]
+fn interval_join_test() {
+    let app = Application::default().with_debug_node();
+    let second = app.clone().iterator(1u64..21, |conf| {
+        conf.set_arcon_time(ArconTime::Event);
+        conf.set_timestamp_extractor(|x: &u64| *x);
+    });
+    let mut app = app
+        .iterator(1u64..21, |conf| {
+            conf.set_arcon_time(ArconTime::Event);
+            conf.set_timestamp_extractor(|x: &u64| *x);
+        })
+        .connect(second)
+        .key_by(|x| x % 2, |y| y % 2)
+        .interval_join(
+            IntervalJoinConf {
+                lower: Time::seconds(2),
+                upper: Time::seconds(0),
+                kind: StreamKind::Keyed,
+            },
+            |a, b| a * 100 + b,
+        )
+        .build();
+
+    // Pairs of the same parity where b is at most 2 below a
+    let mut expected: Vec<u64> = (1u64..21)
+        .flat_map(|a| {
+            vec![a, a.saturating_sub(2)]
+                .into_iter()
+                .map(move |b| (a, b))
+        })
+        .filter(|(_, b)| *b >= 1)
+        .map(|(a, b)| a * 100 + b)
+        .collect();
+    expected.sort_unstable();
+
+    app.start();
+    let debug_node = app.get_debug_node::<u64>().unwrap();
+    assert!(wait_until(TIMEOUT, || {
+        debug_node.on_definition(|cd| cd.data.len() == expected.len())
+    }));
+    debug_node.on_definition(|cd| {
+        let mut data: Vec<u64> = cd.data.iter().map(|elem| elem.data).collect();
+        data.sort_unstable();
+        assert_eq!(data, expected);
+    });
+    app.shutdown();
+}
+
+/// Emits the watermarks it is notified of, which are aligned across both inputs
+struct EmitWatermarks;
+
+impl CoOperator for EmitWatermarks {
+    type IN1 = u64;
+    type IN2 = u64;
+    type OUT = u64;
+    type TimerState = ArconNever;
+    type OperatorState = EmptyState;
+    type ElementIterator = Option<ArconElement<Self::OUT>>;
+
+    fn handle_element1(
+        &mut self,
+        _element: ArconElement<u64>,
+        _ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator> {
+        Ok(None)
+    }
+
+    fn handle_element2(
+        &mut self,
+        _element: ArconElement<u64>,
+        _ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator> {
+        Ok(None)
+    }
+
+    fn handle_watermark(
+        &mut self,
+        watermark: u64,
+        _ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Option<Self::ElementIterator>> {
+        Ok(Some(Some(ArconElement::new(watermark))))
+    }
+
+    crate::ignore_timeout!();
+}
+
+#[test]
+fn co_operator_watermark_test() {
+    let conf = ApplicationConf {
+        watermark_interval: 25,
+        ..Default::default()
+    };
+    let app = Application::with_conf(conf).with_debug_node();
+    // The second input runs far ahead in event time
+    let fast = app.clone().iterator(slow(1001..1101), |conf| {
+        conf.set_arcon_time(ArconTime::Event);
+        conf.set_timestamp_extractor(|x: &u64| *x);
+    });
+    let mut app = app
+        .iterator(slow(1..101), |conf| {
+            conf.set_arcon_time(ArconTime::Event);
+            conf.set_timestamp_extractor(|x: &u64| *x);
+        })
+        .connect(fast)
+        .operator(CoOperatorBuilder {
+            operator: Arc::new(|| EmitWatermarks),
+            state: Arc::new(|_| EmptyState),
+            conf: OperatorConf {
+                parallelism_strategy: ParallelismStrategy::Static(1),
+                ..Default::default()
+            },
+        })
+        .build();
+
+    app.start();
+    let debug_node = app.get_debug_node::<u64>().unwrap();
+    assert!(wait_until(TIMEOUT, || {
+        debug_node.on_definition(|cd| cd.data.iter().any(|elem| elem.data == 100))
+    }));
+    debug_node.on_definition(|cd| {
+        // The CoOperator only sees the watermarks of the slowest input
+        let watermarks: Vec<u64> = cd.data.iter().map(|elem| elem.data).collect();
+        assert!(watermarks.windows(2).all(|w| w[0] < w[1]));
+        assert!(watermarks.iter().all(|w| *w <= 100));
+    });
+    app.shutdown();
+}
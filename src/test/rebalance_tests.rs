@@ -0,0 +1,83 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{dataflow::conf::DefaultBackend, prelude::*, test_utils::wait_until};
+use arcon_macros::ArconState;
+use arcon_state::Backend;
+use std::time::Duration;
+
+const ELEMENTS: u64 = 1000;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn parallel(num: usize) -> OperatorConf {
+    OperatorConf {
+        parallelism_strategy: ParallelismStrategy::Static(num),
+        ..Default::default()
+    }
+}
+
+/// Every element passes through one of the instances of the rebalanced map exactly once
+fn assert_rebalanced(stream: fn(Stream<u64>) -> RebalancedStream<u64>) {
+    let source = Application::default()
+        .with_debug_node()
+        .iterator(0u64..ELEMENTS, |conf| {
+            conf.set_arcon_time(ArconTime::Process);
+        });
+    let mut app = stream(source)
+        .operator(OperatorBuilder {
+            operator: Arc::new(|| Map::new(|x: u64| x + 1)),
+            state: Arc::new(|_| EmptyState),
+            conf: parallel(3),
+        })
+        .build();
+
+    app.start();
+    let debug_node = app.get_debug_node::<u64>().unwrap();
+    assert!(wait_until(TIMEOUT, || {
+        debug_node.on_definition(|cd| cd.data.len() as u64 >= ELEMENTS)
+    }));
+    debug_node.on_definition(|cd| {
+        let mut data: Vec<u64> = cd.data.iter().map(|elem| elem.data).collect();
+        data.sort_unstable();
+        assert_eq!(data, (1..=ELEMENTS).collect::<Vec<u64>>());
+    });
+    app.shutdown();
+}
+
+#[test]
+fn shuffle_test() {
+    assert_rebalanced(Stream::shuffle);
+}
+
+#[test]
+fn rebalance_test() {
+    assert_rebalanced(Stream::rebalance);
+}
+
+#[derive(ArconState)]
+pub struct Counts<B: Backend> {
+    count: EagerValue<u64, B>,
+}
+
+#[test]
+#[should_panic(expected = "Rebalanced streams are not supported")]
+fn rebalance_keyed_state_test() {
+    let _ = Application::default()
+        .iterator(0u64..ELEMENTS, |conf| {
+            conf.set_arcon_time(ArconTime::Process);
+        })
+        .rebalance()
+        .operator(OperatorBuilder {
+            operator: Arc::new(|| {
+                Map::stateful(|x: u64, state: &mut Counts<DefaultBackend>| {
+                    let count = state.count().get()?.map(|c| *c).unwrap_or(0) + 1;
+                    state.count().put(count)?;
+                    Ok(x + count)
+                })
+            }),
+            state: Arc::new(|backend: Arc<DefaultBackend>| Counts {
+                count: EagerValue::new("_count", backend),
+            }),
+            conf: Default::default(),
+        });
+}
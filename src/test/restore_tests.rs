@@ -0,0 +1,293 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{
+    application::restore::{checkpoint_name, mark_complete, RestoredSnapshot},
+    dataflow::conf::DefaultBackend,
+    prelude::*,
+    test_utils::wait_until,
+};
+use arcon_macros::ArconState;
+use arcon_state::Backend;
+use std::{path::Path, sync::Arc, thread, time::Duration};
+
+const ELEMENTS: u64 = 100;
+const KEYS: u64 = 4;
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(ArconState)]
+pub struct Sums<B: Backend> {
+    sum: EagerValue<u64, B>,
+}
+
+/// Keeps a running sum per key and emits it once the last element of the key has arrived
+struct KeyedSum;
+
+impl Operator for KeyedSum {
+    type IN = u64;
+    type OUT = u64;
+    type TimerState = ArconNever;
+    type OperatorState = Sums<DefaultBackend>;
+    type ElementIterator = Option<ArconElement<Self::OUT>>;
+
+    fn handle_element(
+        &mut self,
+        element: ArconElement<u64>,
+        ctx: &mut OperatorContext<Self::TimerState, Self::OperatorState>,
+    ) -> ArconResult<Self::ElementIterator> {
+        let value = element.data;
+        let sum = ctx.state().sum().get()?.map(|sum| *sum).unwrap_or(0) + value;
+        ctx.state().sum().put(sum)?;
+        if value + KEYS >= ELEMENTS {
+            Ok(Some(ArconElement::new(sum)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    crate::ignore_timeout!();
+}
+
+fn conf(base_dir: &Path) -> ApplicationConf {
+    ApplicationConf {
+        base_dir: base_dir.to_path_buf(),
+        epoch_interval: 100,
+        ..Default::default()
+    }
+}
+
+fn app(base_dir: &Path, restore: Option<RestorePoint>) -> AssembledApplication {
+    app_with_conf(conf(base_dir), restore)
+}
+
+fn app_with_conf(conf: ApplicationConf, restore: Option<RestorePoint>) -> AssembledApplication {
+    keyed_sum_app(conf, Default::default(), restore)
+}
+
+fn keyed_sum_app(
+    conf: ApplicationConf,
+    operator_conf: OperatorConf,
+    restore: Option<RestorePoint>,
+) -> AssembledApplication {
+    let mut app = Application::with_conf(conf).with_debug_node();
+    if let Some(point) = restore {
+        app = app.restore_from(point).unwrap();
+    }
+
+    app.iterator(0u64..ELEMENTS, |conf| {
+        conf.set_source_name("numbers".to_string());
+        conf.set_arcon_time(ArconTime::Process);
+    })
+    .map(|x| {
+        // slow down the pipeline so that checkpoints are taken mid-stream
+        thread::sleep(Duration::from_millis(10));
+        x
+    })
+    .key_by(|x| x % KEYS)
+    .operator(OperatorBuilder {
+        operator: Arc::new(|| KeyedSum),
+        state: Arc::new(|backend: Arc<DefaultBackend>| Sums {
+            sum: EagerValue::new("_sum", backend),
+        }),
+        conf: operator_conf,
+    })
+    .build()
+}
+
+#[test]
+fn restore_pipeline_test() {
+    let base_dir = tempfile::tempdir().unwrap();
+    restore_pipeline(conf(base_dir.path()));
+}
+
+#[test]
+fn restore_unaligned_pipeline_test() {
+    let base_dir = tempfile::tempdir().unwrap();
+    let conf = ApplicationConf {
+        unaligned_checkpoints: true,
+        ..conf(base_dir.path())
+    };
+    restore_pipeline(conf);
+}
+
+fn restore_pipeline(conf: ApplicationConf) {
+    let checkpoints_dir = conf.checkpoints_dir();
+
+    // run the pipeline until a checkpoint has been completed and then kill it
+    let mut first = app_with_conf(conf.clone(), None);
+    first.start();
+    await_checkpoint(&checkpoints_dir);
+    first.shutdown();
+
+    // the restored pipeline continues from the checkpoint and processes every element once
+    let mut second = app_with_conf(conf, Some(RestorePoint::Latest));
+    second.start();
+    assert_complete_sums(&second);
+}
+
+#[test]
+fn restore_without_checkpoint_test() {
+    let dir = tempfile::tempdir().unwrap();
+    let restore = RestorePoint::Dir(dir.path().to_path_buf());
+    let result = Application::default().restore_from(restore);
+    assert!(matches!(result, Err(crate::error::Error::Restore { .. })));
+}
+
+#[test]
+#[should_panic(expected = "source_manager_numbers has no complete checkpoint")]
+fn restore_missing_operator_test() {
+    let base_dir = tempfile::tempdir().unwrap();
+    let conf = conf(base_dir.path());
+    // the checkpoints of the source never made it to the directory
+    let mut path = conf.checkpoints_dir();
+    path.push(checkpoint_name("Sums", 1));
+    mark_complete(&path).unwrap();
+
+    let _ = app_with_conf(conf, Some(RestorePoint::Latest));
+}
+
+#[test]
+fn restore_rescaled_pipeline_test() {
+    let base_dir = tempfile::tempdir().unwrap();
+    let conf = conf(base_dir.path());
+    let parallelism = |num| OperatorConf {
+        parallelism_strategy: ParallelismStrategy::Static(num),
+        ..Default::default()
+    };
+
+    let mut first = keyed_sum_app(conf.clone(), parallelism(2), None);
+    first.start();
+    let snapshot = await_checkpoint(&conf.checkpoints_dir());
+    first.shutdown();
+    // the backend of the two local instances holds every key group
+    let held = snapshot.key_groups("Sums").unwrap();
+    assert_eq!(held.len(), 2);
+    assert_eq!(held[0].start, 0);
+    assert_eq!(held[1].end, conf.max_key - 1);
+
+    // the keyed sums checkpointed at parallelism 2 are continued by 3 instances
+    let mut second = keyed_sum_app(conf, parallelism(3), Some(RestorePoint::Latest));
+    second.start();
+    assert_complete_sums(&second);
+}
+
+#[test]
+fn stop_with_savepoint_test() {
+    stop_with_savepoint(true);
+}
+
+#[test]
+fn stop_with_savepoint_without_drain_test() {
+    stop_with_savepoint(false);
+}
+
+fn stop_with_savepoint(drain: bool) {
+    let base_dir = tempfile::tempdir().unwrap();
+    let savepoint_dir = tempfile::tempdir().unwrap();
+
+    let mut first = app(base_dir.path(), None);
+    first.start();
+    thread::sleep(Duration::from_millis(300));
+    let savepoint = first
+        .stop_with_savepoint(savepoint_dir.path(), drain)
+        .unwrap();
+    assert!(savepoint.snapshot("source_manager_numbers").is_some());
+    assert!(savepoint.snapshot("Sums").is_some());
+
+    // the savepoint is self-contained, so it may be restored into another base directory
+    let other_dir = tempfile::tempdir().unwrap();
+    let restore = RestorePoint::Dir(savepoint_dir.path().to_path_buf());
+    let mut second = app(other_dir.path(), Some(restore));
+    second.start();
+    assert_complete_sums(&second);
+}
+
+/// Waits until a checkpoint of the whole application has been completed in `checkpoints_dir`
+fn await_checkpoint(checkpoints_dir: &Path) -> RestoredSnapshot {
+    let mut snapshot = None;
+    let completed = wait_until(TIMEOUT, || {
+        snapshot = RestoredSnapshot::find(checkpoints_dir).unwrap();
+        snapshot.is_some()
+    });
+    assert!(completed, "no checkpoint was completed");
+    snapshot.unwrap()
+}
+
+/// Every element has been accounted for exactly once in the final sums
+fn assert_complete_sums(app: &AssembledApplication) {
+    let debug_node = app.get_debug_node::<u64>().unwrap();
+    assert!(wait_until(TIMEOUT, || {
+        debug_node.on_definition(|cd| cd.data.len() >= KEYS as usize)
+    }));
+    debug_node.on_definition(|cd| {
+        let total: u64 = cd.data.iter().map(|elem| elem.data).sum();
+        assert_eq!(cd.data.len(), KEYS as usize);
+        assert_eq!(total, (0..ELEMENTS).sum::<u64>());
+    });
+}
+
+fn sink_app(
+    conf: ApplicationConf,
+    output: &Path,
+    restore: Option<RestorePoint>,
+) -> AssembledApplication {
+    let mut app = Application::with_conf(conf);
+    if let Some(point) = restore {
+        app = app.restore_from(point).unwrap();
+    }
+
+    let output = output.to_path_buf();
+    app.iterator(0u64..ELEMENTS, |conf| {
+        conf.set_source_name("numbers".to_string());
+        conf.set_arcon_time(ArconTime::Process);
+    })
+    .map(|x| {
+        thread::sleep(Duration::from_millis(10));
+        x
+    })
+    .operator(OperatorBuilder {
+        operator: Arc::new(move || TwoPhaseCommit::new(TransactionalFileSink::new(&output))),
+        state: Arc::new(|_| EmptyState),
+        conf: OperatorConf {
+            parallelism_strategy: ParallelismStrategy::Static(1),
+            ..Default::default()
+        },
+    })
+    .build()
+}
+
+#[test]
+fn transactional_sink_restore_test() {
+    let base_dir = tempfile::tempdir().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    let conf = conf(base_dir.path());
+
+    let mut first = sink_app(conf.clone(), output_dir.path(), None);
+    first.start();
+    await_checkpoint(&conf.checkpoints_dir());
+    first.shutdown();
+
+    let mut second = sink_app(conf, output_dir.path(), Some(RestorePoint::Latest));
+    second.start();
+
+    // the committed output holds every element exactly once
+    let committed_output = || {
+        let mut output: Vec<u64> = Vec::new();
+        for entry in std::fs::read_dir(output_dir.path()).unwrap() {
+            let path = entry.unwrap().path();
+            if path
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with("epoch_")
+            {
+                output.extend(TransactionalFileSink::<u64>::read_committed(path).unwrap());
+            }
+        }
+        output.sort_unstable();
+        output
+    };
+    assert!(wait_until(TIMEOUT, || committed_output().len() >= ELEMENTS as usize));
+    second.shutdown();
+    assert_eq!(committed_output(), (0..ELEMENTS).collect::<Vec<_>>());
+}
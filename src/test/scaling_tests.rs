@@ -0,0 +1,180 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{dataflow::conf::DefaultBackend, prelude::*, test_utils::wait_until};
+use arcon_macros::ArconState;
+use arcon_state::Backend;
+use std::{collections::BTreeMap, sync::Arc, thread, time::Duration};
+
+const ELEMENTS: u64 = 2000;
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn managed() -> OperatorConf {
+    OperatorConf {
+        parallelism_strategy: ParallelismStrategy::Managed,
+        ..Default::default()
+    }
+}
+
+// every reported throughput exceeds the scale up threshold
+fn rescaling_conf() -> ApplicationConf {
+    ApplicationConf {
+        epoch_interval: 50,
+        node_metrics_interval: 50,
+        scaling_interval: 100,
+        max_managed_parallelism: 4,
+        scale_up_throughput: 2.0,
+        scale_down_throughput: 1.0,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn managed_rescale_test() {
+    let mut app = Application::with_conf(rescaling_conf())
+        .with_debug_node()
+        .iterator(0u64..ELEMENTS, |conf| {
+            conf.set_arcon_time(ArconTime::Process);
+        })
+        .map(|x| {
+            // keep the pipeline running across several rescales
+            thread::sleep(Duration::from_millis(1));
+            x
+        })
+        .key_by(|x| x % 16)
+        .operator(OperatorBuilder {
+            operator: Arc::new(|| Map::new(|x: u64| x * 2)),
+            state: Arc::new(|_| EmptyState),
+            conf: managed(),
+        })
+        .build();
+
+    app.start();
+    let debug_node = app.get_debug_node::<u64>().unwrap();
+    assert!(wait_until(TIMEOUT, || {
+        debug_node.on_definition(|cd| cd.data.len() as u64 >= ELEMENTS)
+    }));
+    // no element is lost or duplicated while key ranges change owner
+    thread::sleep(Duration::from_millis(200));
+    debug_node.on_definition(|cd| {
+        let mut data: Vec<u64> = cd.data.iter().map(|elem| elem.data).collect();
+        data.sort_unstable();
+        let expected: Vec<u64> = (0..ELEMENTS).map(|x| x * 2).collect();
+        assert_eq!(data, expected);
+    });
+    app.shutdown();
+}
+
+#[derive(ArconState)]
+pub struct Counts<B: Backend> {
+    count: LazyValue<u64, B>,
+}
+
+#[test]
+fn managed_keyed_state_test() {
+    const KEYS: u64 = 16;
+    let mut app = Application::with_conf(rescaling_conf())
+        .with_debug_node()
+        .iterator(0u64..ELEMENTS, |conf| {
+            conf.set_arcon_time(ArconTime::Process);
+        })
+        .map(|x| {
+            thread::sleep(Duration::from_millis(1));
+            x
+        })
+        .key_by(|x| x % KEYS)
+        .operator(OperatorBuilder {
+            operator: Arc::new(|| {
+                Map::stateful(|x: u64, state: &mut Counts<DefaultBackend>| {
+                    let count = state.count().get()?.map(|c| *c).unwrap_or(0) + 1;
+                    state.count().put(count)?;
+                    Ok((x % KEYS) * ELEMENTS + count)
+                })
+            }),
+            state: Arc::new(|backend: Arc<DefaultBackend>| Counts {
+                count: LazyValue::new("_count", backend),
+            }),
+            conf: managed(),
+        })
+        .build();
+
+    app.start();
+    let debug_node = app.get_debug_node::<u64>().unwrap();
+    assert!(wait_until(TIMEOUT, || {
+        debug_node.on_definition(|cd| cd.data.len() as u64 >= ELEMENTS)
+    }));
+    // every key keeps counting where its previous owner left off
+    thread::sleep(Duration::from_millis(200));
+    debug_node.on_definition(|cd| {
+        let mut data: Vec<u64> = cd.data.iter().map(|elem| elem.data).collect();
+        data.sort_unstable();
+        let expected: Vec<u64> = (0..KEYS)
+            .flat_map(|key| (1..=ELEMENTS / KEYS).map(move |count| key * ELEMENTS + count))
+            .collect();
+        assert_eq!(data, expected);
+    });
+    app.shutdown();
+}
+
+fn window_sum(buffer: &[u64]) -> u64 {
+    buffer.iter().sum()
+}
+
+#[test]
+fn managed_window_rescale_test() {
+    const KEYS: u64 = 8;
+    const LENGTH: u64 = 100;
+    // the last element moves the watermark past the end of every other window
+    let last = ELEMENTS + 10 * LENGTH;
+    let mut app = Application::with_conf(rescaling_conf())
+        .with_debug_node()
+        .iterator((0u64..ELEMENTS).chain(std::iter::once(last)), |conf| {
+            conf.set_arcon_time(ArconTime::Event);
+            conf.set_timestamp_extractor(|x: &u64| *x);
+        })
+        .map(|x| {
+            thread::sleep(Duration::from_millis(1));
+            x
+        })
+        .key_by(|x| x % KEYS)
+        .operator(OperatorBuilder {
+            operator: Arc::new(|| {
+                WindowAssigner::new(WindowConf {
+                    assigner: Assigner::Tumbling {
+                        length: Time(LENGTH),
+                        late_arrival: Time(0),
+                    },
+                    kind: StreamKind::Keyed,
+                })
+            }),
+            state: Arc::new(|backend: Arc<DefaultBackend>| {
+                let index = AppenderWindow::new(backend.clone(), &window_sum);
+                WindowState::new(index, backend)
+            }),
+            conf: managed(),
+        })
+        .build();
+
+    // the windows of a key start at its first element
+    let mut sums: BTreeMap<(u64, u64), u64> = BTreeMap::new();
+    for x in 0..ELEMENTS {
+        let key = x % KEYS;
+        *sums.entry((key, (x - key) / LENGTH)).or_default() += x;
+    }
+    let mut expected: Vec<u64> = sums.into_iter().map(|(_, sum)| sum).collect();
+    expected.sort_unstable();
+
+    app.start();
+    let debug_node = app.get_debug_node::<u64>().unwrap();
+    assert!(wait_until(TIMEOUT, || {
+        debug_node.on_definition(|cd| cd.data.len() >= expected.len())
+    }));
+    // every window fires once with all of its elements, whichever instance owns its key
+    thread::sleep(Duration::from_millis(200));
+    debug_node.on_definition(|cd| {
+        let mut data: Vec<u64> = cd.data.iter().map(|elem| elem.data).collect();
+        data.sort_unstable();
+        assert_eq!(data, expected);
+    });
+    app.shutdown();
+}
@@ -0,0 +1,60 @@
+// Copyright (c) 2021, KTH Royal Institute of Technology.
+// SPDX-License-Identifier: AGPL-3.0-only
+
+use crate::{prelude::*, test_utils::wait_until};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[test]
+fn split_test() {
+    let (evens, odds) = Application::default()
+        .with_debug_node()
+        .iterator(0u64..100, |conf| {
+            conf.set_arcon_time(ArconTime::Process);
+        })
+        .split();
+
+    let odds = odds.filter(|x| x % 2 == 1).map(|x| x * 10);
+    let mut app = evens.filter(|x| x % 2 == 0).union(odds).build();
+
+    app.start();
+    let debug_node = app.get_debug_node::<u64>().unwrap();
+    assert!(wait_until(TIMEOUT, || {
+        debug_node.on_definition(|cd| cd.data.len() == 100)
+    }));
+    debug_node.on_definition(|cd| {
+        let mut data: Vec<u64> = cd.data.iter().map(|elem| elem.data).collect();
+        data.sort_unstable();
+        let mut expected: Vec<u64> = (0u64..100)
+            .map(|x| if x % 2 == 0 { x } else { x * 10 })
+            .collect();
+        expected.sort_unstable();
+        assert_eq!(data, expected);
+    });
+    app.shutdown();
+}
+
+#[test]
+fn split_debug_node_test() {
+    // The built stream ends at a node that also feeds another branch
+    let stream = Application::default()
+        .with_debug_node()
+        .iterator(0u64..100, |conf| {
+            conf.set_arcon_time(ArconTime::Process);
+        })
+        .map(|x| x + 1);
+    let _printed = stream.clone().filter(|x| x % 10 == 0).to_console();
+    let mut app = stream.build();
+
+    app.start();
+    let debug_node = app.get_debug_node::<u64>().unwrap();
+    assert!(wait_until(TIMEOUT, || {
+        debug_node.on_definition(|cd| cd.data.len() == 100)
+    }));
+    debug_node.on_definition(|cd| {
+        let sum: u64 = cd.data.iter().map(|elem| elem.data).sum();
+        assert_eq!(sum, (1u64..=100).sum::<u64>());
+    });
+    app.shutdown();
+}
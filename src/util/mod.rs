@@ -8,7 +8,11 @@ pub mod io;
 pub mod prost_helpers;
 pub mod system_killer;
 
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    fs, io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 #[inline]
 pub fn get_system_time() -> u64 {
@@ -31,5 +35,20 @@ pub fn get_system_time_nano() -> u64 {
     since_the_epoch.as_nanos() as u64
 }
 
+/// Recursively copies the directory `from` into `to`
+pub fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
 pub trait ArconFnBounds: Send + Sync + Clone + 'static {}
 impl<T> ArconFnBounds for T where T: Send + Sync + Clone + 'static {}